@@ -0,0 +1,519 @@
+use crate::ScenarioParseError;
+use anyhow::anyhow;
+use axion_core::{parse_scenario_all, prefix_step_names, ImportAlias, Scenario, Step};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+pub(crate) fn load_scenario(path: &Path, allow_remote: bool) -> anyhow::Result<Scenario> {
+    let mut loader = ScenarioLoader {
+        allow_remote,
+        offline_imports: OFFLINE_IMPORTS.load(Ordering::Relaxed),
+        cache_dir: import_cache_dir(),
+        visited: HashSet::new(),
+        client: None,
+    };
+    loader.load(ImportSource::Local(path.to_path_buf()))
+}
+
+#[derive(Debug, Clone)]
+enum ImportSource {
+    Local(PathBuf),
+    Remote(Url),
+}
+
+impl ImportSource {
+    fn resolve(&self, target: &str) -> anyhow::Result<ImportSource> {
+        if target.starts_with("https://") || target.starts_with("http://") {
+            return Ok(ImportSource::Remote(Url::parse(target)?));
+        }
+        match self {
+            ImportSource::Local(path) => {
+                let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+                Ok(ImportSource::Local(base_dir.join(target)))
+            }
+            ImportSource::Remote(url) => Ok(ImportSource::Remote(url.join(target)?)),
+        }
+    }
+}
+
+struct ScenarioLoader {
+    allow_remote: bool,
+    // Reuse a cached remote import when refreshing it fails.
+    offline_imports: bool,
+    // Where remote imports are cached; `None` disables the cache.
+    cache_dir: Option<PathBuf>,
+    // Canonical paths and post-redirect URLs already loaded.
+    visited: HashSet<String>,
+    client: Option<reqwest::blocking::Client>,
+}
+
+impl ScenarioLoader {
+    fn load(&mut self, source: ImportSource) -> anyhow::Result<Scenario> {
+        let (source, content) = match source {
+            ImportSource::Local(path) => {
+                let canonical = fs::canonicalize(&path)?;
+                let content = fs::read_to_string(&canonical)?;
+                (ImportSource::Local(canonical), content)
+            }
+            ImportSource::Remote(url) => {
+                if !self.allow_remote {
+                    anyhow::bail!("remote import '{url}' is disabled by --no-remote-imports");
+                }
+                let (final_url, content) = self.fetch_remote(&url)?;
+                (ImportSource::Remote(final_url), content)
+            }
+        };
+        if !self.visited.insert(source_display(&source)) {
+            return Ok(Scenario::default());
+        }
+
+        let parsed = parse_scenario_all(&content).map_err(|errors| ScenarioParseError {
+            file: source_display(&source),
+            errors,
+        })?;
+        let mut steps = Vec::new();
+        let mut imports = Vec::new();
+        let mut aliases = Vec::new();
+
+        for step in parsed.steps {
+            match step {
+                Step::Import(import_step) => {
+                    let import_source = source.resolve(&import_step.path)?;
+                    let import_display = match &import_source {
+                        ImportSource::Local(path) => path.to_string_lossy().to_string(),
+                        ImportSource::Remote(url) => url.to_string(),
+                    };
+                    let mut imported = self.load(import_source)?;
+                    if let Some(alias) = &import_step.alias {
+                        prefix_step_names(&mut imported.steps, alias);
+                        let mut alias_imports = vec![import_display.clone()];
+                        alias_imports.extend(imported.imports.iter().cloned());
+                        aliases.push(ImportAlias {
+                            alias: alias.clone(),
+                            imports: alias_imports,
+                        });
+                    }
+                    imports.push(import_display);
+                    steps.extend(imported.steps);
+                    imports.extend(imported.imports);
+                    aliases.extend(imported.aliases);
+                }
+                other => steps.push(other),
+            }
+        }
+
+        Ok(Scenario {
+            steps,
+            imports,
+            aliases,
+        })
+    }
+
+    fn fetch_remote(&mut self, url: &Url) -> anyhow::Result<(Url, String)> {
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => {
+                let client = reqwest::blocking::Client::builder()
+                    .timeout(Duration::from_secs(30))
+                    .build()?;
+                self.client = Some(client.clone());
+                client
+            }
+        };
+
+        let cache = self
+            .cache_dir
+            .as_deref()
+            .and_then(|dir| RemoteImportCache::for_url(dir, url));
+        let cached = cache.as_ref().and_then(RemoteImportCache::read);
+        let mut request = client.get(url.clone());
+        if let Some((meta, _)) = &cached {
+            if let Some(etag) = &meta.etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(modified) = &meta.last_modified {
+                request = request.header(reqwest::header::IF_MODIFIED_SINCE, modified);
+            }
+        }
+
+        let response = match request.send() {
+            Ok(response) => response,
+            Err(err) => match cached {
+                Some((meta, body)) if self.offline_imports => {
+                    tracing::warn!(
+                        "failed to refresh remote import {url}: {err}; using cached copy"
+                    );
+                    return Ok((Url::parse(&meta.final_url)?, body));
+                }
+                Some(_) => {
+                    return Err(anyhow!(
+                        "failed to fetch remote import {url}: {err} (pass --offline-imports to reuse the cached copy)"
+                    ))
+                }
+                None => return Err(anyhow!("failed to fetch remote import {url}: {err}")),
+            },
+        };
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some((meta, body)) = cached {
+                return Ok((Url::parse(&meta.final_url)?, body));
+            }
+        }
+        if !response.status().is_success() {
+            anyhow::bail!(
+                "failed to fetch remote import {url}: HTTP {}",
+                response.status()
+            );
+        }
+
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header(reqwest::header::ETAG);
+        let last_modified = header(reqwest::header::LAST_MODIFIED);
+        let final_url = response.url().clone();
+        let body = response.text()?;
+        let meta = CachedImport {
+            url: url.to_string(),
+            final_url: final_url.to_string(),
+            etag,
+            last_modified,
+            sha256: sha256_hex(&body),
+        };
+        if let Some(cache) = &cache {
+            cache.write(&meta, &body);
+        }
+        Ok((final_url, body))
+    }
+}
+
+fn source_display(source: &ImportSource) -> String {
+    match source {
+        ImportSource::Local(path) => path.to_string_lossy().to_string(),
+        ImportSource::Remote(url) => url.to_string(),
+    }
+}
+
+// Set once from `--offline-imports`; allows falling back to a cached remote
+// import when the fetch fails.
+pub(crate) static OFFLINE_IMPORTS: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedImport {
+    url: String,
+    final_url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    // SHA-256 of the cached body; a body that no longer matches is refetched.
+    sha256: String,
+}
+
+struct RemoteImportCache {
+    body_path: PathBuf,
+    meta_path: PathBuf,
+}
+
+impl RemoteImportCache {
+    /// The cache entry for `url` under `dir`, or `None` when `dir` cannot be
+    /// made private to the current user.
+    fn for_url(dir: &Path, url: &Url) -> Option<Self> {
+        if let Err(err) = ensure_private_dir(dir) {
+            tracing::warn!(
+                "not caching remote imports: {} is unusable: {err}",
+                dir.display()
+            );
+            return None;
+        }
+        // FNV-1a keeps cache file names stable across builds.
+        let hash = url
+            .as_str()
+            .bytes()
+            .fold(0xcbf29ce484222325u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+            });
+        Some(Self {
+            body_path: dir.join(format!("{hash:016x}.axion")),
+            meta_path: dir.join(format!("{hash:016x}.json")),
+        })
+    }
+
+    fn read(&self) -> Option<(CachedImport, String)> {
+        let meta = fs::read_to_string(&self.meta_path).ok()?;
+        let meta: CachedImport = serde_json::from_str(&meta).ok()?;
+        let body = fs::read_to_string(&self.body_path).ok()?;
+        if sha256_hex(&body) != meta.sha256 {
+            tracing::warn!(
+                "ignoring cached remote import {}: checksum mismatch",
+                meta.url
+            );
+            return None;
+        }
+        Some((meta, body))
+    }
+
+    fn write(&self, meta: &CachedImport, body: &str) {
+        let result = fs::write(&self.body_path, body).and_then(|_| {
+            let encoded = serde_json::to_string(meta).map_err(std::io::Error::other)?;
+            fs::write(&self.meta_path, encoded)
+        });
+        if let Err(err) = result {
+            tracing::warn!("failed to cache remote import {}: {err}", meta.url);
+        }
+    }
+}
+
+fn sha256_hex(body: &str) -> String {
+    Sha256::digest(body.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+// Per-user cache location: `$XDG_CACHE_HOME`, `%LOCALAPPDATA%`, or
+// `~/.cache`. Never the shared temp directory, where another local user
+// could plant a scenario body.
+fn import_cache_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("axion").join("imports"))
+}
+
+/// Creates `dir` readable only by the current user, and refuses a directory
+/// that is a symlink or belongs to someone else.
+#[cfg(unix)]
+fn ensure_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)?;
+    let metadata = fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() {
+        return Err(std::io::Error::other("not a directory"));
+    }
+    // SAFETY: geteuid has no preconditions and cannot fail.
+    let uid = unsafe { libc::geteuid() };
+    if metadata.uid() != uid {
+        return Err(std::io::Error::other(format!(
+            "owned by uid {}, not {uid}",
+            metadata.uid()
+        )));
+    }
+    if metadata.mode() & 0o077 != 0 {
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn ensure_private_dir(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    // Serves one `(status, headers, body)` response per connection, in order,
+    // and returns the request heads it received. `{address}` in a header or
+    // body is replaced with the server's own `http://host:port`.
+    fn spawn_mock_http(
+        responses: Vec<(u16, &'static str, &'static str)>,
+    ) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let address = format!("http://{}", listener.local_addr().expect("mock address"));
+        let server_address = address.clone();
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, headers, body) in responses {
+                let (stream, _) = listener.accept().expect("accept mock connection");
+                let mut reader = BufReader::new(stream);
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("read request line");
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+                requests.push(head);
+
+                let body = body.replace("{address}", &server_address);
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    headers.replace("{address}", &server_address),
+                    body.len(),
+                    body
+                );
+                let mut stream = reader.into_inner();
+                stream
+                    .write_all(response.as_bytes())
+                    .expect("write mock response");
+            }
+            requests
+        });
+        (address, handle)
+    }
+
+    fn loader(cache_dir: &Path, offline_imports: bool) -> ScenarioLoader {
+        ScenarioLoader {
+            allow_remote: true,
+            offline_imports,
+            cache_dir: Some(cache_dir.to_path_buf()),
+            visited: HashSet::new(),
+            client: None,
+        }
+    }
+
+    fn load_remote(loader: &mut ScenarioLoader, url: &str) -> anyhow::Result<Scenario> {
+        loader.load(ImportSource::Remote(Url::parse(url).expect("mock url")))
+    }
+
+    fn step_names(scenario: &Scenario) -> Vec<&str> {
+        scenario.steps.iter().filter_map(Step::name).collect()
+    }
+
+    fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+        request.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    #[test]
+    fn remote_import_is_cached_and_revalidated() {
+        let cache = tempfile::tempdir().expect("temp cache dir");
+        let (address, server) = spawn_mock_http(vec![
+            (200, "ETag: \"v1\"\r\n", "sleep first 1s\n"),
+            (304, "", ""),
+            (200, "ETag: \"v2\"\r\n", "sleep second 1s\n"),
+        ]);
+        let url = format!("{address}/remote.ax");
+
+        let first = load_remote(&mut loader(cache.path(), false), &url).expect("first fetch");
+        assert_eq!(step_names(&first), vec!["first"]);
+
+        // Not modified: the cached body is used.
+        let second = load_remote(&mut loader(cache.path(), false), &url).expect("revalidate");
+        assert_eq!(step_names(&second), vec!["first"]);
+
+        // A body that no longer matches its checksum is refetched without
+        // conditional headers.
+        let body_path = RemoteImportCache::for_url(cache.path(), &Url::parse(&url).unwrap())
+            .expect("cache entry")
+            .body_path;
+        fs::write(&body_path, "sleep tampered 1s\n").expect("tamper cached body");
+        let third = load_remote(&mut loader(cache.path(), false), &url).expect("refetch");
+        assert_eq!(step_names(&third), vec!["second"]);
+
+        let requests = server.join().expect("mock server");
+        assert_eq!(header(&requests[0], "if-none-match"), None);
+        assert_eq!(header(&requests[1], "if-none-match"), Some("\"v1\""));
+        assert_eq!(header(&requests[2], "if-none-match"), None);
+    }
+
+    #[test]
+    fn stale_cache_is_reused_only_with_offline_imports() {
+        let cache = tempfile::tempdir().expect("temp cache dir");
+        let (address, server) = spawn_mock_http(vec![(200, "", "sleep cached 1s\n")]);
+        let url = format!("{address}/remote.ax");
+        load_remote(&mut loader(cache.path(), false), &url).expect("populate cache");
+        // The listener is dropped once the server thread finishes, so later
+        // fetches fail to connect.
+        server.join().expect("mock server");
+
+        let err = load_remote(&mut loader(cache.path(), false), &url)
+            .expect_err("stale cache used without --offline-imports");
+        assert!(
+            err.to_string().contains("pass --offline-imports"),
+            "unexpected error: {err}"
+        );
+
+        let scenario =
+            load_remote(&mut loader(cache.path(), true), &url).expect("offline fallback");
+        assert_eq!(step_names(&scenario), vec!["cached"]);
+    }
+
+    #[test]
+    fn offline_imports_without_cache_still_fail() {
+        let cache = tempfile::tempdir().expect("temp cache dir");
+        let (address, server) = spawn_mock_http(Vec::new());
+        server.join().expect("mock server");
+
+        let err = load_remote(&mut loader(cache.path(), true), &format!("{address}/x.ax"))
+            .expect_err("nothing cached");
+        assert!(
+            !err.to_string().contains("--offline-imports"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cache_dir_is_private_to_the_user() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let base = tempfile::tempdir().expect("temp cache base");
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        let created = base.path().join("axion").join("imports");
+        ensure_private_dir(&created).expect("create cache dir");
+        assert_eq!(mode(&created), 0o700);
+
+        let shared = base.path().join("shared");
+        fs::create_dir(&shared).unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o777)).unwrap();
+        ensure_private_dir(&shared).expect("tighten cache dir");
+        assert_eq!(mode(&shared), 0o700);
+
+        let link = base.path().join("link");
+        std::os::unix::fs::symlink(&shared, &link).unwrap();
+        assert!(ensure_private_dir(&link).is_err());
+        assert!(RemoteImportCache::for_url(&link, &Url::parse("http://x/a.ax").unwrap()).is_none());
+    }
+
+    #[test]
+    fn import_cycle_through_a_redirect_is_loaded_once() {
+        let cache = tempfile::tempdir().expect("temp cache dir");
+        let (address, server) = spawn_mock_http(vec![
+            (200, "", "import \"b.ax\"\nsleep a 1s\n"),
+            (200, "", "import \"alias.ax\"\nsleep b 1s\n"),
+            (302, "Location: {address}/a.ax\r\n", ""),
+            (200, "", "import \"b.ax\"\nsleep a 1s\n"),
+        ]);
+
+        let scenario = load_remote(&mut loader(cache.path(), false), &format!("{address}/a.ax"))
+            .expect("cyclic imports load");
+        assert_eq!(step_names(&scenario), vec!["b", "a"]);
+        assert_eq!(
+            scenario.imports,
+            vec![format!("{address}/b.ax"), format!("{address}/alias.ax")]
+        );
+
+        let requests = server.join().expect("mock server");
+        let paths: Vec<&str> = requests
+            .iter()
+            .map(|request| request.split_whitespace().nth(1).unwrap_or_default())
+            .collect();
+        assert_eq!(paths, vec!["/a.ax", "/b.ax", "/alias.ax", "/a.ax"]);
+    }
+}
@@ -1,19 +1,43 @@
 use anyhow::anyhow;
 use axion_core::{
-    builtin_tool_schema_bundle, parse_scenario, validate_scenario, Diagnostic, DiagnosticLevel,
-    ExecutionOutcome, Executor, LiteralValue, Scenario, ScenarioSummary, Step, StoredArtifact,
-    ToolSchema,
+    builtin_tool_schema_bundle, parse_scenario_all, parse_schema_plugin, tool_json_schema,
+    tool_schema_bundle, validate_scenario_with_schemas, Checkpoint, Diagnostic, DiagnosticLevel,
+    ExecutionDiff, ExecutionOutcome, ExecutionReport, Executor, LiteralValue, Location, ParseError,
+    ScenarioSummary, StoredArtifact, ToolSchema, DEFAULT_MAX_ITERATIONS,
 };
-use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use clap::{ArgAction, CommandFactory, Parser, Subcommand, ValueEnum};
+use loader::{load_scenario, OFFLINE_IMPORTS};
+use notify::Watcher;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+
+mod loader;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about = "Axion DSL command line interface (MVP)")]
 struct AxionCli {
+    /// Disable colored output (also honoured via the NO_COLOR environment variable)
+    #[arg(long, global = true)]
+    no_color: bool,
+    /// Log debug details, including per-step timing, to stderr
+    #[arg(long, short, global = true, conflicts_with = "quiet")]
+    verbose: bool,
+    /// Only log errors to stderr
+    #[arg(long, short, global = true)]
+    quiet: bool,
+    /// Load extra tool schemas from a TOML file; they replace builtin schemas
+    /// of the same name. Repeat for multiple files.
+    #[arg(long = "schema-plugin", value_name = "FILE", global = true, action = ArgAction::Append)]
+    schema_plugins: Vec<PathBuf>,
+    /// Reuse cached remote imports when they cannot be fetched
+    #[arg(long, global = true, env = "AXION_OFFLINE_IMPORTS")]
+    offline_imports: bool,
     #[command(subcommand)]
     command: Command,
 }
@@ -33,6 +57,32 @@ enum Command {
         /// Override a secret (format: key=value). Repeat for multiple overrides.
         #[arg(long = "secret", value_parser = parse_key_val, value_name = "KEY=VALUE", action = ArgAction::Append)]
         secrets: Vec<(String, String)>,
+        /// Load variable overrides from a JSON or YAML file. Later files win; --var wins over files.
+        #[arg(long = "var-file", value_name = "PATH", action = ArgAction::Append)]
+        var_files: Vec<PathBuf>,
+        /// Load secret overrides from a JSON file of string values. --secret wins over files.
+        #[arg(long = "secret-file", value_name = "PATH", action = ArgAction::Append)]
+        secret_files: Vec<PathBuf>,
+        /// Reject imports that point at http:// or https:// URLs
+        #[arg(long)]
+        no_remote_imports: bool,
+        /// Hide info-level diagnostics
+        #[arg(long)]
+        no_info: bool,
+    },
+    /// Parse and validate a scenario file, printing only diagnostics
+    Validate {
+        /// Path to the Axion DSL scenario file
+        input: PathBuf,
+        /// Output format
+        #[arg(long, default_value_t = ValidateFormat::Text)]
+        format: ValidateFormat,
+        /// Treat warnings as errors
+        #[arg(long)]
+        strict: bool,
+        /// Reject imports that point at http:// or https:// URLs
+        #[arg(long)]
+        no_remote_imports: bool,
     },
     /// Parse a scenario file and perform a dry-run (plan + placeholder execution)
     Run {
@@ -47,8 +97,110 @@ enum Command {
         /// Override a secret (format: key=value). Repeat for multiple overrides.
         #[arg(long = "secret", value_parser = parse_key_val, value_name = "KEY=VALUE", action = ArgAction::Append)]
         secrets: Vec<(String, String)>,
+        /// Load variable overrides from a JSON or YAML file. Later files win; --var wins over files.
+        #[arg(long = "var-file", value_name = "PATH", action = ArgAction::Append)]
+        var_files: Vec<PathBuf>,
+        /// Load secret overrides from a JSON file of string values. --secret wins over files.
+        #[arg(long = "secret-file", value_name = "PATH", action = ArgAction::Append)]
+        secret_files: Vec<PathBuf>,
+        /// Resolve variables and control flow without running scans, scripts, notifications, sleeps, or reports
+        #[arg(long)]
+        dry_run: bool,
+        /// Maximum iterations per loop unless the loop sets `option max_iter`
+        #[arg(long = "max-iter", value_name = "N", default_value_t = DEFAULT_MAX_ITERATIONS)]
+        max_iter: usize,
+        /// Directory for artifacts and report files, created if missing
+        #[arg(
+            long,
+            value_name = "DIR",
+            env = "AXION_ARTIFACTS_DIR",
+            default_value = "artifacts"
+        )]
+        artifacts_dir: PathBuf,
+        /// Gzip artifact JSON, writing `<label>.json.gz` files
+        #[arg(long)]
+        compress_artifacts: bool,
+        /// Store artifacts under `artifacts/cas/<sha256>.json`, linked from their label
+        #[arg(long)]
+        content_addressable: bool,
+        /// Compare the run against a previous `run --json` output or execution report
+        #[arg(long, value_name = "FILE")]
+        baseline: Option<PathBuf>,
+        /// Reject imports that point at http:// or https:// URLs
+        #[arg(long)]
+        no_remote_imports: bool,
+        /// Resume from a checkpoint file (`artifacts/checkpoints/<name>.json`),
+        /// skipping steps that already completed
+        #[arg(long, value_name = "PATH")]
+        resume: Option<PathBuf>,
+        /// Read `keyring` secrets from `AXION_KEYRING_<SERVICE>_<ACCOUNT>`
+        /// environment variables instead of the OS keychain
+        #[arg(long)]
+        keyring_mock_env: bool,
+        /// Run only steps tagged with one of these tags (comma-separated)
+        #[arg(long, value_name = "TAGS", value_delimiter = ',')]
+        only_tags: Vec<String>,
+        /// Skip steps tagged with any of these tags (comma-separated)
+        #[arg(long, value_name = "TAGS", value_delimiter = ',')]
+        skip_tags: Vec<String>,
+    },
+    /// Re-plan (or re-run with --run) a scenario whenever it or a local
+    /// import changes
+    Watch {
+        /// Path to the Axion DSL scenario file
+        input: PathBuf,
+        /// Execute the scenario on each change instead of a dry run
+        #[arg(long)]
+        run: bool,
+        /// Directory for artifacts and report files (with --run)
+        #[arg(
+            long,
+            value_name = "DIR",
+            env = "AXION_ARTIFACTS_DIR",
+            default_value = "artifacts"
+        )]
+        artifacts_dir: PathBuf,
+        /// Reject imports that point at http:// or https:// URLs
+        #[arg(long)]
+        no_remote_imports: bool,
+    },
+    /// Compare two execution reports (JSON from `run --json`)
+    Diff {
+        /// Baseline report
+        baseline: PathBuf,
+        /// Report to compare against the baseline
+        current: PathBuf,
+        /// Output JSON instead of a diff listing
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write a skeleton scenario to `<name>.ax`
+    Init {
+        /// Scenario name, used for the file and report names
+        #[arg(long, default_value = "scenario")]
+        name: String,
+        /// Builtin tool to scaffold a step for; without it every builtin tool
+        /// is listed as a commented-out example
+        #[arg(long)]
+        tool: Option<String>,
+        /// Overwrite the file if it already exists
+        #[arg(long)]
+        force: bool,
     },
-    /// Export builtin tool schemas
+    /// Rewrite scenario files in canonical formatting, keeping comments
+    /// between steps
+    Fmt {
+        /// Scenario files or glob patterns such as `examples/**/*.ax`
+        #[arg(required = true)]
+        inputs: Vec<String>,
+        /// Fail if any file is not already formatted, without modifying it
+        #[arg(long, conflicts_with = "diff")]
+        check: bool,
+        /// Print a unified diff of the changes instead of writing them
+        #[arg(long)]
+        diff: bool,
+    },
+    /// Export builtin and plugin tool schemas
     Schema {
         /// Filter by tool name
         #[arg(long)]
@@ -56,11 +208,23 @@ enum Command {
         /// Output format
         #[arg(long, default_value_t = SchemaFormat::Json)]
         format: SchemaFormat,
+        /// Emit a JSON Schema (draft 2020-12) document with one definition per tool
+        #[arg(long)]
+        json_schema: bool,
+    },
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate the script for
+        shell: clap_complete::Shell,
     },
 }
 
 fn main() -> anyhow::Result<()> {
     let cli = AxionCli::parse();
+    COLOR.store(color_enabled(cli.no_color), Ordering::Relaxed);
+    OFFLINE_IMPORTS.store(cli.offline_imports, Ordering::Relaxed);
+    init_logging(cli.verbose, cli.quiet, cli.no_color);
+    let plugin_schemas = load_schema_plugins(&cli.schema_plugins)?;
 
     match cli.command {
         Command::Plan {
@@ -68,81 +232,627 @@ fn main() -> anyhow::Result<()> {
             json,
             vars,
             secrets,
+            var_files,
+            secret_files,
+            no_remote_imports,
+            no_info,
         } => {
-            let scenario = load_scenario(&input)?;
-            let overrides = parse_overrides(vars)?;
-            let secret_overrides = parse_secret_overrides(secrets)?;
-            let diagnostics = validate_scenario(&scenario);
+            let scenario = match load_scenario(&input, !no_remote_imports) {
+                Ok(scenario) => scenario,
+                Err(err) => match err.downcast_ref::<ScenarioParseError>() {
+                    Some(parse_error) => {
+                        output_parse_error(parse_error, json)?;
+                        anyhow::bail!("validation failed");
+                    }
+                    None => return Err(err),
+                },
+            };
+            let overrides = parse_overrides(vars, &var_files)?;
+            let secret_overrides = parse_secret_overrides(secrets, &secret_files)?;
+            let mut diagnostics =
+                validate_scenario_with_schemas(&scenario, &overrides, &plugin_schemas);
+            if no_info {
+                diagnostics.retain(|diagnostic| !diagnostic.is_info());
+            }
             let summary = scenario.summary();
-            let has_errors =
-                output_plan(summary, &diagnostics, json, &overrides, &secret_overrides)?;
+            let dry_run = if diagnostics.iter().any(Diagnostic::is_error) {
+                None
+            } else {
+                Some(Executor::new().dry_run(&scenario, &overrides, &secret_overrides))
+            };
+            let has_errors = output_plan(
+                summary,
+                &diagnostics,
+                dry_run.as_ref(),
+                json,
+                &overrides,
+                &secret_overrides,
+            )?;
             if has_errors {
                 anyhow::bail!("validation failed");
             }
         }
+        Command::Validate {
+            input,
+            format,
+            strict,
+            no_remote_imports,
+        } => {
+            let json = matches!(format, ValidateFormat::Json);
+            let scenario = match load_scenario(&input, !no_remote_imports) {
+                Ok(scenario) => scenario,
+                Err(err) => match err.downcast_ref::<ScenarioParseError>() {
+                    Some(parse_error) => {
+                        output_parse_error(parse_error, json)?;
+                        anyhow::bail!("validation failed");
+                    }
+                    None => return Err(err),
+                },
+            };
+            let source = fs::read_to_string(&input)?;
+            let diagnostics =
+                validate_scenario_with_schemas(&scenario, &HashMap::new(), &plugin_schemas);
+            if output_validation(&input, &source, &diagnostics, json, strict)? {
+                if strict {
+                    anyhow::bail!("validation failed (warnings treated as errors)");
+                }
+                anyhow::bail!("validation failed");
+            }
+        }
         Command::Run {
             input,
             json,
             vars,
             secrets,
+            var_files,
+            secret_files,
+            dry_run,
+            max_iter,
+            artifacts_dir,
+            compress_artifacts,
+            content_addressable,
+            baseline,
+            no_remote_imports,
+            resume,
+            keyring_mock_env,
+            only_tags,
+            skip_tags,
         } => {
-            let scenario = load_scenario(&input)?;
-            let overrides = parse_overrides(vars)?;
-            let secret_overrides = parse_secret_overrides(secrets)?;
+            let scenario = load_scenario(&input, !no_remote_imports)?;
+            let overrides = parse_overrides(vars, &var_files)?;
+            let secret_overrides = parse_secret_overrides(secrets, &secret_files)?;
+            // Load the baseline up front so a bad path fails before the run.
+            let baseline = baseline.map(|path| load_report(&path)).transpose()?;
             let summary = scenario.summary();
-            let executor = Executor::new();
-            let outcome = executor.execute_with_vars(&scenario, &overrides, &secret_overrides);
-            output_run(summary, outcome, json, &overrides, &secret_overrides)?;
+            if !dry_run {
+                fs::create_dir_all(&artifacts_dir).map_err(|err| {
+                    anyhow!(
+                        "failed to create artifacts directory {}: {err}",
+                        artifacts_dir.display()
+                    )
+                })?;
+            }
+            let mut executor = Executor::new()
+                .with_artifacts_dir(artifacts_dir)
+                .with_max_iterations(max_iter)
+                .with_compressed_artifacts(compress_artifacts)
+                .with_content_addressable(content_addressable)
+                .with_keyring_mock_env(keyring_mock_env)
+                .with_only_tags(only_tags)
+                .with_skip_tags(skip_tags)
+                .with_scenario_path(&input);
+            if let Some(path) = resume {
+                executor =
+                    executor.with_resume(Checkpoint::load(&path).map_err(|err| anyhow!(err))?);
+            }
+            let outcome = if dry_run {
+                executor.dry_run(&scenario, &overrides, &secret_overrides)
+            } else {
+                executor.execute_with_vars(&scenario, &overrides, &secret_overrides)
+            };
+            let diff = baseline.map(|baseline| ExecutionReport::diff(&baseline, &outcome.report));
+            output_run(
+                summary,
+                outcome,
+                diff.as_ref(),
+                json,
+                &overrides,
+                &secret_overrides,
+            )?;
+        }
+        Command::Watch {
+            input,
+            run,
+            artifacts_dir,
+            no_remote_imports,
+        } => {
+            watch_scenario(
+                &input,
+                run,
+                artifacts_dir,
+                !no_remote_imports,
+                &plugin_schemas,
+            )?;
+        }
+        Command::Diff {
+            baseline,
+            current,
+            json,
+        } => {
+            let diff = ExecutionReport::diff(&load_report(&baseline)?, &load_report(&current)?);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print!("{diff}");
+            }
+        }
+        Command::Fmt {
+            inputs,
+            check,
+            diff,
+        } => {
+            let mut unformatted = Vec::new();
+            let mut refused = 0;
+            for input in expand_fmt_inputs(&inputs)? {
+                let source = fs::read_to_string(&input)
+                    .map_err(|err| anyhow!("failed to read {}: {err}", input.display()))?;
+                let scenario = match parse_scenario_all(&source) {
+                    Ok(scenario) => scenario,
+                    Err(errors) => {
+                        let parse_error = ScenarioParseError {
+                            file: input.display().to_string(),
+                            errors,
+                        };
+                        output_parse_error(&parse_error, false)?;
+                        anyhow::bail!("failed to parse {}", input.display());
+                    }
+                };
+                let formatted = scenario.to_dsl();
+                if formatted == source {
+                    continue;
+                }
+                let dropped = dropped_comments(&source, &formatted);
+                if !dropped.is_empty() {
+                    tracing::warn!(
+                        "formatting {} would drop {} comment(s), starting with `{}`",
+                        input.display(),
+                        dropped.len(),
+                        dropped[0]
+                    );
+                }
+                if check {
+                    println!("{}", input.display());
+                    unformatted.push(input);
+                } else if diff {
+                    let name = input.display().to_string();
+                    print!(
+                        "{}",
+                        similar::TextDiff::from_lines(&source, &formatted)
+                            .unified_diff()
+                            .header(&name, &name)
+                    );
+                } else if !dropped.is_empty() {
+                    refused += 1;
+                } else {
+                    fs::write(&input, formatted)
+                        .map_err(|err| anyhow!("failed to write {}: {err}", input.display()))?;
+                }
+            }
+            if !unformatted.is_empty() {
+                anyhow::bail!("{} file(s) are not formatted", unformatted.len());
+            }
+            if refused > 0 {
+                anyhow::bail!(
+                    "left {refused} file(s) unchanged; move the comments above their step and rerun"
+                );
+            }
+        }
+        Command::Init { name, tool, force } => {
+            let scenario = render_init_template(&name, tool.as_deref())?;
+            let path = PathBuf::from(format!("{name}.ax"));
+            if path.exists() && !force {
+                anyhow::bail!(
+                    "{} already exists; pass --force to overwrite it",
+                    path.display()
+                );
+            }
+            fs::write(&path, scenario)?;
+            println!("Wrote {}", path.display());
+        }
+        Command::Schema {
+            tool,
+            format,
+            json_schema,
+        } => {
+            output_schema(tool, format, json_schema, &plugin_schemas)?;
         }
-        Command::Schema { tool, format } => {
-            output_schema(tool, format)?;
+        Command::Completions { shell } => {
+            let mut command = completion_command();
+            clap_complete::generate(shell, &mut command, "axion", &mut std::io::stdout());
         }
     }
 
     Ok(())
 }
 
-fn load_scenario(path: &PathBuf) -> anyhow::Result<Scenario> {
-    let mut visited = HashSet::new();
-    load_scenario_recursive(path, &mut visited)
+// The CLI definition with builtin tool names attached to `--tool`, so the
+// generated script completes them. Parsing keeps accepting any string and
+// reports unknown tools itself.
+fn completion_command() -> clap::Command {
+    let tools: Vec<String> = builtin_tool_schema_bundle()
+        .tools
+        .into_iter()
+        .map(|schema| schema.name)
+        .collect();
+    let with_tools =
+        |arg: clap::Arg| arg.value_parser(clap::builder::PossibleValuesParser::new(tools.clone()));
+    AxionCli::command()
+        .mut_subcommand("schema", |command| command.mut_arg("tool", with_tools))
+        .mut_subcommand("init", |command| command.mut_arg("tool", with_tools))
+}
+
+const INIT_TEMPLATE: &str = include_str!("../templates/init.ax");
+
+fn render_init_template(name: &str, tool: Option<&str>) -> anyhow::Result<String> {
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        || name.is_empty()
+    {
+        anyhow::bail!("invalid scenario name '{name}': use letters, digits, '_' or '-'");
+    }
+    let schemas = builtin_tool_schema_bundle().tools;
+    let (steps, includes) = match tool {
+        Some(tool) => {
+            let schema = schemas
+                .iter()
+                .find(|schema| schema.name == tool)
+                .ok_or_else(|| {
+                    let known: Vec<&str> =
+                        schemas.iter().map(|schema| schema.name.as_str()).collect();
+                    anyhow!(
+                        "unknown tool '{tool}' (builtin tools: {})",
+                        known.join(", ")
+                    )
+                })?;
+            let (step, artifact) = init_step(schema);
+            (step, format!("  include {artifact}\n"))
+        }
+        None => {
+            let mut steps = String::from(
+                "# Uncomment a step below and add its artifact to the report with\n# `include <artifact>`.\n",
+            );
+            for schema in &schemas {
+                let (step, artifact) = init_step(schema);
+                steps.push_str(&format!("#\n# Produces `{artifact}`.\n"));
+                for line in step.lines() {
+                    steps.push_str(&format!("# {line}\n"));
+                }
+            }
+            (steps, String::new())
+        }
+    };
+    Ok(INIT_TEMPLATE
+        .replace("{{steps}}", &steps)
+        .replace("{{includes}}", &includes)
+        .replace("{{file}}", &format!("{name}.ax"))
+        .replace("{{name}}", &name.replace('-', "_")))
 }
 
-fn load_scenario_recursive(
-    path: &Path,
-    visited: &mut HashSet<PathBuf>,
-) -> anyhow::Result<Scenario> {
-    let canonical = fs::canonicalize(path)?;
-    if !visited.insert(canonical.clone()) {
-        return Ok(Scenario::default());
+/// A step for `schema` with its required parameters filled in, and the
+/// artifact it produces.
+fn init_step(schema: &ToolSchema) -> (String, String) {
+    let mut lines = Vec::new();
+    if let Some(description) = &schema.description {
+        lines.push(format!("// {description}"));
     }
+    let step_name = format!("{}_scan", schema.name);
+    let artifact = if schema.kind.as_deref() == Some("script") {
+        lines.push(format!("script {step_name} {{"));
+        format!("script_{step_name}")
+    } else {
+        lines.push(format!("scan {step_name} {} {{", schema.name));
+        // Tools without a findings parser store their raw output instead.
+        match schema.name.as_str() {
+            "nmap" | "gobuster" | "masscan" | "nuclei" => format!("findings_{step_name}"),
+            _ => format!("scan_{step_name}"),
+        }
+    };
+    for param in &schema.required {
+        let value = match param.as_str() {
+            "target" => "${target}",
+            "run" => "echo scanning ${target}",
+            "args" => "dir -w wordlist.txt",
+            "templates" => "http/cves/",
+            "rate" => "1000",
+            "url" => "https://${target}/FUZZ",
+            "wordlist" => "wordlist.txt",
+            "domain" => "${target}",
+            _ => "TODO",
+        };
+        lines.push(format!("  {param} \"{value}\""));
+    }
+    // Without these the runtime cannot parse findings and validation warns.
+    let flags = match schema.name.as_str() {
+        "masscan" => Some("-p1-1000 --output-format xml"),
+        "nuclei" => Some("-jsonl"),
+        _ => None,
+    };
+    if let Some(flags) = flags {
+        lines.push(format!("  flags \"{flags}\""));
+    }
+    lines.push("}".to_string());
+    (lines.join("\n") + "\n", artifact)
+}
 
-    let content = fs::read_to_string(&canonical)?;
-    let parsed = parse_scenario(&content)?;
-    let base_dir = canonical.parent().unwrap_or_else(|| Path::new(""));
+// Quiet period after a change before re-running, so an editor's burst of
+// write/rename events triggers a single run.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(100);
 
-    let mut steps = Vec::new();
-    let mut imports = Vec::new();
+enum WatchSignal {
+    Changed(Vec<PathBuf>),
+    Interrupted,
+}
 
-    for step in parsed.steps {
-        match step {
-            Step::Import(import_step) => {
-                let import_path = base_dir.join(&import_step.path);
-                let import_display = import_path.to_string_lossy().to_string();
-                let imported = load_scenario_recursive(&import_path, visited)?;
-                imports.push(import_display);
-                steps.extend(imported.steps);
-                imports.extend(imported.imports);
+fn watch_scenario(
+    input: &Path,
+    execute: bool,
+    artifacts_dir: PathBuf,
+    allow_remote: bool,
+    plugin_schemas: &[ToolSchema],
+) -> anyhow::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let interrupt = sender.clone();
+    ctrlc::set_handler(move || {
+        let _ = interrupt.send(WatchSignal::Interrupted);
+    })?;
+    // Editors often save by writing a new file and renaming it over the old
+    // one, so watch the parent directories and filter by path.
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            if !event.kind.is_access() {
+                let _ = sender.send(WatchSignal::Changed(event.paths));
+            }
+        }
+    })?;
+    let mut watched_dirs = HashSet::new();
+    let mut previous = None;
+
+    loop {
+        print!("\x1b[2J\x1b[H");
+        let (report, files) =
+            watch_iteration(input, execute, &artifacts_dir, allow_remote, plugin_schemas);
+        if let Some(report) = report {
+            if let Some(previous) = &previous {
+                println!(
+                    "\nChanges since the previous run:\n{}",
+                    ExecutionReport::diff(previous, &report)
+                );
+            }
+            previous = Some(report);
+        }
+
+        for dir in files.iter().filter_map(|file| file.parent()) {
+            if watched_dirs.insert(dir.to_path_buf()) {
+                watcher.watch(dir, notify::RecursiveMode::NonRecursive)?;
+            }
+        }
+        println!(
+            "[watch] waiting for changes to {} file(s); press Ctrl-C to stop",
+            files.len()
+        );
+
+        loop {
+            match receiver.recv() {
+                Ok(WatchSignal::Changed(paths)) => {
+                    if paths.iter().any(|path| files.contains(path)) {
+                        break;
+                    }
+                }
+                Ok(WatchSignal::Interrupted) | Err(_) => return Ok(()),
+            }
+        }
+        loop {
+            match receiver.recv_timeout(WATCH_DEBOUNCE) {
+                Ok(WatchSignal::Changed(_)) => continue,
+                Ok(WatchSignal::Interrupted) => return Ok(()),
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
             }
-            other => steps.push(other),
         }
     }
+}
 
-    Ok(Scenario { steps, imports })
+/// One `watch` pass: load, validate, and plan or run the scenario. Returns
+/// the report (if it got that far) and the local files to watch next.
+fn watch_iteration(
+    input: &Path,
+    execute: bool,
+    artifacts_dir: &Path,
+    allow_remote: bool,
+    plugin_schemas: &[ToolSchema],
+) -> (Option<ExecutionReport>, HashSet<PathBuf>) {
+    let mut files =
+        HashSet::from([fs::canonicalize(input).unwrap_or_else(|_| input.to_path_buf())]);
+    let scenario = match load_scenario(input, allow_remote) {
+        Ok(scenario) => scenario,
+        Err(err) => {
+            match err.downcast_ref::<ScenarioParseError>() {
+                Some(parse_error) => {
+                    let _ = output_parse_error(parse_error, false);
+                }
+                None => eprintln!("Error: {err}"),
+            }
+            return (None, files);
+        }
+    };
+    files.extend(
+        scenario
+            .imports
+            .iter()
+            .filter(|import| !import.contains("://"))
+            .map(PathBuf::from),
+    );
+
+    let diagnostics = validate_scenario_with_schemas(&scenario, &HashMap::new(), plugin_schemas);
+    print_diagnostics(&diagnostics);
+    if diagnostics.iter().any(Diagnostic::is_error) {
+        return (None, files);
+    }
+
+    let executor = Executor::new()
+        .with_artifacts_dir(artifacts_dir)
+        .with_scenario_path(input);
+    let outcome = if execute {
+        executor.execute_with_vars(&scenario, &HashMap::new(), &HashMap::new())
+    } else {
+        executor.dry_run(&scenario, &HashMap::new(), &HashMap::new())
+    };
+    let heading = if execute { "Run" } else { "Dry run" };
+    println!("{heading}:\n{}", paint_report(&outcome.report));
+    (Some(outcome.report), files)
+}
+
+// Expands glob patterns itself so `**` works in shells without globstar.
+// Plain paths are passed through; a pattern that matches nothing is an error.
+fn expand_fmt_inputs(inputs: &[String]) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if !input.contains(['*', '?', '[']) {
+            files.push(PathBuf::from(input));
+            continue;
+        }
+        let paths =
+            glob::glob(input).map_err(|err| anyhow!("invalid glob pattern {input}: {err}"))?;
+        let before = files.len();
+        for path in paths {
+            let path = path.map_err(|err| anyhow!("failed to read {input}: {err}"))?;
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+        if files.len() == before {
+            anyhow::bail!("no files match {input}");
+        }
+    }
+    let mut seen = HashSet::new();
+    files.retain(|path| seen.insert(path.clone()));
+    Ok(files)
+}
+
+// Comment lines of `source` that `formatted` no longer contains. Comments
+// between steps and inside scan, script, report and asset group blocks are
+// kept; those in other blocks (notify, assert, transform, secret, or a nested
+// `env`/`retry` block) are not, so `fmt` refuses to write such a file.
+fn dropped_comments<'a>(source: &'a str, formatted: &str) -> Vec<&'a str> {
+    let is_comment = |line: &&str| line.starts_with('#') || line.starts_with("//");
+    let mut kept: HashMap<&str, usize> = HashMap::new();
+    for line in formatted.lines().map(str::trim).filter(is_comment) {
+        *kept.entry(line).or_default() += 1;
+    }
+    source
+        .lines()
+        .map(str::trim)
+        .filter(is_comment)
+        .filter(|line| match kept.get_mut(line) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+fn load_schema_plugins(paths: &[PathBuf]) -> anyhow::Result<Vec<ToolSchema>> {
+    let mut schemas: Vec<ToolSchema> = Vec::new();
+    for path in paths {
+        let source = fs::read_to_string(path)
+            .map_err(|err| anyhow!("failed to read schema plugin {}: {err}", path.display()))?;
+        let loaded =
+            parse_schema_plugin(&source).map_err(|err| anyhow!("{}: {err}", path.display()))?;
+        // A later file replaces an earlier file's schema of the same name.
+        schemas.retain(|schema| !loaded.iter().any(|new| new.name == schema.name));
+        schemas.extend(loaded);
+    }
+    Ok(schemas)
+}
+
+// Accepts `run --json` output, a serialised `ExecutionOutcome`, or a bare
+// `ExecutionReport`.
+fn load_report(path: &Path) -> anyhow::Result<ExecutionReport> {
+    let source = fs::read_to_string(path)
+        .map_err(|err| anyhow!("failed to read report {}: {err}", path.display()))?;
+    let mut value: serde_json::Value = serde_json::from_str(&source)
+        .map_err(|err| anyhow!("failed to parse report {}: {err}", path.display()))?;
+    for key in ["execution", "report"] {
+        if let Some(inner) = value.get_mut(key) {
+            value = inner.take();
+            break;
+        }
+    }
+    serde_json::from_value(value)
+        .map_err(|err| anyhow!("{} is not an execution report: {err}", path.display()))
+}
+
+/// A scenario or import that failed to parse. Each error is shown as
+/// `file:line:col: message`.
+#[derive(Debug)]
+struct ScenarioParseError {
+    file: String,
+    errors: Vec<ParseError>,
+}
+
+impl ScenarioParseError {
+    fn location(&self, error: &ParseError) -> String {
+        match error.location() {
+            Some(Location {
+                line,
+                column: Some(column),
+            }) => format!("{}:{line}:{column}", self.file),
+            Some(Location { line, column: None }) => format!("{}:{line}", self.file),
+            None => self.file.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for ScenarioParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, error) in self.errors.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}: {}", self.location(error), error.inner())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ScenarioParseError {}
+
+fn output_parse_error(parse_error: &ScenarioParseError, json: bool) -> anyhow::Result<()> {
+    let diagnostics: Vec<Diagnostic> = parse_error
+        .errors
+        .iter()
+        .map(|error| Diagnostic {
+            level: DiagnosticLevel::Error,
+            location: Some(parse_error.location(error)),
+            message: error.inner().to_string(),
+        })
+        .collect();
+    if json {
+        let payload = json!({ "diagnostics": diagnostics });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        print_diagnostics(&diagnostics);
+    }
+    Ok(())
 }
 
 fn output_plan(
     summary: ScenarioSummary,
     diagnostics: &[Diagnostic],
+    dry_run: Option<&ExecutionOutcome>,
     json: bool,
     overrides: &HashMap<String, LiteralValue>,
     secret_overrides: &HashMap<String, String>,
@@ -158,6 +868,7 @@ fn output_plan(
         let payload = json!({
             "summary": summary,
             "diagnostics": diagnostics,
+            "dry_run": dry_run.map(|outcome| &outcome.report),
             "overrides": overrides,
             "secrets": masked_secrets,
         });
@@ -182,14 +893,104 @@ fn output_plan(
                 println!("  - {} = ***", key);
             }
         }
+        if let Some(outcome) = dry_run {
+            println!("\nDry run:\n{}", paint_report(&outcome.report));
+        }
     }
 
     Ok(has_errors)
 }
 
+/// Prints `validate` diagnostics and reports whether the scenario fails,
+/// counting warnings as failures under `--strict`.
+fn output_validation(
+    input: &Path,
+    source: &str,
+    diagnostics: &[Diagnostic],
+    json: bool,
+    strict: bool,
+) -> anyhow::Result<bool> {
+    let errors = diagnostics.iter().filter(|diag| diag.is_error()).count();
+    let warnings = diagnostics.iter().filter(|diag| diag.is_warning()).count();
+    let lines: Vec<Option<usize>> = diagnostics
+        .iter()
+        .map(|diag| {
+            diag.location
+                .as_deref()
+                .and_then(|location| step_line(source, location))
+        })
+        .collect();
+
+    if json {
+        let located: Vec<_> = diagnostics
+            .iter()
+            .zip(&lines)
+            .map(|(diagnostic, line)| {
+                json!({
+                    "level": diagnostic.level,
+                    "location": diagnostic.location,
+                    "line": line,
+                    "message": diagnostic.message,
+                })
+            })
+            .collect();
+        let payload = json!({
+            "file": input.display().to_string(),
+            "diagnostics": located,
+            "errors": errors,
+            "warnings": warnings,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        let located: Vec<Diagnostic> = diagnostics
+            .iter()
+            .zip(&lines)
+            .map(|(diagnostic, line)| {
+                let mut diagnostic = diagnostic.clone();
+                if let (Some(location), Some(line)) = (&diagnostic.location, line) {
+                    diagnostic.location = Some(format!("{}:{line}: {location}", input.display()));
+                }
+                diagnostic
+            })
+            .collect();
+        print_diagnostics(&located);
+        println!(
+            "{}: {errors} error{}, {warnings} warning{}",
+            input.display(),
+            if errors == 1 { "" } else { "s" },
+            if warnings == 1 { "" } else { "s" }
+        );
+    }
+
+    Ok(errors > 0 || (strict && warnings > 0))
+}
+
+// Validation locations are step label paths ("for host > script probe").
+// Finds each label in turn as a line prefix, returning the 1-based line of the
+// innermost one found; steps pulled in through imports have no line here.
+fn step_line(source: &str, location: &str) -> Option<usize> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut start = 0;
+    let mut found = None;
+    for label in location.split(" > ") {
+        let Some(offset) = lines[start..].iter().position(|line| {
+            line.trim_start().strip_prefix(label).is_some_and(|rest| {
+                rest.is_empty() || rest.starts_with(|c: char| c.is_whitespace() || c == '{')
+            })
+        }) else {
+            break;
+        };
+        start += offset;
+        found = Some(start + 1);
+        start += 1;
+    }
+    found
+}
+
 fn output_run(
     summary: ScenarioSummary,
     outcome: ExecutionOutcome,
+    diff: Option<&ExecutionDiff>,
     json: bool,
     overrides: &HashMap<String, LiteralValue>,
     secret_overrides: &HashMap<String, String>,
@@ -200,13 +1001,19 @@ fn output_run(
             .cloned()
             .map(|key| (key, "***".to_string()))
             .collect();
-        let payload = json!({
+        let mut payload = json!({
             "summary": summary,
             "execution": outcome.report,
+            "status_summary": outcome.summary,
+            "secret_warnings": outcome.secret_warnings,
+            "secret_usage": outcome.secret_usage,
             "artifacts": outcome.artifacts,
             "overrides": overrides,
             "secrets": masked_secrets,
         });
+        if let Some(diff) = diff {
+            payload["diff"] = serde_json::to_value(diff)?;
+        }
         println!("{}", serde_json::to_string_pretty(&payload)?);
     } else {
         println!("{summary}\n");
@@ -226,10 +1033,27 @@ fn output_run(
             }
             println!();
         }
-        println!("{}", outcome.report);
+        println!("{}", paint_report(&outcome.report));
+        if !outcome.summary.is_empty() {
+            let counts: Vec<String> = outcome
+                .summary
+                .iter()
+                .map(|(status, count)| format!("{count} {status}"))
+                .collect();
+            println!("Summary: {}", counts.join(", "));
+        }
         if outcome.report.has_failures() {
             println!("\n[warn] some steps failed");
         }
+        if !outcome.secret_usage.is_empty() {
+            println!("\nSecrets accessed:");
+            for name in &outcome.secret_usage {
+                println!("  - {name}");
+            }
+        }
+        for name in &outcome.secret_warnings {
+            println!("[warn] secret '{name}' expired during execution");
+        }
         if !outcome.artifacts.is_empty() {
             println!("\nArtifacts:");
             for StoredArtifact {
@@ -242,10 +1066,29 @@ fn output_run(
                 }
             }
         }
+        if let Some(diff) = diff {
+            print!("\n{diff}");
+        }
     }
     Ok(())
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ValidateFormat {
+    Text,
+    Json,
+}
+
+impl std::fmt::Display for ValidateFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            ValidateFormat::Text => "text",
+            ValidateFormat::Json => "json",
+        };
+        write!(f, "{value}")
+    }
+}
+
 #[derive(Debug, Clone, Copy, ValueEnum)]
 enum SchemaFormat {
     Json,
@@ -269,21 +1112,29 @@ struct SchemaResponse {
     tools: Vec<ToolSchema>,
 }
 
-fn output_schema(tool: Option<String>, format: SchemaFormat) -> anyhow::Result<()> {
-    let bundle = builtin_tool_schema_bundle();
-    let mut tools = bundle.tools;
+fn output_schema(
+    tool: Option<String>,
+    format: SchemaFormat,
+    json_schema: bool,
+    plugin_schemas: &[ToolSchema],
+) -> anyhow::Result<()> {
+    let mut bundle = tool_schema_bundle(plugin_schemas);
 
     if let Some(filter) = tool {
-        tools.retain(|schema| schema.name == filter);
-        if tools.is_empty() {
+        bundle.tools.retain(|schema| schema.name == filter);
+        if bundle.tools.is_empty() {
             anyhow::bail!("unknown tool '{filter}'");
         }
     }
 
-    let response = SchemaResponse {
-        version: bundle.version,
-        generated_at: bundle.generated_at,
-        tools,
+    let response = if json_schema {
+        tool_json_schema(&bundle)
+    } else {
+        serde_json::to_value(SchemaResponse {
+            version: bundle.version,
+            generated_at: bundle.generated_at,
+            tools: bundle.tools,
+        })?
     };
 
     match format {
@@ -307,8 +1158,9 @@ fn print_diagnostics(diagnostics: &[Diagnostic]) {
     println!("Diagnostics:");
     for diagnostic in diagnostics {
         let level = match diagnostic.level {
-            DiagnosticLevel::Error => "error",
-            DiagnosticLevel::Warning => "warn",
+            DiagnosticLevel::Error => paint("error", RED),
+            DiagnosticLevel::Warning => paint("warn", YELLOW),
+            DiagnosticLevel::Info => paint("info", CYAN),
         };
         match &diagnostic.location {
             Some(location) => println!("  - [{level}] {location}: {}", diagnostic.message),
@@ -318,6 +1170,92 @@ fn print_diagnostics(diagnostics: &[Diagnostic]) {
     println!();
 }
 
+// Set once from `--no-color`, NO_COLOR, and TTY detection. JSON output never
+// goes through `paint`, so it stays free of escape sequences.
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+const RED: &str = "31";
+const GREEN: &str = "32";
+const YELLOW: &str = "33";
+const CYAN: &str = "36";
+const GREY: &str = "90";
+
+fn color_enabled(no_color: bool) -> bool {
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    !no_color && !no_color_env && supports_color::on(supports_color::Stream::Stdout).is_some()
+}
+
+// Runtime warnings and step spans go to stderr; warnings show by default.
+fn init_logging(verbose: bool, quiet: bool, no_color: bool) {
+    let level = if verbose {
+        tracing::Level::DEBUG
+    } else if quiet {
+        tracing::Level::ERROR
+    } else {
+        tracing::Level::WARN
+    };
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let ansi =
+        !no_color && !no_color_env && supports_color::on(supports_color::Stream::Stderr).is_some();
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .with_ansi(ansi)
+        .without_time()
+        .init();
+}
+
+fn paint(text: &str, code: &str) -> String {
+    if COLOR.load(Ordering::Relaxed) {
+        format!("\x1b[{code}m{text}\x1b[0m")
+    } else {
+        text.to_string()
+    }
+}
+
+// Colors the `[status]` tag on each step line of the report's text rendering.
+fn paint_report(report: &ExecutionReport) -> String {
+    let text = report.to_string();
+    if !COLOR.load(Ordering::Relaxed) {
+        return text;
+    }
+    let mut out = String::with_capacity(text.len());
+    for line in text.lines() {
+        let painted = [("completed", GREEN), ("failed", RED), ("skipped", GREY)]
+            .into_iter()
+            .find_map(|(status, code)| {
+                let rest = line.strip_prefix(&format!("  - [{status}]"))?;
+                Some(format!("  - [{}]{rest}", paint(status, code)))
+            });
+        out.push_str(painted.as_deref().unwrap_or(line));
+        out.push('\n');
+    }
+    out
+}
+
+/// Reads a `--var-file`/`--secret-file` mapping. `.yaml`/`.yml` files are
+/// parsed as YAML, everything else as JSON.
+fn read_override_file(path: &Path) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| anyhow!("failed to read {}: {err}", path.display()))?;
+    let is_yaml = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+    let document: serde_json::Value = if is_yaml {
+        serde_yaml::from_str(&contents)
+            .map_err(|err| anyhow!("invalid YAML in {}: {err}", path.display()))?
+    } else {
+        serde_json::from_str(&contents)
+            .map_err(|err| anyhow!("invalid JSON in {}: {err}", path.display()))?
+    };
+    match document {
+        serde_json::Value::Object(map) => Ok(map),
+        _ => anyhow::bail!("{} must contain a top-level object", path.display()),
+    }
+}
+
 fn parse_key_val(s: &str) -> Result<(String, String), String> {
     let parts: Vec<&str> = s.splitn(2, '=').collect();
     if parts.len() != 2 || parts[0].trim().is_empty() {
@@ -326,8 +1264,24 @@ fn parse_key_val(s: &str) -> Result<(String, String), String> {
     Ok((parts[0].trim().to_string(), parts[1].to_string()))
 }
 
-fn parse_overrides(vars: Vec<(String, String)>) -> anyhow::Result<HashMap<String, LiteralValue>> {
+fn parse_overrides(
+    vars: Vec<(String, String)>,
+    var_files: &[PathBuf],
+) -> anyhow::Result<HashMap<String, LiteralValue>> {
     let mut map = HashMap::new();
+    for path in var_files {
+        for (key, value) in read_override_file(path)? {
+            // Strings go through the literal parser like `--var` values;
+            // anything else is re-encoded as JSON, which it also accepts.
+            let raw = match value {
+                serde_json::Value::String(text) => text,
+                other => other.to_string(),
+            };
+            let literal = axion_core::parse_literal_expression(&raw)
+                .map_err(|err| anyhow!("invalid override {key} in {}: {err}", path.display()))?;
+            map.insert(key, literal);
+        }
+    }
     for (key, raw) in vars {
         let literal = axion_core::parse_literal_expression(&raw)
             .map_err(|err| anyhow!("invalid override {key}: {err}"))?;
@@ -338,8 +1292,17 @@ fn parse_overrides(vars: Vec<(String, String)>) -> anyhow::Result<HashMap<String
 
 fn parse_secret_overrides(
     secrets: Vec<(String, String)>,
+    secret_files: &[PathBuf],
 ) -> anyhow::Result<HashMap<String, String>> {
     let mut map = HashMap::new();
+    for path in secret_files {
+        for (key, value) in read_override_file(path)? {
+            let serde_json::Value::String(value) = value else {
+                anyhow::bail!("secret {key} in {} must be a string value", path.display());
+            };
+            map.insert(key, value);
+        }
+    }
     for (key, value) in secrets {
         map.insert(key, value);
     }
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::BTreeMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Asset {
@@ -10,6 +11,14 @@ pub struct Asset {
     pub hostnames: Vec<String>,
     #[serde(default)]
     pub labels: BTreeMap<String, String>,
+    #[serde(default)]
+    pub os_matches: Vec<OsMatch>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OsMatch {
+    pub name: String,
+    pub accuracy: u8,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +34,16 @@ pub struct Finding {
     pub severity: String,
     #[serde(default)]
     pub evidence: BTreeMap<String, Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cvss_score: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cvss_vector: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cve_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cwe_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,7 +52,160 @@ pub struct ScanArtifacts {
     pub target: String,
     pub assets: Vec<Asset>,
     pub findings: Vec<Finding>,
+    #[serde(default)]
     pub raw_xml: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub raw_output: String,
+}
+
+impl ScanArtifacts {
+    /// Combines two scan results, deduplicating assets and findings by `id`.
+    /// The first occurrence wins on conflicting fields; assets still pick up
+    /// addresses, hostnames, and labels that only the later copy carries.
+    pub fn merge(mut self, other: ScanArtifacts) -> ScanArtifacts {
+        for asset in other.assets {
+            match self
+                .assets
+                .iter_mut()
+                .find(|existing| existing.id == asset.id)
+            {
+                Some(existing) => {
+                    for address in asset.addresses {
+                        if !existing.addresses.contains(&address) {
+                            existing.addresses.push(address);
+                        }
+                    }
+                    for hostname in asset.hostnames {
+                        if !existing.hostnames.contains(&hostname) {
+                            existing.hostnames.push(hostname);
+                        }
+                    }
+                    for (key, value) in asset.labels {
+                        existing.labels.entry(key).or_insert(value);
+                    }
+                    if existing.os_matches.is_empty() {
+                        existing.os_matches = asset.os_matches;
+                    }
+                }
+                None => self.assets.push(asset),
+            }
+        }
+
+        for finding in other.findings {
+            if !self
+                .findings
+                .iter()
+                .any(|existing| existing.id == finding.id)
+            {
+                self.findings.push(finding);
+            }
+        }
+
+        for (mine, theirs) in [
+            (&mut self.raw_xml, other.raw_xml),
+            (&mut self.raw_output, other.raw_output),
+        ] {
+            if mine.is_empty() {
+                *mine = theirs;
+            } else if !theirs.is_empty() {
+                mine.push('\n');
+                mine.push_str(&theirs);
+            }
+        }
+
+        self
+    }
+
+    /// Removes findings that share `asset_id`, `port`, `protocol`, and
+    /// `title`, keeping the most severe copy in the position of the first.
+    /// Returns the number of findings removed.
+    pub fn deduplicate_findings(&mut self) -> usize {
+        self.deduplicate_findings_by(DEFAULT_FINDING_KEY)
+    }
+
+    /// Like [`deduplicate_findings`](Self::deduplicate_findings), keyed on the
+    /// named [`Finding`] fields instead (see [`FINDING_KEY_FIELDS`]). Unknown
+    /// names contribute nothing to the key.
+    pub fn deduplicate_findings_by(&mut self, key_fields: &[&str]) -> usize {
+        let before = self.findings.len();
+        let mut kept: Vec<Finding> = Vec::with_capacity(before);
+        let mut positions: HashMap<Vec<Option<String>>, usize> = HashMap::new();
+        for finding in self.findings.drain(..) {
+            let key: Vec<Option<String>> = key_fields
+                .iter()
+                .map(|field| finding.key_field(field))
+                .collect();
+            match positions.get(&key) {
+                Some(&index) => {
+                    if severity_rank(&finding.severity) > severity_rank(&kept[index].severity) {
+                        kept[index] = finding;
+                    }
+                }
+                None => {
+                    positions.insert(key, kept.len());
+                    kept.push(finding);
+                }
+            }
+        }
+        self.findings = kept;
+        before - self.findings.len()
+    }
+}
+
+/// Maps tool-specific severity labels onto the canonical set used by
+/// findings: `critical`, `high`, `medium`, `low`, and `informational`.
+/// Unrecognised labels (including nuclei's `unknown`) become `informational`.
+pub fn normalize_severity(raw: &str) -> &'static str {
+    match raw.trim().to_lowercase().as_str() {
+        "critical" | "crit" | "severe" | "urgent" | "very high" | "very-high" => "critical",
+        "high" | "important" | "error" | "serious" => "high",
+        "medium" | "moderate" | "med" | "warning" | "warn" => "medium",
+        "low" | "minor" | "note" => "low",
+        _ => "informational",
+    }
+}
+
+/// Fields that [`ScanArtifacts::deduplicate_findings`] compares.
+pub const DEFAULT_FINDING_KEY: &[&str] = &["asset_id", "port", "protocol", "title"];
+
+/// Finding fields that may form a deduplication key.
+pub const FINDING_KEY_FIELDS: &[&str] = &[
+    "id",
+    "asset_id",
+    "port",
+    "protocol",
+    "state",
+    "service",
+    "title",
+    "description",
+    "severity",
+];
+
+impl Finding {
+    fn key_field(&self, field: &str) -> Option<String> {
+        match field {
+            "id" => Some(self.id.clone()),
+            "asset_id" => Some(self.asset_id.clone()),
+            "port" => Some(self.port.to_string()),
+            "protocol" => Some(self.protocol.clone()),
+            "state" => Some(self.state.clone()),
+            "service" => self.service.clone(),
+            "title" => Some(self.title.clone()),
+            "description" => Some(self.description.clone()),
+            "severity" => Some(normalize_severity(&self.severity).to_string()),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn severity_rank(label: &str) -> u8 {
+    match normalize_severity(label) {
+        "critical" => 4,
+        "high" => 3,
+        "medium" => 2,
+        "low" => 1,
+        _ => 0,
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,12 +217,18 @@ pub struct ScriptArtifact {
     pub exit_code: Option<i32>,
     pub started_at: String,
     pub duration_ms: u128,
+    /// Set when the script was killed by its `timeout`; the output is partial.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssetGroupArtifact {
     pub name: String,
     pub properties: BTreeMap<String, String>,
+    /// Addresses expanded from a CIDR `scope`, also bound to `<name>_hosts`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hosts: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,6 +242,8 @@ pub struct ReportArtifact {
     pub output_path: Option<String>,
     #[serde(default)]
     pub options: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row_count: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,12 +252,58 @@ pub struct TableArtifact {
     pub rows: Vec<BTreeMap<String, Value>>,
 }
 
+impl TableArtifact {
+    /// Returns a copy with rows ordered by `column`. Numeric cells compare
+    /// numerically and the `severity` column compares by rank; unknown columns
+    /// leave the row order untouched. Rows with equal keys keep their order.
+    pub fn sort_by(&self, column: &str, descending: bool) -> TableArtifact {
+        let mut sorted = self.clone();
+        if !self.columns.iter().any(|name| name == column) {
+            return sorted;
+        }
+        sorted.rows.sort_by(|left, right| {
+            let ordering = compare_cells(column, left.get(column), right.get(column));
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        sorted
+    }
+}
+
+fn compare_cells(column: &str, left: Option<&Value>, right: Option<&Value>) -> Ordering {
+    let text = |value: Option<&Value>| match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    };
+    if column == "severity" {
+        return severity_rank(&text(left)).cmp(&severity_rank(&text(right)));
+    }
+    let number = |value: Option<&Value>| match value {
+        Some(Value::Number(number)) => number.as_f64(),
+        Some(Value::String(text)) => text.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    match (number(left), number(right)) {
+        (Some(left), Some(right)) => left.partial_cmp(&right).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => text(left).cmp(&text(right)),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ArtifactKind {
     AssetGroup,
     Scan,
     Script,
     Report,
+    Parallel,
+    Notification,
+    Transform,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,4 +312,172 @@ pub struct StoredArtifact {
     pub kind: ArtifactKind,
     pub path: Option<String>,
     pub data: Value,
+    /// SHA-256 of the stored JSON when the executor writes content-addressed
+    /// artifacts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(address: &str, hostnames: &[&str]) -> Asset {
+        Asset {
+            id: format!("asset://host/{address}"),
+            addresses: vec![address.to_string()],
+            hostnames: hostnames.iter().map(|name| name.to_string()).collect(),
+            labels: BTreeMap::new(),
+            os_matches: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn normalize_severity_maps_tool_variants() {
+        assert_eq!(normalize_severity("unknown"), "informational");
+        assert_eq!(normalize_severity("info"), "informational");
+        assert_eq!(normalize_severity("Informational"), "informational");
+        assert_eq!(normalize_severity("CRITICAL"), "critical");
+        assert_eq!(normalize_severity("UNKNOWN"), "informational");
+        assert_eq!(normalize_severity("Moderate"), "medium");
+        assert_eq!(normalize_severity("warning"), "medium");
+        assert_eq!(normalize_severity("note"), "low");
+        assert_eq!(normalize_severity(" High "), "high");
+        assert_eq!(normalize_severity(""), "informational");
+        assert_eq!(severity_rank("CRITICAL"), 4);
+        assert_eq!(severity_rank("moderate"), 2);
+    }
+
+    fn port_table() -> TableArtifact {
+        let row = |port: u16, severity: &str| {
+            BTreeMap::from([
+                ("port".to_string(), Value::from(port)),
+                ("severity".to_string(), Value::from(severity)),
+            ])
+        };
+        TableArtifact {
+            columns: vec!["port".to_string(), "severity".to_string()],
+            rows: vec![row(443, "low"), row(22, "critical"), row(8080, "medium")],
+        }
+    }
+
+    fn column_values(table: &TableArtifact, column: &str) -> Vec<String> {
+        table
+            .rows
+            .iter()
+            .map(|row| match &row[column] {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn table_sort_orders_ports_numerically() {
+        let sorted = port_table().sort_by("port", false);
+        assert_eq!(column_values(&sorted, "port"), ["22", "443", "8080"]);
+    }
+
+    #[test]
+    fn table_sort_descending_uses_severity_rank() {
+        let sorted = port_table().sort_by("severity", true);
+        assert_eq!(
+            column_values(&sorted, "severity"),
+            ["critical", "medium", "low"]
+        );
+    }
+
+    #[test]
+    fn table_sort_ignores_unknown_columns() {
+        let table = port_table();
+        let sorted = table.sort_by("banner", true);
+        assert_eq!(
+            column_values(&sorted, "port"),
+            column_values(&table, "port")
+        );
+    }
+
+    fn finding(address: &str, protocol: &str, port: u16, service: &str) -> Finding {
+        Finding {
+            id: format!("finding://{address}/{protocol}-{port}"),
+            asset_id: format!("asset://host/{address}"),
+            port,
+            protocol: protocol.to_string(),
+            state: "open".to_string(),
+            service: Some(service.to_string()),
+            title: format!("{address}:{port} {protocol} open"),
+            description: String::new(),
+            severity: "informational".to_string(),
+            evidence: BTreeMap::new(),
+            cvss_score: None,
+            cvss_vector: None,
+            cve_ids: Vec::new(),
+            cwe_ids: Vec::new(),
+            remediation: None,
+        }
+    }
+
+    fn scan(assets: Vec<Asset>, findings: Vec<Finding>, raw_xml: &str) -> ScanArtifacts {
+        ScanArtifacts {
+            tool: "nmap".to_string(),
+            target: "10.0.0.0/24".to_string(),
+            assets,
+            findings,
+            raw_xml: raw_xml.to_string(),
+            raw_output: String::new(),
+        }
+    }
+
+    #[test]
+    fn merge_deduplicates_assets_and_findings() {
+        let tcp = scan(
+            vec![asset("10.0.0.1", &[]), asset("10.0.0.2", &["db"])],
+            vec![
+                finding("10.0.0.1", "tcp", 22, "ssh"),
+                finding("10.0.0.1", "tcp", 80, "http"),
+                finding("10.0.0.2", "tcp", 5432, "postgresql"),
+            ],
+            "<tcp/>",
+        );
+        let mut udp_web = asset("10.0.0.1", &["web"]);
+        udp_web.labels.insert("os".to_string(), "Linux".to_string());
+        let udp = scan(
+            vec![udp_web, asset("10.0.0.3", &[])],
+            vec![
+                finding("10.0.0.1", "tcp", 80, "http-alt"),
+                finding("10.0.0.1", "udp", 53, "domain"),
+                finding("10.0.0.3", "udp", 161, "snmp"),
+            ],
+            "<udp/>",
+        );
+
+        let merged = tcp.merge(udp);
+        let asset_ids: Vec<&str> = merged.assets.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(
+            asset_ids,
+            vec![
+                "asset://host/10.0.0.1",
+                "asset://host/10.0.0.2",
+                "asset://host/10.0.0.3"
+            ]
+        );
+        assert_eq!(merged.assets[0].hostnames, vec!["web".to_string()]);
+        assert_eq!(
+            merged.assets[0].labels.get("os"),
+            Some(&"Linux".to_string())
+        );
+
+        assert_eq!(merged.findings.len(), 5);
+        let http = merged
+            .findings
+            .iter()
+            .find(|f| f.port == 80)
+            .expect("http finding kept");
+        assert_eq!(http.service.as_deref(), Some("http"));
+        assert!(merged
+            .findings
+            .iter()
+            .any(|f| f.protocol == "udp" && f.port == 53));
+        assert_eq!(merged.raw_xml, "<tcp/>\n<udp/>");
+    }
 }
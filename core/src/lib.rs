@@ -1,21 +1,33 @@
 pub mod artifact;
 pub mod runtime;
 pub mod scenario;
+pub mod store;
 pub mod validation;
 
 pub use artifact::{
-    ArtifactKind, Asset, AssetGroupArtifact, Finding, ReportArtifact, ScanArtifacts,
-    ScriptArtifact, StoredArtifact, TableArtifact,
+    normalize_severity, ArtifactKind, Asset, AssetGroupArtifact, Finding, OsMatch, ReportArtifact,
+    ScanArtifacts, ScriptArtifact, StoredArtifact, TableArtifact,
 };
 pub use runtime::{
-    ExecutionOutcome, ExecutionReport, ExecutionStatus, Executor, StepExecution, StepKind,
+    evaluate_condition_expression, Checkpoint, ExecutionDiff, ExecutionOutcome, ExecutionReport,
+    ExecutionStatus, Executor, StatusChange, StepExecution, StepKind, DEFAULT_MAX_ITERATIONS,
 };
+#[allow(deprecated)]
+pub use scenario::parse_scenario;
 pub use scenario::{
-    parse_literal_expression, parse_scenario, AssetGroupStep, AssetGroupSummary, ImportStep,
-    LiteralValue, ParseError, ReportFormat, ReportStep, ReportSummary, ScanStep, ScanSummary,
-    Scenario, ScenarioSummary, ScriptStep, ScriptSummary, Step, VariableDecl, VariableSummary,
+    parse_literal_expression, parse_scenario_all, prefix_step_names, AssetGroupStep,
+    AssetGroupSummary, BlockComments, ImportAlias, ImportStep, LiteralValue, Location, ParseError,
+    ParseResult, ReportFormat, ReportStep, ReportSummary, RetryBackoff, RetryPolicy, ScanStep,
+    ScanSummary, Scenario, ScenarioSummary, ScriptStep, ScriptSummary, Step, VariableDecl,
+    VariableSummary,
+};
+pub use store::{
+    ArtifactStore, FilesystemArtifactStore, InMemoryArtifactStore, S3ArtifactStore,
+    S3ArtifactStoreConfig,
 };
 pub use validation::{
-    builtin_tool_schema_bundle, builtin_tool_schemas, validate_scenario, Diagnostic,
-    DiagnosticLevel, ToolSchema, ToolSchemaBundle,
+    builtin_tool_schema_bundle, builtin_tool_schemas, parse_schema_plugin, tool_json_schema,
+    tool_schema_bundle, validate_scenario, validate_scenario_with_schemas,
+    validate_scenario_with_vars, Diagnostic, DiagnosticLevel, ToolSchema, ToolSchemaBundle,
+    ToolSchemaExample, JSON_SCHEMA_DIALECT,
 };
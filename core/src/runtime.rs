@@ -1,37 +1,101 @@
 use crate::artifact::{
-    ArtifactKind, Asset, AssetGroupArtifact, Finding, ReportArtifact, ScanArtifacts,
-    ScriptArtifact, StoredArtifact, TableArtifact,
+    normalize_severity, severity_rank, ArtifactKind, Asset, AssetGroupArtifact, Finding, OsMatch,
+    ReportArtifact, ScanArtifacts, ScriptArtifact, StoredArtifact, TableArtifact,
+    DEFAULT_FINDING_KEY, FINDING_KEY_FIELDS,
 };
 use crate::scenario::{
-    AssetGroupStep, ConditionExpr, ConditionOperand, ConditionalStep, LiteralValue, LoopIterable,
-    LoopStep, ReportFormat, ReportStep, ScanStep, Scenario, ScriptStep, SecretSource, SecretStep,
-    Step, VariableDecl,
+    format_duration_ms, parse_condition_expr, parse_duration_ms, split_transform_path, AssertStep,
+    AssetGroupStep, CallStep, CheckpointStep, ConditionExpr, ConditionOperand, ConditionalStep,
+    FunctionDef, LiteralValue, LoopIterable, LoopStep, MergeStep, NotifyStep, ParallelStep,
+    ReportFormat, ReportStep, RetryPolicy, ScanStep, Scenario, ScriptStep, SecretSource,
+    SecretStep, SleepStep, Step, TransformOp, TransformStep, VariableDecl,
 };
+use crate::store::{
+    content_digest, write_atomically, ArtifactStore, FilesystemArtifactStore, InMemoryArtifactStore,
+};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
 use comfy_table::{presets::ASCII_FULL, Table};
-use quick_xml::events::Event;
+use ipnet::{IpNet, Ipv4AddrRange, Ipv6AddrRange};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use quick_xml::events::{BytesStart, Event};
 use quick_xml::name::QName;
 use quick_xml::Reader;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::env;
 use std::fmt;
 use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
-use std::process::Command;
-use std::time::Instant;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 use time::OffsetDateTime;
+use uuid::Uuid;
 
-#[derive(Debug, Default)]
+/// Runs parsed scenarios. Warnings and per-step spans are emitted through
+/// `tracing`; installing a subscriber is left to the caller.
+#[derive(Debug)]
 pub struct Executor {
     artifacts_dir: PathBuf,
+    store: Option<Box<dyn ArtifactStore>>,
+    compress_artifacts: bool,
+    content_addressable: bool,
+    dry_run: bool,
+    max_iterations: Option<usize>,
+    resume: Option<Checkpoint>,
+    keyring_mock_env: bool,
+    only_tags: Vec<String>,
+    skip_tags: Vec<String>,
+    scenario_dir: Option<PathBuf>,
+}
+
+pub const DEFAULT_MAX_ITERATIONS: usize = 10_000;
+
+/// Read-only state shared by every step of one run.
+struct RunContext<'a> {
+    overrides: &'a HashMap<String, LiteralValue>,
+    functions: HashMap<&'a str, &'a FunctionDef>,
+    // Functions on a call cycle; calling them fails instead of recursing forever.
+    recursive: HashSet<String>,
+    // Definition digest of every step a resumed run may skip, keyed by name.
+    step_hashes: HashMap<String, String>,
+}
+
+/// Run state saved by a `checkpoint` step under
+/// `artifacts/checkpoints/<name>.json`. Secret values are masked before the
+/// file is written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub name: String,
+    pub created_at: String,
+    pub variables: BTreeMap<String, LiteralValue>,
+    pub artifacts: Vec<StoredArtifact>,
+    pub steps: Vec<StepExecution>,
+    /// Definition digest of each step that completed before the checkpoint.
+    pub completed: BTreeMap<String, String>,
+}
+
+impl Checkpoint {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .map_err(|err| format!("failed to read checkpoint {}: {err}", path.display()))?;
+        serde_json::from_str(&source)
+            .map_err(|err| format!("{} is not a checkpoint: {err}", path.display()))
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 struct SecretStore {
     values: HashMap<String, String>,
     usage: HashSet<String>,
+    expires_at: HashMap<String, Instant>,
+    expired: BTreeSet<String>,
 }
 
 impl SecretStore {
@@ -41,6 +105,24 @@ impl SecretStore {
         self.values.entry(key).or_insert(value);
     }
 
+    // Like `insert`, but the value stops resolving once `ttl` has elapsed.
+    // Values that were already present (e.g. `--secret` overrides) keep
+    // their existing lifetime.
+    fn insert_with_ttl(&mut self, key: impl Into<String>, value: impl Into<String>, ttl: Duration) {
+        let key = key.into();
+        if self.values.contains_key(&key) {
+            return;
+        }
+        self.expires_at.insert(key.clone(), Instant::now() + ttl);
+        self.values.insert(key, value.into());
+    }
+
+    fn is_expired(&self, key: &str) -> bool {
+        self.expires_at
+            .get(key)
+            .is_some_and(|expires_at| Instant::now() >= *expires_at)
+    }
+
     fn set(&mut self, key: impl Into<String>, value: impl Into<String>) {
         let key = key.into();
         let value = value.into();
@@ -48,6 +130,12 @@ impl SecretStore {
     }
 
     fn resolve(&mut self, key: &str) -> Option<String> {
+        if self.is_expired(key) {
+            if self.expired.insert(key.to_string()) {
+                tracing::warn!(secret = key, "secret has expired");
+            }
+            return None;
+        }
         if let Some(value) = self.values.get(key) {
             self.usage.insert(key.to_string());
             Some(value.clone())
@@ -60,6 +148,13 @@ impl SecretStore {
         self.values.get(key).map(|value| value.as_str())
     }
 
+    fn absorb(&mut self, other: SecretStore) {
+        self.values.extend(other.values);
+        self.usage.extend(other.usage);
+        self.expires_at.extend(other.expires_at);
+        self.expired.extend(other.expired);
+    }
+
     fn mask(&self, input: &str) -> String {
         let mut masked = input.to_string();
         for value in self.values.values() {
@@ -71,13 +166,140 @@ impl SecretStore {
     }
 }
 
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Executor {
     pub fn new() -> Self {
-        let artifacts_dir = PathBuf::from("artifacts");
-        if let Err(err) = fs::create_dir_all(&artifacts_dir) {
-            eprintln!("[warn] failed to create artifacts directory: {err}");
+        // Directories are created on first write, so dry runs and plans leave
+        // the working tree untouched.
+        Self {
+            artifacts_dir: PathBuf::from("artifacts"),
+            store: None,
+            compress_artifacts: false,
+            content_addressable: false,
+            dry_run: false,
+            max_iterations: None,
+            resume: None,
+            keyring_mock_env: false,
+            only_tags: Vec::new(),
+            skip_tags: Vec::new(),
+            scenario_dir: None,
+        }
+    }
+
+    /// Replaces the artifact store. Report files are still written below
+    /// `artifacts/`; only artifact JSON goes through the store.
+    pub fn with_store(mut self, store: impl ArtifactStore + 'static) -> Self {
+        self.store = Some(Box::new(store));
+        self
+    }
+
+    /// Sets the directory for report files and, unless a custom store is
+    /// configured, the filesystem artifact store. Defaults to `artifacts/`.
+    pub fn with_artifacts_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.artifacts_dir = dir.into();
+        self
+    }
+
+    /// Gzip artifact JSON before it reaches the store (`<label>.json.gz` on
+    /// disk). Stores decompress transparently on `read`.
+    pub fn with_compressed_artifacts(mut self, compress: bool) -> Self {
+        self.compress_artifacts = compress;
+        self
+    }
+
+    /// Store artifacts under the SHA-256 of their JSON (`artifacts/cas/<hex>.json`)
+    /// with `<label>.json` linked to the content, and record the digest as
+    /// `StoredArtifact::content_hash`.
+    pub fn with_content_addressable(mut self, enabled: bool) -> Self {
+        self.content_addressable = enabled;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_max_iterations(mut self, limit: usize) -> Self {
+        self.max_iterations = Some(limit);
+        self
+    }
+
+    /// Starts from the variables and artifacts saved in `checkpoint`. Steps
+    /// that completed before it are reported as skipped as long as their
+    /// definition is unchanged and their artifacts are still intact on disk;
+    /// everything else runs again.
+    pub fn with_resume(mut self, checkpoint: Checkpoint) -> Self {
+        self.resume = Some(checkpoint);
+        self
+    }
+
+    /// Reads `keyring` secrets from `AXION_KEYRING_<SERVICE>_<ACCOUNT>`
+    /// environment variables instead of the OS keychain, for CI runners
+    /// without one.
+    pub fn with_keyring_mock_env(mut self, enabled: bool) -> Self {
+        self.keyring_mock_env = enabled;
+        self
+    }
+
+    /// Runs only the tagged steps that carry at least one of `tags`; other
+    /// taggable steps are reported as skipped. Variables, secrets, and
+    /// control flow always run.
+    pub fn with_only_tags(mut self, tags: Vec<String>) -> Self {
+        self.only_tags = tags;
+        self
+    }
+
+    /// Skips steps carrying any of `tags`. Applied after `with_only_tags`.
+    pub fn with_skip_tags(mut self, tags: Vec<String>) -> Self {
+        self.skip_tags = tags;
+        self
+    }
+
+    /// Resolves relative `cwd` parameters of scans and scripts against the
+    /// directory containing `path` instead of the process working directory.
+    pub fn with_scenario_path(mut self, path: &Path) -> Self {
+        self.scenario_dir = Some(path.parent().unwrap_or(Path::new("")).to_path_buf());
+        self
+    }
+
+    fn working_dir(&self, cwd: &str) -> PathBuf {
+        match &self.scenario_dir {
+            Some(dir) if Path::new(cwd).is_relative() => dir.join(cwd),
+            _ => PathBuf::from(cwd),
         }
-        Self { artifacts_dir }
+    }
+
+    /// Runs the scenario without side effects: variables, asset groups,
+    /// conditionals, loops, and asserts execute normally, while scans, scripts,
+    /// notifications, sleeps, and reports are skipped with a `[dry-run]`
+    /// message. Scans and scripts leave placeholder artifacts behind and
+    /// nothing is persisted.
+    pub fn dry_run(
+        &self,
+        scenario: &Scenario,
+        overrides: &HashMap<String, LiteralValue>,
+        secret_overrides: &HashMap<String, String>,
+    ) -> ExecutionOutcome {
+        let planner = Executor {
+            artifacts_dir: self.artifacts_dir.clone(),
+            store: Some(Box::new(InMemoryArtifactStore::new())),
+            compress_artifacts: false,
+            content_addressable: false,
+            dry_run: true,
+            max_iterations: self.max_iterations,
+            resume: self.resume.clone(),
+            keyring_mock_env: self.keyring_mock_env,
+            only_tags: self.only_tags.clone(),
+            skip_tags: self.skip_tags.clone(),
+            scenario_dir: self.scenario_dir.clone(),
+        };
+        planner.execute_with_vars(scenario, overrides, secret_overrides)
     }
 
     pub fn execute(&self, scenario: &Scenario) -> ExecutionOutcome {
@@ -97,73 +319,339 @@ impl Executor {
         let mut report_steps = Vec::new();
         let mut secrets = SecretStore::default();
 
+        if let Some(checkpoint) = &self.resume {
+            for (name, value) in &checkpoint.variables {
+                variables
+                    .entry(name.clone())
+                    .or_insert_with(|| value.clone());
+            }
+            for artifact in &checkpoint.artifacts {
+                if self.artifact_is_intact(artifact) {
+                    store.insert(artifact.name.clone(), artifact.clone());
+                } else {
+                    tracing::warn!(
+                        artifact = %artifact.name,
+                        checkpoint = %checkpoint.name,
+                        "checkpoint artifact is missing or changed; its step runs again"
+                    );
+                }
+            }
+        }
+
         for (key, value) in secret_overrides {
             secrets.set(key.clone(), value.clone());
         }
 
-        self.execute_steps(
-            &scenario.steps,
+        let context = RunContext {
             overrides,
-            &mut variables,
-            &mut store,
-            &mut report_steps,
-            &mut secrets,
-        );
+            functions: scenario
+                .steps
+                .iter()
+                .filter_map(|step| match step {
+                    Step::Function(function) => Some((function.name.as_str(), function)),
+                    _ => None,
+                })
+                .collect(),
+            recursive: scenario.function_cycles().into_iter().flatten().collect(),
+            step_hashes: {
+                let mut hashes = HashMap::new();
+                collect_step_hashes(&scenario.steps, &mut hashes);
+                hashes
+            },
+        };
+        let span = tracing::info_span!("scenario", steps = scenario.steps.len());
+        span.in_scope(|| {
+            self.execute_steps(
+                &scenario.steps,
+                &context,
+                &mut variables,
+                &mut store,
+                &mut report_steps,
+                &mut secrets,
+            )
+        });
 
         let artifacts = store.into_values().collect();
+        let report = ExecutionReport {
+            steps: report_steps,
+        };
 
         ExecutionOutcome {
-            report: ExecutionReport {
-                steps: report_steps,
-            },
+            summary: report.summary_by_status(),
+            report,
             artifacts,
+            secret_warnings: secrets.expired.into_iter().collect(),
+            secret_usage: {
+                let mut usage: Vec<String> = secrets.usage.into_iter().collect();
+                usage.sort();
+                usage
+            },
         }
     }
 
     fn execute_steps(
         &self,
         steps: &[Step],
-        overrides: &HashMap<String, LiteralValue>,
+        context: &RunContext<'_>,
         variables: &mut HashMap<String, LiteralValue>,
         store: &mut HashMap<String, StoredArtifact>,
         report: &mut Vec<StepExecution>,
         secrets: &mut SecretStore,
-    ) {
+    ) -> Option<LoopSignal> {
         for step in steps {
+            let _span = step_span(step).entered();
+            if let Some(execution) = self.tag_filtered_step(step) {
+                report.push(execution);
+                continue;
+            }
+            if let Some(execution) = self.resumed_step(step, context, store) {
+                report.push(execution);
+                continue;
+            }
             match step {
-                Step::Import(_) => continue,
+                Step::Import(_) | Step::Function(_) | Step::Comment(_) => continue,
+                Step::Call(call) => {
+                    self.process_call(call, context, variables, store, report, secrets);
+                }
                 Step::Variable(var) => {
-                    let outcome = self.process_variable(var, overrides, variables, secrets);
+                    let outcome =
+                        timed(|| self.process_variable(var, context.overrides, variables, secrets));
                     self.record_outcome(report, store, secrets, outcome);
                 }
                 Step::AssetGroup(group) => {
-                    let outcome = self.process_asset_group(group, variables, secrets);
+                    let outcome = timed(|| self.process_asset_group(group, variables, secrets));
                     self.record_outcome(report, store, secrets, outcome);
                 }
                 Step::Scan(scan) => {
-                    let outcome = self.process_scan(scan, variables, secrets);
+                    let outcome = timed(|| self.process_scan(scan, variables, secrets));
                     self.record_outcome(report, store, secrets, outcome);
                 }
                 Step::Script(script) => {
-                    let outcome = self.process_script(script, variables, secrets);
+                    let outcome = timed(|| self.process_script(script, variables, secrets));
                     self.record_outcome(report, store, secrets, outcome);
                 }
                 Step::Report(report_step) => {
-                    let outcome = self.process_report(report_step, store, variables, secrets);
+                    let outcome =
+                        timed(|| self.process_report(report_step, store, variables, secrets));
                     self.record_outcome(report, store, secrets, outcome);
                 }
                 Step::Secret(secret_step) => {
-                    let outcome = self.process_secret(secret_step, secrets);
+                    let outcome = timed(|| self.process_secret(secret_step, secrets));
                     self.record_outcome(report, store, secrets, outcome);
                 }
                 Step::Conditional(block) => {
-                    self.process_conditional(block, overrides, variables, store, report, secrets);
+                    let signal =
+                        self.process_conditional(block, context, variables, store, report, secrets);
+                    if signal.is_some() {
+                        return signal;
+                    }
                 }
+                Step::Break => return Some(LoopSignal::Break),
+                Step::Continue => return Some(LoopSignal::Continue),
                 Step::Loop(loop_step) => {
-                    self.process_loop(loop_step, overrides, variables, store, report, secrets);
+                    self.process_loop(loop_step, context, variables, store, report, secrets);
+                }
+                Step::Parallel(block) => {
+                    self.process_parallel(block, context, variables, store, report, secrets);
+                }
+                Step::Notify(notify) => {
+                    let outcome = timed(|| self.process_notify(notify, variables, secrets));
+                    self.record_outcome(report, store, secrets, outcome);
+                }
+                Step::Sleep(sleep) => {
+                    let outcome = timed(|| self.process_sleep(sleep));
+                    self.record_outcome(report, store, secrets, outcome);
+                }
+                Step::Merge(merge) => {
+                    let outcome = timed(|| self.process_merge(merge, variables, store, secrets));
+                    self.record_outcome(report, store, secrets, outcome);
+                }
+                Step::Transform(transform) => {
+                    let outcome =
+                        timed(|| self.process_transform(transform, variables, store, secrets));
+                    self.record_outcome(report, store, secrets, outcome);
+                }
+                Step::Checkpoint(checkpoint) => {
+                    let outcome = timed(|| {
+                        self.process_checkpoint(
+                            checkpoint, context, variables, store, report, secrets,
+                        )
+                    });
+                    self.record_outcome(report, store, secrets, outcome);
+                }
+                Step::Assert(assert_step) => {
+                    let outcome =
+                        timed(|| self.process_assert(assert_step, variables, store, secrets));
+                    self.record_outcome(report, store, secrets, outcome);
+                }
+            }
+            // Steps with handlers record exactly one execution, so the last
+            // entry is this step's own result.
+            let handlers = step.on_failure();
+            let failed = report
+                .last()
+                .is_some_and(|execution| execution.status == ExecutionStatus::Failed);
+            if !handlers.is_empty() && failed {
+                tracing::info!("running on_failure handlers");
+                let signal =
+                    self.execute_steps(handlers, context, variables, store, report, secrets);
+                if signal.is_some() {
+                    return signal;
+                }
+            }
+        }
+        None
+    }
+
+    /// The skipped execution to report when the tag filters exclude `step`.
+    fn tag_filtered_step(&self, step: &Step) -> Option<StepExecution> {
+        if self.only_tags.is_empty() && self.skip_tags.is_empty() {
+            return None;
+        }
+        let kind = match step {
+            Step::AssetGroup(_) => StepKind::AssetGroup,
+            Step::Scan(_) => StepKind::Scan,
+            Step::Script(_) => StepKind::Script,
+            Step::Report(_) => StepKind::Report,
+            Step::Assert(_) => StepKind::Assert,
+            Step::Notify(_) => StepKind::Notify,
+            Step::Transform(_) => StepKind::Transform,
+            _ => return None,
+        };
+        let tags = step.tags();
+        let message = if let Some(tag) = tags.iter().find(|tag| self.skip_tags.contains(tag)) {
+            format!("[tags] excluded by skip tag '{tag}'")
+        } else if !self.only_tags.is_empty() && !tags.iter().any(|tag| self.only_tags.contains(tag))
+        {
+            format!("[tags] no tag in [{}]", self.only_tags.join(", "))
+        } else {
+            return None;
+        };
+        Some(StepExecution::skipped(
+            step.name()?.to_string(),
+            kind,
+            Some(message),
+        ))
+    }
+
+    /// The execution to report instead of running `step` again, when resuming
+    /// from a checkpoint that already completed it.
+    fn resumed_step(
+        &self,
+        step: &Step,
+        context: &RunContext<'_>,
+        store: &HashMap<String, StoredArtifact>,
+    ) -> Option<StepExecution> {
+        let checkpoint = self.resume.as_ref()?;
+        let labels = resumable_labels(step)?;
+        let name = step.name()?;
+        let hash = context.step_hashes.get(name)?;
+        if checkpoint.completed.get(name) != Some(hash) {
+            return None;
+        }
+        if !labels.is_empty() && !labels.iter().any(|label| store.contains_key(label)) {
+            return None;
+        }
+        let previous = checkpoint
+            .steps
+            .iter()
+            .rev()
+            .find(|execution| execution.name == name)?;
+        Some(StepExecution::skipped(
+            name.to_string(),
+            previous.kind.clone(),
+            Some(format!(
+                "[resume] completed before checkpoint '{}'",
+                checkpoint.name
+            )),
+        ))
+    }
+
+    fn process_checkpoint(
+        &self,
+        step: &CheckpointStep,
+        context: &RunContext<'_>,
+        variables: &HashMap<String, LiteralValue>,
+        store: &HashMap<String, StoredArtifact>,
+        report: &[StepExecution],
+        secrets: &SecretStore,
+    ) -> StepOutcome {
+        let path = self
+            .artifacts_dir
+            .join("checkpoints")
+            .join(format!("{}.json", step.name));
+        if self.dry_run {
+            return StepOutcome::from_execution(StepExecution::skipped(
+                step.name.clone(),
+                StepKind::Checkpoint,
+                Some(format!(
+                    "[dry-run] would save checkpoint to {}",
+                    path.display()
+                )),
+            ));
+        }
+
+        // Steps skipped by an earlier resume stay completed.
+        let mut completed: BTreeMap<String, String> = self
+            .resume
+            .iter()
+            .flat_map(|checkpoint| &checkpoint.completed)
+            .filter(|(name, hash)| context.step_hashes.get(*name) == Some(*hash))
+            .map(|(name, hash)| (name.clone(), hash.clone()))
+            .collect();
+        for execution in report {
+            if execution.status == ExecutionStatus::Completed {
+                if let Some(hash) = context.step_hashes.get(&execution.name) {
+                    completed.insert(execution.name.clone(), hash.clone());
                 }
             }
         }
+        let mut artifacts: Vec<StoredArtifact> = store.values().cloned().collect();
+        artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+        let checkpoint = Checkpoint {
+            name: step.name.clone(),
+            created_at: OffsetDateTime::now_utc()
+                .format(&time::format_description::well_known::Rfc3339)
+                .unwrap_or_else(|_| "unknown".to_string()),
+            variables: variables
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            artifacts,
+            steps: report.to_vec(),
+            completed,
+        };
+
+        let saved = serde_json::to_string_pretty(&checkpoint)
+            .map_err(|err| format!("failed to serialize checkpoint: {err}"))
+            .and_then(|json| {
+                fs::create_dir_all(self.artifacts_dir.join("checkpoints"))
+                    .and_then(|_| {
+                        // A crash mid-write must leave the previous checkpoint intact.
+                        write_atomically(&path, |file| {
+                            file.write_all(secrets.mask(&json).as_bytes())
+                        })
+                    })
+                    .map_err(|err| format!("failed to write {}: {err}", path.display()))
+            });
+        match saved {
+            Ok(()) => StepOutcome::from_execution(StepExecution::completed(
+                step.name.clone(),
+                StepKind::Checkpoint,
+                Some(format!(
+                    "saved {} variable(s), {} artifact(s), and {} completed step(s) to {}",
+                    checkpoint.variables.len(),
+                    checkpoint.artifacts.len(),
+                    checkpoint.completed.len(),
+                    path.display()
+                )),
+            )),
+            Err(err) => StepOutcome::from_execution(StepExecution::failed(
+                step.name.clone(),
+                StepKind::Checkpoint,
+                Some(err),
+            )),
+        }
     }
 
     fn record_outcome(
@@ -179,18 +667,94 @@ impl Executor {
         if let Some(message) = outcome.execution.message.take() {
             outcome.execution.message = Some(secrets.mask(&message));
         }
+        let execution = &outcome.execution;
+        tracing::debug!(
+            step = %execution.name,
+            status = ?execution.status,
+            duration_ms = execution.duration_ms.map(|ms| ms as u64),
+            "step finished"
+        );
         report.push(outcome.execution);
     }
 
+    fn process_call(
+        &self,
+        call: &CallStep,
+        context: &RunContext<'_>,
+        variables: &mut HashMap<String, LiteralValue>,
+        store: &mut HashMap<String, StoredArtifact>,
+        report: &mut Vec<StepExecution>,
+        secrets: &mut SecretStore,
+    ) {
+        let args: Vec<String> = call.args.iter().map(ToString::to_string).collect();
+        let call_name = format!("call {}({})", call.function, args.join(", "));
+        let bound = match context.functions.get(call.function.as_str()) {
+            None => Err(format!("undefined function '{}'", call.function)),
+            Some(_) if context.recursive.contains(&call.function) => Err(format!(
+                "function '{}' is recursive and cannot be called",
+                call.function
+            )),
+            Some(function) if function.params.len() != call.args.len() => Err(format!(
+                "function '{}' expects {} argument(s), got {}",
+                call.function,
+                function.params.len(),
+                call.args.len()
+            )),
+            Some(function) => function
+                .params
+                .iter()
+                .zip(&call.args)
+                .map(|(param, arg)| {
+                    evaluate_operand(arg, variables, secrets).map(|value| (param.clone(), value))
+                })
+                .collect::<Result<Vec<_>, String>>()
+                .map(|bindings| (*function, bindings)),
+        };
+        let (function, bindings) = match bound {
+            Ok(bound) => bound,
+            Err(err) => {
+                let outcome = StepOutcome::from_execution(StepExecution::failed(
+                    call_name,
+                    StepKind::Call,
+                    Some(err),
+                ));
+                self.record_outcome(report, store, secrets, outcome);
+                return;
+            }
+        };
+
+        let message = if bindings.is_empty() {
+            "no arguments".to_string()
+        } else {
+            let bound: Vec<String> = bindings
+                .iter()
+                .map(|(param, value)| format!("{param} = {}", value.to_dsl()))
+                .collect();
+            bound.join(", ")
+        };
+        let outcome = StepOutcome::from_execution(StepExecution::completed(
+            call_name,
+            StepKind::Call,
+            Some(message),
+        ));
+        self.record_outcome(report, store, secrets, outcome);
+
+        // The body sees the caller's variables plus the parameters; anything it
+        // declares is dropped on return. Artifacts and secrets stay shared.
+        let mut scope = variables.clone();
+        scope.extend(bindings);
+        self.execute_steps(&function.body, context, &mut scope, store, report, secrets);
+    }
+
     fn process_conditional(
         &self,
         block: &ConditionalStep,
-        overrides: &HashMap<String, LiteralValue>,
+        context: &RunContext<'_>,
         variables: &mut HashMap<String, LiteralValue>,
         store: &mut HashMap<String, StoredArtifact>,
         report: &mut Vec<StepExecution>,
         secrets: &mut SecretStore,
-    ) {
+    ) -> Option<LoopSignal> {
         let condition_name = format!("if {}", block.condition);
         match evaluate_condition(&block.condition, variables, secrets) {
             Ok(result) => {
@@ -206,9 +770,7 @@ impl Executor {
                 } else {
                     &block.else_steps
                 };
-                if !branch.is_empty() {
-                    self.execute_steps(branch, overrides, variables, store, report, secrets);
-                }
+                self.execute_steps(branch, context, variables, store, report, secrets)
             }
             Err(err) => {
                 let outcome = StepOutcome::from_execution(StepExecution::failed(
@@ -217,6 +779,7 @@ impl Executor {
                     Some(err),
                 ));
                 self.record_outcome(report, store, secrets, outcome);
+                None
             }
         }
     }
@@ -224,42 +787,120 @@ impl Executor {
     fn process_loop(
         &self,
         loop_step: &LoopStep,
-        overrides: &HashMap<String, LiteralValue>,
+        context: &RunContext<'_>,
         variables: &mut HashMap<String, LiteralValue>,
         store: &mut HashMap<String, StoredArtifact>,
         report: &mut Vec<StepExecution>,
         secrets: &mut SecretStore,
     ) {
-        let loop_name = format!("for {} in {}", loop_step.iterator, loop_step.iterable);
-        match resolve_iterable(&loop_step.iterable, variables, secrets) {
+        let loop_name = match &loop_step.guard {
+            Some(guard) => format!(
+                "for {} in {} when {}",
+                loop_step.bindings(),
+                loop_step.iterable,
+                guard
+            ),
+            None => format!("for {} in {}", loop_step.bindings(), loop_step.iterable),
+        };
+        let limit = loop_step
+            .max_iter
+            .or(self.max_iterations)
+            .unwrap_or(DEFAULT_MAX_ITERATIONS);
+        // Check ranges before expanding them so huge bounds never allocate.
+        let resolved = match loop_step.iterable.range_len() {
+            Some(count) if count > limit as u64 => Err(iteration_limit_message(count, limit)),
+            _ => resolve_iterable(&loop_step.iterable, variables, secrets).and_then(|items| {
+                if items.len() > limit {
+                    Err(iteration_limit_message(items.len() as u64, limit))
+                } else if loop_step.value_iterator.is_some()
+                    && items.iter().any(|(_, value)| value.is_none())
+                {
+                    Err(format!(
+                        "'for {}' requires an object to iterate over ({})",
+                        loop_step.bindings(),
+                        loop_step.iterable
+                    ))
+                } else {
+                    Ok(items)
+                }
+            }),
+        };
+        match resolved {
             Ok(items) => {
-                let previous = variables.get(&loop_step.iterator).cloned();
+                let mut scoped = vec![loop_step.iterator.as_str(), LOOP_INDEX_VAR, LOOP_INDEX1_VAR];
+                scoped.extend(loop_step.value_iterator.as_deref());
+                let previous: Vec<Option<LiteralValue>> = scoped
+                    .iter()
+                    .map(|key| variables.get(*key).cloned())
+                    .collect();
+                let total = items.len();
                 let mut iterations = 0usize;
-                for item in items {
+                let mut continued = 0usize;
+                let mut filtered = 0usize;
+                let mut broke = false;
+                let mut guard_error = None;
+                for (index, (item, value)) in items.into_iter().enumerate() {
                     variables.insert(loop_step.iterator.clone(), item);
+                    if let (Some(name), Some(value)) = (&loop_step.value_iterator, value) {
+                        variables.insert(name.clone(), value);
+                    }
+                    variables.insert(
+                        LOOP_INDEX_VAR.to_string(),
+                        LiteralValue::Integer(index as i64),
+                    );
+                    variables.insert(
+                        LOOP_INDEX1_VAR.to_string(),
+                        LiteralValue::Integer((index + 1) as i64),
+                    );
+                    if let Some(guard) = &loop_step.guard {
+                        match evaluate_condition(guard, variables, secrets) {
+                            Ok(true) => {}
+                            Ok(false) => {
+                                filtered += 1;
+                                continue;
+                            }
+                            Err(err) => {
+                                guard_error = Some(format!("when guard failed: {err}"));
+                                break;
+                            }
+                        }
+                    }
                     iterations += 1;
-                    self.execute_steps(
+                    match self.execute_steps(
                         &loop_step.body,
-                        overrides,
+                        context,
                         variables,
                         store,
                         report,
                         secrets,
-                    );
-                }
-                match previous {
-                    Some(value) => {
-                        variables.insert(loop_step.iterator.clone(), value);
+                    ) {
+                        Some(LoopSignal::Break) => {
+                            broke = true;
+                            break;
+                        }
+                        Some(LoopSignal::Continue) => continued += 1,
+                        None => {}
                     }
-                    None => {
-                        variables.remove(&loop_step.iterator);
+                }
+                for (key, value) in scoped.into_iter().zip(previous) {
+                    match value {
+                        Some(value) => {
+                            variables.insert(key.to_string(), value);
+                        }
+                        None => {
+                            variables.remove(key);
+                        }
                     }
                 }
-                let outcome = StepOutcome::from_execution(StepExecution::completed(
-                    loop_name,
-                    StepKind::Loop,
-                    Some(format!("executed {iterations} iteration(s)")),
-                ));
+                let summary = loop_summary(iterations, continued, filtered, broke, total);
+                let outcome = StepOutcome::from_execution(match guard_error {
+                    Some(err) => StepExecution::failed(
+                        loop_name,
+                        StepKind::Loop,
+                        Some(format!("{err} ({summary})")),
+                    ),
+                    None => StepExecution::completed(loop_name, StepKind::Loop, Some(summary)),
+                });
                 self.record_outcome(report, store, secrets, outcome);
             }
             Err(err) => {
@@ -273,68 +914,354 @@ impl Executor {
         }
     }
 
-    fn process_generic_scan(
+    fn process_parallel(
         &self,
-        scan: &ScanStep,
-        params: BTreeMap<String, String>,
-    ) -> StepOutcome {
-        let mut cmd = Command::new(&scan.tool);
-        let mut invocation = vec![scan.tool.clone()];
+        block: &ParallelStep,
+        context: &RunContext<'_>,
+        variables: &mut HashMap<String, LiteralValue>,
+        store: &mut HashMap<String, StoredArtifact>,
+        report: &mut Vec<StepExecution>,
+        secrets: &mut SecretStore,
+    ) {
+        let block_name = match &block.name {
+            Some(name) => format!("parallel {name}"),
+            None => "parallel".to_string(),
+        };
+        let timer = Instant::now();
 
-        if let Some(flags) = params.get("flags") {
-            match shell_words::split(flags) {
-                Ok(parts) => {
-                    for part in parts {
-                        cmd.arg(&part);
-                        invocation.push(part);
-                    }
-                }
-                Err(err) => {
-                    return StepOutcome::from_execution(StepExecution::failed(
-                        scan.name.clone(),
-                        StepKind::Scan,
-                        Some(format!("failed to parse flags: {err}")),
-                    ));
-                }
-            }
-        }
+        // Each branch works on its own snapshot; results are merged in declaration order.
+        let branches: Vec<std::thread::Result<ParallelBranch>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = block
+                .steps
+                .iter()
+                .filter(|step| !matches!(step, Step::Comment(_)))
+                .map(|step| {
+                    let span = tracing::Span::current();
+                    let mut branch = ParallelBranch {
+                        variables: variables.clone(),
+                        store: store.clone(),
+                        report: Vec::new(),
+                        secrets: secrets.clone(),
+                    };
+                    scope.spawn(move || {
+                        let _span = span.entered();
+                        self.execute_steps(
+                            std::slice::from_ref(step),
+                            context,
+                            &mut branch.variables,
+                            &mut branch.store,
+                            &mut branch.report,
+                            &mut branch.secrets,
+                        );
+                        branch
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join()).collect()
+        });
 
-        if let Some(args) = params.get("args") {
-            match shell_words::split(args) {
-                Ok(parts) => {
-                    for part in parts {
-                        cmd.arg(&part);
-                        invocation.push(part);
+        let branch_count = branches.len();
+        let mut summaries = Vec::with_capacity(branch_count);
+        let mut panicked = 0usize;
+        for (index, branch) in branches.into_iter().enumerate() {
+            match branch {
+                Ok(branch) => {
+                    for (name, value) in branch.variables {
+                        if variables.get(&name) != Some(&value) {
+                            variables.insert(name, value);
+                        }
+                    }
+                    for (name, artifact) in branch.store {
+                        let changed = store
+                            .get(&name)
+                            .map(|existing| {
+                                existing.data != artifact.data || existing.path != artifact.path
+                            })
+                            .unwrap_or(true);
+                        if changed {
+                            store.insert(name, artifact);
+                        }
                     }
+                    secrets.absorb(branch.secrets);
+                    for execution in &branch.report {
+                        summaries.push(json!({
+                            "branch": index,
+                            "name": execution.name,
+                            "kind": execution.kind,
+                            "status": execution.status,
+                        }));
+                    }
+                    report.extend(branch.report);
                 }
-                Err(err) => {
-                    return StepOutcome::from_execution(StepExecution::failed(
-                        scan.name.clone(),
-                        StepKind::Scan,
-                        Some(format!("failed to parse args: {err}")),
-                    ));
+                Err(_) => {
+                    panicked += 1;
+                    summaries.push(json!({
+                        "branch": index,
+                        "status": ExecutionStatus::Failed,
+                    }));
                 }
             }
         }
 
-        if let Some(target) = params.get("target") {
-            if !target.is_empty() {
-                cmd.arg(target);
-                invocation.push(target.clone());
-            }
-        }
-
-        if let Some(cwd) = params.get("cwd") {
-            if !cwd.is_empty() {
-                cmd.current_dir(cwd);
-            }
-        }
+        let duration_ms = timer.elapsed().as_millis();
+        let artifact = StoredArtifact {
+            name: format!("parallel:{}", block.name.as_deref().unwrap_or("block")),
+            kind: ArtifactKind::Parallel,
+            path: None,
+            content_hash: None,
+            data: json!({
+                "name": block.name,
+                "branches": branch_count,
+                "duration_ms": duration_ms,
+                "steps": summaries,
+            }),
+        };
 
-        let started_at = OffsetDateTime::now_utc();
+        let execution = if panicked > 0 {
+            StepExecution::failed(
+                block_name,
+                StepKind::Parallel,
+                Some(format!("{panicked} branch(es) panicked during execution")),
+            )
+        } else {
+            StepExecution::completed(
+                block_name,
+                StepKind::Parallel,
+                Some(format!(
+                    "executed {} branch(es) concurrently in {} ms",
+                    branch_count, duration_ms
+                )),
+            )
+        };
+        self.record_outcome(
+            report,
+            store,
+            secrets,
+            StepOutcome::with_artifact(execution, artifact),
+        );
+    }
+
+    fn process_assert(
+        &self,
+        assert_step: &AssertStep,
+        variables: &HashMap<String, LiteralValue>,
+        store: &HashMap<String, StoredArtifact>,
+        secrets: &mut SecretStore,
+    ) -> StepOutcome {
+        let name = format!("assert {}", assert_step.name);
+        let mut scope = variables.clone();
+        for path in assert_step.condition.variable_names() {
+            if scope.contains_key(path) {
+                continue;
+            }
+            if let Some(value) = resolve_artifact_path(store, path) {
+                scope.insert(path.to_string(), value);
+            }
+        }
+
+        let failure = match evaluate_condition(&assert_step.condition, &scope, secrets) {
+            Ok(true) => {
+                return StepOutcome::from_execution(StepExecution::completed(
+                    name,
+                    StepKind::Assert,
+                    Some(format!("assertion held: {}", assert_step.condition)),
+                ));
+            }
+            Ok(false) => None,
+            Err(err) => Some(err),
+        };
+        let message = assert_step
+            .message
+            .clone()
+            .unwrap_or_else(|| format!("assertion failed: {}", assert_step.condition));
+        let message = match failure {
+            Some(err) => format!("{message} ({err})"),
+            None => message,
+        };
+        StepOutcome::from_execution(StepExecution::failed(name, StepKind::Assert, Some(message)))
+    }
+
+    fn process_sleep(&self, sleep: &SleepStep) -> StepOutcome {
+        let configured = format_duration_ms(sleep.duration_ms);
+        if self.dry_run {
+            return StepOutcome::from_execution(StepExecution::skipped(
+                sleep.name.clone(),
+                StepKind::Sleep,
+                Some(format!("[dry-run] would sleep for {configured}")),
+            ));
+        }
+
+        let timer = Instant::now();
+        std::thread::sleep(Duration::from_millis(sleep.duration_ms));
+        StepOutcome::from_execution(StepExecution::completed(
+            sleep.name.clone(),
+            StepKind::Sleep,
+            Some(format!(
+                "slept {} ms (configured {configured})",
+                timer.elapsed().as_millis()
+            )),
+        ))
+    }
+
+    fn process_notify(
+        &self,
+        notify: &NotifyStep,
+        variables: &HashMap<String, LiteralValue>,
+        secrets: &mut SecretStore,
+    ) -> StepOutcome {
+        let resolved = substitute_variables(&notify.url, variables, secrets).and_then(|url| {
+            let body = notify
+                .body
+                .as_deref()
+                .map(|body| substitute_variables(body, variables, secrets))
+                .transpose()?;
+            let headers = resolve_map(&notify.headers, variables, secrets)?;
+            Ok((url, body, headers))
+        });
+        let (url, body, headers) = match resolved {
+            Ok(values) => values,
+            Err(err) => {
+                return StepOutcome::from_execution(StepExecution::failed(
+                    notify.name.clone(),
+                    StepKind::Notify,
+                    Some(format!("failed to resolve variables: {err}")),
+                ))
+            }
+        };
+
+        let masked_url = secrets.mask(&url);
+        let masked_body = body.as_deref().map(|body| secrets.mask(body));
+        let artifact_name = format!("notify:{}", notify.name);
+
+        if self.dry_run {
+            return StepOutcome::with_artifact(
+                StepExecution::skipped(
+                    notify.name.clone(),
+                    StepKind::Notify,
+                    Some(format!(
+                        "[dry-run] would send {} {}",
+                        notify.method, masked_url
+                    )),
+                ),
+                StoredArtifact {
+                    name: artifact_name,
+                    kind: ArtifactKind::Notification,
+                    path: None,
+                    content_hash: None,
+                    data: json!({
+                        "name": notify.name,
+                        "method": notify.method,
+                        "url": masked_url,
+                        "body": masked_body,
+                        "dry_run": true,
+                    }),
+                },
+            );
+        }
+
+        match send_notification(&notify.method, &url, body, &headers) {
+            Ok((status, response)) => {
+                let response = secrets.mask(&response);
+                let message = format!("{} {} returned HTTP {}", notify.method, masked_url, status);
+                let execution = if status >= 400 {
+                    StepExecution::failed(notify.name.clone(), StepKind::Notify, Some(message))
+                } else {
+                    StepExecution::completed(notify.name.clone(), StepKind::Notify, Some(message))
+                };
+                StepOutcome::with_artifact(
+                    execution,
+                    StoredArtifact {
+                        name: artifact_name,
+                        kind: ArtifactKind::Notification,
+                        path: None,
+                        content_hash: None,
+                        data: json!({
+                            "name": notify.name,
+                            "method": notify.method,
+                            "url": masked_url,
+                            "body": masked_body,
+                            "status": status,
+                            "response": response,
+                        }),
+                    },
+                )
+            }
+            Err(err) => StepOutcome::from_execution(StepExecution::failed(
+                notify.name.clone(),
+                StepKind::Notify,
+                Some(secrets.mask(&err)),
+            )),
+        }
+    }
+
+    fn process_generic_scan(
+        &self,
+        scan: &ScanStep,
+        params: BTreeMap<String, String>,
+        timeout: Option<Duration>,
+    ) -> StepOutcome {
+        let mut cmd = Command::new(&scan.tool);
+        let mut invocation = vec![scan.tool.clone()];
+
+        if let Some(flags) = params.get("flags") {
+            match shell_words::split(flags) {
+                Ok(parts) => {
+                    for part in parts {
+                        cmd.arg(&part);
+                        invocation.push(part);
+                    }
+                }
+                Err(err) => {
+                    return StepOutcome::from_execution(StepExecution::failed(
+                        scan.name.clone(),
+                        StepKind::Scan,
+                        Some(format!("failed to parse flags: {err}")),
+                    ));
+                }
+            }
+        }
+
+        if let Some(args) = params.get("args") {
+            match shell_words::split(args) {
+                Ok(parts) => {
+                    for part in parts {
+                        cmd.arg(&part);
+                        invocation.push(part);
+                    }
+                }
+                Err(err) => {
+                    return StepOutcome::from_execution(StepExecution::failed(
+                        scan.name.clone(),
+                        StepKind::Scan,
+                        Some(format!("failed to parse args: {err}")),
+                    ));
+                }
+            }
+        }
+
+        for part in tool_param_args(&scan.tool, &params, &invocation[1..]) {
+            cmd.arg(&part);
+            invocation.push(part);
+        }
+
+        if let Some(target) = params.get("target") {
+            if !target.is_empty() {
+                cmd.arg(target);
+                invocation.push(target.clone());
+            }
+        }
+
+        if let Some(cwd) = params.get("cwd") {
+            if !cwd.is_empty() {
+                cmd.current_dir(self.working_dir(cwd));
+            }
+        }
+
+        let started_at = OffsetDateTime::now_utc();
         let timer = Instant::now();
 
-        match cmd.output() {
-            Ok(output) => {
+        match run_command(&mut cmd, timeout) {
+            Ok((output, timed_out)) => {
                 let timestamp = started_at
                     .format(&time::format_description::well_known::Rfc3339)
                     .unwrap_or_else(|_| "unknown".to_string());
@@ -347,7 +1274,7 @@ impl Executor {
                     .output
                     .clone()
                     .unwrap_or_else(|| format!("scan_{}", scan.name));
-                let artifact_data = json!({
+                let mut artifact_data = json!({
                     "tool": scan.tool.clone(),
                     "params": params,
                     "invocation": invocation,
@@ -357,16 +1284,34 @@ impl Executor {
                     "started_at": timestamp,
                     "duration_ms": duration_ms,
                 });
+                if timed_out {
+                    artifact_data["timed_out"] = json!(true);
+                }
+
+                let (path, content_hash) = self.write_artifact(&label, &artifact_data);
+                let path_text = path.clone().unwrap_or_else(|| "<memory>".to_string());
 
-                let path = self
-                    .write_artifact(&label, &artifact_data)
-                    .map(|p| p.to_string_lossy().to_string());
+                if timed_out {
+                    let message = format!(
+                        "{} timed out after {} and was killed. artifact: {path_text}",
+                        scan.tool,
+                        format_duration_ms(timeout.unwrap_or_default().as_millis() as u64)
+                    );
+                    return StepOutcome::with_artifact(
+                        StepExecution::failed(scan.name.clone(), StepKind::Scan, Some(message)),
+                        StoredArtifact {
+                            name: label,
+                            kind: ArtifactKind::Scan,
+                            path,
+                            content_hash,
+                            data: artifact_data,
+                        },
+                    );
+                }
 
                 let message = format!(
-                    "{} executed. exit: {:?}. artifact: {}",
-                    scan.tool,
-                    exit_code,
-                    path.clone().unwrap_or_else(|| "<memory>".to_string())
+                    "{} executed. exit: {:?}. artifact: {path_text}",
+                    scan.tool, exit_code
                 );
 
                 let execution = if output.status.success() {
@@ -381,6 +1326,7 @@ impl Executor {
                         name: label,
                         kind: ArtifactKind::Scan,
                         path,
+                        content_hash,
                         data: artifact_data,
                     },
                 )
@@ -393,1749 +1339,7858 @@ impl Executor {
         }
     }
 
-    fn process_script(
+    fn process_merge(
         &self,
-        script: &ScriptStep,
+        merge: &MergeStep,
         variables: &HashMap<String, LiteralValue>,
+        store: &HashMap<String, StoredArtifact>,
         secrets: &mut SecretStore,
     ) -> StepOutcome {
-        let params = match resolve_map(&script.params, variables, secrets) {
-            Ok(map) => map,
+        let resolved = resolve_list(&merge.inputs, variables, secrets).and_then(|inputs| {
+            substitute_variables(&merge.output, variables, secrets).map(|output| (inputs, output))
+        });
+        let (inputs, label) = match resolved {
+            Ok(resolved) => resolved,
             Err(err) => {
                 return StepOutcome::from_execution(StepExecution::failed(
-                    script.name.clone(),
-                    StepKind::Script,
+                    merge.output.clone(),
+                    StepKind::Merge,
                     Some(format!("failed to resolve variables: {err}")),
                 ))
             }
         };
 
-        let run_value = match params.get("run") {
-            Some(value) if !value.trim().is_empty() => value.clone(),
-            _ => {
-                return StepOutcome::from_execution(StepExecution::failed(
-                    script.name.clone(),
-                    StepKind::Script,
-                    Some("missing required parameter: run".to_string()),
-                ))
-            }
-        };
-
-        let mut program_and_initial_args = match shell_words::split(&run_value) {
-            Ok(parts) => parts,
-            Err(err) => {
-                return StepOutcome::from_execution(StepExecution::failed(
-                    script.name.clone(),
-                    StepKind::Script,
-                    Some(format!("failed to parse 'run' command: {err}")),
-                ))
-            }
-        };
-
-        if program_and_initial_args.is_empty() {
-            return StepOutcome::from_execution(StepExecution::failed(
-                script.name.clone(),
-                StepKind::Script,
-                Some("run command produced no executable".to_string()),
-            ));
-        }
-
-        let program = program_and_initial_args.remove(0);
-        let mut invocation = vec![program.clone()];
-        let mut cmd = Command::new(&program);
-        for arg in program_and_initial_args {
-            cmd.arg(&arg);
-            invocation.push(arg);
-        }
-
-        if let Some(args_value) = params.get("args") {
-            match shell_words::split(args_value) {
-                Ok(extra) => {
-                    for arg in extra {
-                        cmd.arg(&arg);
-                        invocation.push(arg);
-                    }
+        let mut merged: Option<ScanArtifacts> = None;
+        for input in &inputs {
+            let parsed = match store.get(input) {
+                Some(artifact)
+                    if matches!(artifact.kind, ArtifactKind::Scan | ArtifactKind::Transform) =>
+                {
+                    serde_json::from_value::<ScanArtifacts>(artifact.data.clone()).map_err(|_| {
+                        format!("artifact '{input}' does not contain parsed scan results")
+                    })
+                }
+                Some(_) => Err(format!("artifact '{input}' is not a scan artifact")),
+                None => Err(format!("artifact '{input}' not found")),
+            };
+            match parsed {
+                Ok(scan) => {
+                    merged = Some(match merged {
+                        Some(existing) => existing.merge(scan),
+                        None => scan,
+                    });
                 }
                 Err(err) => {
                     return StepOutcome::from_execution(StepExecution::failed(
-                        script.name.clone(),
-                        StepKind::Script,
-                        Some(format!("failed to parse 'args' value: {err}")),
+                        label,
+                        StepKind::Merge,
+                        Some(err),
                     ))
                 }
             }
         }
+        let Some(merged) = merged else {
+            return StepOutcome::from_execution(StepExecution::failed(
+                label,
+                StepKind::Merge,
+                Some("no artifacts to merge".to_string()),
+            ));
+        };
 
-        if let Some(cwd) = params.get("cwd") {
-            cmd.current_dir(cwd);
-        }
-
-        let started_at = OffsetDateTime::now_utc();
-        let timer = Instant::now();
-
-        match cmd.output() {
-            Ok(output) => {
-                let duration_ms = timer.elapsed().as_millis();
-                let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-                let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-                let exit_code = output.status.code();
-                let timestamp = started_at
-                    .format(&time::format_description::well_known::Rfc3339)
-                    .unwrap_or_else(|_| "unknown".to_string());
-
-                let artifact_label = script
-                    .output
-                    .clone()
-                    .unwrap_or_else(|| format!("script_{}", script.name));
-                let artifact_data = json!(ScriptArtifact {
-                    name: script.name.clone(),
-                    command: invocation.clone(),
-                    stdout,
-                    stderr,
-                    exit_code,
-                    started_at: timestamp,
-                    duration_ms,
-                });
-                let path = self
-                    .write_artifact(&artifact_label, &artifact_data)
-                    .map(|p| p.to_string_lossy().to_string());
+        let message = format!(
+            "merged {} artifact(s): {} asset(s), {} finding(s)",
+            inputs.len(),
+            merged.assets.len(),
+            merged.findings.len()
+        );
+        let data = json!(merged);
+        let (path, content_hash) = self.write_artifact(&label, &data);
 
-                let message = format!(
-                    "script '{}' executed with code {:?}. artifact: {}",
-                    artifact_label,
-                    exit_code,
-                    path.clone().unwrap_or_else(|| "<memory>".to_string())
-                );
+        StepOutcome::with_artifact(
+            StepExecution::completed(label.clone(), StepKind::Merge, Some(message)),
+            StoredArtifact {
+                name: label,
+                kind: ArtifactKind::Scan,
+                path,
+                content_hash,
+                data,
+            },
+        )
+    }
 
-                let execution = if output.status.success() {
-                    StepExecution::completed(script.name.clone(), StepKind::Script, Some(message))
-                } else {
-                    StepExecution::failed(script.name.clone(), StepKind::Script, Some(message))
+    fn process_transform(
+        &self,
+        transform: &TransformStep,
+        variables: &HashMap<String, LiteralValue>,
+        store: &HashMap<String, StoredArtifact>,
+        secrets: &mut SecretStore,
+    ) -> StepOutcome {
+        let fail = |message: String| {
+            StepOutcome::from_execution(StepExecution::failed(
+                transform.name.clone(),
+                StepKind::Transform,
+                Some(message),
+            ))
+        };
+        let resolved =
+            substitute_variables(&transform.source, variables, secrets).and_then(|source| {
+                let output = match &transform.output {
+                    Some(output) => substitute_variables(output, variables, secrets)?,
+                    None => source.clone(),
                 };
+                Ok((source, output))
+            });
+        let (source, label) = match resolved {
+            Ok(resolved) => resolved,
+            Err(err) => return fail(format!("failed to resolve variables: {err}")),
+        };
+        let Some(artifact) = store.get(&source) else {
+            return fail(format!("artifact '{source}' not found"));
+        };
 
-                StepOutcome::with_artifact(
-                    execution,
-                    StoredArtifact {
-                        name: artifact_label,
-                        kind: ArtifactKind::Script,
-                        path,
-                        data: artifact_data,
-                    },
-                )
+        let mut data = artifact.data.clone();
+        for operation in &transform.operations {
+            if let Err(err) = apply_transform_op(&mut data, operation, variables, secrets) {
+                return fail(format!("{operation}: {err}"));
             }
-            Err(err) => StepOutcome::from_execution(StepExecution::failed(
-                script.name.clone(),
-                StepKind::Script,
-                Some(format!("failed to execute script '{}': {err}", script.name)),
-            )),
         }
+
+        let message = format!(
+            "applied {} operation(s) to '{source}' -> {label}",
+            transform.operations.len()
+        );
+        let (path, content_hash) = self.write_artifact(&label, &data);
+
+        StepOutcome::with_artifact(
+            StepExecution::completed(transform.name.clone(), StepKind::Transform, Some(message)),
+            StoredArtifact {
+                name: label,
+                kind: ArtifactKind::Transform,
+                path,
+                content_hash,
+                data,
+            },
+        )
     }
 
-    fn process_variable(
+    fn process_nuclei_scan(
         &self,
-        variable: &VariableDecl,
-        overrides: &HashMap<String, LiteralValue>,
-        variables: &mut HashMap<String, LiteralValue>,
-        secrets: &mut SecretStore,
+        scan: &ScanStep,
+        params: BTreeMap<String, String>,
+        timeout: Option<Duration>,
     ) -> StepOutcome {
-        let (resolved, note) = if let Some(raw) = overrides.get(&variable.name) {
-            match resolve_literal_value(raw, variables, secrets) {
-                Ok(value) => (value, Some("(override)")),
-                Err(err) => {
-                    return StepOutcome::from_execution(StepExecution::failed(
-                        variable.name.clone(),
-                        StepKind::Variable,
-                        Some(format!("failed to resolve override: {err}")),
-                    ))
-                }
-            }
-        } else {
-            match resolve_literal_value(&variable.value, variables, secrets) {
-                Ok(value) => (value, None),
-                Err(err) => {
-                    return StepOutcome::from_execution(StepExecution::failed(
-                        variable.name.clone(),
-                        StepKind::Variable,
-                        Some(format!("failed to resolve variables: {err}")),
-                    ))
-                }
+        let target = params.get("target").cloned().unwrap_or_default();
+        let mut args = match split_scan_params(&params, &["flags", "args"]) {
+            Ok(args) => args,
+            Err(err) => {
+                return StepOutcome::from_execution(StepExecution::failed(
+                    scan.name.clone(),
+                    StepKind::Scan,
+                    Some(err),
+                ))
             }
         };
+        let extra = tool_param_args(&scan.tool, &params, &args);
+        args.extend(extra);
+        if !target.is_empty() {
+            args.push("-u".to_string());
+            args.push(target.clone());
+        }
 
-        variables.insert(variable.name.clone(), resolved.clone());
+        self.run_parsed_scan(scan, &params, args, &target, timeout, parse_nuclei_jsonl)
+    }
 
-        let message = match note {
-            Some(tag) => format!("{} = {} {}", variable.name, resolved.display(), tag),
-            None => format!("{} = {}", variable.name, resolved.display()),
+    fn process_gobuster_scan(
+        &self,
+        scan: &ScanStep,
+        params: BTreeMap<String, String>,
+        timeout: Option<Duration>,
+    ) -> StepOutcome {
+        let target = params.get("target").cloned().unwrap_or_default();
+        let mut args = match split_scan_params(&params, &["mode", "args", "flags"]) {
+            Ok(args) => args,
+            Err(err) => {
+                return StepOutcome::from_execution(StepExecution::failed(
+                    scan.name.clone(),
+                    StepKind::Scan,
+                    Some(err),
+                ))
+            }
         };
+        let has_arg =
+            |args: &[String], names: &[&str]| args.iter().any(|arg| names.contains(&arg.as_str()));
+        if !target.is_empty() && !has_arg(&args, &["-u", "--url"]) {
+            args.push("-u".to_string());
+            args.push(target.clone());
+        }
+        if let Some(wordlist) = params.get("wordlist") {
+            if !wordlist.is_empty() && !has_arg(&args, &["-w", "--wordlist"]) {
+                args.push("-w".to_string());
+                args.push(wordlist.clone());
+            }
+        }
 
-        StepOutcome::from_execution(StepExecution::completed(
-            variable.name.clone(),
-            StepKind::Variable,
-            Some(message),
-        ))
+        let json_output = gobuster_emits_json(&args);
+        self.run_parsed_scan(scan, &params, args, &target, timeout, |output, target| {
+            parse_gobuster_output(output, target, json_output)
+        })
     }
 
-    fn process_secret(&self, secret: &SecretStep, secrets: &mut SecretStore) -> StepOutcome {
-        match &secret.source {
-            SecretSource::Env { mappings } => {
-                let mut aggregated: BTreeMap<String, String> = BTreeMap::new();
-
-                for (alias, env_key) in mappings {
-                    let scoped = format!("{}.{}", secret.name, alias);
-                    if let Some(existing) = secrets.peek(&scoped) {
-                        aggregated.insert(alias.clone(), existing.to_string());
-                        continue;
-                    }
-
-                    match env::var(env_key) {
-                        Ok(value) => {
-                            secrets.insert(scoped, value.clone());
-                            aggregated.insert(alias.clone(), value);
-                        }
-                        Err(err) => {
-                            return StepOutcome::from_execution(StepExecution::failed(
-                                secret.name.clone(),
-                                StepKind::Secret,
-                                Some(format!(
-                                    "failed to read environment variable {}: {err}",
-                                    env_key
-                                )),
-                            ))
-                        }
-                    }
-                }
-
-                if aggregated.len() == 1 {
-                    if let Some((_, value)) = aggregated.iter().next() {
-                        secrets.insert(secret.name.clone(), value.clone());
-                    }
-                }
+    // A scan killed by its `timeout`: fail the step but keep the partial
+    // output under the usual findings label.
+    fn timed_out_scan(
+        &self,
+        scan: &ScanStep,
+        label: String,
+        output: &std::process::Output,
+        timeout: Duration,
+    ) -> StepOutcome {
+        let data = json!({
+            "tool": scan.tool,
+            "stdout": truncate_output(&output.stdout),
+            "stderr": truncate_output(&output.stderr),
+            "timed_out": true,
+        });
+        let (path, content_hash) = self.write_artifact(&label, &data);
+        let message = format!(
+            "{} timed out after {} and was killed. artifact: {}",
+            scan.tool,
+            format_duration_ms(timeout.as_millis() as u64),
+            path.clone().unwrap_or_else(|| "<memory>".to_string())
+        );
+        StepOutcome::with_artifact(
+            StepExecution::failed(scan.name.clone(), StepKind::Scan, Some(message)),
+            StoredArtifact {
+                name: label,
+                kind: ArtifactKind::Scan,
+                path,
+                content_hash,
+                data,
+            },
+        )
+    }
 
-                let count = aggregated.len();
-                let message = if count == 1 {
-                    format!("secret '{}' loaded from env (1 value)", secret.name)
-                } else {
-                    format!(
-                        "secret '{}' loaded from env ({} values)",
-                        secret.name, count
-                    )
-                };
+    fn run_parsed_scan(
+        &self,
+        scan: &ScanStep,
+        params: &BTreeMap<String, String>,
+        args: Vec<String>,
+        target: &str,
+        timeout: Option<Duration>,
+        parse: impl FnOnce(&str, &str) -> Result<ScanArtifacts, String>,
+    ) -> StepOutcome {
+        let mut cmd = Command::new(&scan.tool);
+        cmd.args(&args);
+        if let Some(cwd) = params.get("cwd") {
+            if !cwd.is_empty() {
+                cmd.current_dir(self.working_dir(cwd));
+            }
+        }
 
-                StepOutcome::from_execution(StepExecution::completed(
-                    secret.name.clone(),
-                    StepKind::Secret,
-                    Some(message),
-                ))
+        let label = scan
+            .output
+            .clone()
+            .unwrap_or_else(|| format!("findings_{}", scan.name));
+        let output = match run_command(&mut cmd, timeout) {
+            Ok((output, false)) => output,
+            Ok((output, true)) => {
+                return self.timed_out_scan(scan, label, &output, timeout.unwrap_or_default())
             }
-            SecretSource::File { path } => match fs::read_to_string(path) {
-                Ok(value) => {
-                    secrets.insert(secret.name.clone(), value);
-                    StepOutcome::from_execution(StepExecution::completed(
-                        secret.name.clone(),
-                        StepKind::Secret,
-                        Some(format!("secret '{}' loaded from file", secret.name)),
-                    ))
-                }
-                Err(err) => StepOutcome::from_execution(StepExecution::failed(
-                    secret.name.clone(),
-                    StepKind::Secret,
-                    Some(format!("failed to read file {}: {err}", path)),
-                )),
-            },
-            SecretSource::Vault { .. } => {
-                StepOutcome::from_execution(StepExecution::not_implemented(
-                    secret.name.clone(),
-                    StepKind::Secret,
-                    Some("vault provider is not implemented yet".to_string()),
+            Err(err) => {
+                return StepOutcome::from_execution(StepExecution::failed(
+                    scan.name.clone(),
+                    StepKind::Scan,
+                    Some(format!("failed to spawn '{}': {}", scan.tool, err)),
                 ))
             }
+        };
+
+        if !output.status.success() {
+            let error_msg = format!(
+                "{} exited with code {:?}\nstdout:\n{}\nstderr:\n{}",
+                scan.tool,
+                output.status.code(),
+                truncate_output(&output.stdout),
+                truncate_output(&output.stderr)
+            );
+            return StepOutcome::from_execution(StepExecution::failed(
+                scan.name.clone(),
+                StepKind::Scan,
+                Some(error_msg),
+            ));
         }
-    }
 
-    fn process_asset_group(
-        &self,
-        group: &AssetGroupStep,
-        variables: &HashMap<String, LiteralValue>,
-        secrets: &mut SecretStore,
-    ) -> StepOutcome {
-        let resolved = match resolve_map(&group.properties, variables, secrets) {
-            Ok(map) => map,
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut parsed = match parse(&stdout, target) {
+            Ok(parsed) => parsed,
             Err(err) => {
                 return StepOutcome::from_execution(StepExecution::failed(
-                    group.name.clone(),
-                    StepKind::AssetGroup,
-                    Some(format!("failed to resolve variables: {err}")),
+                    scan.name.clone(),
+                    StepKind::Scan,
+                    Some(format!("failed to parse {} output: {err}", scan.tool)),
                 ))
             }
         };
 
-        let artifact_name = format!("asset_group:{}", group.name);
-        let data = json!(AssetGroupArtifact {
-            name: group.name.clone(),
-            properties: resolved.clone(),
-        });
+        let dedup_note = deduplicate_scan(&mut parsed, params);
+        let message = format!(
+            "{} reported {} finding(s) for target {}.{}",
+            scan.tool,
+            parsed.findings.len(),
+            target,
+            dedup_note
+        );
+        let data = json!(parsed);
+        let (path, content_hash) = self.write_artifact(&label, &data);
+        let message = format!(
+            "{message}\nartifact: {}",
+            path.clone().unwrap_or_else(|| "<memory>".to_string())
+        );
 
         StepOutcome::with_artifact(
-            StepExecution::skipped(
-                group.name.clone(),
-                StepKind::AssetGroup,
-                Some("stored asset group definition".to_string()),
-            ),
+            StepExecution::completed(scan.name.clone(), StepKind::Scan, Some(message)),
             StoredArtifact {
-                name: artifact_name,
-                kind: ArtifactKind::AssetGroup,
-                path: None,
+                name: label,
+                kind: ArtifactKind::Scan,
+                path,
+                content_hash,
                 data,
             },
         )
     }
 
-    fn process_scan(
+    fn process_script(
         &self,
-        scan: &ScanStep,
+        script: &ScriptStep,
         variables: &HashMap<String, LiteralValue>,
         secrets: &mut SecretStore,
     ) -> StepOutcome {
-        let params = match resolve_map(&scan.params, variables, secrets) {
+        let params = match resolve_map(&script.params, variables, secrets) {
             Ok(map) => map,
             Err(err) => {
                 return StepOutcome::from_execution(StepExecution::failed(
-                    scan.name.clone(),
-                    StepKind::Scan,
+                    script.name.clone(),
+                    StepKind::Script,
                     Some(format!("failed to resolve variables: {err}")),
                 ))
             }
         };
 
-        if scan.tool != "nmap" {
-            return self.process_generic_scan(scan, params);
-        }
+        let run_value = match params.get("run") {
+            Some(value) if !value.trim().is_empty() => value.clone(),
+            _ => {
+                return StepOutcome::from_execution(StepExecution::failed(
+                    script.name.clone(),
+                    StepKind::Script,
+                    Some("missing required parameter: run".to_string()),
+                ))
+            }
+        };
 
-        let target = match params.get("target") {
-            Some(value) => value.clone(),
-            None => {
+        let mut program_and_initial_args = match shell_words::split(&run_value) {
+            Ok(parts) => parts,
+            Err(err) => {
                 return StepOutcome::from_execution(StepExecution::failed(
-                    scan.name.clone(),
-                    StepKind::Scan,
-                    Some("missing required parameter: target".to_string()),
+                    script.name.clone(),
+                    StepKind::Script,
+                    Some(format!("failed to parse 'run' command: {err}")),
                 ))
             }
         };
 
-        let mut cmd = Command::new(&scan.tool);
+        if program_and_initial_args.is_empty() {
+            return StepOutcome::from_execution(StepExecution::failed(
+                script.name.clone(),
+                StepKind::Script,
+                Some("run command produced no executable".to_string()),
+            ));
+        }
 
-        if let Some(flags) = params.get("flags") {
-            match shell_words::split(flags) {
-                Ok(parts) => {
-                    for part in parts {
-                        cmd.arg(part);
+        let program = program_and_initial_args.remove(0);
+        let mut invocation = vec![program.clone()];
+        let mut cmd = Command::new(&program);
+        for arg in program_and_initial_args {
+            cmd.arg(&arg);
+            invocation.push(arg);
+        }
+
+        if let Some(args_value) = params.get("args") {
+            match shell_words::split(args_value) {
+                Ok(extra) => {
+                    for arg in extra {
+                        cmd.arg(&arg);
+                        invocation.push(arg);
                     }
                 }
                 Err(err) => {
                     return StepOutcome::from_execution(StepExecution::failed(
-                        scan.name.clone(),
-                        StepKind::Scan,
-                        Some(format!("failed to parse flags: {err}")),
-                    ));
+                        script.name.clone(),
+                        StepKind::Script,
+                        Some(format!("failed to parse 'args' value: {err}")),
+                    ))
                 }
             }
         }
 
-        cmd.arg("-oX");
-        cmd.arg("-");
-        cmd.arg(&target);
-
-        match cmd.output() {
-            Ok(output) => {
-                if output.status.success() {
-                    match parse_nmap_xml(&output.stdout, &target) {
-                        Ok(parsed) => {
-                            let label = scan
-                                .output
-                                .clone()
-                                .unwrap_or_else(|| format!("findings_{}", scan.name));
-                            let data = json!(parsed);
-                            let path = self
-                                .write_artifact(&label, &data)
-                                .map(|p| p.to_string_lossy().to_string());
+        if let Some(cwd) = params.get("cwd") {
+            cmd.current_dir(self.working_dir(cwd));
+        }
 
-                            let message = format!(
-                                "{} completed for target {}.\nartifact: {}",
-                                scan.tool,
-                                target,
-                                path.clone().unwrap_or_else(|| "<memory>".to_string())
-                            );
-
-                            StepOutcome::with_artifact(
-                                StepExecution::completed(
-                                    scan.name.clone(),
-                                    StepKind::Scan,
-                                    Some(message),
-                                ),
-                                StoredArtifact {
-                                    name: label,
-                                    kind: ArtifactKind::Scan,
-                                    path,
-                                    data,
-                                },
-                            )
-                        }
-                        Err(err) => StepOutcome::from_execution(StepExecution::failed(
-                            scan.name.clone(),
-                            StepKind::Scan,
-                            Some(format!("failed to parse nmap output: {err}")),
-                        )),
-                    }
-                } else {
-                    let error_msg = format!(
-                        "{} exited with code {:?}\nstdout:\n{}\nstderr:\n{}",
-                        scan.tool,
-                        output.status.code(),
-                        truncate_output(&output.stdout),
-                        truncate_output(&output.stderr)
-                    );
-                    StepOutcome::from_execution(StepExecution::failed(
-                        scan.name.clone(),
-                        StepKind::Scan,
-                        Some(error_msg),
-                    ))
-                }
-            }
-            Err(err) => StepOutcome::from_execution(StepExecution::failed(
-                scan.name.clone(),
-                StepKind::Scan,
-                Some(format!("failed to spawn '{}': {}", scan.tool, err)),
-            )),
-        }
-    }
-
-    fn process_report(
-        &self,
-        report: &ReportStep,
-        store: &HashMap<String, StoredArtifact>,
-        variables: &HashMap<String, LiteralValue>,
-        secrets: &mut SecretStore,
-    ) -> StepOutcome {
-        let include_names = match resolve_list(&report.includes, variables, secrets) {
-            Ok(list) => list,
+        let env = match resolve_map(&script.env, variables, secrets) {
+            Ok(map) => map,
             Err(err) => {
                 return StepOutcome::from_execution(StepExecution::failed(
-                    report.name.clone(),
-                    StepKind::Report,
-                    Some(format!("failed to resolve variables: {err}")),
+                    script.name.clone(),
+                    StepKind::Script,
+                    Some(format!("failed to resolve env: {err}")),
                 ))
             }
         };
+        cmd.envs(&env);
+        // Values are listed so secrets among them get masked like any other message text.
+        let env_note = if env.is_empty() {
+            String::new()
+        } else {
+            let entries: Vec<String> = env
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect();
+            format!(" with env {}", entries.join(", "))
+        };
 
-        let mut includes = BTreeMap::new();
-        let mut tables = BTreeMap::new();
-
-        for include in &include_names {
-            match store.get(include) {
-                Some(artifact) => {
-                    includes.insert(include.clone(), artifact.data.clone());
-                    if artifact.kind == ArtifactKind::Scan {
-                        if let Some(table) = build_table_from_scan(&artifact.data) {
-                            tables.insert(include.clone(), table);
-                        }
-                    }
-                }
-                None => {
-                    return StepOutcome::from_execution(StepExecution::failed(
-                        report.name.clone(),
-                        StepKind::Report,
-                        Some(format!("missing artifact '{}'", include)),
-                    ));
-                }
-            }
+        if self.dry_run {
+            let artifact_label = script
+                .output
+                .clone()
+                .unwrap_or_else(|| format!("script_{}", script.name));
+            let message = format!("[dry-run] would run {}{env_note}", invocation.join(" "));
+            return StepOutcome::with_artifact(
+                StepExecution::skipped(script.name.clone(), StepKind::Script, Some(message)),
+                StoredArtifact {
+                    name: artifact_label,
+                    kind: ArtifactKind::Script,
+                    path: None,
+                    content_hash: None,
+                    data: json!(ScriptArtifact {
+                        name: script.name.clone(),
+                        command: invocation,
+                        stdout: String::new(),
+                        stderr: String::new(),
+                        exit_code: None,
+                        started_at: "dry-run".to_string(),
+                        duration_ms: 0,
+                        timed_out: false,
+                    }),
+                },
+            );
         }
 
-        let generated_at = OffsetDateTime::now_utc()
-            .format(&time::format_description::well_known::Rfc3339)
-            .unwrap_or_else(|_| "unknown".to_string());
-        let report_label = format!("report:{}", report.name);
-
-        let options = match resolve_map(&report.options, variables, secrets) {
-            Ok(map) => map,
+        let timeout = match step_timeout(&params) {
+            Ok(timeout) => timeout,
             Err(err) => {
                 return StepOutcome::from_execution(StepExecution::failed(
-                    report.name.clone(),
-                    StepKind::Report,
-                    Some(format!("failed to resolve report options: {err}")),
+                    script.name.clone(),
+                    StepKind::Script,
+                    Some(err),
                 ))
             }
         };
 
-        let display_title = options
-            .get("title")
-            .cloned()
-            .unwrap_or_else(|| report.name.clone());
+        self.with_retries(&script.name, script.retry.as_ref(), || {
+            self.run_script(script, &params, &mut cmd, &invocation, timeout, &env_note)
+        })
+    }
 
-        match report.format {
-            ReportFormat::Stdout => {
-                let report_data = json!(ReportArtifact {
-                    target: report.name.clone(),
-                    format: report.format.as_str().to_string(),
-                    generated_at: generated_at.clone(),
-                    includes: includes.clone(),
-                    tables: tables.clone(),
-                    output_path: None::<String>,
-                    options: options.clone(),
-                });
+    // One attempt at running the script; `process_script` repeats it on failure.
+    fn run_script(
+        &self,
+        script: &ScriptStep,
+        params: &BTreeMap<String, String>,
+        cmd: &mut Command,
+        invocation: &[String],
+        timeout: Option<Duration>,
+        env_note: &str,
+    ) -> StepOutcome {
+        let started_at = OffsetDateTime::now_utc();
+        let timer = Instant::now();
 
-                let path = self
-                    .write_artifact(&report_label, &report_data)
-                    .map(|p| p.to_string_lossy().to_string());
+        match run_command(cmd, timeout) {
+            Ok((output, timed_out)) => {
+                let duration_ms = timer.elapsed().as_millis();
+                let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+                let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+                let exit_code = output.status.code();
+                let timestamp = started_at
+                    .format(&time::format_description::well_known::Rfc3339)
+                    .unwrap_or_else(|_| "unknown".to_string());
 
-                if let Ok(pretty) = serde_json::to_string_pretty(&report_data) {
-                    println!("{pretty}");
-                }
-                for (alias, table) in &tables {
-                    if table.rows.is_empty() {
-                        continue;
+                let artifact_label = script
+                    .output
+                    .clone()
+                    .unwrap_or_else(|| format!("script_{}", script.name));
+                // With `json_output true`, parsed stdout becomes the artifact
+                // itself so reports and transforms can read it directly.
+                let json_output = !timed_out
+                    && params
+                        .get("json_output")
+                        .is_some_and(|value| value.eq_ignore_ascii_case("true"));
+                let (parsed, json_note) = if json_output {
+                    match serde_json::from_str::<Value>(&stdout) {
+                        Ok(value) => (Some(value), " stored parsed JSON output."),
+                        Err(err) => {
+                            tracing::warn!(
+                                script = %script.name,
+                                "stdout is not valid JSON ({err}); storing raw output"
+                            );
+                            (None, " stdout was not valid JSON; stored raw output.")
+                        }
                     }
-                    println!("\n[table] {alias}");
-                    let rendered = render_table(table);
-                    println!("{rendered}");
-                }
+                } else {
+                    (None, "")
+                };
+                let artifact_data = parsed.unwrap_or_else(|| {
+                    json!(ScriptArtifact {
+                        name: script.name.clone(),
+                        command: invocation.to_vec(),
+                        stdout,
+                        stderr,
+                        exit_code,
+                        started_at: timestamp,
+                        duration_ms,
+                        timed_out,
+                    })
+                });
+                let (path, content_hash) = self.write_artifact(&artifact_label, &artifact_data);
+                let path_text = path.clone().unwrap_or_else(|| "<memory>".to_string());
 
-                let message = format!(
-                    "report generated for target '{}'. artifact: {}",
-                    report.name,
-                    path.clone().unwrap_or_else(|| "<memory>".to_string())
-                );
+                let message = if timed_out {
+                    format!(
+                        "script '{artifact_label}' timed out after {} and was killed{env_note}. artifact: {path_text}",
+                        format_duration_ms(timeout.unwrap_or_default().as_millis() as u64)
+                    )
+                } else {
+                    format!(
+                        "script '{artifact_label}' executed with code {exit_code:?}{env_note}.{json_note} artifact: {path_text}"
+                    )
+                };
+
+                let execution = if output.status.success() && !timed_out {
+                    StepExecution::completed(script.name.clone(), StepKind::Script, Some(message))
+                } else {
+                    StepExecution::failed(script.name.clone(), StepKind::Script, Some(message))
+                };
 
                 StepOutcome::with_artifact(
-                    StepExecution::completed(report.name.clone(), StepKind::Report, Some(message)),
+                    execution,
                     StoredArtifact {
-                        name: report_label,
-                        kind: ArtifactKind::Report,
+                        name: artifact_label,
+                        kind: ArtifactKind::Script,
                         path,
-                        data: report_data,
+                        content_hash,
+                        data: artifact_data,
                     },
                 )
             }
-            ReportFormat::Html => {
-                let html = render_html_report(&display_title, &generated_at, &includes, &tables);
-                self.write_file_report(
-                    report,
-                    "html",
-                    html,
-                    includes.clone(),
-                    tables.clone(),
-                    options.clone(),
-                    generated_at.clone(),
-                    report_label,
-                )
-            }
-            ReportFormat::Markdown => {
-                let markdown =
-                    render_markdown_report(&display_title, &generated_at, &includes, &tables);
-                self.write_file_report(
-                    report,
-                    "md",
-                    markdown,
-                    includes.clone(),
-                    tables.clone(),
-                    options.clone(),
-                    generated_at.clone(),
-                    report_label,
-                )
-            }
-            ReportFormat::Sarif => {
-                match render_sarif_report(&display_title, &generated_at, &includes, &options) {
-                    Ok(payload) => self.write_file_report(
-                        report,
-                        "sarif",
-                        payload,
-                        includes.clone(),
-                        tables.clone(),
-                        options.clone(),
-                        generated_at.clone(),
-                        report_label,
-                    ),
-                    Err(err) => StepOutcome::from_execution(StepExecution::failed(
-                        report.name.clone(),
-                        StepKind::Report,
-                        Some(err),
-                    )),
-                }
-            }
+            Err(err) => StepOutcome::from_execution(StepExecution::failed(
+                script.name.clone(),
+                StepKind::Script,
+                Some(format!("failed to execute script '{}': {err}", script.name)),
+            )),
         }
     }
 
-    fn write_artifact(&self, label: &str, data: &Value) -> Option<PathBuf> {
-        let safe_label = sanitize_label(label);
-        let path = self.artifacts_dir.join(format!("{safe_label}.json"));
-
-        if let Some(parent) = path.parent() {
-            if let Err(err) = fs::create_dir_all(parent) {
-                eprintln!(
-                    "[warn] failed to create artifact directory {:?}: {err}",
-                    parent
-                );
-                return None;
-            }
-        }
-
-        match serde_json::to_vec_pretty(data) {
-            Ok(bytes) => match fs::File::create(&path) {
-                Ok(mut file) => {
-                    if let Err(err) = file.write_all(&bytes) {
-                        eprintln!("[warn] failed to write artifact {:?}: {err}", path);
-                        None
-                    } else {
-                        Some(path)
-                    }
-                }
-                Err(err) => {
-                    eprintln!("[warn] failed to create artifact {:?}: {err}", path);
-                    None
+    // Runs `attempt` until it stops failing or the `retry` count is used up,
+    // waiting between attempts as the policy says. Only the last outcome, and
+    // so only its artifact, is kept.
+    fn with_retries(
+        &self,
+        step: &str,
+        retry: Option<&RetryPolicy>,
+        mut attempt: impl FnMut() -> StepOutcome,
+    ) -> StepOutcome {
+        let Some(retry) = retry.filter(|retry| retry.count > 0) else {
+            return attempt();
+        };
+        let total = retry.count.saturating_add(1);
+        let mut number = 1;
+        loop {
+            let mut outcome = attempt();
+            let failed = outcome.execution.status == ExecutionStatus::Failed;
+            if !failed || number == total {
+                let note = format!("attempt {number} of {total}");
+                let message = outcome.execution.message.get_or_insert_with(String::new);
+                if message.is_empty() {
+                    *message = note;
+                } else {
+                    message.push_str(&format!(" ({note})"));
                 }
-            },
-            Err(err) => {
-                eprintln!("[warn] failed to serialize artifact '{}': {err}", label);
-                None
+                return outcome;
             }
+            let wait_ms = retry.wait_ms(number);
+            tracing::warn!(
+                step,
+                attempt = number,
+                total,
+                wait_ms,
+                "attempt failed; retrying"
+            );
+            std::thread::sleep(Duration::from_millis(wait_ms));
+            number += 1;
         }
     }
 
-    fn write_file_report(
+    fn process_variable(
         &self,
-        report: &ReportStep,
-        extension: &str,
-        contents: String,
-        includes: BTreeMap<String, Value>,
-        tables: BTreeMap<String, TableArtifact>,
-        options: BTreeMap<String, String>,
-        generated_at: String,
-        report_label: String,
+        variable: &VariableDecl,
+        overrides: &HashMap<String, LiteralValue>,
+        variables: &mut HashMap<String, LiteralValue>,
+        secrets: &mut SecretStore,
     ) -> StepOutcome {
-        match self.write_report_file(report, extension, &contents) {
-            Ok(path_buf) => {
-                let path_string = path_buf.to_string_lossy().to_string();
-                let report_data = json!(ReportArtifact {
-                    target: report.name.clone(),
-                    format: report.format.as_str().to_string(),
+        let (resolved, note) = if let Some(raw) = overrides.get(&variable.name) {
+            match resolve_literal_value(raw, variables, secrets) {
+                Ok(value) => (value, Some("(override)")),
+                Err(err) => {
+                    return StepOutcome::from_execution(StepExecution::failed(
+                        variable.name.clone(),
+                        StepKind::Variable,
+                        Some(format!("failed to resolve override: {err}")),
+                    ))
+                }
+            }
+        } else {
+            match resolve_literal_value(&variable.value, variables, secrets) {
+                Ok(value) => (value, None),
+                Err(err) => {
+                    return StepOutcome::from_execution(StepExecution::failed(
+                        variable.name.clone(),
+                        StepKind::Variable,
+                        Some(format!("failed to resolve variables: {err}")),
+                    ))
+                }
+            }
+        };
+
+        variables.insert(variable.name.clone(), resolved.clone());
+
+        let message = match note {
+            Some(tag) => format!("{} = {} {}", variable.name, resolved.display(), tag),
+            None => format!("{} = {}", variable.name, resolved.display()),
+        };
+
+        StepOutcome::from_execution(StepExecution::completed(
+            variable.name.clone(),
+            StepKind::Variable,
+            Some(message),
+        ))
+    }
+
+    fn process_secret(&self, secret: &SecretStep, secrets: &mut SecretStore) -> StepOutcome {
+        match &secret.source {
+            SecretSource::Env { mappings } => {
+                let mut aggregated: BTreeMap<String, String> = BTreeMap::new();
+
+                for (alias, env_key) in mappings {
+                    let scoped = format!("{}.{}", secret.name, alias);
+                    if let Some(existing) = secrets.peek(&scoped) {
+                        aggregated.insert(alias.clone(), existing.to_string());
+                        continue;
+                    }
+
+                    match env::var(env_key) {
+                        Ok(value) => {
+                            secrets.insert(scoped, value.clone());
+                            aggregated.insert(alias.clone(), value);
+                        }
+                        Err(err) => {
+                            return StepOutcome::from_execution(StepExecution::failed(
+                                secret.name.clone(),
+                                StepKind::Secret,
+                                Some(format!(
+                                    "failed to read environment variable {}: {err}",
+                                    env_key
+                                )),
+                            ))
+                        }
+                    }
+                }
+
+                if aggregated.len() == 1 {
+                    if let Some((_, value)) = aggregated.iter().next() {
+                        secrets.insert(secret.name.clone(), value.clone());
+                    }
+                }
+
+                let count = aggregated.len();
+                let message = if count == 1 {
+                    format!("secret '{}' loaded from env (1 value)", secret.name)
+                } else {
+                    format!(
+                        "secret '{}' loaded from env ({} values)",
+                        secret.name, count
+                    )
+                };
+
+                StepOutcome::from_execution(StepExecution::completed(
+                    secret.name.clone(),
+                    StepKind::Secret,
+                    Some(message),
+                ))
+            }
+            SecretSource::File { path, key: None } => match fs::read_to_string(path) {
+                Ok(value) => {
+                    secrets.insert(secret.name.clone(), value);
+                    StepOutcome::from_execution(StepExecution::completed(
+                        secret.name.clone(),
+                        StepKind::Secret,
+                        Some(format!("secret '{}' loaded from file", secret.name)),
+                    ))
+                }
+                Err(err) => StepOutcome::from_execution(StepExecution::failed(
+                    secret.name.clone(),
+                    StepKind::Secret,
+                    Some(format!("failed to read file {}: {err}", path)),
+                )),
+            },
+            SecretSource::File {
+                path,
+                key: Some(key),
+            } => {
+                let values = fs::read_to_string(path)
+                    .map_err(|err| format!("failed to read file {}: {err}", path))
+                    .and_then(|contents| parse_file_secret(path, &contents, key));
+                let values = match values {
+                    Ok(values) => values,
+                    Err(err) => {
+                        return StepOutcome::from_execution(StepExecution::failed(
+                            secret.name.clone(),
+                            StepKind::Secret,
+                            Some(err),
+                        ))
+                    }
+                };
+                if key == "*" {
+                    for (field, value) in &values {
+                        secrets.insert(format!("{}.{}", secret.name, field), value.clone());
+                    }
+                }
+                if values.len() == 1 {
+                    if let Some(value) = values.values().next() {
+                        secrets.insert(secret.name.clone(), value.clone());
+                    }
+                }
+
+                StepOutcome::from_execution(StepExecution::completed(
+                    secret.name.clone(),
+                    StepKind::Secret,
+                    Some(format!(
+                        "secret '{}' loaded from file ({} value{})",
+                        secret.name,
+                        values.len(),
+                        if values.len() == 1 { "" } else { "s" }
+                    )),
+                ))
+            }
+            SecretSource::Vault {
+                path,
+                field,
+                namespace,
+                mount,
+                address,
+                role_id,
+                secret_id,
+            } => {
+                if let Some(field_name) = field {
+                    let scoped = format!("{}.{}", secret.name, field_name);
+                    if let Some(existing) = secrets.peek(&scoped).map(str::to_string) {
+                        secrets.insert(secret.name.clone(), existing);
+                        return StepOutcome::from_execution(StepExecution::completed(
+                            secret.name.clone(),
+                            StepKind::Secret,
+                            Some(format!(
+                                "secret '{}' resolved from override (vault skipped)",
+                                secret.name
+                            )),
+                        ));
+                    }
+                }
+
+                let credentials = VaultCredentials {
+                    address: address.as_deref(),
+                    namespace: namespace.as_deref(),
+                    role_id: role_id.as_deref(),
+                    secret_id: secret_id.as_deref(),
+                };
+                let fetched = match fetch_vault_secret(
+                    &credentials,
+                    path,
+                    mount.as_deref(),
+                    field.as_deref(),
+                ) {
+                    Ok(fetched) => fetched,
+                    Err(err) => {
+                        return StepOutcome::from_execution(StepExecution::failed(
+                            secret.name.clone(),
+                            StepKind::Secret,
+                            Some(err),
+                        ))
+                    }
+                };
+
+                let lease = fetched.lease_duration.map(Duration::from_secs);
+                let mut store_value = |key: String, value: &String| match lease {
+                    Some(ttl) => secrets.insert_with_ttl(key, value.clone(), ttl),
+                    None => secrets.insert(key, value.clone()),
+                };
+                for (key, value) in &fetched.values {
+                    store_value(format!("{}.{}", secret.name, key), value);
+                }
+                if fetched.values.len() == 1 {
+                    if let Some(value) = fetched.values.values().next() {
+                        store_value(secret.name.clone(), value);
+                    }
+                }
+
+                let count = fetched.values.len();
+                let mut message = if count == 1 {
+                    format!("secret '{}' loaded from vault (1 value)", secret.name)
+                } else {
+                    format!(
+                        "secret '{}' loaded from vault ({} values)",
+                        secret.name, count
+                    )
+                };
+                if let Some(ttl) = fetched.lease_duration {
+                    message.push_str(&format!("; lease expires in {ttl}s"));
+                    tracing::warn!(
+                        secret = %secret.name,
+                        path = %path,
+                        "vault lease expires in {ttl}s"
+                    );
+                }
+
+                StepOutcome::from_execution(StepExecution::completed(
+                    secret.name.clone(),
+                    StepKind::Secret,
+                    Some(message),
+                ))
+            }
+            SecretSource::AwsSecretsManager {
+                secret_id,
+                region,
+                key,
+            } => {
+                if let Some(key) = key {
+                    let scoped = format!("{}.{}", secret.name, key);
+                    if let Some(existing) = secrets.peek(&scoped).map(str::to_string) {
+                        secrets.insert(secret.name.clone(), existing);
+                        return StepOutcome::from_execution(StepExecution::completed(
+                            secret.name.clone(),
+                            StepKind::Secret,
+                            Some(format!(
+                                "secret '{}' resolved from override (aws secrets manager skipped)",
+                                secret.name
+                            )),
+                        ));
+                    }
+                }
+
+                let values = match fetch_aws_secret(secret_id, region.as_deref(), key.as_deref()) {
+                    Ok(values) => values,
+                    Err(err) => {
+                        return StepOutcome::from_execution(StepExecution::failed(
+                            secret.name.clone(),
+                            StepKind::Secret,
+                            Some(err),
+                        ))
+                    }
+                };
+                for (field, value) in &values {
+                    secrets.insert(format!("{}.{}", secret.name, field), value.clone());
+                }
+                if values.len() == 1 {
+                    if let Some(value) = values.values().next() {
+                        secrets.insert(secret.name.clone(), value.clone());
+                    }
+                }
+
+                StepOutcome::from_execution(StepExecution::completed(
+                    secret.name.clone(),
+                    StepKind::Secret,
+                    Some(format!(
+                        "secret '{}' loaded from aws secrets manager ({} value{})",
+                        secret.name,
+                        values.len(),
+                        if values.len() == 1 { "" } else { "s" }
+                    )),
+                ))
+            }
+            SecretSource::Keyring { service, account } => {
+                if secrets.peek(&secret.name).is_some() {
+                    return StepOutcome::from_execution(StepExecution::completed(
+                        secret.name.clone(),
+                        StepKind::Secret,
+                        Some(format!(
+                            "secret '{}' resolved from override (keyring skipped)",
+                            secret.name
+                        )),
+                    ));
+                }
+                let (value, origin) = if self.keyring_mock_env {
+                    let variable = keyring_env_var(service, account);
+                    match env::var(&variable) {
+                        Ok(value) => (Ok(value), format!("environment variable {variable}")),
+                        Err(_) => (
+                            Err(format!(
+                                "keyring mock: environment variable {variable} is not set"
+                            )),
+                            String::new(),
+                        ),
+                    }
+                } else {
+                    (read_keyring(service, account), "keyring".to_string())
+                };
+
+                match value {
+                    Ok(value) => {
+                        secrets.insert(secret.name.clone(), value);
+                        StepOutcome::from_execution(StepExecution::completed(
+                            secret.name.clone(),
+                            StepKind::Secret,
+                            Some(format!("secret '{}' loaded from {origin}", secret.name)),
+                        ))
+                    }
+                    Err(err) => StepOutcome::from_execution(StepExecution::failed(
+                        secret.name.clone(),
+                        StepKind::Secret,
+                        Some(err),
+                    )),
+                }
+            }
+        }
+    }
+
+    fn process_asset_group(
+        &self,
+        group: &AssetGroupStep,
+        variables: &mut HashMap<String, LiteralValue>,
+        secrets: &mut SecretStore,
+    ) -> StepOutcome {
+        let resolved = match resolve_map(&group.properties, variables, secrets) {
+            Ok(map) => map,
+            Err(err) => {
+                return StepOutcome::from_execution(StepExecution::failed(
+                    group.name.clone(),
+                    StepKind::AssetGroup,
+                    Some(format!("failed to resolve variables: {err}")),
+                ))
+            }
+        };
+
+        let mut message = "stored asset group definition".to_string();
+        let mut hosts = Vec::new();
+        if let Some(scope) = resolved.get("scope") {
+            match cidr_address_count(scope) {
+                Some(count) if count <= MAX_EXPANDED_HOSTS => {
+                    hosts = expand_cidr(scope).unwrap_or_default();
+                    variables.insert(
+                        format!("{}_hosts", group.name),
+                        LiteralValue::Array(
+                            hosts.iter().cloned().map(LiteralValue::String).collect(),
+                        ),
+                    );
+                    message = format!(
+                        "stored asset group definition; expanded {scope} into {}_hosts ({} addresses)",
+                        group.name,
+                        hosts.len()
+                    );
+                }
+                Some(count) => {
+                    message = format!(
+                        "stored asset group definition; {scope} has {count} addresses, too many to expand"
+                    );
+                }
+                None => {}
+            }
+        }
+
+        let artifact_name = format!("asset_group:{}", group.name);
+        let data = json!(AssetGroupArtifact {
+            name: group.name.clone(),
+            properties: resolved.clone(),
+            hosts,
+        });
+
+        StepOutcome::with_artifact(
+            StepExecution::skipped(group.name.clone(), StepKind::AssetGroup, Some(message)),
+            StoredArtifact {
+                name: artifact_name,
+                kind: ArtifactKind::AssetGroup,
+                path: None,
+                content_hash: None,
+                data,
+            },
+        )
+    }
+
+    fn process_scan(
+        &self,
+        scan: &ScanStep,
+        variables: &HashMap<String, LiteralValue>,
+        secrets: &mut SecretStore,
+    ) -> StepOutcome {
+        let params = match resolve_map(&scan.params, variables, secrets) {
+            Ok(map) => map,
+            Err(err) => {
+                return StepOutcome::from_execution(StepExecution::failed(
+                    scan.name.clone(),
+                    StepKind::Scan,
+                    Some(format!("failed to resolve variables: {err}")),
+                ))
+            }
+        };
+
+        if self.dry_run {
+            return dry_run_scan(scan, &params);
+        }
+
+        let timeout = match step_timeout(&params) {
+            Ok(timeout) => timeout,
+            Err(err) => {
+                return StepOutcome::from_execution(StepExecution::failed(
+                    scan.name.clone(),
+                    StepKind::Scan,
+                    Some(err),
+                ))
+            }
+        };
+
+        self.with_retries(&scan.name, scan.retry.as_ref(), || {
+            self.run_scan(scan, params.clone(), timeout)
+        })
+    }
+
+    // One attempt at running the scan tool; `process_scan` repeats it on failure.
+    fn run_scan(
+        &self,
+        scan: &ScanStep,
+        params: BTreeMap<String, String>,
+        timeout: Option<Duration>,
+    ) -> StepOutcome {
+        if scan.tool == "nuclei" && nuclei_emits_json(params.get("flags").map(String::as_str)) {
+            return self.process_nuclei_scan(scan, params, timeout);
+        }
+
+        if scan.tool == "gobuster" {
+            return self.process_gobuster_scan(scan, params, timeout);
+        }
+
+        let masscan_xml =
+            scan.tool == "masscan" && masscan_emits_xml(params.get("flags").map(String::as_str));
+        if scan.tool != "nmap" && !masscan_xml {
+            return self.process_generic_scan(scan, params, timeout);
+        }
+
+        let target = match params.get("target") {
+            Some(value) => value.clone(),
+            None => {
+                return StepOutcome::from_execution(StepExecution::failed(
+                    scan.name.clone(),
+                    StepKind::Scan,
+                    Some("missing required parameter: target".to_string()),
+                ))
+            }
+        };
+
+        let mut cmd = Command::new(&scan.tool);
+
+        let flags = match split_scan_params(&params, &["flags"]) {
+            Ok(flags) => flags,
+            Err(err) => {
+                return StepOutcome::from_execution(StepExecution::failed(
+                    scan.name.clone(),
+                    StepKind::Scan,
+                    Some(err),
+                ))
+            }
+        };
+        let extra = tool_param_args(&scan.tool, &params, &flags);
+        cmd.args(&flags);
+        cmd.args(&extra);
+
+        if masscan_xml {
+            let has_filename = params
+                .get("flags")
+                .is_some_and(|flags| flags.contains("--output-filename"));
+            if !has_filename {
+                cmd.arg("--output-filename");
+                cmd.arg("-");
+            }
+        } else {
+            cmd.arg("-oX");
+            cmd.arg("-");
+        }
+        cmd.arg(&target);
+
+        match run_command(&mut cmd, timeout) {
+            Ok((output, true)) => {
+                let label = scan
+                    .output
+                    .clone()
+                    .unwrap_or_else(|| format!("findings_{}", scan.name));
+                self.timed_out_scan(scan, label, &output, timeout.unwrap_or_default())
+            }
+            Ok((output, false)) => {
+                if output.status.success() {
+                    let parsed = if masscan_xml {
+                        parse_masscan_xml(&output.stdout, &target)
+                    } else {
+                        parse_nmap_xml(&output.stdout, &target)
+                    };
+                    match parsed {
+                        Ok(mut parsed) => {
+                            let dedup_note = deduplicate_scan(&mut parsed, &params);
+                            let label = scan
+                                .output
+                                .clone()
+                                .unwrap_or_else(|| format!("findings_{}", scan.name));
+                            let data = json!(parsed);
+                            let (path, content_hash) = self.write_artifact(&label, &data);
+
+                            let message = format!(
+                                "{} completed for target {}.{}\nartifact: {}",
+                                scan.tool,
+                                target,
+                                dedup_note,
+                                path.clone().unwrap_or_else(|| "<memory>".to_string())
+                            );
+
+                            StepOutcome::with_artifact(
+                                StepExecution::completed(
+                                    scan.name.clone(),
+                                    StepKind::Scan,
+                                    Some(message),
+                                ),
+                                StoredArtifact {
+                                    name: label,
+                                    kind: ArtifactKind::Scan,
+                                    path,
+                                    content_hash,
+                                    data,
+                                },
+                            )
+                        }
+                        Err(err) => StepOutcome::from_execution(StepExecution::failed(
+                            scan.name.clone(),
+                            StepKind::Scan,
+                            Some(format!("failed to parse {} output: {err}", scan.tool)),
+                        )),
+                    }
+                } else {
+                    let error_msg = format!(
+                        "{} exited with code {:?}\nstdout:\n{}\nstderr:\n{}",
+                        scan.tool,
+                        output.status.code(),
+                        truncate_output(&output.stdout),
+                        truncate_output(&output.stderr)
+                    );
+                    StepOutcome::from_execution(StepExecution::failed(
+                        scan.name.clone(),
+                        StepKind::Scan,
+                        Some(error_msg),
+                    ))
+                }
+            }
+            Err(err) => StepOutcome::from_execution(StepExecution::failed(
+                scan.name.clone(),
+                StepKind::Scan,
+                Some(format!("failed to spawn '{}': {}", scan.tool, err)),
+            )),
+        }
+    }
+
+    fn process_report(
+        &self,
+        report: &ReportStep,
+        store: &HashMap<String, StoredArtifact>,
+        variables: &HashMap<String, LiteralValue>,
+        secrets: &mut SecretStore,
+    ) -> StepOutcome {
+        let include_names = match resolve_list(&report.includes, variables, secrets) {
+            Ok(list) => list,
+            Err(err) => {
+                return StepOutcome::from_execution(StepExecution::failed(
+                    report.name.clone(),
+                    StepKind::Report,
+                    Some(format!("failed to resolve variables: {err}")),
+                ))
+            }
+        };
+
+        let (include_names, match_note) = match expand_include_patterns(include_names, store) {
+            Ok(expanded) => expanded,
+            Err(err) => {
+                return StepOutcome::from_execution(StepExecution::failed(
+                    report.name.clone(),
+                    StepKind::Report,
+                    Some(err),
+                ))
+            }
+        };
+
+        let mut includes = BTreeMap::new();
+        let mut tables = BTreeMap::new();
+
+        for include in &include_names {
+            match store.get(include) {
+                Some(artifact) => {
+                    includes.insert(include.clone(), artifact.data.clone());
+                    if matches!(artifact.kind, ArtifactKind::Scan | ArtifactKind::Transform) {
+                        if let Some(table) = build_table_from_scan(&artifact.data) {
+                            tables.insert(include.clone(), table);
+                        }
+                    } else if artifact.kind == ArtifactKind::Script {
+                        // `json_output` scripts may print `{"findings": [...]}` or a bare list.
+                        let table = match &artifact.data {
+                            Value::Array(_) => {
+                                build_table_from_scan(&json!({ "findings": artifact.data }))
+                            }
+                            data => build_table_from_scan(data),
+                        };
+                        if let Some(table) = table {
+                            tables.insert(include.clone(), table);
+                        }
+                    }
+                }
+                None => {
+                    return StepOutcome::from_execution(StepExecution::failed(
+                        report.name.clone(),
+                        StepKind::Report,
+                        Some(format!("missing artifact '{}'", include)),
+                    ));
+                }
+            }
+        }
+
+        if self.dry_run {
+            return StepOutcome::from_execution(StepExecution::skipped(
+                report.name.clone(),
+                StepKind::Report,
+                Some(format!(
+                    "[dry-run] would render {} report with {} artifact(s)",
+                    report.format.as_str(),
+                    includes.len()
+                )),
+            ));
+        }
+
+        let generated_at = OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap_or_else(|_| "unknown".to_string());
+        let report_label = format!("report:{}", report.name);
+
+        let options = match resolve_map(&report.options, variables, secrets) {
+            Ok(map) => map,
+            Err(err) => {
+                return StepOutcome::from_execution(StepExecution::failed(
+                    report.name.clone(),
+                    StepKind::Report,
+                    Some(format!("failed to resolve report options: {err}")),
+                ))
+            }
+        };
+
+        let mut dedup_note = String::new();
+        if let Some(mode) = options.get("deduplicate") {
+            let key = match parse_deduplicate_options(mode, options.get("deduplicate_key")) {
+                Ok(key) => key,
+                Err(err) => {
+                    return StepOutcome::from_execution(StepExecution::failed(
+                        report.name.clone(),
+                        StepKind::Report,
+                        Some(err),
+                    ))
+                }
+            };
+            let key: Vec<&str> = key.iter().map(String::as_str).collect();
+            if let Some((merged, sources, removed)) =
+                merge_included_findings(&mut includes, &mut tables, store, &key)
+            {
+                let label = format!("report:{}:findings", report.name);
+                self.write_artifact(&label, &merged);
+                if let Some(table) = build_table_from_scan(&merged) {
+                    tables.insert(label.clone(), table);
+                }
+                includes.insert(label, merged);
+                dedup_note = format!(
+                    " ({removed} duplicate finding(s) removed across {sources} artifact(s))"
+                );
+            }
+        }
+
+        let mut filter_note = String::new();
+        if let Some(raw) = options.get("filter") {
+            let condition = match parse_condition_expr(raw) {
+                Ok(condition) => condition,
+                Err(err) => {
+                    return StepOutcome::from_execution(StepExecution::failed(
+                        report.name.clone(),
+                        StepKind::Report,
+                        Some(format!("invalid report filter: {err}")),
+                    ))
+                }
+            };
+            let removed: usize = tables
+                .values_mut()
+                .map(|table| filter_table_rows(table, &condition, variables, secrets))
+                .sum();
+            filter_note = format!(" ({removed} row(s) filtered out)");
+        }
+
+        if let Some(raw) = options.get("sort_by") {
+            match parse_sort_option(raw) {
+                Ok((column, descending)) => {
+                    for table in tables.values_mut() {
+                        *table = table.sort_by(&column, descending);
+                    }
+                }
+                Err(err) => {
+                    return StepOutcome::from_execution(StepExecution::failed(
+                        report.name.clone(),
+                        StepKind::Report,
+                        Some(err),
+                    ))
+                }
+            }
+        }
+
+        let display_title = options
+            .get("title")
+            .cloned()
+            .unwrap_or_else(|| report.name.clone());
+
+        let mut outcome = match report.format {
+            ReportFormat::Stdout => {
+                let report_data = json!(ReportArtifact {
+                    target: report.name.clone(),
+                    format: report.format.as_str().to_string(),
+                    generated_at: generated_at.clone(),
+                    includes: includes.clone(),
+                    tables: tables.clone(),
+                    output_path: None::<String>,
+                    options: options.clone(),
+                    row_count: None,
+                });
+
+                let (path, content_hash) = self.write_artifact(&report_label, &report_data);
+
+                if let Ok(pretty) = serde_json::to_string_pretty(&report_data) {
+                    println!("{pretty}");
+                }
+                for (alias, table) in &tables {
+                    if table.rows.is_empty() {
+                        continue;
+                    }
+                    println!("\n[table] {alias}");
+                    let rendered = render_table(table);
+                    println!("{rendered}");
+                }
+
+                let message = format!(
+                    "report generated for target '{}'. artifact: {}",
+                    report.name,
+                    path.clone().unwrap_or_else(|| "<memory>".to_string())
+                );
+
+                StepOutcome::with_artifact(
+                    StepExecution::completed(report.name.clone(), StepKind::Report, Some(message)),
+                    StoredArtifact {
+                        name: report_label,
+                        kind: ArtifactKind::Report,
+                        path,
+                        content_hash,
+                        data: report_data,
+                    },
+                )
+            }
+            ReportFormat::Html => {
+                let html =
+                    render_html_report(&display_title, &generated_at, &includes, &tables, &options);
+                self.write_file_report(
+                    report,
+                    "html",
+                    html,
+                    includes.clone(),
+                    tables.clone(),
+                    options.clone(),
+                    generated_at.clone(),
+                    report_label,
+                    None,
+                )
+            }
+            ReportFormat::Markdown => {
+                let markdown =
+                    render_markdown_report(&display_title, &generated_at, &includes, &tables);
+                self.write_file_report(
+                    report,
+                    "md",
+                    markdown,
+                    includes.clone(),
+                    tables.clone(),
+                    options.clone(),
+                    generated_at.clone(),
+                    report_label,
+                    None,
+                )
+            }
+            ReportFormat::Sarif => {
+                match render_sarif_report(&display_title, &generated_at, &includes, &options) {
+                    Ok(payload) => self.write_file_report(
+                        report,
+                        "sarif",
+                        payload,
+                        includes.clone(),
+                        tables.clone(),
+                        options.clone(),
+                        generated_at.clone(),
+                        report_label,
+                        None,
+                    ),
+                    Err(err) => StepOutcome::from_execution(StepExecution::failed(
+                        report.name.clone(),
+                        StepKind::Report,
+                        Some(err),
+                    )),
+                }
+            }
+            ReportFormat::Csv => {
+                let (csv, rows) = render_csv_report(&includes);
+                self.write_file_report(
+                    report,
+                    "csv",
+                    csv,
+                    includes.clone(),
+                    tables.clone(),
+                    options.clone(),
+                    generated_at.clone(),
+                    report_label,
+                    Some(rows),
+                )
+            }
+            ReportFormat::JunitXml => {
+                let xml = render_junit_report(&display_title, &generated_at, &includes, &options);
+                self.write_file_report(
+                    report,
+                    "xml",
+                    xml,
+                    includes.clone(),
+                    tables.clone(),
+                    options.clone(),
+                    generated_at.clone(),
+                    report_label,
+                    None,
+                )
+            }
+            ReportFormat::CycloneDx => {
+                match render_cyclonedx_report(&display_title, &generated_at, &includes) {
+                    Ok(payload) => self.write_file_report(
+                        report,
+                        "cdx.json",
+                        payload,
+                        includes.clone(),
+                        tables.clone(),
+                        options.clone(),
+                        generated_at.clone(),
+                        report_label,
+                        None,
+                    ),
+                    Err(err) => StepOutcome::from_execution(StepExecution::failed(
+                        report.name.clone(),
+                        StepKind::Report,
+                        Some(err),
+                    )),
+                }
+            }
+        };
+
+        let archive = options
+            .get("archive")
+            .is_some_and(|value| value.eq_ignore_ascii_case("true"));
+        if archive && outcome.execution.status == ExecutionStatus::Completed {
+            if let Some(artifact) = outcome.artifact.as_mut() {
+                match self.write_report_archive(report, &includes, &artifact.data) {
+                    Ok(path_buf) => {
+                        let path_string = path_buf.to_string_lossy().to_string();
+                        artifact.data["output_path"] = json!(path_string);
+                        artifact.path = Some(path_string.clone());
+                        if let Some(message) = outcome.execution.message.as_mut() {
+                            message.push_str(&format!(" (archived to {path_string})"));
+                        }
+                    }
+                    Err(err) => {
+                        outcome = StepOutcome::from_execution(StepExecution::failed(
+                            report.name.clone(),
+                            StepKind::Report,
+                            Some(format!("failed to write report archive: {err}")),
+                        ));
+                    }
+                }
+            }
+        }
+
+        if outcome.execution.status == ExecutionStatus::Completed {
+            if let Some(message) = outcome.execution.message.as_mut() {
+                message.push_str(&match_note);
+                message.push_str(&dedup_note);
+                message.push_str(&filter_note);
+            }
+        }
+        outcome
+    }
+
+    // Returns the stored location and, for content-addressed writes, the
+    // artifact's SHA-256 digest.
+    fn with_artifact_store<T>(&self, run: impl FnOnce(&dyn ArtifactStore) -> T) -> T {
+        match &self.store {
+            Some(store) => run(store.as_ref()),
+            None => run(&FilesystemArtifactStore::new(&self.artifacts_dir)),
+        }
+    }
+
+    /// Whether an artifact restored from a checkpoint still matches what was
+    /// written: report files must exist and stored JSON must be unchanged.
+    fn artifact_is_intact(&self, artifact: &StoredArtifact) -> bool {
+        let Some(path) = &artifact.path else {
+            return true;
+        };
+        if artifact.kind == ArtifactKind::Report {
+            return Path::new(path).is_file();
+        }
+        match self.with_artifact_store(|store| store.read(&artifact.name)) {
+            Ok(Some(data)) => match &artifact.content_hash {
+                Some(hash) => content_digest(&data).ok().as_ref() == Some(hash),
+                None => data == artifact.data,
+            },
+            _ => false,
+        }
+    }
+
+    fn write_artifact(&self, label: &str, data: &Value) -> (Option<String>, Option<String>) {
+        if self.dry_run {
+            return (None, None);
+        }
+        let written = self.with_artifact_store(|store| {
+            if self.content_addressable {
+                content_digest(data).and_then(|digest| {
+                    store
+                        .write_content_addressed(label, &digest, data, self.compress_artifacts)
+                        .map(|location| (location, Some(digest)))
+                })
+            } else if self.compress_artifacts {
+                store
+                    .write_compressed(label, data)
+                    .map(|location| (location, None))
+            } else {
+                store.write(label, data).map(|location| (location, None))
+            }
+        });
+        match written {
+            Ok(written) => written,
+            Err(err) => {
+                tracing::warn!("{err}");
+                (None, None)
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn write_file_report(
+        &self,
+        report: &ReportStep,
+        extension: &str,
+        contents: String,
+        includes: BTreeMap<String, Value>,
+        tables: BTreeMap<String, TableArtifact>,
+        options: BTreeMap<String, String>,
+        generated_at: String,
+        report_label: String,
+        row_count: Option<usize>,
+    ) -> StepOutcome {
+        match self.write_report_file(report, extension, &contents) {
+            Ok(path_buf) => {
+                let path_string = path_buf.to_string_lossy().to_string();
+                let report_data = json!(ReportArtifact {
+                    target: report.name.clone(),
+                    format: report.format.as_str().to_string(),
                     generated_at,
                     includes,
                     tables,
                     output_path: Some(path_string.clone()),
                     options,
+                    row_count,
+                });
+                let message = format!(
+                    "{} report written to {}",
+                    report.format.as_str(),
+                    path_string
+                );
+                StepOutcome::with_artifact(
+                    StepExecution::completed(report.name.clone(), StepKind::Report, Some(message)),
+                    StoredArtifact {
+                        name: report_label,
+                        kind: ArtifactKind::Report,
+                        path: Some(path_string),
+                        content_hash: None,
+                        data: report_data,
+                    },
+                )
+            }
+            Err(err) => StepOutcome::from_execution(StepExecution::failed(
+                report.name.clone(),
+                StepKind::Report,
+                Some(format!("failed to write {} report: {err}", extension)),
+            )),
+        }
+    }
+
+    fn write_report_file(
+        &self,
+        report: &ReportStep,
+        extension: &str,
+        contents: &str,
+    ) -> Result<PathBuf, String> {
+        let path = self.resolve_report_path(report, extension);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| format!("failed to create report directory {:?}: {err}", parent))?;
+        }
+        let mut file = fs::File::create(&path)
+            .map_err(|err| format!("failed to create report file {:?}: {err}", path))?;
+        file.write_all(contents.as_bytes())
+            .map_err(|err| format!("failed to write report file {:?}: {err}", path))?;
+        Ok(path)
+    }
+
+    /// Bundles the rendered report, every included artifact, and a
+    /// `manifest.json` listing them into `reports/<name>.zip`.
+    fn write_report_archive(
+        &self,
+        report: &ReportStep,
+        includes: &BTreeMap<String, Value>,
+        report_data: &Value,
+    ) -> Result<PathBuf, String> {
+        let label = sanitize_label(&report.name);
+        // Stdout reports have no rendered file, so their JSON artifact stands in.
+        let (report_entry, report_bytes) =
+            match report_data.get("output_path").and_then(Value::as_str) {
+                Some(rendered) => {
+                    let rendered = Path::new(rendered);
+                    let bytes = fs::read(rendered).map_err(|err| {
+                        format!("failed to read report file {:?}: {err}", rendered)
+                    })?;
+                    let entry = rendered
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_else(|| label.clone());
+                    (entry, bytes)
+                }
+                None => (
+                    format!("{label}.json"),
+                    serde_json::to_vec_pretty(report_data).map_err(|err| err.to_string())?,
+                ),
+            };
+
+        let mut entries = vec![(report_entry.clone(), report_bytes)];
+        let mut listed = Vec::new();
+        for (name, data) in includes {
+            let entry = format!("artifacts/{}.json", sanitize_label(name));
+            let bytes = serde_json::to_vec_pretty(data).map_err(|err| err.to_string())?;
+            listed.push(json!({ "name": name, "path": entry }));
+            entries.push((entry, bytes));
+        }
+        let manifest = json!({
+            "report": report.name,
+            "format": report.format.as_str(),
+            "generated_at": report_data.get("generated_at").cloned().unwrap_or(Value::Null),
+            "report_file": report_entry,
+            "artifacts": listed,
+        });
+        let manifest = serde_json::to_vec_pretty(&manifest).map_err(|err| err.to_string())?;
+        entries.insert(0, ("manifest.json".to_string(), manifest));
+
+        let path = self
+            .artifacts_dir
+            .join("reports")
+            .join(format!("{label}.zip"));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| format!("failed to create report directory {:?}: {err}", parent))?;
+        }
+        // Like artifacts, the archive only replaces an earlier one once complete.
+        write_atomically(&path, |file| {
+            let mut writer = zip::ZipWriter::new(file);
+            let entry_options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            for (name, bytes) in entries {
+                writer
+                    .start_file(name.as_str(), entry_options)
+                    .map_err(std::io::Error::other)?;
+                writer.write_all(&bytes)?;
+            }
+            writer.finish().map_err(std::io::Error::other)?;
+            Ok(())
+        })
+        .map_err(|err| format!("failed to write archive {:?}: {err}", path))?;
+        Ok(path)
+    }
+
+    fn resolve_report_path(&self, report: &ReportStep, extension: &str) -> PathBuf {
+        if let Some(custom) = &report.output {
+            let candidate = PathBuf::from(custom);
+            if candidate.is_absolute() {
+                candidate
+            } else {
+                self.artifacts_dir.join(candidate)
+            }
+        } else {
+            self.artifacts_dir.join("reports").join(format!(
+                "{}.{}",
+                sanitize_label(&report.name),
+                extension
+            ))
+        }
+    }
+}
+
+struct ParallelBranch {
+    variables: HashMap<String, LiteralValue>,
+    store: HashMap<String, StoredArtifact>,
+    report: Vec<StepExecution>,
+    secrets: SecretStore,
+}
+
+#[derive(Debug, Clone)]
+struct StepOutcome {
+    execution: StepExecution,
+    artifact: Option<StoredArtifact>,
+}
+
+impl StepOutcome {
+    fn from_execution(execution: StepExecution) -> Self {
+        Self {
+            execution,
+            artifact: None,
+        }
+    }
+
+    fn with_artifact(execution: StepExecution, artifact: StoredArtifact) -> Self {
+        Self {
+            execution,
+            artifact: Some(artifact),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    pub steps: Vec<StepExecution>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionOutcome {
+    pub report: ExecutionReport,
+    pub artifacts: Vec<StoredArtifact>,
+    /// Step counts keyed by status, as returned by
+    /// [`ExecutionReport::summary_by_status`].
+    #[serde(default)]
+    pub summary: BTreeMap<String, usize>,
+    /// Secrets that were referenced after their lease expired.
+    #[serde(default)]
+    pub secret_warnings: Vec<String>,
+    /// Sorted names of the secrets resolved through `${secret:...}`. Values
+    /// are never included.
+    #[serde(default)]
+    pub secret_usage: Vec<String>,
+}
+
+impl ExecutionReport {
+    pub fn has_failures(&self) -> bool {
+        self.summary_by_status()
+            .contains_key(ExecutionStatus::Failed.key())
+    }
+
+    /// Counts steps per status, keyed by `ExecutionStatus::key`. Statuses
+    /// with no steps are omitted.
+    pub fn summary_by_status(&self) -> BTreeMap<String, usize> {
+        let mut summary = BTreeMap::new();
+        for step in &self.steps {
+            *summary.entry(step.status.key().to_string()).or_insert(0) += 1;
+        }
+        summary
+    }
+
+    /// Compares two reports step by step. Steps are matched by name; when a
+    /// name repeats (e.g. inside loops) occurrences are paired in order.
+    pub fn diff(baseline: &ExecutionReport, current: &ExecutionReport) -> ExecutionDiff {
+        fn keyed(report: &ExecutionReport) -> Vec<((&str, usize), &StepExecution)> {
+            let mut seen: HashMap<&str, usize> = HashMap::new();
+            report
+                .steps
+                .iter()
+                .map(|step| {
+                    let occurrence = seen.entry(step.name.as_str()).or_insert(0);
+                    *occurrence += 1;
+                    ((step.name.as_str(), *occurrence), step)
+                })
+                .collect()
+        }
+
+        let baseline_steps = keyed(baseline);
+        let current_steps = keyed(current);
+        let baseline_index: HashMap<(&str, usize), &StepExecution> =
+            baseline_steps.iter().copied().collect();
+        let current_keys: HashSet<(&str, usize)> =
+            current_steps.iter().map(|(key, _)| *key).collect();
+
+        let mut diff = ExecutionDiff::default();
+        for (key, step) in &current_steps {
+            match baseline_index.get(key) {
+                None => diff.new_steps.push((*step).clone()),
+                Some(previous) if previous.status != step.status => {
+                    diff.status_changed.push(StatusChange {
+                        name: step.name.clone(),
+                        kind: step.kind.clone(),
+                        baseline: previous.status.clone(),
+                        current: step.status.clone(),
+                    })
+                }
+                Some(_) => diff.unchanged.push((*step).clone()),
+            }
+        }
+        diff.removed_steps = baseline_steps
+            .iter()
+            .filter(|(key, _)| !current_keys.contains(key))
+            .map(|(_, step)| (*step).clone())
+            .collect();
+        diff
+    }
+}
+
+/// Result of [`ExecutionReport::diff`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutionDiff {
+    pub new_steps: Vec<StepExecution>,
+    pub removed_steps: Vec<StepExecution>,
+    pub status_changed: Vec<StatusChange>,
+    pub unchanged: Vec<StepExecution>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusChange {
+    pub name: String,
+    pub kind: StepKind,
+    pub baseline: ExecutionStatus,
+    pub current: ExecutionStatus,
+}
+
+impl ExecutionDiff {
+    pub fn has_changes(&self) -> bool {
+        !self.new_steps.is_empty()
+            || !self.removed_steps.is_empty()
+            || !self.status_changed.is_empty()
+    }
+}
+
+impl fmt::Display for ExecutionDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.has_changes() {
+            writeln!(
+                f,
+                "No changes ({} step(s) unchanged).",
+                self.unchanged.len()
+            )?;
+            return Ok(());
+        }
+
+        writeln!(f, "Execution diff:")?;
+        for step in &self.removed_steps {
+            writeln!(
+                f,
+                "- [{}] {} ({:?})",
+                step.status.key(),
+                step.name,
+                step.kind
+            )?;
+        }
+        for step in &self.new_steps {
+            writeln!(
+                f,
+                "+ [{}] {} ({:?})",
+                step.status.key(),
+                step.name,
+                step.kind
+            )?;
+        }
+        for change in &self.status_changed {
+            writeln!(
+                f,
+                "~ [{} -> {}] {} ({:?})",
+                change.baseline.key(),
+                change.current.key(),
+                change.name,
+                change.kind
+            )?;
+        }
+        writeln!(
+            f,
+            "{} added, {} removed, {} changed, {} unchanged",
+            self.new_steps.len(),
+            self.removed_steps.len(),
+            self.status_changed.len(),
+            self.unchanged.len()
+        )
+    }
+}
+
+impl fmt::Display for ExecutionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.steps.is_empty() {
+            writeln!(f, "No steps to execute.")?;
+            return Ok(());
+        }
+
+        writeln!(f, "Execution results:")?;
+        for step in &self.steps {
+            let status = match step.status {
+                ExecutionStatus::Completed => "completed",
+                ExecutionStatus::Skipped => "skipped",
+                ExecutionStatus::Failed => "failed",
+                ExecutionStatus::NotImplemented => "not implemented",
+            };
+            match step.duration_ms {
+                Some(duration_ms) => writeln!(
+                    f,
+                    "  - [{}] {} ({:?}) in {}ms",
+                    status, step.name, step.kind, duration_ms
+                )?,
+                None => writeln!(f, "  - [{}] {} ({:?})", status, step.name, step.kind)?,
+            }
+            if let Some(message) = &step.message {
+                for line in message.lines() {
+                    writeln!(f, "      {}", line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// One span per executed step so subscribers (including OpenTelemetry
+// exporters) see per-step timing; `name` is empty for unnamed steps.
+fn step_span(step: &Step) -> tracing::Span {
+    let kind = match step {
+        Step::Import(_) => "import",
+        Step::AssetGroup(_) => "asset_group",
+        Step::Scan(_) => "scan",
+        Step::Variable(_) => "variable",
+        Step::Secret(_) => "secret",
+        Step::Script(_) => "script",
+        Step::Report(_) => "report",
+        Step::Conditional(_) => "conditional",
+        Step::Loop(_) => "loop",
+        Step::Parallel(_) => "parallel",
+        Step::Assert(_) => "assert",
+        Step::Notify(_) => "notify",
+        Step::Sleep(_) => "sleep",
+        Step::Merge(_) => "merge",
+        Step::Transform(_) => "transform",
+        Step::Checkpoint(_) => "checkpoint",
+        Step::Function(_) => "function",
+        Step::Call(_) => "call",
+        Step::Break => "break",
+        Step::Continue => "continue",
+        Step::Comment(_) => return tracing::Span::none(),
+    };
+    tracing::info_span!("step", kind, name = step.name().unwrap_or_default())
+}
+
+// Runs one step handler and stamps its execution with the start time and
+// elapsed wall-clock duration.
+fn timed(run: impl FnOnce() -> StepOutcome) -> StepOutcome {
+    let started_at = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .ok();
+    let timer = Instant::now();
+    let mut outcome = run();
+    outcome.execution.started_at = started_at;
+    outcome.execution.duration_ms = Some(timer.elapsed().as_millis());
+    outcome
+}
+
+// Synthetic, read-only variables injected for each `for` iteration.
+pub(crate) const LOOP_VAR_PREFIX: &str = "loop.";
+pub(crate) const LOOP_INDEX_VAR: &str = "loop.index";
+pub(crate) const LOOP_INDEX1_VAR: &str = "loop.index1";
+
+fn iteration_limit_message(count: u64, limit: usize) -> String {
+    format!(
+        "loop would run {count} iteration(s), exceeding the limit of {limit}; raise it with `option max_iter <n>` or --max-iter"
+    )
+}
+
+// Raised by `break`/`continue` and propagated up to the innermost loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LoopSignal {
+    Break,
+    Continue,
+}
+
+fn loop_summary(
+    iterations: usize,
+    continued: usize,
+    filtered: usize,
+    broke: bool,
+    total: usize,
+) -> String {
+    let mut message = format!("executed {iterations} iteration(s)");
+    if filtered > 0 {
+        message.push_str(&format!(", {filtered} skipped by when guard"));
+    }
+    if continued > 0 {
+        message.push_str(&format!(", {continued} skipped via continue"));
+    }
+    if broke {
+        message.push_str(&format!(
+            "; stopped by break ({} of {} item(s) not visited)",
+            total - iterations - filtered,
+            total
+        ));
+    }
+    message
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepExecution {
+    pub name: String,
+    pub kind: StepKind,
+    pub status: ExecutionStatus,
+    pub message: Option<String>,
+    /// RFC 3339 timestamp taken when the step started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u128>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum StepKind {
+    AssetGroup,
+    Scan,
+    Variable,
+    Secret,
+    Script,
+    Report,
+    Conditional,
+    Loop,
+    Parallel,
+    Assert,
+    Notify,
+    Sleep,
+    Merge,
+    Transform,
+    Checkpoint,
+    Call,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ExecutionStatus {
+    Completed,
+    Skipped,
+    Failed,
+    NotImplemented,
+}
+
+impl ExecutionStatus {
+    /// Stable snake_case name used in status summaries.
+    pub fn key(&self) -> &'static str {
+        match self {
+            ExecutionStatus::Completed => "completed",
+            ExecutionStatus::Skipped => "skipped",
+            ExecutionStatus::Failed => "failed",
+            ExecutionStatus::NotImplemented => "not_implemented",
+        }
+    }
+}
+
+impl StepExecution {
+    pub fn completed(name: String, kind: StepKind, message: Option<String>) -> Self {
+        Self {
+            name,
+            kind,
+            status: ExecutionStatus::Completed,
+            message,
+            started_at: None,
+            duration_ms: None,
+        }
+    }
+
+    pub fn failed(name: String, kind: StepKind, message: Option<String>) -> Self {
+        Self {
+            name,
+            kind,
+            status: ExecutionStatus::Failed,
+            message,
+            started_at: None,
+            duration_ms: None,
+        }
+    }
+
+    pub fn skipped(name: String, kind: StepKind, message: Option<String>) -> Self {
+        Self {
+            name,
+            kind,
+            status: ExecutionStatus::Skipped,
+            message,
+            started_at: None,
+            duration_ms: None,
+        }
+    }
+
+    pub fn not_implemented(name: String, kind: StepKind, message: Option<String>) -> Self {
+        Self {
+            name,
+            kind,
+            status: ExecutionStatus::NotImplemented,
+            message,
+            started_at: None,
+            duration_ms: None,
+        }
+    }
+}
+
+// `option deduplicate "findings"` and the optional `deduplicate_key` list of
+// finding fields, which defaults to asset, port, protocol, and title.
+pub(crate) fn parse_deduplicate_options(
+    mode: &str,
+    key: Option<&String>,
+) -> Result<Vec<String>, String> {
+    if mode != "findings" {
+        return Err(format!(
+            "unknown deduplicate option '{mode}' (expected \"findings\")"
+        ));
+    }
+    let Some(raw) = key else {
+        return Ok(DEFAULT_FINDING_KEY
+            .iter()
+            .map(|field| field.to_string())
+            .collect());
+    };
+    let fields: Vec<String> = raw
+        .split([',', ' '])
+        .filter(|field| !field.is_empty())
+        .map(str::to_string)
+        .collect();
+    if fields.is_empty() {
+        return Err("deduplicate_key lists no fields".to_string());
+    }
+    if let Some(unknown) = fields
+        .iter()
+        .find(|field| !FINDING_KEY_FIELDS.contains(&field.as_str()))
+    {
+        return Err(format!(
+            "unknown deduplicate_key field '{unknown}' (expected {})",
+            FINDING_KEY_FIELDS.join(", ")
+        ));
+    }
+    Ok(fields)
+}
+
+/// A `suppress` report option, such as
+/// `option suppress 'finding where asset_id matches "dev-.*" and rule_id == "nmap"'`.
+/// SARIF output keeps matching findings but marks them as suppressed.
+#[derive(Debug, Clone)]
+pub(crate) struct SuppressionRule {
+    /// The option key (`suppress` or `suppress_<label>`) that declared the rule.
+    pub(crate) name: String,
+    pub(crate) condition: ConditionExpr,
+}
+
+impl SuppressionRule {
+    fn is_option(key: &str) -> bool {
+        key == "suppress" || key.starts_with("suppress_")
+    }
+
+    /// Parses every `suppress` / `suppress_<label>` entry in the report options.
+    pub(crate) fn from_options(
+        options: &BTreeMap<String, String>,
+    ) -> Result<Vec<SuppressionRule>, String> {
+        options
+            .iter()
+            .filter(|(key, _)| Self::is_option(key))
+            .map(|(key, raw)| {
+                let criteria = raw.trim();
+                let criteria = criteria.strip_prefix("finding where ").unwrap_or(criteria);
+                parse_condition_expr(&keyword_operators(criteria))
+                    .map(|condition| SuppressionRule {
+                        name: key.clone(),
+                        condition,
+                    })
+                    .map_err(|err| {
+                        format!(
+                            "invalid {key} rule '{criteria}': {err} (expected a condition on finding fields, e.g. severity == \"low\" and port > 1024)"
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Whether the rule matches a finding reported by `tool`. Criteria that
+    /// cannot be evaluated (such as an unknown field) do not match.
+    fn matches(&self, tool: &str, target: &str, finding: &Finding) -> bool {
+        let text = |value: &str| LiteralValue::String(value.to_string());
+        let mut scope = HashMap::from([
+            ("rule_id".to_string(), text(tool)),
+            ("tool".to_string(), text(tool)),
+            ("target".to_string(), text(target)),
+            ("id".to_string(), text(&finding.id)),
+            ("asset_id".to_string(), text(&finding.asset_id)),
+            (
+                "port".to_string(),
+                LiteralValue::Integer(i64::from(finding.port)),
+            ),
+            ("protocol".to_string(), text(&finding.protocol)),
+            ("state".to_string(), text(&finding.state)),
+            ("title".to_string(), text(&finding.title)),
+            ("description".to_string(), text(&finding.description)),
+            (
+                "severity".to_string(),
+                text(normalize_severity(&finding.severity)),
+            ),
+        ]);
+        if let Some(service) = &finding.service {
+            scope.insert("service".to_string(), text(service));
+        }
+        evaluate_condition(&self.condition, &scope, &mut SecretStore::default()).unwrap_or(false)
+    }
+}
+
+// Rewrites the `and` / `or` keywords outside quoted strings to `&&` / `||`.
+fn keyword_operators(criteria: &str) -> String {
+    let mut rewritten = String::with_capacity(criteria.len());
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let flush = |word: &mut String, out: &mut String| {
+        match word.as_str() {
+            "and" => out.push_str("&&"),
+            "or" => out.push_str("||"),
+            _ => out.push_str(word),
+        }
+        word.clear();
+    };
+    for ch in criteria.chars() {
+        if let Some(open) = quote {
+            rewritten.push(ch);
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == open {
+                quote = None;
+            }
+            continue;
+        }
+        if ch.is_alphanumeric() || ch == '_' {
+            word.push(ch);
+            continue;
+        }
+        flush(&mut word, &mut rewritten);
+        if matches!(ch, '"' | '\'') {
+            quote = Some(ch);
+        }
+        rewritten.push(ch);
+    }
+    flush(&mut word, &mut rewritten);
+    rewritten
+}
+
+// Folds the included scan artifacts into one whose findings are deduplicated
+// on `key`, removing them from `includes` and `tables`. Returns the merged
+// data, how many artifacts went into it, and how many findings were dropped.
+fn merge_included_findings(
+    includes: &mut BTreeMap<String, Value>,
+    tables: &mut BTreeMap<String, TableArtifact>,
+    store: &HashMap<String, StoredArtifact>,
+    key: &[&str],
+) -> Option<(Value, usize, usize)> {
+    let mut merged: Option<ScanArtifacts> = None;
+    let mut findings = Vec::new();
+    let mut sources = Vec::new();
+    for (name, data) in includes.iter() {
+        let is_scan = store.get(name).is_some_and(|artifact| {
+            matches!(artifact.kind, ArtifactKind::Scan | ArtifactKind::Transform)
+        });
+        let Some(mut scan) = is_scan
+            .then(|| serde_json::from_value::<ScanArtifacts>(data.clone()).ok())
+            .flatten()
+        else {
+            continue;
+        };
+        // `merge` drops findings by id; the key decides duplicates here.
+        findings.append(&mut scan.findings);
+        merged = Some(match merged {
+            Some(existing) => existing.merge(scan),
+            None => scan,
+        });
+        sources.push(name.clone());
+    }
+    let mut merged = merged?;
+    for name in &sources {
+        includes.remove(name);
+        tables.remove(name);
+    }
+    merged.findings = findings;
+    let removed = merged.deduplicate_findings_by(key);
+    Some((json!(merged), sources.len(), removed))
+}
+
+// Replaces `findings_*`-style includes with the stored artifact names they
+// match, in name order. The note counts the matches for the step message.
+fn expand_include_patterns(
+    includes: Vec<String>,
+    store: &HashMap<String, StoredArtifact>,
+) -> Result<(Vec<String>, String), String> {
+    if !includes.iter().any(|include| is_include_pattern(include)) {
+        return Ok((includes, String::new()));
+    }
+    let mut names: Vec<&String> = store.keys().collect();
+    names.sort();
+    let mut expanded = Vec::new();
+    let mut matched = 0;
+    for include in includes {
+        if !is_include_pattern(&include) {
+            expanded.push(include);
+            continue;
+        }
+        let pattern = glob::Pattern::new(&include)
+            .map_err(|err| format!("invalid include pattern '{include}': {err}"))?;
+        for name in names.iter().filter(|name| pattern.matches(name)) {
+            matched += 1;
+            expanded.push((*name).clone());
+        }
+    }
+    Ok((
+        expanded,
+        format!(" ({matched} artifact(s) matched include patterns)"),
+    ))
+}
+
+pub(crate) fn is_include_pattern(include: &str) -> bool {
+    include.contains(['*', '?', '['])
+}
+
+// Asset group scopes larger than this are stored but not expanded into `<name>_hosts`.
+pub(crate) const MAX_EXPANDED_HOSTS: u128 = 1 << 20;
+
+/// Number of addresses in `scope` when it parses as a CIDR block.
+pub(crate) fn cidr_address_count(scope: &str) -> Option<u128> {
+    let net: IpNet = scope.trim().parse().ok()?;
+    let bits = u32::from(net.max_prefix_len() - net.prefix_len());
+    Some(1u128.checked_shl(bits).unwrap_or(u128::MAX))
+}
+
+/// Every address in the CIDR block, network and broadcast included.
+fn expand_cidr(scope: &str) -> Option<Vec<String>> {
+    let hosts = match scope.trim().parse::<IpNet>().ok()? {
+        IpNet::V4(net) => Ipv4AddrRange::new(net.network(), net.broadcast())
+            .map(|addr| addr.to_string())
+            .collect(),
+        IpNet::V6(net) => Ipv6AddrRange::new(net.network(), net.broadcast())
+            .map(|addr| addr.to_string())
+            .collect(),
+    };
+    Some(hosts)
+}
+
+// `timeout "30s"` on a scan or script; absent means no limit.
+fn step_timeout(params: &BTreeMap<String, String>) -> Result<Option<Duration>, String> {
+    params
+        .get("timeout")
+        .map(|value| {
+            parse_duration_ms(value.trim())
+                .map(Duration::from_millis)
+                .map_err(|err| format!("invalid timeout: {err}"))
+        })
+        .transpose()
+}
+
+// `Command::output` with an optional deadline. Past it the child is killed and
+// whatever it wrote so far is returned, with the flag set.
+fn run_command(
+    cmd: &mut Command,
+    timeout: Option<Duration>,
+) -> std::io::Result<(std::process::Output, bool)> {
+    let Some(timeout) = timeout else {
+        return cmd.output().map(|output| (output, false));
+    };
+    // A process group of its own lets the timeout kill everything the tool
+    // started, not just the tool itself.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(cmd, 0);
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = drain_pipe(child.stdout.take());
+    let stderr = drain_pipe(child.stderr.take());
+    let deadline = Instant::now() + timeout;
+    let (status, timed_out) = loop {
+        if let Some(status) = child.try_wait()? {
+            break (status, false);
+        }
+        if Instant::now() >= deadline {
+            // The child may already be gone; `wait` reports the real status.
+            kill_process_group(&mut child);
+            break (child.wait()?, true);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    // Grandchildren can keep a killed child's pipes open, so only wait
+    // briefly for the rest of its output.
+    let grace = timed_out.then(|| Instant::now() + Duration::from_millis(200));
+    Ok((
+        std::process::Output {
+            status,
+            stdout: collect_pipe(&stdout, grace),
+            stderr: collect_pipe(&stderr, grace),
+        },
+        timed_out,
+    ))
+}
+
+fn kill_process_group(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: `kill` has no memory-safety preconditions; the group was
+        // created for this child in `run_command`.
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+}
+
+fn drain_pipe(pipe: Option<impl Read + Send + 'static>) -> mpsc::Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    if let Some(mut pipe) = pipe {
+        std::thread::spawn(move || {
+            let mut chunk = [0u8; 8192];
+            while let Ok(read @ 1..) = pipe.read(&mut chunk) {
+                if sender.send(chunk[..read].to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    receiver
+}
+
+fn collect_pipe(receiver: &mpsc::Receiver<Vec<u8>>, until: Option<Instant>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let chunk = match until {
+            Some(deadline) => receiver
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .ok(),
+            None => receiver.recv().ok(),
+        };
+        match chunk {
+            Some(chunk) => bytes.extend(chunk),
+            None => return bytes,
+        }
+    }
+}
+
+fn truncate_output(bytes: &[u8]) -> String {
+    const MAX: usize = 512;
+    let text = String::from_utf8_lossy(bytes);
+    if text.len() > MAX {
+        format!("{}…", &text[..MAX])
+    } else {
+        text.to_string()
+    }
+}
+
+fn resolve_literal_value(
+    value: &LiteralValue,
+    variables: &HashMap<String, LiteralValue>,
+    secrets: &mut SecretStore,
+) -> Result<LiteralValue, String> {
+    match value {
+        LiteralValue::String(s) => {
+            let substituted = substitute_variables(s, variables, secrets)?;
+            Ok(LiteralValue::String(substituted))
+        }
+        LiteralValue::Integer(n) => Ok(LiteralValue::Integer(*n)),
+        LiteralValue::Number(n) => Ok(LiteralValue::Number(*n)),
+        LiteralValue::Arithmetic {
+            operator,
+            left,
+            right,
+        } => {
+            let lhs = evaluate_operand(left, variables, secrets)?;
+            let rhs = evaluate_operand(right, variables, secrets)?;
+            operator
+                .apply(&lhs, &rhs)
+                .map_err(|err| format!("{err} ({value})"))
+        }
+        LiteralValue::Boolean(b) => Ok(LiteralValue::Boolean(*b)),
+        LiteralValue::Null => Ok(LiteralValue::Null),
+        LiteralValue::Array(items) => {
+            let mut resolved = Vec::with_capacity(items.len());
+            for item in items {
+                resolved.push(resolve_literal_value(item, variables, secrets)?);
+            }
+            Ok(LiteralValue::Array(resolved))
+        }
+        LiteralValue::Object(map) => {
+            let mut resolved = BTreeMap::new();
+            for (k, v) in map {
+                resolved.insert(k.clone(), resolve_literal_value(v, variables, secrets)?);
+            }
+            Ok(LiteralValue::Object(resolved))
+        }
+        LiteralValue::Env(name) => {
+            let name = substitute_variables(name, variables, secrets)?;
+            env::var(&name)
+                .map(LiteralValue::String)
+                .map_err(|_| format!("environment variable '{name}' is not set"))
+        }
+        LiteralValue::File(path) => {
+            let path = substitute_variables(path, variables, secrets)?;
+            fs::read_to_string(&path)
+                .map(LiteralValue::String)
+                .map_err(|err| format!("failed to read file '{path}': {err}"))
+        }
+    }
+}
+
+fn literal_to_string(value: &LiteralValue) -> String {
+    value.display()
+}
+
+pub(crate) fn sanitize_label(label: &str) -> String {
+    label
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn resolve_map(
+    source: &BTreeMap<String, String>,
+    variables: &HashMap<String, LiteralValue>,
+    secrets: &mut SecretStore,
+) -> Result<BTreeMap<String, String>, String> {
+    let mut resolved = BTreeMap::new();
+    for (key, value) in source {
+        let substituted = substitute_variables(value, variables, secrets)?;
+        resolved.insert(key.clone(), substituted);
+    }
+    Ok(resolved)
+}
+
+fn resolve_list(
+    items: &[String],
+    variables: &HashMap<String, LiteralValue>,
+    secrets: &mut SecretStore,
+) -> Result<Vec<String>, String> {
+    let mut resolved = Vec::with_capacity(items.len());
+    for item in items {
+        let substituted = substitute_variables(item, variables, secrets)?;
+        resolved.push(substituted);
+    }
+    Ok(resolved)
+}
+
+fn substitute_variables(
+    value: &str,
+    variables: &HashMap<String, LiteralValue>,
+    secrets: &mut SecretStore,
+) -> Result<String, String> {
+    let mut result = String::with_capacity(value.len());
+    let mut cursor = 0;
+
+    while let Some(start_offset) = value[cursor..].find("${") {
+        let start_idx = cursor + start_offset;
+        result.push_str(&value[cursor..start_idx]);
+
+        let remainder = &value[start_idx + 2..];
+        let end_offset = remainder
+            .find('}')
+            .ok_or_else(|| "unterminated variable placeholder".to_string())?;
+        let end_idx = start_idx + 2 + end_offset;
+        let token = remainder[..end_offset].trim();
+
+        if token.is_empty() {
+            return Err("empty variable placeholder".to_string());
+        }
+
+        if let Some(secret_name) = token.strip_prefix("secret:") {
+            let key = secret_name.trim();
+            if key.is_empty() {
+                return Err("empty secret placeholder".to_string());
+            }
+            // A transformed secret would no longer match the masking in step messages.
+            if key.contains('|') {
+                return Err(format!(
+                    "transforms are not supported on secret placeholders ('{key}')"
+                ));
+            }
+            let resolved = secrets.resolve(key).ok_or_else(|| {
+                if secrets.is_expired(key) {
+                    format!("secret '{key}' has expired")
+                } else {
+                    format!("undefined secret '{key}'")
+                }
+            })?;
+            result.push_str(&resolved);
+        } else {
+            let mut pipes = token.split('|');
+            let (name, fallback) = split_placeholder(pipes.next().unwrap_or_default().trim());
+            // As in the shell, `:-` and `:?` treat a null variable like an undefined one.
+            let mut replacement = match (lookup_placeholder(name, variables), fallback) {
+                (Ok(LiteralValue::Null), None) => {
+                    tracing::warn!(
+                        variable = name,
+                        "variable is null; substituting an empty string"
+                    );
+                    String::new()
+                }
+                (Ok(LiteralValue::Null), Some(PlaceholderFallback::Default(default))) => {
+                    default.to_string()
+                }
+                (Ok(LiteralValue::Null), Some(PlaceholderFallback::Required(message))) => {
+                    return Err(if message.is_empty() {
+                        format!("variable '{name}' is null")
+                    } else {
+                        message.to_string()
+                    });
+                }
+                (Ok(value), _) => literal_to_string(&value),
+                (Err(_), Some(PlaceholderFallback::Default(default))) => default.to_string(),
+                (Err(err), Some(PlaceholderFallback::Required(message))) => {
+                    return Err(if message.is_empty() {
+                        err
+                    } else {
+                        message.to_string()
+                    });
+                }
+                (Err(err), None) => return Err(err),
+            };
+            for transform in pipes {
+                replacement = apply_transform(transform.trim(), &replacement)?;
+            }
+            result.push_str(&replacement);
+        }
+        cursor = end_idx + 1;
+    }
+
+    result.push_str(&value[cursor..]);
+    Ok(result)
+}
+
+/// What to do when the variable named in a placeholder is not defined:
+/// `${name:-text}` substitutes `text`, `${name:?message}` fails with
+/// `message`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PlaceholderFallback<'a> {
+    Default(&'a str),
+    Required(&'a str),
+}
+
+/// Splits a placeholder token at the first `:-` or `:?` into the variable
+/// name and its fallback.
+pub(crate) fn split_placeholder(token: &str) -> (&str, Option<PlaceholderFallback<'_>>) {
+    match token.find(":-").into_iter().chain(token.find(":?")).min() {
+        Some(pos) => {
+            let name = token[..pos].trim();
+            let rest = &token[pos + 2..];
+            let fallback = if token[pos..].starts_with(":-") {
+                PlaceholderFallback::Default(rest)
+            } else {
+                PlaceholderFallback::Required(rest.trim())
+            };
+            (name, Some(fallback))
+        }
+        None => (token, None),
+    }
+}
+
+/// Returns the variable a placeholder path starts from: `config` for
+/// `config.host` or `hosts[0]`. Loop variables keep their dotted name.
+pub(crate) fn placeholder_root(path: &str) -> &str {
+    if path.starts_with(LOOP_VAR_PREFIX) {
+        return path;
+    }
+    &path[..path.find(['.', '[']).unwrap_or(path.len())]
+}
+
+// Resolves `name`, `obj.key`, and `arr[0]`/`arr[-1]` paths. A variable whose
+// name contains the whole path (e.g. `loop.index`) wins over an accessor.
+fn lookup_placeholder(
+    path: &str,
+    variables: &HashMap<String, LiteralValue>,
+) -> Result<LiteralValue, String> {
+    if let Some(value) = variables.get(path) {
+        return Ok(value.clone());
+    }
+    if matches!(path, LOOP_INDEX_VAR | LOOP_INDEX1_VAR) {
+        return Err(format!("'{path}' is only available inside a for loop body"));
+    }
+    if path.starts_with(LOOP_VAR_PREFIX) {
+        return Err(format!(
+            "unknown loop variable '{path}' (expected loop.index or loop.index1)"
+        ));
+    }
+
+    let root = placeholder_root(path);
+    let mut current = variables
+        .get(root)
+        .ok_or_else(|| format!("undefined variable '{root}'"))?;
+    let mut rest = &path[root.len()..];
+    while !rest.is_empty() {
+        let resolved = &path[..path.len() - rest.len()];
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            let key = &after[..end];
+            current = match current {
+                LiteralValue::Object(map) => map
+                    .get(key)
+                    .ok_or_else(|| format!("'{resolved}' has no key '{key}'"))?,
+                other => {
+                    return Err(format!(
+                        "cannot read key '{key}' of '{resolved}' ({})",
+                        other.type_name()
+                    ))
+                }
+            };
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after
+                .find(']')
+                .ok_or_else(|| format!("unterminated index in '{path}'"))?;
+            let raw = after[..end].trim();
+            let index: i64 = raw
+                .parse()
+                .map_err(|_| format!("invalid index '{raw}' in '{path}'"))?;
+            current = match current {
+                LiteralValue::Array(items) => {
+                    let length = items.len() as i64;
+                    let position = if index < 0 { length + index } else { index };
+                    usize::try_from(position)
+                        .ok()
+                        .and_then(|position| items.get(position))
+                        .ok_or_else(|| {
+                            format!(
+                                "index {index} is out of bounds for '{resolved}' (length {length})"
+                            )
+                        })?
+                }
+                other => return Err(format!("cannot index '{resolved}' ({})", other.type_name())),
+            };
+            rest = &after[end + 1..];
+        } else {
+            return Err(format!("invalid placeholder path '{path}'"));
+        }
+    }
+    Ok(current.clone())
+}
+
+/// Transforms accepted after `|` in a placeholder, e.g. `${host|trim|upper}`.
+pub(crate) const PLACEHOLDER_TRANSFORMS: [&str; 5] =
+    ["upper", "lower", "trim", "base64", "urlencode"];
+
+// RFC 3986 unreserved characters pass through `urlencode` unchanged.
+const URL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+fn apply_transform(name: &str, value: &str) -> Result<String, String> {
+    match name {
+        "upper" => Ok(value.to_uppercase()),
+        "lower" => Ok(value.to_lowercase()),
+        "trim" => Ok(value.trim().to_string()),
+        "base64" => Ok(BASE64_STANDARD.encode(value)),
+        "urlencode" => Ok(utf8_percent_encode(value, URL_ENCODE_SET).to_string()),
+        other => Err(format!(
+            "unknown transform '{other}' (expected one of: {})",
+            PLACEHOLDER_TRANSFORMS.join(", ")
+        )),
+    }
+}
+
+/// Items to bind to the loop variable; objects also carry each entry's value.
+fn resolve_iterable(
+    iterable: &LoopIterable,
+    variables: &HashMap<String, LiteralValue>,
+    secrets: &mut SecretStore,
+) -> Result<Vec<(LiteralValue, Option<LiteralValue>)>, String> {
+    let items = |value: LiteralValue| match value {
+        LiteralValue::Array(items) => Some(items.into_iter().map(|item| (item, None)).collect()),
+        LiteralValue::String(value) => Some(vec![(LiteralValue::String(value), None)]),
+        LiteralValue::Object(entries) => Some(
+            entries
+                .into_iter()
+                .map(|(key, value)| (LiteralValue::String(key), Some(value)))
+                .collect(),
+        ),
+        _ => None,
+    };
+    match iterable {
+        LoopIterable::Variable(name) => match variables.get(name) {
+            Some(value) => items(value.clone()).ok_or_else(|| {
+                format!(
+                    "variable '{}' is not iterable (found {})",
+                    name,
+                    value.display()
+                )
+            }),
+            None => Err(format!("undefined variable '{}'", name)),
+        },
+        LoopIterable::Range {
+            start,
+            end,
+            inclusive,
+        } => {
+            let values: Vec<LiteralValue> = if *inclusive {
+                (*start..=*end).map(LiteralValue::Integer).collect()
+            } else {
+                (*start..*end).map(LiteralValue::Integer).collect()
+            };
+            Ok(values.into_iter().map(|value| (value, None)).collect())
+        }
+        LoopIterable::Literal(literal) => {
+            let resolved = resolve_literal_value(literal, variables, secrets)?;
+            let found = resolved.display();
+            items(resolved).ok_or_else(|| {
+                format!("loop iterable must be array, object or string, found {found}")
+            })
+        }
+    }
+}
+
+/// Parses and evaluates a standalone condition such as
+/// `mode == "fast" && defined port` against `variables`. Secret placeholders
+/// resolve against an empty store.
+pub fn evaluate_condition_expression(
+    expr: &str,
+    variables: &HashMap<String, LiteralValue>,
+) -> Result<bool, String> {
+    let expr = parse_condition_expr(expr).map_err(|err| err.to_string())?;
+    evaluate_condition(&expr, variables, &mut SecretStore::default())
+}
+
+fn evaluate_condition(
+    expr: &ConditionExpr,
+    variables: &HashMap<String, LiteralValue>,
+    secrets: &mut SecretStore,
+) -> Result<bool, String> {
+    match expr {
+        ConditionExpr::Literal(value) => Ok(*value),
+        ConditionExpr::Variable(name) => match variables.get(name) {
+            Some(LiteralValue::Boolean(value)) => Ok(*value),
+            Some(other) => Err(format!(
+                "variable '{}' is not boolean (found {})",
+                name,
+                other.display()
+            )),
+            None => Err(format!("undefined variable '{}'", name)),
+        },
+        ConditionExpr::Defined(name) => Ok(variables.contains_key(name)),
+        ConditionExpr::Not(inner) => Ok(!evaluate_condition(inner, variables, secrets)?),
+        ConditionExpr::And(left, right) => {
+            if !evaluate_condition(left, variables, secrets)? {
+                return Ok(false);
+            }
+            evaluate_condition(right, variables, secrets)
+        }
+        ConditionExpr::Or(left, right) => {
+            if evaluate_condition(left, variables, secrets)? {
+                return Ok(true);
+            }
+            evaluate_condition(right, variables, secrets)
+        }
+        ConditionExpr::Equals(left, right) => {
+            let lhs = evaluate_operand(left, variables, secrets)?;
+            let rhs = evaluate_operand(right, variables, secrets)?;
+            Ok(literals_equal(&lhs, &rhs))
+        }
+        ConditionExpr::NotEquals(left, right) => {
+            let lhs = evaluate_operand(left, variables, secrets)?;
+            let rhs = evaluate_operand(right, variables, secrets)?;
+            Ok(!literals_equal(&lhs, &rhs))
+        }
+        ConditionExpr::LessThan(left, right) => {
+            let ordering = compare_operands(left, right, "<", variables, secrets)?;
+            Ok(ordering == Ordering::Less)
+        }
+        ConditionExpr::GreaterThan(left, right) => {
+            let ordering = compare_operands(left, right, ">", variables, secrets)?;
+            Ok(ordering == Ordering::Greater)
+        }
+        ConditionExpr::LessThanOrEqual(left, right) => {
+            let ordering = compare_operands(left, right, "<=", variables, secrets)?;
+            Ok(ordering != Ordering::Greater)
+        }
+        ConditionExpr::GreaterThanOrEqual(left, right) => {
+            let ordering = compare_operands(left, right, ">=", variables, secrets)?;
+            Ok(ordering != Ordering::Less)
+        }
+        ConditionExpr::Contains(left, right) => {
+            let haystack = evaluate_operand(left, variables, secrets)?;
+            let needle = evaluate_operand(right, variables, secrets)?;
+            match (&haystack, &needle) {
+                (LiteralValue::String(text), LiteralValue::String(fragment)) => {
+                    Ok(text.contains(fragment.as_str()))
+                }
+                (LiteralValue::Array(items), _) => {
+                    Ok(items.iter().any(|item| literals_equal(item, &needle)))
+                }
+                (LiteralValue::String(_), other) => Err(format!(
+                    "cannot check whether a string contains a {} ({})",
+                    other.type_name(),
+                    expr
+                )),
+                (other, _) => Err(format!(
+                    "'contains' requires a string or array on the left, found {} ({})",
+                    other.type_name(),
+                    expr
+                )),
+            }
+        }
+        ConditionExpr::Matches(left, right) => {
+            let subject = evaluate_operand(left, variables, secrets)?;
+            let pattern = evaluate_operand(right, variables, secrets)?;
+            let (LiteralValue::String(subject), LiteralValue::String(pattern)) =
+                (&subject, &pattern)
+            else {
+                return Err(format!(
+                    "'matches' requires string operands, found {} and {} ({})",
+                    subject.type_name(),
+                    pattern.type_name(),
+                    expr
+                ));
+            };
+            let regex = compiled_regex(pattern)?;
+            Ok(regex.is_match(subject))
+        }
+    }
+}
+
+// Resolves `artifact.field.0.length`-style paths against stored artifact data.
+fn resolve_artifact_path(
+    store: &HashMap<String, StoredArtifact>,
+    path: &str,
+) -> Option<LiteralValue> {
+    let mut segments = path.split('.');
+    let artifact = store.get(segments.next()?)?;
+    let mut current = &artifact.data;
+    let mut length = None;
+    for segment in segments {
+        if length.is_some() {
+            return None;
+        }
+        current = match current {
+            Value::Object(map) => match map.get(segment) {
+                Some(value) => value,
+                None if segment == "length" => {
+                    length = Some(map.len());
+                    continue;
+                }
+                None => return None,
+            },
+            Value::Array(items) if segment == "length" => {
+                length = Some(items.len());
+                continue;
+            }
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            Value::String(text) if segment == "length" => {
+                length = Some(text.chars().count());
+                continue;
+            }
+            _ => return None,
+        };
+    }
+    match length {
+        Some(count) => Some(LiteralValue::Integer(count as i64)),
+        None => literal_from_json(current),
+    }
+}
+
+fn literal_from_json(value: &Value) -> Option<LiteralValue> {
+    match value {
+        Value::Null => Some(LiteralValue::Null),
+        Value::Bool(flag) => Some(LiteralValue::Boolean(*flag)),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => Some(LiteralValue::Integer(integer)),
+            None => number.as_f64().map(LiteralValue::Number),
+        },
+        Value::String(text) => Some(LiteralValue::String(text.clone())),
+        Value::Array(items) => items
+            .iter()
+            .map(literal_from_json)
+            .collect::<Option<Vec<_>>>()
+            .map(LiteralValue::Array),
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, item)| literal_from_json(item).map(|literal| (key.clone(), literal)))
+            .collect::<Option<BTreeMap<_, _>>>()
+            .map(LiteralValue::Object),
+    }
+}
+
+thread_local! {
+    static REGEX_CACHE: RefCell<HashMap<String, Regex>> = RefCell::new(HashMap::new());
+}
+
+fn compiled_regex(pattern: &str) -> Result<Regex, String> {
+    REGEX_CACHE.with(|cache| {
+        if let Some(regex) = cache.borrow().get(pattern) {
+            return Ok(regex.clone());
+        }
+        let regex = Regex::new(pattern)
+            .map_err(|err| format!("invalid regular expression '{}': {err}", pattern))?;
+        cache
+            .borrow_mut()
+            .insert(pattern.to_string(), regex.clone());
+        Ok(regex)
+    })
+}
+
+fn compare_operands(
+    left: &ConditionOperand,
+    right: &ConditionOperand,
+    operator: &str,
+    variables: &HashMap<String, LiteralValue>,
+    secrets: &mut SecretStore,
+) -> Result<Ordering, String> {
+    let lhs = evaluate_operand(left, variables, secrets)?;
+    let rhs = evaluate_operand(right, variables, secrets)?;
+    match (&lhs, &rhs) {
+        (LiteralValue::Integer(a), LiteralValue::Integer(b)) => Ok(a.cmp(b)),
+        (
+            LiteralValue::Integer(_) | LiteralValue::Number(_),
+            LiteralValue::Integer(_) | LiteralValue::Number(_),
+        ) => {
+            let (a, b) = (numeric_value(&lhs), numeric_value(&rhs));
+            a.partial_cmp(&b)
+                .ok_or_else(|| format!("cannot compare {} {} {}", a, operator, b))
+        }
+        (LiteralValue::String(a), LiteralValue::String(b)) => Ok(a.cmp(b)),
+        _ => Err(format!(
+            "cannot apply '{}' to {} and {} ({} {} {})",
+            operator,
+            lhs.type_name(),
+            rhs.type_name(),
+            left,
+            operator,
+            right
+        )),
+    }
+}
+
+/// Integers and floats are promoted when compared, so `1 == 1.0` holds.
+fn literals_equal(lhs: &LiteralValue, rhs: &LiteralValue) -> bool {
+    match (lhs, rhs) {
+        (LiteralValue::Integer(_), LiteralValue::Number(_))
+        | (LiteralValue::Number(_), LiteralValue::Integer(_)) => {
+            numeric_value(lhs) == numeric_value(rhs)
+        }
+        (LiteralValue::Array(a), LiteralValue::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(x, y)| literals_equal(x, y))
+        }
+        (LiteralValue::Object(a), LiteralValue::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|((ka, va), (kb, vb))| ka == kb && literals_equal(va, vb))
+        }
+        _ => lhs == rhs,
+    }
+}
+
+fn numeric_value(value: &LiteralValue) -> f64 {
+    value.as_f64().unwrap_or(f64::NAN)
+}
+
+fn evaluate_operand(
+    operand: &ConditionOperand,
+    variables: &HashMap<String, LiteralValue>,
+    secrets: &mut SecretStore,
+) -> Result<LiteralValue, String> {
+    match operand {
+        ConditionOperand::Variable(name) => variables
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("undefined variable '{}'", name)),
+        ConditionOperand::Literal(value) => resolve_literal_value(value, variables, secrets),
+    }
+}
+
+struct VaultCredentials<'a> {
+    address: Option<&'a str>,
+    namespace: Option<&'a str>,
+    role_id: Option<&'a str>,
+    secret_id: Option<&'a str>,
+}
+
+struct VaultSecret {
+    values: BTreeMap<String, String>,
+    lease_duration: Option<u64>,
+}
+
+const DEFAULT_VAULT_ADDR: &str = "http://127.0.0.1:8200";
+
+fn fetch_vault_secret(
+    credentials: &VaultCredentials<'_>,
+    path: &str,
+    mount: Option<&str>,
+    field: Option<&str>,
+) -> Result<VaultSecret, String> {
+    let address = credentials
+        .address
+        .map(str::to_string)
+        .or_else(|| env::var("VAULT_ADDR").ok())
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_VAULT_ADDR.to_string());
+    let address = address.trim().trim_end_matches('/').to_string();
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|err| format!("failed to initialise vault client: {err}"))?;
+
+    let token = resolve_vault_token(&client, &address, credentials)?;
+
+    let api_path = match mount {
+        Some(mount) => format!(
+            "{}/data/{}",
+            mount.trim_matches('/'),
+            path.trim_start_matches('/')
+        ),
+        None => path.trim_start_matches('/').to_string(),
+    };
+    let url = format!("{}/v1/{}", address, api_path);
+
+    let mut request = client.get(&url).header("X-Vault-Token", token);
+    if let Some(namespace) = credentials.namespace {
+        request = request.header("X-Vault-Namespace", namespace);
+    }
+    let response = request
+        .send()
+        .map_err(|err| format!("failed to reach vault at {}: {err}", address))?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Err(format!("vault path '{}' not found", path));
+    }
+    if !status.is_success() {
+        return Err(format!(
+            "vault returned status {} for path '{}'",
+            status.as_u16(),
+            path
+        ));
+    }
+
+    let body: Value = response
+        .json()
+        .map_err(|err| format!("failed to decode vault response for '{}': {err}", path))?;
+
+    let data = body
+        .get("data")
+        .ok_or_else(|| format!("vault response for '{}' has no data", path))?;
+    // KV v2 nests the payload under data.data; KV v1 returns it directly.
+    let entries = match data.get("data") {
+        Some(Value::Object(inner)) => inner,
+        _ => data
+            .as_object()
+            .ok_or_else(|| format!("vault response for '{}' is not an object", path))?,
+    };
+
+    let mut values = BTreeMap::new();
+    for (key, value) in entries {
+        if let Some(selected) = field {
+            if key != selected {
+                continue;
+            }
+        }
+        let rendered = match value {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        values.insert(key.clone(), rendered);
+    }
+
+    if let Some(selected) = field {
+        if values.is_empty() {
+            return Err(format!(
+                "field '{}' not present at vault path '{}'",
+                selected, path
+            ));
+        }
+    }
+
+    let lease_duration = body
+        .get("lease_duration")
+        .and_then(Value::as_u64)
+        .filter(|ttl| *ttl > 0);
+
+    Ok(VaultSecret {
+        values,
+        lease_duration,
+    })
+}
+
+/// Environment variable consulted for a keyring entry under
+/// `--keyring-mock-env`: `AXION_KEYRING_<SERVICE>_<ACCOUNT>`, upper-cased
+/// with every other character replaced by `_`.
+pub(crate) fn keyring_env_var(service: &str, account: &str) -> String {
+    let sanitize = |part: &str| -> String {
+        part.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    };
+    format!("AXION_KEYRING_{}_{}", sanitize(service), sanitize(account))
+}
+
+fn read_keyring(service: &str, account: &str) -> Result<String, String> {
+    let entry = keyring::Entry::new(service, account)
+        .map_err(|err| format!("invalid keyring entry '{service}/{account}': {err}"))?;
+    entry.get_password().map_err(|err| match err {
+        keyring::Error::NoEntry => format!(
+            "no keyring entry for service '{service}' and account '{account}'; add it with {}",
+            keyring_setup_hint(service, account)
+        ),
+        keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_) => format!(
+            "keyring unavailable ({err}); in headless environments pass --keyring-mock-env and set {}",
+            keyring_env_var(service, account)
+        ),
+        other => format!("failed to read keyring entry '{service}/{account}': {other}"),
+    })
+}
+
+fn keyring_setup_hint(service: &str, account: &str) -> String {
+    if cfg!(target_os = "macos") {
+        format!("`security add-generic-password -s {service} -a {account} -w`")
+    } else if cfg!(target_os = "windows") {
+        format!("`cmdkey /generic:{account}.{service} /user:{account} /pass`")
+    } else {
+        format!("`secret-tool store --label={service} service {service} username {account}`")
+    }
+}
+
+fn fetch_aws_secret(
+    secret_id: &str,
+    region: Option<&str>,
+    key: Option<&str>,
+) -> Result<BTreeMap<String, String>, String> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|err| format!("failed to start AWS runtime: {err}"))?;
+    let payload = runtime.block_on(async {
+        // Credentials and, unless set on the secret, the region come from the
+        // standard chain: environment, shared config, SSO, instance profile.
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest());
+        if let Some(region) = region {
+            loader = loader.region(aws_config::Region::new(region.to_string()));
+        }
+        let config = loader.load().await;
+        if config.region().is_none() {
+            return Err(format!(
+                "no AWS region for secret '{secret_id}'; set region or AWS_REGION"
+            ));
+        }
+        let output = aws_sdk_secretsmanager::Client::new(&config)
+            .get_secret_value()
+            .secret_id(secret_id)
+            .send()
+            .await
+            .map_err(|err| {
+                format!(
+                    "failed to fetch AWS secret '{secret_id}': {}",
+                    aws_sdk_secretsmanager::error::DisplayErrorContext(&err)
+                )
+            })?;
+        match (output.secret_string(), output.secret_binary()) {
+            (Some(text), _) => Ok(text.to_string()),
+            (None, Some(blob)) => String::from_utf8(blob.as_ref().to_vec())
+                .map_err(|_| format!("AWS secret '{secret_id}' is binary and not UTF-8")),
+            (None, None) => Err(format!("AWS secret '{secret_id}' has no value")),
+        }
+    })?;
+    parse_aws_secret(secret_id, &payload, key)
+}
+
+/// Splits a Secrets Manager payload into values: one per field for a JSON
+/// object, or a single `value` entry for plain text.
+fn parse_aws_secret(
+    secret_id: &str,
+    payload: &str,
+    key: Option<&str>,
+) -> Result<BTreeMap<String, String>, String> {
+    let fields = match serde_json::from_str::<Value>(payload) {
+        Ok(Value::Object(fields)) => fields,
+        _ => {
+            if let Some(key) = key {
+                return Err(format!(
+                    "AWS secret '{secret_id}' is not a JSON object; cannot extract key '{key}'"
+                ));
+            }
+            return Ok(BTreeMap::from([("value".to_string(), payload.to_string())]));
+        }
+    };
+    let mut values = BTreeMap::new();
+    for (field, value) in fields {
+        if key.is_some_and(|key| key != field) {
+            continue;
+        }
+        let rendered = match value {
+            Value::String(text) => text,
+            other => other.to_string(),
+        };
+        values.insert(field, rendered);
+    }
+    if let Some(key) = key {
+        if values.is_empty() {
+            return Err(format!(
+                "key '{key}' not present in AWS secret '{secret_id}'"
+            ));
+        }
+    }
+    Ok(values)
+}
+
+/// Reads JSON secret values from a file: the field at dotted `key`, or every
+/// top-level string field when `key` is `*`.
+fn parse_file_secret(
+    path: &str,
+    contents: &str,
+    key: &str,
+) -> Result<BTreeMap<String, String>, String> {
+    let document: Value = serde_json::from_str(contents)
+        .map_err(|err| format!("file {path} is not valid JSON: {err}"))?;
+    if key == "*" {
+        let Value::Object(fields) = document else {
+            return Err(format!(
+                "file {path} must hold a JSON object to load all keys"
+            ));
+        };
+        return Ok(fields
+            .into_iter()
+            .filter_map(|(field, value)| match value {
+                Value::String(text) => Some((field, text)),
+                _ => None,
+            })
+            .collect());
+    }
+
+    let mut current = &document;
+    for segment in key.split('.') {
+        current = current
+            .get(segment)
+            .ok_or_else(|| format!("key '{key}' not present in file {path}"))?;
+    }
+    let rendered = match current {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    Ok(BTreeMap::from([(key.to_string(), rendered)]))
+}
+
+fn send_notification(
+    method: &str,
+    url: &str,
+    body: Option<String>,
+    headers: &BTreeMap<String, String>,
+) -> Result<(u16, String), String> {
+    let method = reqwest::Method::from_bytes(method.as_bytes())
+        .map_err(|_| format!("invalid HTTP method '{method}'"))?;
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|err| format!("failed to initialise notification client: {err}"))?;
+
+    let mut request = client.request(method, url);
+    for (name, value) in headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    if let Some(body) = body {
+        request = request.body(body);
+    }
+
+    let response = request
+        .send()
+        .map_err(|err| format!("notification request failed: {err}"))?;
+    let status = response.status().as_u16();
+    let payload = response
+        .bytes()
+        .map_err(|err| format!("failed to read notification response: {err}"))?;
+    Ok((status, truncate_output(&payload)))
+}
+
+fn resolve_vault_token(
+    client: &reqwest::blocking::Client,
+    address: &str,
+    credentials: &VaultCredentials<'_>,
+) -> Result<String, String> {
+    if let (Some(role_id), Some(secret_id)) = (credentials.role_id, credentials.secret_id) {
+        return vault_approle_login(client, address, credentials.namespace, role_id, secret_id);
+    }
+
+    if let Ok(token) = env::var("VAULT_TOKEN") {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+
+    let role_id = credentials
+        .role_id
+        .map(str::to_string)
+        .or_else(|| env::var("VAULT_ROLE_ID").ok());
+    let secret_id = credentials
+        .secret_id
+        .map(str::to_string)
+        .or_else(|| env::var("VAULT_SECRET_ID").ok());
+    match (role_id, secret_id) {
+        (Some(role_id), Some(secret_id)) => {
+            vault_approle_login(client, address, credentials.namespace, &role_id, &secret_id)
+        }
+        _ => Err("no vault credentials: set VAULT_TOKEN or provide role_id/secret_id".to_string()),
+    }
+}
+
+fn vault_approle_login(
+    client: &reqwest::blocking::Client,
+    address: &str,
+    namespace: Option<&str>,
+    role_id: &str,
+    secret_id: &str,
+) -> Result<String, String> {
+    let url = format!("{}/v1/auth/approle/login", address);
+    let mut request = client.post(&url).json(&json!({
+        "role_id": role_id,
+        "secret_id": secret_id,
+    }));
+    if let Some(namespace) = namespace {
+        request = request.header("X-Vault-Namespace", namespace);
+    }
+    let response = request
+        .send()
+        .map_err(|err| format!("failed to reach vault at {}: {err}", address))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!(
+            "vault approle login failed with status {}",
+            status.as_u16()
+        ));
+    }
+    let body: Value = response
+        .json()
+        .map_err(|err| format!("failed to decode vault login response: {err}"))?;
+    body.get("auth")
+        .and_then(|auth| auth.get("client_token"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| "vault login response did not include a client token".to_string())
+}
+
+// Drops table rows for which `condition` is false. Row cells shadow scenario
+// variables of the same name; rows the condition cannot be evaluated against
+// (for example a missing cvss score) are dropped as well.
+fn filter_table_rows(
+    table: &mut TableArtifact,
+    condition: &ConditionExpr,
+    variables: &HashMap<String, LiteralValue>,
+    secrets: &mut SecretStore,
+) -> usize {
+    let before = table.rows.len();
+    table.rows.retain(|row| {
+        let mut scope = variables.clone();
+        for (column, cell) in row {
+            if let Some(literal) = literal_from_json(cell) {
+                scope.insert(column.clone(), literal);
+            }
+        }
+        evaluate_condition(condition, &scope, secrets).unwrap_or(false)
+    });
+    before - table.rows.len()
+}
+
+/// Artifact labels a step may leave behind, or `None` for steps a resumed run
+/// always executes again (variables, control flow, asserts, checkpoints).
+fn resumable_labels(step: &Step) -> Option<Vec<String>> {
+    let labels = match step {
+        Step::Scan(scan) => match &scan.output {
+            Some(output) => vec![output.clone()],
+            None => vec![
+                format!("scan_{}", scan.name),
+                format!("findings_{}", scan.name),
+            ],
+        },
+        Step::Script(script) => vec![script
+            .output
+            .clone()
+            .unwrap_or_else(|| format!("script_{}", script.name))],
+        Step::Report(report) => vec![format!("report:{}", report.name)],
+        Step::Notify(notify) => vec![format!("notify:{}", notify.name)],
+        Step::AssetGroup(group) => vec![format!("asset_group:{}", group.name)],
+        Step::Merge(merge) => vec![merge.output.clone()],
+        Step::Transform(transform) => vec![transform
+            .output
+            .clone()
+            .unwrap_or_else(|| transform.source.clone())],
+        Step::Sleep(_) => Vec::new(),
+        _ => return None,
+    };
+    Some(labels)
+}
+
+fn collect_step_hashes(steps: &[Step], hashes: &mut HashMap<String, String>) {
+    for step in steps {
+        if let (Some(name), Some(_)) = (step.name(), resumable_labels(step)) {
+            let digest = serde_json::to_value(step)
+                .map_err(|err| err.to_string())
+                .and_then(|value| content_digest(&value));
+            if let Ok(digest) = digest {
+                hashes.insert(name.to_string(), digest);
+            }
+        }
+        match step {
+            Step::Conditional(block) => {
+                collect_step_hashes(&block.then_steps, hashes);
+                collect_step_hashes(&block.else_steps, hashes);
+            }
+            Step::Loop(loop_step) => collect_step_hashes(&loop_step.body, hashes),
+            Step::Parallel(block) => collect_step_hashes(&block.steps, hashes),
+            Step::Function(function) => collect_step_hashes(&function.body, hashes),
+            _ => {}
+        }
+        collect_step_hashes(step.on_failure(), hashes);
+    }
+}
+
+/// Applies one `transform` operation to artifact data in place.
+fn apply_transform_op(
+    data: &mut Value,
+    operation: &TransformOp,
+    variables: &HashMap<String, LiteralValue>,
+    secrets: &mut SecretStore,
+) -> Result<(), String> {
+    let segments = split_transform_path(operation.path())
+        .ok_or_else(|| format!("invalid path '{}'", operation.path()))?;
+    // `set` and `rename` visit the objects holding the final field.
+    let (parent, field) = match segments.split_last() {
+        Some(((field, _), parent)) => (parent, *field),
+        None => return Err(format!("invalid path '{}'", operation.path())),
+    };
+
+    match operation {
+        TransformOp::Filter { condition, .. } => {
+            visit_transform_path(data, &segments, &mut |target| {
+                let items = target
+                    .as_array_mut()
+                    .ok_or_else(|| "filter expects an array".to_string())?;
+                items.retain(|item| {
+                    let mut scope = variables.clone();
+                    for (key, value) in item.as_object().into_iter().flatten() {
+                        if let Some(literal) = literal_from_json(value) {
+                            scope.insert(key.clone(), literal);
+                        }
+                    }
+                    evaluate_condition(condition, &scope, secrets).unwrap_or(false)
+                });
+                Ok(())
+            })
+        }
+        TransformOp::Project { fields, .. } => {
+            visit_transform_path(data, &segments, &mut |target| {
+                let objects: Vec<&mut Value> = match target {
+                    Value::Array(items) => items.iter_mut().collect(),
+                    other => vec![other],
+                };
+                for object in objects {
+                    object
+                        .as_object_mut()
+                        .ok_or_else(|| "project expects objects".to_string())?
+                        .retain(|key, _| fields.contains(key));
+                }
+                Ok(())
+            })
+        }
+        TransformOp::Set { value, .. } => {
+            let value = resolve_literal_value(value, variables, secrets)?.to_json();
+            visit_transform_path(data, parent, &mut |target| {
+                target
+                    .as_object_mut()
+                    .ok_or_else(|| format!("cannot set '{field}' on a non-object value"))?
+                    .insert(field.to_string(), value.clone());
+                Ok(())
+            })
+        }
+        TransformOp::Rename { to, .. } => visit_transform_path(data, parent, &mut |target| {
+            let object = target
+                .as_object_mut()
+                .ok_or_else(|| format!("cannot rename '{field}' on a non-object value"))?;
+            if let Some(value) = object.remove(field) {
+                object.insert(to.clone(), value);
+            }
+            Ok(())
+        }),
+    }
+}
+
+/// Calls `apply` on every value reached by `segments`, expanding `[*]`
+/// segments over each array element.
+fn visit_transform_path(
+    value: &mut Value,
+    segments: &[(&str, bool)],
+    apply: &mut dyn FnMut(&mut Value) -> Result<(), String>,
+) -> Result<(), String> {
+    let Some(((field, expand), rest)) = segments.split_first() else {
+        return apply(value);
+    };
+    let child = value
+        .as_object_mut()
+        .and_then(|object| object.get_mut(*field))
+        .ok_or_else(|| format!("field '{field}' not found"))?;
+    if !expand {
+        return visit_transform_path(child, rest, apply);
+    }
+    let items = child
+        .as_array_mut()
+        .ok_or_else(|| format!("'{field}' is not an array"))?;
+    for item in items {
+        visit_transform_path(item, rest, apply)?;
+    }
+    Ok(())
+}
+
+/// Columns `build_table_from_scan` may emit, in display order.
+pub(crate) const SCAN_TABLE_COLUMNS: [&str; 12] = [
+    "asset_id",
+    "port",
+    "protocol",
+    "service",
+    "product",
+    "version",
+    "state",
+    "severity",
+    "cvss",
+    "description",
+    "cve",
+    "remediation",
+];
+
+/// Parses a report `sort_by` option such as `"port"` or `"severity desc"`
+/// into a column name and a descending flag.
+pub(crate) fn parse_sort_option(raw: &str) -> Result<(String, bool), String> {
+    let mut parts = raw.split_whitespace();
+    let column = parts
+        .next()
+        .ok_or_else(|| "sort_by option requires a column name".to_string())?;
+    let descending = match parts.next().map(str::to_lowercase).as_deref() {
+        None | Some("asc") => false,
+        Some("desc") => true,
+        Some(other) => {
+            return Err(format!(
+                "sort_by direction must be 'asc' or 'desc' (found '{other}')"
+            ))
+        }
+    };
+    if parts.next().is_some() {
+        return Err(format!("sort_by option '{raw}' has trailing text"));
+    }
+    Ok((column.to_string(), descending))
+}
+
+fn build_table_from_scan(data: &Value) -> Option<TableArtifact> {
+    let findings = data.get("findings")?.as_array()?;
+    if findings.is_empty() {
+        return None;
+    }
+
+    let evidence_text = |finding: &Value, key: &str| {
+        finding
+            .get("evidence")
+            .and_then(|evidence| evidence.get(key))
+            .and_then(Value::as_str)
+            .filter(|value| !value.is_empty())
+            .map(|value| Value::String(value.to_string()))
+    };
+    let fingerprinted = findings.iter().any(|finding| {
+        evidence_text(finding, "service_product").is_some()
+            || evidence_text(finding, "service_version").is_some()
+    });
+
+    let mut columns = vec![
+        "asset_id".to_string(),
+        "port".to_string(),
+        "protocol".to_string(),
+        "service".to_string(),
+        "state".to_string(),
+        "severity".to_string(),
+        "description".to_string(),
+    ];
+    if fingerprinted {
+        columns.insert(4, "product".to_string());
+        columns.insert(5, "version".to_string());
+    }
+    let scored = findings
+        .iter()
+        .any(|finding| finding.get("cvss_score").is_some_and(Value::is_number));
+    if scored {
+        let severity_index = columns
+            .iter()
+            .position(|column| column == "severity")
+            .unwrap_or(columns.len() - 1);
+        columns.insert(severity_index + 1, "cvss".to_string());
+    }
+
+    let referenced = findings.iter().any(|finding| {
+        finding
+            .get("cve_ids")
+            .and_then(Value::as_array)
+            .is_some_and(|ids| !ids.is_empty())
+    });
+    if referenced {
+        columns.push("cve".to_string());
+    }
+    let remediated = findings
+        .iter()
+        .any(|finding| finding.get("remediation").is_some_and(Value::is_string));
+    if remediated {
+        columns.push("remediation".to_string());
+    }
+
+    let mut rows = Vec::new();
+    for finding in findings {
+        let mut row = BTreeMap::new();
+        if remediated {
+            row.insert(
+                "remediation".to_string(),
+                finding.get("remediation").cloned().unwrap_or(Value::Null),
+            );
+        }
+        if referenced {
+            row.insert(
+                "cve".to_string(),
+                finding
+                    .get("cve_ids")
+                    .cloned()
+                    .unwrap_or_else(|| Value::Array(Vec::new())),
+            );
+        }
+        if scored {
+            row.insert(
+                "cvss".to_string(),
+                finding.get("cvss_score").cloned().unwrap_or(Value::Null),
+            );
+        }
+        if fingerprinted {
+            row.insert(
+                "product".to_string(),
+                evidence_text(finding, "service_product").unwrap_or(Value::Null),
+            );
+            row.insert(
+                "version".to_string(),
+                evidence_text(finding, "service_version").unwrap_or(Value::Null),
+            );
+        }
+        row.insert(
+            "asset_id".to_string(),
+            finding.get("asset_id").cloned().unwrap_or(Value::Null),
+        );
+        row.insert(
+            "port".to_string(),
+            finding.get("port").cloned().unwrap_or(Value::Null),
+        );
+        row.insert(
+            "protocol".to_string(),
+            finding.get("protocol").cloned().unwrap_or(Value::Null),
+        );
+        row.insert(
+            "service".to_string(),
+            finding.get("service").cloned().unwrap_or(Value::Null),
+        );
+        row.insert(
+            "state".to_string(),
+            finding.get("state").cloned().unwrap_or(Value::Null),
+        );
+        row.insert(
+            "severity".to_string(),
+            finding.get("severity").cloned().unwrap_or(Value::Null),
+        );
+        row.insert(
+            "description".to_string(),
+            finding.get("description").cloned().unwrap_or(Value::Null),
+        );
+        rows.push(row);
+    }
+
+    Some(TableArtifact { columns, rows })
+}
+
+fn render_html_report(
+    title: &str,
+    generated_at: &str,
+    includes: &BTreeMap<String, Value>,
+    tables: &BTreeMap<String, TableArtifact>,
+    options: &BTreeMap<String, String>,
+) -> String {
+    let search = !tables.is_empty()
+        && !options
+            .get("disable_search")
+            .is_some_and(|value| value.eq_ignore_ascii_case("true"));
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\" />\n");
+    html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\n");
+    html.push_str(&format!(
+        "<title>Axion Report – {}</title>\n",
+        escape_html(title)
+    ));
+    html.push_str("<style>");
+    html.push_str(&render_html_theme(options));
+    html.push_str(
+        "body{font-family:var(--font, system-ui,-apple-system,\"Segoe UI\",sans-serif);background:var(--primary, #0f172a);color:var(--text, #e2e8f0);margin:0;padding:0;}\
+        header{background:var(--header, #1e293b);padding:24px 32px;border-bottom:1px solid rgba(148,163,184,0.2);}\
+        h1{margin:0;font-size:28px;}\
+        h2{margin-top:32px;margin-bottom:12px;font-size:22px;}\
+        main{padding:32px;}\
+        section{margin-bottom:40px;background:var(--surface, #111c34);padding:24px;border-radius:12px;border:1px solid rgba(148,163,184,0.1);}\
+        table{width:100%;border-collapse:collapse;margin-top:16px;font-size:14px;}\
+        th,td{border:1px solid rgba(148,163,184,0.2);padding:8px 10px;text-align:left;}\
+        th{background:var(--header, #1e293b);font-weight:600;}\
+        tr:nth-child(even){background:rgba(148,163,184,0.05);}\
+        code,pre{font-family:\"Fira Code\",Consolas,monospace;background:var(--code-bg, #0b1120);color:var(--code-text, #f8fafc);border-radius:8px;}\
+        pre{padding:16px;overflow:auto;}\
+        details{margin-top:16px;}\
+        details>summary{cursor:pointer;color:var(--accent, #38bdf8);font-weight:600;}\
+        footer{padding:16px 32px;border-top:1px solid rgba(148,163,184,0.2);color:var(--muted, #94a3b8);font-size:13px;}\
+        .toolbar{margin-top:16px;}\
+        .toolbar input{width:100%;max-width:420px;padding:8px 12px;border-radius:8px;border:1px solid rgba(148,163,184,0.4);background:var(--code-bg, #0b1120);color:var(--text, #e2e8f0);font-size:14px;}",
+    );
+    html.push_str("</style>\n</head>\n<body>\n");
+    html.push_str("<header>");
+    html.push_str(&format!("<h1>Axion Report: {}</h1>", escape_html(title)));
+    html.push_str(&format!(
+        "<p>Generated at {}</p>",
+        escape_html(generated_at)
+    ));
+    if search {
+        html.push_str(
+            "<div class=\"toolbar\"><input type=\"search\" id=\"axion-search\" placeholder=\"Filter findings\" aria-label=\"Filter findings\" /></div>",
+        );
+    }
+    html.push_str("</header>\n<main>\n");
+
+    if let Some(chart) = render_severity_chart(includes) {
+        html.push_str("<section><h2>Findings by severity</h2>");
+        html.push_str(&chart);
+        html.push_str("</section>\n");
+    }
+
+    for (name, value) in includes {
+        html.push_str("<section>");
+        html.push_str(&format!("<h2>{}</h2>", escape_html(name)));
+        if let Some(table) = tables.get(name) {
+            html.push_str(&render_html_table(table));
+        }
+        if let Ok(raw) = serde_json::to_string_pretty(value) {
+            html.push_str("<details><summary>Raw JSON</summary>");
+            html.push_str("<pre><code>");
+            html.push_str(&escape_html(&raw));
+            html.push_str("</code></pre></details>");
+        }
+        html.push_str("</section>\n");
+    }
+
+    if includes.is_empty() {
+        html.push_str("<section><p>No artifacts were included in this report.</p></section>");
+    }
+
+    html.push_str("</main>\n<footer>Generated by axion-core</footer>\n");
+    if search {
+        html.push_str(HTML_SEARCH_SCRIPT);
+    }
+    html.push_str("</body>\n</html>");
+    html
+}
+
+// Custom properties for `option theme "light"`; the dark defaults live in the
+// `var()` fallbacks of the report stylesheet.
+const HTML_LIGHT_THEME: &[(&str, &str)] = &[
+    ("--primary", "#f8fafc"),
+    ("--surface", "#ffffff"),
+    ("--header", "#e2e8f0"),
+    ("--text", "#0f172a"),
+    ("--muted", "#475569"),
+    ("--accent", "#0369a1"),
+    ("--code-bg", "#f1f5f9"),
+    ("--code-text", "#0f172a"),
+];
+
+/// Builds the `:root` block of CSS custom properties selected by the `theme`,
+/// `primary_color`, and `font` report options. Values that could close the
+/// style rule (`;`, braces, angle brackets, backslashes) are ignored.
+fn render_html_theme(options: &BTreeMap<String, String>) -> String {
+    let mut properties: BTreeMap<&str, String> = BTreeMap::new();
+    if options
+        .get("theme")
+        .is_some_and(|theme| theme.eq_ignore_ascii_case("light"))
+    {
+        for (name, value) in HTML_LIGHT_THEME {
+            properties.insert(*name, value.to_string());
+        }
+    }
+    for (option, property) in [("primary_color", "--primary"), ("font", "--font")] {
+        if let Some(value) = options.get(option) {
+            let value = value.trim();
+            if !value.is_empty() && !value.contains([';', '{', '}', '<', '>', '\\']) {
+                properties.insert(property, value.to_string());
+            }
+        }
+    }
+    if properties.is_empty() {
+        return String::new();
+    }
+    let declarations: String = properties
+        .iter()
+        .map(|(name, value)| format!("{name}:{value};"))
+        .collect();
+    format!(":root{{{declarations}}}")
+}
+
+// Hides table rows whose cells do not contain the search term. Kept inline so
+// the report stays a single self-contained file.
+const HTML_SEARCH_SCRIPT: &str = r#"<script>
+(function () {
+  var input = document.getElementById("axion-search");
+  if (!input) {
+    return;
+  }
+  var rows = Array.prototype.slice.call(
+    document.querySelectorAll("main table tbody tr")
+  );
+  var texts = rows.map(function (row) {
+    return Array.prototype.map
+      .call(row.cells, function (cell) {
+        return cell.textContent;
+      })
+      .join("\u0000")
+      .toLowerCase();
+  });
+  input.addEventListener("input", function () {
+    var term = input.value.trim().toLowerCase();
+    rows.forEach(function (row, index) {
+      var match = term === "" || texts[index].indexOf(term) !== -1;
+      row.style.display = match ? "" : "none";
+    });
+  });
+})();
+</script>
+"#;
+
+// Donut slice colours, in the order slices are drawn clockwise from the top.
+const SEVERITY_CHART_COLORS: &[(&str, &str)] = &[
+    ("critical", "#dc2626"),
+    ("high", "#f97316"),
+    ("medium", "#eab308"),
+    ("low", "#3b82f6"),
+    ("informational", "#9ca3af"),
+];
+
+/// Inline SVG donut of finding counts per severity across the included scan
+/// artifacts, or `None` when there are fewer than two findings to compare.
+fn render_severity_chart(includes: &BTreeMap<String, Value>) -> Option<String> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for value in includes.values() {
+        let Ok(scan) = serde_json::from_value::<ScanArtifacts>(value.clone()) else {
+            continue;
+        };
+        for finding in &scan.findings {
+            *counts
+                .entry(normalize_severity(&finding.severity))
+                .or_default() += 1;
+        }
+    }
+    let total: usize = counts.values().sum();
+    if total < 2 {
+        return None;
+    }
+
+    const CENTER: f64 = 100.0;
+    const RADIUS: f64 = 70.0;
+    const THICKNESS: f64 = 30.0;
+    let point = |fraction: f64| {
+        let angle = fraction * std::f64::consts::TAU - std::f64::consts::FRAC_PI_2;
+        (CENTER + RADIUS * angle.cos(), CENTER + RADIUS * angle.sin())
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"360\" height=\"200\" viewBox=\"0 0 360 200\" role=\"img\" aria-label=\"{total} findings by severity\">"
+    );
+    let mut legend = String::new();
+    let mut start = 0usize;
+    let mut row = 0.0;
+    for (severity, color) in SEVERITY_CHART_COLORS {
+        let count = counts.get(severity).copied().unwrap_or(0);
+        if count == 0 {
+            continue;
+        }
+        if count == total {
+            // A single arc cannot close on itself, so a full ring is a circle.
+            svg.push_str(&format!(
+                "<circle cx=\"{CENTER}\" cy=\"{CENTER}\" r=\"{RADIUS}\" fill=\"none\" stroke=\"{color}\" stroke-width=\"{THICKNESS}\"><title>{severity}: {count}</title></circle>"
+            ));
+        } else {
+            let (x0, y0) = point(start as f64 / total as f64);
+            let (x1, y1) = point((start + count) as f64 / total as f64);
+            let large_arc = u8::from(count * 2 > total);
+            svg.push_str(&format!(
+                "<path d=\"M {x0:.2} {y0:.2} A {RADIUS} {RADIUS} 0 {large_arc} 1 {x1:.2} {y1:.2}\" fill=\"none\" stroke=\"{color}\" stroke-width=\"{THICKNESS}\"><title>{severity}: {count}</title></path>"
+            ));
+        }
+        let y = 40.0 + row * 26.0;
+        legend.push_str(&format!(
+            "<rect x=\"210\" y=\"{}\" width=\"14\" height=\"14\" fill=\"{color}\" /><text x=\"232\" y=\"{}\" fill=\"currentColor\" font-size=\"14\">{severity} ({count})</text>",
+            y - 12.0,
+            y
+        ));
+        start += count;
+        row += 1.0;
+    }
+    svg.push_str(&format!(
+        "<text x=\"{CENTER}\" y=\"{}\" text-anchor=\"middle\" fill=\"currentColor\" font-size=\"24\" font-weight=\"600\">{total}</text>",
+        CENTER + 8.0
+    ));
+    svg.push_str(&legend);
+    svg.push_str("</svg>");
+    Some(svg)
+}
+
+fn render_html_table(table: &TableArtifact) -> String {
+    let mut html = String::new();
+    html.push_str("<table>");
+    html.push_str("<thead><tr>");
+    for column in &table.columns {
+        html.push_str(&format!("<th>{}</th>", escape_html(column)));
+    }
+    html.push_str("</tr></thead>");
+    html.push_str("<tbody>");
+    for row in &table.rows {
+        html.push_str("<tr>");
+        for column in &table.columns {
+            if let Some(ids) = cve_cell(column, row.get(column)) {
+                let links: Vec<String> = ids
+                    .iter()
+                    .map(|id| {
+                        format!(
+                            "<a href=\"{}\">{}</a>",
+                            escape_html(&nvd_url(id)),
+                            escape_html(id)
+                        )
+                    })
+                    .collect();
+                html.push_str(&format!("<td>{}</td>", links.join(", ")));
+                continue;
+            }
+            let value = value_to_string(row.get(column));
+            if column == REMEDIATION_COLUMN && !value.is_empty() {
+                html.push_str(&format!(
+                    "<td><details><summary>Remediation</summary>{}</details></td>",
+                    escape_html(&value)
+                ));
+                continue;
+            }
+            html.push_str(&format!("<td>{}</td>", escape_html(&value)));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</tbody></table>");
+    html
+}
+
+fn render_markdown_report(
+    title: &str,
+    generated_at: &str,
+    includes: &BTreeMap<String, Value>,
+    tables: &BTreeMap<String, TableArtifact>,
+) -> String {
+    let mut md = String::new();
+    md.push_str("# Axion Report\n\n");
+    md.push_str(&format!("**Title:** {}\n\n", title));
+    md.push_str(&format!("_Generated at {}_\n\n", generated_at));
+
+    if includes.is_empty() {
+        md.push_str("No artifacts were included in this report.\n");
+        return md;
+    }
+
+    for (name, value) in includes {
+        md.push_str(&format!("## {}\n\n", name));
+        if let Some(table) = tables.get(name) {
+            if !table.columns.is_empty() {
+                md.push_str(&render_markdown_table(table));
+                md.push('\n');
+            }
+        }
+        if let Ok(raw) = serde_json::to_string_pretty(value) {
+            md.push_str("```json\n");
+            md.push_str(&raw);
+            md.push_str("\n```\n\n");
+        }
+    }
+
+    md
+}
+
+// Markdown tables cannot hold block content, so remediation text is emitted
+// as blockquotes after the table, keyed by the finding's asset and port.
+fn render_markdown_table(table: &TableArtifact) -> String {
+    let columns: Vec<&String> = table
+        .columns
+        .iter()
+        .filter(|column| column.as_str() != REMEDIATION_COLUMN)
+        .collect();
+    let mut md = String::new();
+    md.push('|');
+    for column in &columns {
+        md.push(' ');
+        md.push_str(&sanitize_markdown_cell(column));
+        md.push_str(" |");
+    }
+    md.push('\n');
+
+    md.push('|');
+    for _ in &columns {
+        md.push_str(" --- |");
+    }
+    md.push('\n');
+
+    let mut remediations = Vec::new();
+    for row in &table.rows {
+        if let Some(Value::String(text)) = row.get(REMEDIATION_COLUMN) {
+            remediations.push(format!(
+                "> **{}:{}** {}\n",
+                value_to_string(row.get("asset_id")),
+                value_to_string(row.get("port")),
+                text.replace('\n', "\n> ")
+            ));
+        }
+        md.push('|');
+        for &column in &columns {
+            let value = match cve_cell(column, row.get(column)) {
+                Some(ids) => ids
+                    .iter()
+                    .map(|id| format!("[{}]({})", id, nvd_url(id)))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                None => value_to_string(row.get(column)),
+            };
+            md.push(' ');
+            md.push_str(&sanitize_markdown_cell(&value));
+            md.push_str(" |");
+        }
+        md.push('\n');
+    }
+
+    for remediation in remediations {
+        md.push('\n');
+        md.push_str(&remediation);
+    }
+
+    md
+}
+
+const REMEDIATION_COLUMN: &str = "remediation";
+
+fn cve_cell(column: &str, value: Option<&Value>) -> Option<Vec<String>> {
+    if column != "cve" {
+        return None;
+    }
+    let ids = value?.as_array()?;
+    Some(
+        ids.iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+fn nvd_url(cve: &str) -> String {
+    format!("https://nvd.nist.gov/vuln/detail/{cve}")
+}
+
+fn sanitize_markdown_cell(value: &str) -> String {
+    let replaced = value.replace('\n', "<br>");
+    replaced.replace('|', "\\|")
+}
+
+fn render_csv_report(includes: &BTreeMap<String, Value>) -> (String, usize) {
+    let scans: Vec<(&String, ScanArtifacts)> = includes
+        .iter()
+        .filter_map(|(name, value)| {
+            serde_json::from_value::<ScanArtifacts>(value.clone())
+                .ok()
+                .map(|scan| (name, scan))
+        })
+        .collect();
+    let with_source = scans.len() > 1;
+
+    let mut header = vec![
+        "id",
+        "asset_id",
+        "port",
+        "protocol",
+        "state",
+        "service",
+        "title",
+        "description",
+        "severity",
+        "remediation",
+    ];
+    if with_source {
+        header.insert(0, "source_artifact");
+    }
+
+    let mut output = String::new();
+    push_csv_record(&mut output, header.iter().map(|column| column.to_string()));
+    let mut rows = 0usize;
+    for (name, scan) in &scans {
+        for finding in &scan.findings {
+            let mut record = vec![
+                finding.id.clone(),
+                finding.asset_id.clone(),
+                finding.port.to_string(),
+                finding.protocol.clone(),
+                finding.state.clone(),
+                finding.service.clone().unwrap_or_default(),
+                finding.title.clone(),
+                finding.description.clone(),
+                finding.severity.clone(),
+                finding.remediation.clone().unwrap_or_default(),
+            ];
+            if with_source {
+                record.insert(0, name.to_string());
+            }
+            push_csv_record(&mut output, record.into_iter());
+            rows += 1;
+        }
+    }
+    (output, rows)
+}
+
+// RFC 4180: fields with separators, quotes, or line breaks are quoted and
+// records end with CRLF.
+fn push_csv_record(output: &mut String, fields: impl Iterator<Item = String>) {
+    let encoded: Vec<String> = fields
+        .map(|field| {
+            if field.contains([',', '"', '\r', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect();
+    output.push_str(&encoded.join(","));
+    output.push_str("\r\n");
+}
+
+// Findings at or above the threshold (default "high") become failing
+// testcases so CI pipelines can gate on them.
+fn render_junit_report(
+    title: &str,
+    generated_at: &str,
+    includes: &BTreeMap<String, Value>,
+    options: &BTreeMap<String, String>,
+) -> String {
+    let threshold = options
+        .get("severity_threshold")
+        .map(|value| severity_rank(value))
+        .unwrap_or_else(|| severity_rank("high"));
+
+    let mut suites = String::new();
+    let mut total_tests = 0usize;
+    let mut total_failures = 0usize;
+    for (name, value) in includes {
+        let scan: ScanArtifacts = match serde_json::from_value(value.clone()) {
+            Ok(scan) => scan,
+            Err(_) => continue,
+        };
+
+        let mut cases = String::new();
+        let mut failures = 0usize;
+        for finding in &scan.findings {
+            let case_name = format!("{} ({}/{})", finding.title, finding.port, finding.protocol);
+            let classname = format!("{}.{}", scan.tool, finding.asset_id);
+            let mut body = String::new();
+            if severity_rank(&finding.severity) >= threshold {
+                failures += 1;
+                body.push_str(&format!(
+                    "      <failure message=\"{}\" type=\"{}\">{}</failure>\n",
+                    escape_html(&finding.title),
+                    escape_html(&finding.severity),
+                    escape_html(&finding.description)
+                ));
+            }
+            if let Some(remediation) = &finding.remediation {
+                body.push_str(&format!(
+                    "      <system-out>{}</system-out>\n",
+                    escape_html(remediation)
+                ));
+            }
+            if body.is_empty() {
+                cases.push_str(&format!(
+                    "    <testcase classname=\"{}\" name=\"{}\"/>\n",
+                    escape_html(&classname),
+                    escape_html(&case_name)
+                ));
+            } else {
+                cases.push_str(&format!(
+                    "    <testcase classname=\"{}\" name=\"{}\">\n{}    </testcase>\n",
+                    escape_html(&classname),
+                    escape_html(&case_name),
+                    body
+                ));
+            }
+        }
+
+        total_tests += scan.findings.len();
+        total_failures += failures;
+        suites.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" timestamp=\"{}\">\n",
+            escape_html(name),
+            scan.findings.len(),
+            failures,
+            escape_html(generated_at)
+        ));
+        suites.push_str(&cases);
+        suites.push_str("  </testsuite>\n");
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\">\n{}</testsuites>\n",
+        escape_html(title),
+        total_tests,
+        total_failures,
+        suites
+    )
+}
+
+const CYCLONEDX_SPEC_VERSION: &str = "1.4";
+
+fn render_cyclonedx_report(
+    title: &str,
+    generated_at: &str,
+    includes: &BTreeMap<String, Value>,
+) -> Result<String, String> {
+    let mut components: Vec<Value> = Vec::new();
+    let mut component_refs = HashSet::new();
+    let mut vulnerabilities = Vec::new();
+
+    for (name, value) in includes {
+        let scan: ScanArtifacts = match serde_json::from_value(value.clone()) {
+            Ok(scan) => scan,
+            Err(_) => continue,
+        };
+
+        for asset in &scan.assets {
+            if component_refs.insert(asset.id.clone()) {
+                components.push(cyclonedx_component(asset));
+            }
+        }
+
+        for finding in &scan.findings {
+            // Findings may reference hosts the parser did not emit an asset
+            // for; synthesise a component so `affects` always resolves.
+            if component_refs.insert(finding.asset_id.clone()) {
+                components.push(cyclonedx_component(&Asset {
+                    id: finding.asset_id.clone(),
+                    addresses: Vec::new(),
+                    hostnames: Vec::new(),
+                    labels: BTreeMap::new(),
+                    os_matches: Vec::new(),
+                }));
+            }
+
+            let (severity, placeholder) = cyclonedx_rating(&finding.severity);
+            let score = finding.cvss_score.map(f64::from).unwrap_or(placeholder);
+            let mut properties = vec![
+                json!({ "name": "axion:port", "value": finding.port.to_string() }),
+                json!({ "name": "axion:protocol", "value": finding.protocol }),
+                json!({ "name": "axion:state", "value": finding.state }),
+                json!({ "name": "axion:source_artifact", "value": name }),
+            ];
+            if let Some(service) = &finding.service {
+                properties.push(json!({ "name": "axion:service", "value": service }));
+            }
+
+            vulnerabilities.push(json!({
+                "bom-ref": format!("{}:{}", name, finding.id),
+                "id": finding.id,
+                "source": { "name": scan.tool },
+                "ratings": [cyclonedx_rating_entry(severity, score, finding.cvss_vector.as_deref())],
+                "description": finding.title,
+                "detail": finding.description,
+                "affects": [{ "ref": finding.asset_id }],
+                "properties": properties
+            }));
+            if let Some(remediation) = &finding.remediation {
+                if let Some(last) = vulnerabilities.last_mut() {
+                    last["recommendation"] = json!(remediation);
+                }
+            }
+        }
+    }
+
+    let bom = json!({
+        "$schema": "http://cyclonedx.org/schema/bom-1.4.schema.json",
+        "bomFormat": "CycloneDX",
+        "specVersion": CYCLONEDX_SPEC_VERSION,
+        "serialNumber": format!("urn:uuid:{}", Uuid::new_v4()),
+        "version": 1,
+        "metadata": {
+            "timestamp": generated_at,
+            "tools": [{
+                "vendor": "Axion",
+                "name": "axion",
+                "version": env!("CARGO_PKG_VERSION")
+            }],
+            "component": {
+                "type": "application",
+                "bom-ref": format!("axion:report:{}", title),
+                "name": title
+            }
+        },
+        "components": components,
+        "vulnerabilities": vulnerabilities
+    });
+
+    validate_cyclonedx(&bom)?;
+    serde_json::to_string_pretty(&bom).map_err(|err| err.to_string())
+}
+
+fn cyclonedx_component(asset: &Asset) -> Value {
+    let mut properties = Vec::new();
+    for address in &asset.addresses {
+        properties.push(json!({ "name": "axion:address", "value": address }));
+    }
+    for hostname in &asset.hostnames {
+        properties.push(json!({ "name": "axion:hostname", "value": hostname }));
+    }
+    for (key, value) in &asset.labels {
+        properties.push(json!({ "name": format!("axion:label:{key}"), "value": value }));
+    }
+
+    json!({
+        "type": "device",
+        "bom-ref": asset.id,
+        "name": asset.hostnames.first().unwrap_or(&asset.id),
+        "properties": properties
+    })
+}
+
+fn cyclonedx_rating_entry(severity: &str, score: f64, vector: Option<&str>) -> Value {
+    let mut rating = json!({
+        "source": { "name": "axion" },
+        "score": score,
+        "severity": severity,
+        "method": "CVSSv31"
+    });
+    if let Some(vector) = vector {
+        rating["vector"] = json!(vector);
+    }
+    rating
+}
+
+// Findings without a parsed CVSS score map each severity to the lower bound
+// of its CVSS v3.1 band as a placeholder score.
+fn cyclonedx_rating(label: &str) -> (&'static str, f64) {
+    match normalize_severity(label) {
+        "critical" => ("critical", 9.0),
+        "high" => ("high", 7.0),
+        "medium" => ("medium", 4.0),
+        "low" => ("low", 0.1),
+        _ => ("info", 0.0),
+    }
+}
+
+// Checks the subset of the CycloneDX 1.4 JSON schema this renderer emits:
+// required top-level fields, enumerations, and bom-ref integrity.
+fn validate_cyclonedx(bom: &Value) -> Result<(), String> {
+    const COMPONENT_TYPES: [&str; 8] = [
+        "application",
+        "framework",
+        "library",
+        "container",
+        "operating-system",
+        "device",
+        "firmware",
+        "file",
+    ];
+    const SEVERITIES: [&str; 7] = [
+        "critical", "high", "medium", "low", "info", "none", "unknown",
+    ];
+
+    let violation = |detail: String| format!("cyclonedx schema violation: {detail}");
+
+    if bom.get("bomFormat").and_then(Value::as_str) != Some("CycloneDX") {
+        return Err(violation("bomFormat must be 'CycloneDX'".to_string()));
+    }
+    if bom.get("specVersion").and_then(Value::as_str) != Some(CYCLONEDX_SPEC_VERSION) {
+        return Err(violation(format!(
+            "specVersion must be '{CYCLONEDX_SPEC_VERSION}'"
+        )));
+    }
+    let serial = bom
+        .get("serialNumber")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let serial_pattern =
+        compiled_regex("^urn:uuid:[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$")?;
+    if !serial_pattern.is_match(serial) {
+        return Err(violation(format!("invalid serialNumber '{serial}'")));
+    }
+    if bom
+        .get("version")
+        .and_then(Value::as_u64)
+        .is_none_or(|version| version < 1)
+    {
+        return Err(violation("version must be an integer >= 1".to_string()));
+    }
+
+    let mut refs = HashSet::new();
+    let components = bom
+        .get("components")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    for component in &components {
+        let kind = component.get("type").and_then(Value::as_str).unwrap_or("");
+        if !COMPONENT_TYPES.contains(&kind) {
+            return Err(violation(format!("unknown component type '{kind}'")));
+        }
+        if component.get("name").and_then(Value::as_str).is_none() {
+            return Err(violation("component is missing 'name'".to_string()));
+        }
+        if let Some(bom_ref) = component.get("bom-ref").and_then(Value::as_str) {
+            if !refs.insert(bom_ref.to_string()) {
+                return Err(violation(format!("duplicate bom-ref '{bom_ref}'")));
+            }
+        }
+    }
+
+    let vulnerabilities = bom
+        .get("vulnerabilities")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    for vulnerability in &vulnerabilities {
+        let bom_ref = vulnerability
+            .get("bom-ref")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if !bom_ref.is_empty() && !refs.insert(bom_ref.to_string()) {
+            return Err(violation(format!("duplicate bom-ref '{bom_ref}'")));
+        }
+        for rating in vulnerability
+            .get("ratings")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let severity = rating.get("severity").and_then(Value::as_str).unwrap_or("");
+            if !SEVERITIES.contains(&severity) {
+                return Err(violation(format!("unknown rating severity '{severity}'")));
+            }
+            if rating.get("score").is_some_and(|score| !score.is_number()) {
+                return Err(violation("rating score must be a number".to_string()));
+            }
+        }
+    }
+    for vulnerability in &vulnerabilities {
+        for affected in vulnerability
+            .get("affects")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let target = affected.get("ref").and_then(Value::as_str).unwrap_or("");
+            if !refs.contains(target) {
+                return Err(violation(format!(
+                    "vulnerability affects unknown ref '{target}'"
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Project pages used as `informationUri` for the drivers of known tools.
+const SARIF_TOOL_URIS: &[(&str, &str)] = &[
+    ("nmap", "https://nmap.org"),
+    ("nuclei", "https://github.com/projectdiscovery/nuclei"),
+    ("gobuster", "https://github.com/OJ/gobuster"),
+    ("masscan", "https://github.com/robertdavidgraham/masscan"),
+];
+
+// Results, rules, and artifacts of one SARIF run (one per scan tool).
+#[derive(Default)]
+struct SarifRun {
+    results: Vec<Value>,
+    rules: BTreeMap<String, Value>,
+    artifacts: Vec<Value>,
+    artifact_ids: HashSet<String>,
+}
+
+fn render_sarif_report(
+    title: &str,
+    generated_at: &str,
+    includes: &BTreeMap<String, Value>,
+    options: &BTreeMap<String, String>,
+) -> Result<String, String> {
+    let min_rank = options
+        .get("severity_threshold")
+        .map(|value| severity_rank(value))
+        .unwrap_or(0);
+    let suppressions = SuppressionRule::from_options(options)?;
+
+    let mut tool_runs: BTreeMap<String, SarifRun> = BTreeMap::new();
+
+    for value in includes.values() {
+        let scan: ScanArtifacts = match serde_json::from_value(value.clone()) {
+            Ok(scan) => scan,
+            Err(_) => continue,
+        };
+        let tool_run = tool_runs.entry(scan.tool.clone()).or_default();
+
+        tool_run.rules.entry(scan.tool.clone()).or_insert_with(|| {
+            json!({
+                "id": scan.tool,
+                "name": scan.tool,
+                "shortDescription": {
+                    "text": format!("Findings emitted by {}", scan.tool)
+                }
+            })
+        });
+
+        for finding in scan.findings {
+            let rank = severity_rank(&finding.severity);
+            if rank < min_rank {
+                continue;
+            }
+
+            if tool_run.artifact_ids.insert(finding.asset_id.clone()) {
+                tool_run.artifacts.push(json!({
+                    "location": {
+                        "uri": finding.asset_id
+                    }
+                }));
+            }
+
+            let level = sarif_level(&finding.severity);
+            let mut properties = serde_json::Map::new();
+            properties.insert("severity".to_string(), json!(finding.severity));
+            properties.insert("service".to_string(), json!(finding.service));
+            properties.insert("state".to_string(), json!(finding.state));
+            properties.insert("protocol".to_string(), json!(finding.protocol));
+            properties.insert("port".to_string(), json!(finding.port));
+            properties.insert("target".to_string(), json!(scan.target));
+            properties.insert("description".to_string(), json!(finding.description));
+            properties.insert("evidence".to_string(), json!(finding.evidence));
+            if let Some(score) = finding.cvss_score {
+                properties.insert(
+                    "security-severity".to_string(),
+                    json!(format!("{score:.1}")),
+                );
+            }
+            if let Some(vector) = &finding.cvss_vector {
+                properties.insert("cvssVector".to_string(), json!(vector));
+            }
+            if !finding.cve_ids.is_empty() {
+                properties.insert("cveIds".to_string(), json!(finding.cve_ids));
+            }
+            if !finding.cwe_ids.is_empty() {
+                properties.insert("cweIds".to_string(), json!(finding.cwe_ids));
+            }
+
+            let mut result = json!({
+                "ruleId": scan.tool,
+                "level": level,
+                "message": {
+                    "text": finding.title
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": {
+                            "uri": finding.asset_id
+                        }
+                    }
+                }],
+                "properties": properties
+            });
+            if let Some(remediation) = &finding.remediation {
+                result["fixes"] = json!([{ "description": { "text": remediation } }]);
+            }
+            if let Some(rule) = suppressions
+                .iter()
+                .find(|rule| rule.matches(&scan.tool, &scan.target, &finding))
+            {
+                result["suppressions"] = json!([{
+                    "kind": "inSource",
+                    "justification": format!("matched report option {}", rule.name)
+                }]);
+            }
+            tool_run.results.push(result);
+        }
+    }
+
+    if let Some(path) = options.get("baseline_path") {
+        let raw = fs::read_to_string(path)
+            .map_err(|err| format!("failed to read SARIF baseline '{path}': {err}"))?;
+        let baseline: Value = serde_json::from_str(&raw)
+            .map_err(|err| format!("SARIF baseline '{path}' is not valid JSON: {err}"))?;
+        let mut results: Vec<Value> = tool_runs
+            .values_mut()
+            .flat_map(|tool_run| std::mem::take(&mut tool_run.results))
+            .collect();
+        apply_sarif_baseline(&mut results, &baseline);
+        // Absent results may belong to a tool that no longer ran.
+        for result in results {
+            let tool = result["ruleId"].as_str().unwrap_or_default().to_string();
+            tool_runs.entry(tool).or_default().results.push(result);
+        }
+    }
+
+    let invocations = json!([{
+        "executionSuccessful": true,
+        "endTimeUtc": generated_at
+    }]);
+    let automation = json!({
+        "id": format!("axion::{}", title),
+        "description": {
+            "text": format!("Axion SARIF report {}", title)
+        }
+    });
+
+    let mut runs = Vec::new();
+    for (tool, tool_run) in tool_runs {
+        let mut driver = serde_json::Map::new();
+        driver.insert("name".to_string(), json!(tool));
+        if let Some((_, uri)) = SARIF_TOOL_URIS.iter().find(|(name, _)| *name == tool) {
+            driver.insert("informationUri".to_string(), json!(uri));
+        }
+        if !tool_run.rules.is_empty() {
+            driver.insert(
+                "rules".to_string(),
+                json!(tool_run.rules.into_values().collect::<Vec<_>>()),
+            );
+        }
+        runs.push(sarif_run(
+            driver,
+            tool_run.results,
+            tool_run.artifacts,
+            &invocations,
+            &automation,
+        ));
+    }
+
+    // Without scan results, a single run describes the report itself.
+    if runs.is_empty() {
+        let mut driver = serde_json::Map::new();
+        let tool_name = options
+            .get("tool_name")
+            .or_else(|| options.get("tool"))
+            .cloned()
+            .unwrap_or_else(|| "Axion".to_string());
+        driver.insert("name".to_string(), json!(tool_name));
+        if let Some(version) = options.get("tool_version") {
+            driver.insert("version".to_string(), json!(version));
+        }
+        if let Some(uri) = options.get("tool_uri") {
+            driver.insert("informationUri".to_string(), json!(uri));
+        }
+        runs.push(sarif_run(
+            driver,
+            Vec::new(),
+            Vec::new(),
+            &invocations,
+            &automation,
+        ));
+    }
+
+    let sarif = json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": runs
+    });
+
+    serde_json::to_string_pretty(&sarif).map_err(|err| err.to_string())
+}
+
+fn sarif_run(
+    driver: serde_json::Map<String, Value>,
+    results: Vec<Value>,
+    artifacts: Vec<Value>,
+    invocations: &Value,
+    automation: &Value,
+) -> Value {
+    let mut run = serde_json::Map::new();
+    run.insert("tool".to_string(), json!({ "driver": driver }));
+    run.insert("results".to_string(), json!(results));
+    if !artifacts.is_empty() {
+        run.insert("artifacts".to_string(), json!(artifacts));
+    }
+    run.insert("invocations".to_string(), invocations.clone());
+    run.insert("automationDetails".to_string(), automation.clone());
+    Value::Object(run)
+}
+
+// Results are matched across runs by rule and first location URI.
+fn sarif_baseline_key(result: &Value) -> (String, String) {
+    let rule = result["ruleId"].as_str().unwrap_or_default().to_string();
+    let uri = result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    (rule, uri)
+}
+
+/// Sets `baselineState` on every result by comparing against the results of
+/// a previous SARIF log. A result with the same key, message, and level is
+/// `unchanged`; same key with a different message or level is `updated`;
+/// otherwise it is `new`. Baseline results left unmatched are appended as
+/// `absent` so fixed findings stay visible.
+fn apply_sarif_baseline(results: &mut Vec<Value>, baseline: &Value) {
+    let mut previous: BTreeMap<(String, String), Vec<Value>> = BTreeMap::new();
+    for run in baseline["runs"].as_array().into_iter().flatten() {
+        for result in run["results"].as_array().into_iter().flatten() {
+            // Findings already reported as fixed do not carry forward.
+            if result["baselineState"] == "absent" {
+                continue;
+            }
+            previous
+                .entry(sarif_baseline_key(result))
+                .or_default()
+                .push(result.clone());
+        }
+    }
+
+    for result in results.iter_mut() {
+        let state = match previous.get_mut(&sarif_baseline_key(result)) {
+            Some(candidates) if !candidates.is_empty() => {
+                let same = candidates.iter().position(|candidate| {
+                    candidate["message"]["text"] == result["message"]["text"]
+                        && candidate["level"] == result["level"]
+                });
+                match same {
+                    Some(index) => {
+                        candidates.remove(index);
+                        "unchanged"
+                    }
+                    None => {
+                        candidates.remove(0);
+                        "updated"
+                    }
+                }
+            }
+            _ => "new",
+        };
+        result["baselineState"] = json!(state);
+    }
+
+    for mut result in previous.into_values().flatten() {
+        result["baselineState"] = json!("absent");
+        results.push(result);
+    }
+}
+
+fn sarif_level(label: &str) -> &'static str {
+    match normalize_severity(label) {
+        "critical" | "high" => "error",
+        "medium" => "warning",
+        _ => "note",
+    }
+}
+
+fn escape_html(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for ch in input.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn render_table(table: &TableArtifact) -> String {
+    let mut display = Table::new();
+    display.load_preset(ASCII_FULL);
+    display.set_header(table.columns.clone());
+
+    for row in &table.rows {
+        let cells: Vec<String> = table
+            .columns
+            .iter()
+            .map(|column| value_to_string(row.get(column)))
+            .collect();
+        display.add_row(cells);
+    }
+
+    display.to_string()
+}
+
+fn value_to_string(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::Null) | None => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        Some(Value::Bool(b)) => b.to_string(),
+        Some(other) => serde_json::to_string(other).unwrap_or_default(),
+    }
+}
+
+// Applies finding deduplication unless the scan sets `no_dedup true`, and
+// returns a note for the step message.
+fn deduplicate_scan(parsed: &mut ScanArtifacts, params: &BTreeMap<String, String>) -> String {
+    if params
+        .get("no_dedup")
+        .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+    {
+        return " deduplication disabled.".to_string();
+    }
+    match parsed.deduplicate_findings() {
+        0 => String::new(),
+        removed => format!(" removed {removed} duplicate finding(s)."),
+    }
+}
+
+// Stands in for a scan during dry runs: records what would be invoked and
+// stores an empty artifact under the label the real run would use, so later
+// steps and reports can still resolve it.
+fn dry_run_scan(scan: &ScanStep, params: &BTreeMap<String, String>) -> StepOutcome {
+    let flags = params.get("flags").map(String::as_str);
+    let parsed = matches!(scan.tool.as_str(), "nmap" | "gobuster")
+        || (scan.tool == "nuclei" && nuclei_emits_json(flags))
+        || (scan.tool == "masscan" && masscan_emits_xml(flags));
+    let target = params.get("target").cloned().unwrap_or_default();
+    let label = scan.output.clone().unwrap_or_else(|| {
+        if parsed {
+            format!("findings_{}", scan.name)
+        } else {
+            format!("scan_{}", scan.name)
+        }
+    });
+    let data = if parsed {
+        json!(ScanArtifacts {
+            tool: scan.tool.clone(),
+            target: target.clone(),
+            assets: Vec::new(),
+            findings: Vec::new(),
+            raw_xml: String::new(),
+            raw_output: String::new(),
+        })
+    } else {
+        json!({
+            "tool": scan.tool,
+            "params": params,
+            "dry_run": true,
+        })
+    };
+    let message = if target.is_empty() {
+        format!("[dry-run] would run {}", scan.tool)
+    } else {
+        format!("[dry-run] would run {} against {}", scan.tool, target)
+    };
+    StepOutcome::with_artifact(
+        StepExecution::skipped(scan.name.clone(), StepKind::Scan, Some(message)),
+        StoredArtifact {
+            name: label,
+            kind: ArtifactKind::Scan,
+            path: None,
+            content_hash: None,
+            data,
+        },
+    )
+}
+
+fn split_scan_params(
+    params: &BTreeMap<String, String>,
+    keys: &[&str],
+) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    for key in keys {
+        if let Some(value) = params.get(*key) {
+            let parts =
+                shell_words::split(value).map_err(|err| format!("failed to parse {key}: {err}"))?;
+            args.extend(parts);
+        }
+    }
+    Ok(args)
+}
+
+// Scan parameters passed to a tool as command-line options. The first option
+// is the one emitted; any of them already present in `flags`/`args` wins.
+const TOOL_PARAM_OPTIONS: &[(&str, &str, &[&str])] = &[
+    ("nuclei", "templates", &["-t", "-templates"]),
+    ("masscan", "rate", &["--rate", "--max-rate"]),
+    ("masscan", "ports", &["-p", "--ports"]),
+    ("ffuf", "url", &["-u"]),
+    ("ffuf", "wordlist", &["-w"]),
+    ("ffuf", "method", &["-X"]),
+    ("ffuf", "match_codes", &["-mc"]),
+    ("subfinder", "domain", &["-d", "-domain"]),
+    ("subfinder", "sources", &["-s", "-sources"]),
+];
+
+fn tool_param_args(tool: &str, params: &BTreeMap<String, String>, args: &[String]) -> Vec<String> {
+    let mut extra = Vec::new();
+    for (_, key, options) in TOOL_PARAM_OPTIONS
+        .iter()
+        .filter(|(name, _, _)| *name == tool)
+    {
+        let Some(value) = params.get(*key).filter(|value| !value.is_empty()) else {
+            continue;
+        };
+        let present = args.iter().any(|arg| {
+            options.iter().any(|option| {
+                arg == option
+                    || arg
+                        .strip_prefix(option)
+                        .is_some_and(|rest| rest.starts_with('='))
+            })
+        });
+        if !present {
+            extra.push(options[0].to_string());
+            extra.push(value.clone());
+        }
+    }
+    extra
+}
+
+/// Returns true when gobuster arguments select JSON output (`-o json`).
+fn gobuster_emits_json(args: &[String]) -> bool {
+    args.iter().enumerate().any(|(index, arg)| {
+        arg == "--output=json"
+            || (matches!(arg.as_str(), "-o" | "--output")
+                && args.get(index + 1).is_some_and(|next| next == "json"))
+    })
+}
+
+fn parse_gobuster_output(
+    output: &str,
+    target: &str,
+    json_mode: bool,
+) -> Result<ScanArtifacts, String> {
+    let base = reqwest::Url::parse(target).ok();
+    let host = base
+        .as_ref()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| target.to_string());
+    let port = base
+        .as_ref()
+        .and_then(|url| url.port_or_known_default())
+        .unwrap_or(0);
+    let scheme = base.as_ref().map(|url| url.scheme().to_string());
+    let asset_id = format!("asset://host/{host}");
+
+    // v3 plain text: `/admin (Status: 301) [Size: 178] [--> http://host/admin/]`
+    let modern = compiled_regex(
+        r"^(?P<path>\S+)\s+\(Status:\s*(?P<status>\d+)\)(?:\s*\[Size:\s*(?P<size>\d+)\])?(?:\s*\[-->\s*(?P<redirect>[^\]]+)\])?",
+    )?;
+    // Older builds: `Status: 200 [Size: 1234] --> /admin`
+    let legacy = compiled_regex(
+        r"^Status:\s*(?P<status>\d+)(?:\s*\[Size:\s*(?P<size>\d+)\])?\s*-->\s*(?P<path>\S+)",
+    )?;
+
+    let mut findings = Vec::new();
+    for (index, line) in output.lines().enumerate() {
+        let line = line.trim_start_matches("\u{1b}[2K").trim();
+        let entry = if json_mode {
+            if !line.starts_with('{') {
+                continue;
+            }
+            let event: Value = serde_json::from_str(line)
+                .map_err(|err| format!("line {}: invalid JSON entry: {err}", index + 1))?;
+            let text = |keys: &[&str]| {
+                keys.iter()
+                    .find_map(|key| event.get(*key).and_then(Value::as_str))
+                    .map(str::to_string)
+            };
+            let number = |keys: &[&str]| {
+                keys.iter()
+                    .find_map(|key| event.get(*key).and_then(Value::as_u64))
+            };
+            let Some(path) = text(&["path", "url"]) else {
+                continue;
+            };
+            (
+                path,
+                number(&["status", "status_code"]),
+                number(&["size", "length"]),
+                text(&["redirect", "location"]),
+            )
+        } else {
+            let Some(captures) = modern.captures(line).or_else(|| legacy.captures(line)) else {
+                continue;
+            };
+            let number = |name: &str| {
+                captures
+                    .name(name)
+                    .and_then(|value| value.as_str().parse::<u64>().ok())
+            };
+            (
+                captures["path"].to_string(),
+                number("status"),
+                number("size"),
+                captures
+                    .name("redirect")
+                    .map(|value| value.as_str().trim().to_string()),
+            )
+        };
+
+        let (path, status, size, redirect) = entry;
+        let location = match &base {
+            Some(url) if !path.contains("://") => url
+                .join(&path)
+                .map(|joined| joined.to_string())
+                .unwrap_or_else(|_| path.clone()),
+            _ => path.clone(),
+        };
+
+        let mut evidence = BTreeMap::new();
+        evidence.insert("path".to_string(), json!(path));
+        if let Some(status) = status {
+            evidence.insert("status".to_string(), json!(status));
+        }
+        if let Some(size) = size {
+            evidence.insert("size".to_string(), json!(size));
+        }
+        if let Some(redirect) = &redirect {
+            evidence.insert("redirect".to_string(), json!(redirect));
+        }
+
+        let title = match status {
+            Some(status) => format!("{path} ({status})"),
+            None => path.clone(),
+        };
+        findings.push(Finding {
+            id: format!("finding://{host}:{port}/{}", path.trim_start_matches('/')),
+            asset_id: asset_id.clone(),
+            port,
+            protocol: "tcp".to_string(),
+            state: "found".to_string(),
+            service: scheme.clone(),
+            title,
+            description: location,
+            severity: "informational".to_string(),
+            evidence,
+            cvss_score: None,
+            cvss_vector: None,
+            cve_ids: Vec::new(),
+            cwe_ids: Vec::new(),
+            remediation: None,
+        });
+    }
+
+    let assets = if findings.is_empty() {
+        Vec::new()
+    } else {
+        let is_ip = host.parse::<std::net::IpAddr>().is_ok();
+        vec![Asset {
+            id: asset_id,
+            addresses: if is_ip {
+                vec![host.clone()]
+            } else {
+                Vec::new()
+            },
+            hostnames: if is_ip {
+                Vec::new()
+            } else {
+                vec![host.clone()]
+            },
+            labels: BTreeMap::new(),
+            os_matches: Vec::new(),
+        }]
+    };
+
+    Ok(ScanArtifacts {
+        tool: "gobuster".to_string(),
+        target: target.to_string(),
+        assets,
+        findings,
+        raw_xml: String::new(),
+        raw_output: output.to_string(),
+    })
+}
+
+// nuclei emits `cve-id`/`cwe-id` as either a string or a list, in mixed case.
+fn classification_ids(classification: Option<&Value>, key: &str) -> Vec<String> {
+    let Some(value) = classification.and_then(|value| value.get(key)) else {
+        return Vec::new();
+    };
+    let raw: Vec<&str> = match value {
+        Value::String(id) => id.split(',').collect(),
+        Value::Array(ids) => ids.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    raw.into_iter()
+        .map(|id| id.trim().to_uppercase())
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+/// Returns true when nuclei flags request JSON Lines output on stdout.
+pub(crate) fn nuclei_emits_json(flags: Option<&str>) -> bool {
+    flags
+        .and_then(|flags| shell_words::split(flags).ok())
+        .is_some_and(|parts| {
+            parts
+                .iter()
+                .any(|part| matches!(part.as_str(), "-jsonl" | "-json" | "--jsonl" | "--json"))
+        })
+}
+
+fn parse_nuclei_jsonl(output: &str, target: &str) -> Result<ScanArtifacts, String> {
+    let mut assets: Vec<Asset> = Vec::new();
+    let mut findings = Vec::new();
+
+    for (index, line) in output.lines().enumerate() {
+        let line = line.trim();
+        // nuclei interleaves banner and progress lines when -silent is not set.
+        if !line.starts_with('{') {
+            continue;
+        }
+        let event: Value = serde_json::from_str(line)
+            .map_err(|err| format!("line {}: invalid JSON event: {err}", index + 1))?;
+        let field = |key: &str| event.get(key).and_then(Value::as_str).map(str::to_string);
+
+        let host = field("host").unwrap_or_else(|| target.to_string());
+        let matched_at = field("matched-at").unwrap_or_else(|| host.clone());
+        let template_id = field("template-id").unwrap_or_else(|| "unknown".to_string());
+        let info = event.get("info").cloned().unwrap_or(Value::Null);
+        let title = info
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or(&template_id)
+            .to_string();
+        let severity = info
+            .get("severity")
+            .and_then(Value::as_str)
+            .map(normalize_severity)
+            .unwrap_or("informational")
+            .to_string();
+
+        let url = field("url")
+            .or_else(|| Some(matched_at.clone()))
+            .and_then(|raw| reqwest::Url::parse(&raw).ok())
+            .filter(|url| url.has_host());
+        let port = match event.get("port") {
+            Some(Value::String(raw)) => raw.parse::<u16>().ok(),
+            Some(Value::Number(raw)) => raw.as_u64().and_then(|port| u16::try_from(port).ok()),
+            _ => None,
+        }
+        .or_else(|| url.as_ref().and_then(|url| url.port_or_known_default()))
+        .unwrap_or(0);
+        let service = field("scheme").or_else(|| url.as_ref().map(|url| url.scheme().to_string()));
+
+        let mut evidence = BTreeMap::new();
+        evidence.insert("template_id".to_string(), json!(template_id));
+        for key in [
+            "matcher-name",
+            "extracted-results",
+            "timestamp",
+            "curl-command",
+        ] {
+            if let Some(value) = event.get(key) {
+                evidence.insert(key.replace('-', "_"), value.clone());
+            }
+        }
+        for key in ["tags", "reference", "description", "classification"] {
+            if let Some(value) = info.get(key) {
+                evidence.insert(key.to_string(), value.clone());
+            }
+        }
+
+        if !assets.iter().any(|asset| asset.id == host) {
+            let hostname = url
+                .as_ref()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .unwrap_or_else(|| host.split(':').next().unwrap_or(&host).to_string());
+            let mut addresses = Vec::new();
+            if let Some(ip) = field("ip") {
+                addresses.push(ip);
+            }
+            let hostnames = if addresses.contains(&hostname) {
+                Vec::new()
+            } else {
+                vec![hostname]
+            };
+            assets.push(Asset {
+                id: host.clone(),
+                addresses,
+                hostnames,
+                labels: BTreeMap::new(),
+                os_matches: Vec::new(),
+            });
+        }
+
+        let classification = info.get("classification");
+        let cvss_score = classification
+            .and_then(|value| value.get("cvss-score"))
+            .and_then(|value| match value {
+                Value::String(raw) => raw.parse::<f64>().ok(),
+                other => other.as_f64(),
+            })
+            .map(|score| score as f32);
+        let cvss_vector = classification
+            .and_then(|value| value.get("cvss-metrics"))
+            .and_then(Value::as_str)
+            .filter(|vector| !vector.is_empty())
+            .map(str::to_string);
+
+        let id = match field("matcher-name") {
+            Some(matcher) => format!("{template_id}:{matcher}@{matched_at}"),
+            None => format!("{template_id}@{matched_at}"),
+        };
+        findings.push(Finding {
+            id,
+            asset_id: host,
+            port,
+            protocol: field("type").unwrap_or_else(|| "unknown".to_string()),
+            state: "matched".to_string(),
+            service,
+            title,
+            description: matched_at,
+            severity,
+            evidence,
+            cvss_score,
+            cvss_vector,
+            cve_ids: classification_ids(classification, "cve-id"),
+            cwe_ids: classification_ids(classification, "cwe-id"),
+            remediation: info
+                .get("remediation")
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .map(str::to_string),
+        });
+    }
+
+    Ok(ScanArtifacts {
+        tool: "nuclei".to_string(),
+        target: target.to_string(),
+        assets,
+        findings,
+        raw_xml: String::new(),
+        raw_output: output.to_string(),
+    })
+}
+
+fn parse_nmap_xml(xml: &[u8], target: &str) -> Result<ScanArtifacts, String> {
+    let hosts = collect_xml_hosts(xml)?;
+    Ok(build_scan_artifacts("nmap", target, hosts, xml))
+}
+
+// masscan reuses nmap's XML vocabulary but emits one <host> element per
+// discovered port, and reports banners as separate stateless entries.
+fn parse_masscan_xml(xml: &[u8], target: &str) -> Result<ScanArtifacts, String> {
+    let mut merged: Vec<HostBuilder> = Vec::new();
+    for host in collect_xml_hosts(xml)? {
+        let Some((address, _)) = host.addresses.first().cloned() else {
+            continue;
+        };
+        let index = match merged
+            .iter()
+            .position(|existing| existing.addresses.iter().any(|(addr, _)| *addr == address))
+        {
+            Some(index) => index,
+            None => {
+                merged.push(HostBuilder {
+                    addresses: host.addresses.clone(),
+                    ..HostBuilder::default()
                 });
-                let message = format!(
-                    "{} report written to {}",
-                    report.format.as_str(),
-                    path_string
-                );
-                StepOutcome::with_artifact(
-                    StepExecution::completed(report.name.clone(), StepKind::Report, Some(message)),
-                    StoredArtifact {
-                        name: report_label,
-                        kind: ArtifactKind::Report,
-                        path: Some(path_string),
-                        data: report_data,
-                    },
-                )
+                merged.len() - 1
             }
-            Err(err) => StepOutcome::from_execution(StepExecution::failed(
-                report.name.clone(),
-                StepKind::Report,
-                Some(format!("failed to write {} report: {err}", extension)),
-            )),
+        };
+        let entry = &mut merged[index];
+        for hostname in host.hostnames {
+            if !entry.hostnames.contains(&hostname) {
+                entry.hostnames.push(hostname);
+            }
+        }
+        for port in host.ports {
+            let existing = entry.ports.iter_mut().find(|candidate| {
+                candidate.port == port.port && candidate.protocol == port.protocol
+            });
+            match existing {
+                Some(existing) => existing.merge(port),
+                None => entry.ports.push(port),
+            }
+        }
+    }
+
+    for host in &mut merged {
+        for port in &mut host.ports {
+            // masscan only grabs banners from ports that answered the sweep.
+            if port.state.is_none() && !port.banners.is_empty() {
+                port.state = Some("open".to_string());
+            }
+        }
+    }
+
+    Ok(build_scan_artifacts("masscan", target, merged, xml))
+}
+
+/// Returns true when masscan flags select XML output.
+pub(crate) fn masscan_emits_xml(flags: Option<&str>) -> bool {
+    let parts = flags
+        .and_then(|flags| shell_words::split(flags).ok())
+        .unwrap_or_default();
+    parts.iter().enumerate().any(|(index, part)| {
+        part == "--output-format=xml"
+            || (part == "--output-format" && parts.get(index + 1).is_some_and(|next| next == "xml"))
+    })
+}
+
+fn collect_xml_hosts(xml: &[u8]) -> Result<Vec<HostBuilder>, String> {
+    let mut reader = Reader::from_reader(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut buf = Vec::new();
+    let mut collector = HostCollector::default();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(element)) => collector.open(&element),
+            Ok(Event::Empty(element)) => {
+                collector.open(&element);
+                collector.close(element.name());
+            }
+            Ok(Event::End(element)) => collector.close(element.name()),
+            Ok(Event::Eof) => break,
+            Err(err) => return Err(format!("XML parsing error: {err}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(collector.hosts)
+}
+
+fn build_scan_artifacts(
+    tool: &str,
+    target: &str,
+    hosts: Vec<HostBuilder>,
+    xml: &[u8],
+) -> ScanArtifacts {
+    let mut assets = Vec::new();
+    let mut findings = Vec::new();
+
+    for host in hosts {
+        let addresses: Vec<String> = host
+            .addresses
+            .iter()
+            .map(|(addr, _)| addr.clone())
+            .collect();
+        if addresses.is_empty() {
+            continue;
+        }
+        let primary_addr = addresses.first().cloned().unwrap_or_default();
+        let asset_id = format!("asset://host/{}", primary_addr);
+
+        let mut labels: BTreeMap<String, String> = host
+            .scripts
+            .iter()
+            .map(|(id, output)| (format!("nse_{id}"), output.clone()))
+            .collect();
+        // Ties keep nmap's own ordering, which lists the preferred guess first.
+        if let Some(best) = host
+            .os_matches
+            .iter()
+            .rev()
+            .max_by_key(|candidate| candidate.accuracy)
+        {
+            labels.insert("os".to_string(), best.name.clone());
+        }
+        let asset = Asset {
+            id: asset_id.clone(),
+            addresses: addresses.clone(),
+            hostnames: host.hostnames.clone(),
+            labels,
+            os_matches: host.os_matches.clone(),
+        };
+
+        for port in host.ports {
+            if port.state.as_deref() != Some("open") {
+                continue;
+            }
+
+            let port_number = port.port.unwrap_or(0);
+            let protocol = port.protocol.clone().unwrap_or_else(|| "tcp".to_string());
+            let service = port.service.clone();
+            let finding_id = format!("finding://{}/{}-{}", primary_addr, protocol, port_number);
+
+            let mut evidence = BTreeMap::new();
+            evidence.insert("port".to_string(), Value::from(port_number));
+            if let Some(state) = &port.state {
+                evidence.insert("state".to_string(), Value::String(state.clone()));
+            }
+            if let Some(svc) = &service {
+                evidence.insert("service".to_string(), Value::String(svc.clone()));
+            }
+            if !port.banners.is_empty() {
+                let banners: serde_json::Map<String, Value> = port
+                    .banners
+                    .iter()
+                    .map(|(name, banner)| (name.clone(), Value::String(banner.clone())))
+                    .collect();
+                evidence.insert("banners".to_string(), Value::Object(banners));
+            }
+            for (id, output) in &port.scripts {
+                evidence.insert(format!("nse_{id}"), Value::String(output.clone()));
+            }
+            if let Some(detail) = &port.detail {
+                for (key, value) in [
+                    ("service_product", &detail.product),
+                    ("service_version", &detail.version),
+                    ("service_extra", &detail.extrainfo),
+                    ("service_tunnel", &detail.tunnel),
+                ] {
+                    if let Some(value) = value {
+                        evidence.insert(key.to_string(), Value::String(value.clone()));
+                    }
+                }
+            }
+
+            findings.push(Finding {
+                id: finding_id,
+                asset_id: asset_id.clone(),
+                port: port_number,
+                protocol: protocol.clone(),
+                state: port.state.unwrap_or_else(|| "unknown".to_string()),
+                service: service.clone(),
+                title: format!("{}:{} {} open", primary_addr, port_number, protocol),
+                description: format!(
+                    "Port {} {} is open on asset {} with service {:?}",
+                    port_number, protocol, primary_addr, service
+                ),
+                severity: "informational".to_string(),
+                evidence,
+                cvss_score: None,
+                cvss_vector: None,
+                cve_ids: Vec::new(),
+                cwe_ids: Vec::new(),
+                remediation: None,
+            });
         }
-    }
 
-    fn write_report_file(
-        &self,
-        report: &ReportStep,
-        extension: &str,
-        contents: &str,
-    ) -> Result<PathBuf, String> {
-        let path = self.resolve_report_path(report, extension);
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)
-                .map_err(|err| format!("failed to create report directory {:?}: {err}", parent))?;
-        }
-        let mut file = fs::File::create(&path)
-            .map_err(|err| format!("failed to create report file {:?}: {err}", path))?;
-        file.write_all(contents.as_bytes())
-            .map_err(|err| format!("failed to write report file {:?}: {err}", path))?;
-        Ok(path)
+        assets.push(asset);
     }
 
-    fn resolve_report_path(&self, report: &ReportStep, extension: &str) -> PathBuf {
-        if let Some(custom) = &report.output {
-            let candidate = PathBuf::from(custom);
-            if candidate.is_absolute() {
-                candidate
-            } else {
-                self.artifacts_dir.join(candidate)
-            }
-        } else {
-            self.artifacts_dir.join("reports").join(format!(
-                "{}.{}",
-                sanitize_label(&report.name),
-                extension
-            ))
-        }
+    ScanArtifacts {
+        tool: tool.to_string(),
+        target: target.to_string(),
+        assets,
+        findings,
+        raw_xml: String::from_utf8_lossy(xml).to_string(),
+        raw_output: String::new(),
     }
 }
 
-#[derive(Debug, Clone)]
-struct StepOutcome {
-    execution: StepExecution,
-    artifact: Option<StoredArtifact>,
+fn attribute_value(element: &BytesStart, key: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|attr| attr.key.as_ref() == key)
+        .map(|attr| {
+            attr.unescape_value()
+                .map(|value| value.to_string())
+                .unwrap_or_else(|_| String::from_utf8_lossy(&attr.value).to_string())
+        })
 }
 
-impl StepOutcome {
-    fn from_execution(execution: StepExecution) -> Self {
-        Self {
-            execution,
-            artifact: None,
+#[derive(Default)]
+struct HostCollector {
+    hosts: Vec<HostBuilder>,
+    current_host: Option<HostBuilder>,
+    current_port: Option<PortBuilder>,
+}
+
+impl HostCollector {
+    fn open(&mut self, element: &BytesStart) {
+        match element.name() {
+            QName(b"host") => {
+                self.current_host = Some(HostBuilder::default());
+            }
+            QName(b"address") => {
+                if let (Some(host), Some(addr)) = (
+                    self.current_host.as_mut(),
+                    attribute_value(element, b"addr"),
+                ) {
+                    let addr_type = attribute_value(element, b"addrtype")
+                        .unwrap_or_else(|| "unknown".to_string());
+                    host.addresses.push((addr, addr_type));
+                }
+            }
+            QName(b"hostname") => {
+                if let (Some(host), Some(name)) = (
+                    self.current_host.as_mut(),
+                    attribute_value(element, b"name"),
+                ) {
+                    host.hostnames.push(name);
+                }
+            }
+            QName(b"port") => {
+                self.current_port = Some(PortBuilder {
+                    protocol: attribute_value(element, b"protocol"),
+                    port: attribute_value(element, b"portid").and_then(|id| id.parse().ok()),
+                    ..PortBuilder::default()
+                });
+            }
+            QName(b"state") => {
+                if let Some(port) = self.current_port.as_mut() {
+                    if let Some(state) = attribute_value(element, b"state") {
+                        port.state = Some(state);
+                    }
+                }
+            }
+            QName(b"service") => {
+                if let Some(port) = self.current_port.as_mut() {
+                    let name = attribute_value(element, b"name");
+                    match (name, attribute_value(element, b"banner")) {
+                        (Some(name), Some(banner)) => {
+                            if port.service.is_none() && !is_masscan_pseudo_service(&name) {
+                                port.service = Some(name.clone());
+                            }
+                            port.banners.push((name, banner));
+                        }
+                        (Some(name), None) => port.service = Some(name),
+                        _ => {}
+                    }
+                    let detail = ServiceDetail {
+                        product: attribute_value(element, b"product"),
+                        version: attribute_value(element, b"version"),
+                        extrainfo: attribute_value(element, b"extrainfo"),
+                        tunnel: attribute_value(element, b"tunnel"),
+                    };
+                    if !detail.is_empty() {
+                        port.detail = Some(detail);
+                    }
+                }
+            }
+            QName(b"osmatch") => {
+                if let (Some(host), Some(name)) = (
+                    self.current_host.as_mut(),
+                    attribute_value(element, b"name"),
+                ) {
+                    let accuracy = attribute_value(element, b"accuracy")
+                        .and_then(|value| value.parse().ok())
+                        .unwrap_or(0);
+                    host.os_matches.push(OsMatch { name, accuracy });
+                }
+            }
+            QName(b"script") => {
+                let (Some(id), Some(output)) = (
+                    attribute_value(element, b"id"),
+                    attribute_value(element, b"output"),
+                ) else {
+                    return;
+                };
+                if let Some(port) = self.current_port.as_mut() {
+                    port.scripts.push((id, output));
+                } else if let Some(host) = self.current_host.as_mut() {
+                    host.scripts.push((id, output));
+                }
+            }
+            _ => {}
         }
     }
 
-    fn with_artifact(execution: StepExecution, artifact: StoredArtifact) -> Self {
-        Self {
-            execution,
-            artifact: Some(artifact),
+    fn close(&mut self, name: QName) {
+        match name {
+            QName(b"port") => {
+                if let (Some(host), Some(port)) =
+                    (self.current_host.as_mut(), self.current_port.take())
+                {
+                    host.ports.push(port);
+                }
+            }
+            QName(b"host") => {
+                if let Some(host) = self.current_host.take() {
+                    self.hosts.push(host);
+                }
+            }
+            _ => {}
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ExecutionReport {
-    pub steps: Vec<StepExecution>,
+// Banner categories masscan reports that describe content, not a service.
+fn is_masscan_pseudo_service(name: &str) -> bool {
+    matches!(name, "title" | "X509" | "X509CA" | "html")
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ExecutionOutcome {
-    pub report: ExecutionReport,
-    pub artifacts: Vec<StoredArtifact>,
+#[derive(Default)]
+struct HostBuilder {
+    addresses: Vec<(String, String)>,
+    hostnames: Vec<String>,
+    ports: Vec<PortBuilder>,
+    scripts: Vec<(String, String)>,
+    os_matches: Vec<OsMatch>,
 }
 
-impl ExecutionReport {
-    pub fn has_failures(&self) -> bool {
-        self.steps
-            .iter()
-            .any(|step| step.status == ExecutionStatus::Failed)
-    }
+#[derive(Default)]
+struct PortBuilder {
+    port: Option<u16>,
+    protocol: Option<String>,
+    state: Option<String>,
+    service: Option<String>,
+    banners: Vec<(String, String)>,
+    scripts: Vec<(String, String)>,
+    detail: Option<ServiceDetail>,
 }
 
-impl fmt::Display for ExecutionReport {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.steps.is_empty() {
-            writeln!(f, "No steps to execute.")?;
-            return Ok(());
+impl PortBuilder {
+    fn merge(&mut self, other: PortBuilder) {
+        if other.state.is_some() {
+            self.state = other.state;
         }
-
-        writeln!(f, "Execution results:")?;
-        for step in &self.steps {
-            let status = match step.status {
-                ExecutionStatus::Completed => "completed",
-                ExecutionStatus::Skipped => "skipped",
-                ExecutionStatus::Failed => "failed",
-                ExecutionStatus::NotImplemented => "not implemented",
-            };
-            writeln!(f, "  - [{}] {} ({:?})", status, step.name, step.kind)?;
-            if let Some(message) = &step.message {
-                for line in message.lines() {
-                    writeln!(f, "      {}", line)?;
-                }
-            }
+        if self.service.is_none() {
+            self.service = other.service;
         }
-        Ok(())
+        if self.detail.is_none() {
+            self.detail = other.detail;
+        }
+        self.banners.extend(other.banners);
+        self.scripts.extend(other.scripts);
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StepExecution {
-    pub name: String,
-    pub kind: StepKind,
-    pub status: ExecutionStatus,
-    pub message: Option<String>,
+// Fingerprint attributes nmap attaches to <service> under -sV.
+#[derive(Default)]
+struct ServiceDetail {
+    product: Option<String>,
+    version: Option<String>,
+    extrainfo: Option<String>,
+    tunnel: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-pub enum StepKind {
-    AssetGroup,
-    Scan,
-    Variable,
-    Secret,
-    Script,
-    Report,
-    Conditional,
-    Loop,
+impl ServiceDetail {
+    fn is_empty(&self) -> bool {
+        self.product.is_none()
+            && self.version.is_none()
+            && self.extrainfo.is_none()
+            && self.tunnel.is_none()
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-pub enum ExecutionStatus {
-    Completed,
-    Skipped,
-    Failed,
-    NotImplemented,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn spawn_mock_http(responses: Vec<(u16, String)>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let address = format!("http://{}", listener.local_addr().expect("mock address"));
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().expect("accept mock connection");
+                let mut reader = BufReader::new(stream);
+                let mut head = String::new();
+                let mut content_length = 0usize;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("read request line");
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap_or(0);
+                    }
+                    head.push_str(&line);
+                }
+                let mut payload = vec![0u8; content_length];
+                reader.read_exact(&mut payload).expect("read request body");
+                head.push_str(&String::from_utf8_lossy(&payload));
+                requests.push(head);
+
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let mut stream = reader.into_inner();
+                stream
+                    .write_all(response.as_bytes())
+                    .expect("write mock response");
+            }
+            requests
+        });
+        (address, handle)
+    }
 
-impl StepExecution {
-    pub fn completed(name: String, kind: StepKind, message: Option<String>) -> Self {
-        Self {
-            name,
-            kind,
-            status: ExecutionStatus::Completed,
-            message,
-        }
+    #[test]
+    fn sort_option_parses_direction() {
+        assert_eq!(
+            parse_sort_option("port").unwrap(),
+            ("port".to_string(), false)
+        );
+        assert_eq!(
+            parse_sort_option("severity DESC").unwrap(),
+            ("severity".to_string(), true)
+        );
+        assert!(parse_sort_option("severity sideways").is_err());
+        assert!(parse_sort_option("  ").is_err());
     }
 
-    pub fn failed(name: String, kind: StepKind, message: Option<String>) -> Self {
-        Self {
-            name,
-            kind,
-            status: ExecutionStatus::Failed,
-            message,
-        }
+    #[test]
+    fn secret_usage_lists_only_resolved_secrets() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = crate::scenario::parse_scenario_all(
+            "let a = \"${secret:token}\"\nlet b = \"${secret:api_key}:${secret:token}\"\n",
+        )
+        .expect("failed to parse scenario");
+        let secrets = HashMap::from([
+            ("token".to_string(), "t0ken".to_string()),
+            ("api_key".to_string(), "k3y".to_string()),
+            ("unused".to_string(), "n0pe".to_string()),
+        ]);
+
+        let outcome =
+            test_executor(&artifacts).execute_with_vars(&scenario, &HashMap::new(), &secrets);
+        assert!(!outcome.report.has_failures(), "{}", outcome.report);
+        assert_eq!(outcome.secret_usage, vec!["api_key", "token"]);
+
+        let json = serde_json::to_string(&outcome).expect("serialize outcome");
+        assert!(!json.contains("t0ken") && !json.contains("k3y"));
+    }
+
+    #[test]
+    fn secrets_with_ttl_stop_resolving_after_expiry() {
+        let mut secrets = SecretStore::default();
+        secrets.set("override", "kept");
+        secrets.insert_with_ttl("override", "ignored", Duration::ZERO);
+        secrets.insert_with_ttl("lease", "short-lived", Duration::ZERO);
+        secrets.insert_with_ttl("long", "long-lived", Duration::from_secs(3600));
+
+        assert_eq!(secrets.resolve("override").as_deref(), Some("kept"));
+        assert_eq!(secrets.resolve("long").as_deref(), Some("long-lived"));
+        assert_eq!(secrets.resolve("lease"), None);
+        assert!(secrets.is_expired("lease"));
+        assert_eq!(secrets.mask("token=short-lived"), "token=***");
+        assert_eq!(secrets.expired.iter().collect::<Vec<_>>(), vec!["lease"]);
+    }
+
+    #[test]
+    fn expired_vault_lease_is_reported_in_outcome() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let (address, server) = spawn_mock_http(vec![
+            (
+                200,
+                r#"{"auth":{"client_token":"s.mock-token"}}"#.to_string(),
+            ),
+            (
+                200,
+                r#"{"data":{"data":{"token":"lease-value"}},"lease_duration":1}"#.to_string(),
+            ),
+        ]);
+
+        let source = format!(
+            r#"
+secret api from vault {{
+  address = "{address}"
+  path = "app/api"
+  role_id = "role"
+  secret_id = "sid"
+}}
+
+sleep wait 1100ms
+
+let header = "Bearer ${{secret:api.token}}"
+"#
+        );
+
+        let scenario =
+            crate::scenario::parse_scenario_all(&source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
+        server.join().expect("mock server finished");
+
+        let header = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.name == "header")
+            .expect("variable step present");
+        assert_eq!(header.status, ExecutionStatus::Failed);
+        assert!(header
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .contains("secret 'api.token' has expired"));
+        assert_eq!(outcome.secret_warnings, vec!["api.token".to_string()]);
+        assert_eq!(
+            outcome.report.steps[0].message.as_deref(),
+            Some("secret 'api' loaded from vault (1 value); lease expires in 1s")
+        );
+    }
+
+    #[test]
+    fn vault_secret_is_fetched_via_approle_and_masked() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let (address, server) = spawn_mock_http(vec![
+            (200, r#"{"auth":{"client_token":"s.mock-token"}}"#.to_string()),
+            (
+                200,
+                r#"{"data":{"data":{"password":"vault-db-pass","user":"svc"},"metadata":{"version":3}},"lease_duration":0}"#
+                    .to_string(),
+            ),
+        ]);
+
+        let source = format!(
+            r#"
+secret db from vault {{
+  address = "{address}"
+  mount = "kv"
+  path = "app/db"
+  field = "password"
+  role_id = "role"
+  secret_id = "sid"
+}}
+
+let dsn = "postgres://svc:${{secret:db.password}}@db"
+"#
+        );
+
+        let scenario =
+            crate::scenario::parse_scenario_all(&source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
+
+        let secret_step = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.name == "db")
+            .expect("secret step present");
+        assert_eq!(
+            secret_step.status,
+            ExecutionStatus::Completed,
+            "{:?}",
+            secret_step.message
+        );
+
+        let variable_step = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.name == "dsn")
+            .expect("variable step present");
+        assert_eq!(variable_step.status, ExecutionStatus::Completed);
+        let message = variable_step.message.as_deref().unwrap_or_default();
+        assert!(message.contains("postgres://svc:***@db"));
+        assert!(!message.contains("vault-db-pass"));
+
+        let requests = server.join().expect("mock server finished");
+        assert!(requests[0].starts_with("POST /v1/auth/approle/login"));
+        assert!(requests[0].contains("\"secret_id\":\"sid\""));
+        assert!(requests[1].starts_with("GET /v1/kv/data/app/db"));
+        assert!(requests[1]
+            .to_ascii_lowercase()
+            .contains("x-vault-token: s.mock-token"));
+    }
+
+    #[test]
+    fn vault_missing_path_fails_with_path_in_message() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let (address, server) = spawn_mock_http(vec![
+            (
+                200,
+                r#"{"auth":{"client_token":"s.mock-token"}}"#.to_string(),
+            ),
+            (404, r#"{"errors":[]}"#.to_string()),
+        ]);
+
+        let source = format!(
+            r#"
+secret missing from vault {{
+  address = "{address}"
+  path = "kv/data/does/not/exist"
+  role_id = "role"
+  secret_id = "sid"
+}}
+"#
+        );
+
+        let scenario =
+            crate::scenario::parse_scenario_all(&source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
+        server.join().expect("mock server finished");
+
+        let step = &outcome.report.steps[0];
+        assert_eq!(step.status, ExecutionStatus::Failed);
+        let message = step.message.as_deref().unwrap_or_default();
+        assert!(message.contains("kv/data/does/not/exist"), "{message}");
+    }
+
+    #[test]
+    fn vault_unreachable_fails_step() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind probe");
+        let address = format!("http://{}", listener.local_addr().expect("probe address"));
+        drop(listener);
+
+        let source = format!(
+            r#"
+secret offline from vault {{
+  address = "{address}"
+  path = "kv/data/app"
+  role_id = "role"
+  secret_id = "sid"
+}}
+"#
+        );
+
+        let scenario =
+            crate::scenario::parse_scenario_all(&source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
+
+        let step = &outcome.report.steps[0];
+        assert_eq!(step.status, ExecutionStatus::Failed);
+        let message = step.message.as_deref().unwrap_or_default();
+        assert!(message.contains("failed to reach vault"), "{message}");
     }
 
-    pub fn skipped(name: String, kind: StepKind, message: Option<String>) -> Self {
-        Self {
-            name,
-            kind,
-            status: ExecutionStatus::Skipped,
-            message,
-        }
+    #[test]
+    fn aws_secret_payloads_split_into_fields() {
+        let payload = r#"{"username": "admin", "password": "s3cret", "port": 5432}"#;
+        let values = parse_aws_secret("prod/db", payload, None).expect("json secret");
+        assert_eq!(
+            values,
+            BTreeMap::from([
+                ("password".to_string(), "s3cret".to_string()),
+                ("port".to_string(), "5432".to_string()),
+                ("username".to_string(), "admin".to_string()),
+            ])
+        );
+        let values = parse_aws_secret("prod/db", payload, Some("password")).expect("key");
+        assert_eq!(values.len(), 1);
+        assert_eq!(
+            parse_aws_secret("prod/db", payload, Some("token")).unwrap_err(),
+            "key 'token' not present in AWS secret 'prod/db'"
+        );
+
+        let values = parse_aws_secret("ci/token", "plain-token", None).expect("plain secret");
+        assert_eq!(values.get("value").map(String::as_str), Some("plain-token"));
+        assert_eq!(
+            parse_aws_secret("ci/token", "plain-token", Some("token")).unwrap_err(),
+            "AWS secret 'ci/token' is not a JSON object; cannot extract key 'token'"
+        );
     }
 
-    pub fn not_implemented(name: String, kind: StepKind, message: Option<String>) -> Self {
-        Self {
-            name,
-            kind,
-            status: ExecutionStatus::NotImplemented,
-            message,
-        }
+    #[test]
+    fn aws_secret_key_overrides_skip_the_api_call() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = crate::scenario::parse_scenario_all(
+            "secret db from aws_secrets_manager {\n  secret_id = \"prod/db\"\n  key = \"password\"\n}\nscript show {\n  run \"echo ${secret:db}\"\n}\n",
+        )
+        .expect("failed to parse scenario");
+        let secrets = HashMap::from([("db.password".to_string(), "s3cret".to_string())]);
+        let outcome =
+            test_executor(&artifacts).execute_with_vars(&scenario, &HashMap::new(), &secrets);
+
+        assert!(!outcome.report.has_failures(), "{}", outcome.report);
+        assert_eq!(
+            outcome.report.steps[0].message.as_deref(),
+            Some("secret 'db' resolved from override (aws secrets manager skipped)")
+        );
+        assert_eq!(outcome.secret_usage, vec!["db".to_string()]);
     }
-}
 
-fn truncate_output(bytes: &[u8]) -> String {
-    const MAX: usize = 512;
-    let text = String::from_utf8_lossy(bytes);
-    if text.len() > MAX {
-        format!("{}…", &text[..MAX])
-    } else {
-        text.to_string()
+    #[test]
+    fn file_secrets_extract_json_keys() {
+        let contents =
+            r#"{"api_key": "k-1", "token": "t-2", "retries": 3, "db": {"password": "p-3"}}"#;
+        let all = parse_file_secret("creds.json", contents, "*").unwrap();
+        assert_eq!(
+            all.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("api_key".to_string(), "k-1".to_string()),
+                ("token".to_string(), "t-2".to_string()),
+            ]
+        );
+        let nested = parse_file_secret("creds.json", contents, "db.password").unwrap();
+        assert_eq!(nested.get("db.password").map(String::as_str), Some("p-3"));
+        assert_eq!(
+            parse_file_secret("creds.json", contents, "db.user").unwrap_err(),
+            "key 'db.user' not present in file creds.json"
+        );
+        let malformed = parse_file_secret("creds.json", "{\"api_key\": ", "api_key").unwrap_err();
+        assert!(
+            malformed.starts_with("file creds.json is not valid JSON:"),
+            "{malformed}"
+        );
     }
-}
 
-fn resolve_literal_value(
-    value: &LiteralValue,
-    variables: &HashMap<String, LiteralValue>,
-    secrets: &mut SecretStore,
-) -> Result<LiteralValue, String> {
-    match value {
-        LiteralValue::String(s) => {
-            let substituted = substitute_variables(s, variables, secrets)?;
-            Ok(LiteralValue::String(substituted))
-        }
-        LiteralValue::Number(n) => Ok(LiteralValue::Number(*n)),
-        LiteralValue::Boolean(b) => Ok(LiteralValue::Boolean(*b)),
-        LiteralValue::Array(items) => {
-            let mut resolved = Vec::with_capacity(items.len());
-            for item in items {
-                resolved.push(resolve_literal_value(item, variables, secrets)?);
-            }
-            Ok(LiteralValue::Array(resolved))
-        }
-        LiteralValue::Object(map) => {
-            let mut resolved = BTreeMap::new();
-            for (k, v) in map {
-                resolved.insert(k.clone(), resolve_literal_value(v, variables, secrets)?);
-            }
-            Ok(LiteralValue::Object(resolved))
-        }
+    #[test]
+    fn file_secrets_with_wildcard_key_scope_each_field() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let creds = artifacts.path().join("creds.json");
+        fs::write(&creds, r#"{"user": "svc", "password": "hunter2"}"#).unwrap();
+        let scenario = crate::scenario::parse_scenario_all(&format!(
+            "secret db from file {{\n  path = \"{}\"\n  key = \"*\"\n}}\nscript show {{\n  run \"echo ${{secret:db.user}}:${{secret:db.password}}\"\n}}\n",
+            creds.display()
+        ))
+        .expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute_with_vars(
+            &scenario,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+
+        assert!(!outcome.report.has_failures(), "{}", outcome.report);
+        assert_eq!(
+            outcome.report.steps[0].message.as_deref(),
+            Some("secret 'db' loaded from file (2 values)")
+        );
     }
-}
 
-fn literal_to_string(value: &LiteralValue) -> String {
-    value.display()
-}
+    #[test]
+    fn script_env_is_injected_and_secrets_masked() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = crate::scenario::parse_scenario_all(
+            "script show {\n  run \"sh -c 'echo $API_KEY-$LOG_LEVEL'\"\n  env { API_KEY = \"${secret:api}\"; LOG_LEVEL = \"debug\" }\n}\n",
+        )
+        .expect("failed to parse scenario");
+        let secrets = HashMap::from([("api".to_string(), "s3cr3t".to_string())]);
+        let outcome =
+            test_executor(&artifacts).execute_with_vars(&scenario, &HashMap::new(), &secrets);
 
-fn sanitize_label(label: &str) -> String {
-    label
-        .chars()
-        .map(|c| {
-            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
-                c
-            } else {
-                '_'
-            }
-        })
-        .collect()
-}
+        assert!(!outcome.report.has_failures(), "{}", outcome.report);
+        let message = outcome.report.steps[0].message.as_deref().unwrap();
+        assert!(
+            message.contains("with env API_KEY=***, LOG_LEVEL=debug"),
+            "{message}"
+        );
+        let artifact = outcome
+            .artifacts
+            .iter()
+            .find(|artifact| artifact.name == "script_show")
+            .expect("script artifact");
+        assert_eq!(artifact.data["stdout"], "s3cr3t-debug\n");
+    }
 
-fn resolve_map(
-    source: &BTreeMap<String, String>,
-    variables: &HashMap<String, LiteralValue>,
-    secrets: &mut SecretStore,
-) -> Result<BTreeMap<String, String>, String> {
-    let mut resolved = BTreeMap::new();
-    for (key, value) in source {
-        let substituted = substitute_variables(value, variables, secrets)?;
-        resolved.insert(key.clone(), substituted);
+    #[test]
+    fn script_json_output_is_stored_as_artifact_data() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let findings = artifacts.path().join("findings.json");
+        fs::write(
+            &findings,
+            r#"[{"asset_id": "10.0.0.5", "severity": "high", "description": "weak cipher"}]"#,
+        )
+        .unwrap();
+        let scenario = crate::scenario::parse_scenario_all(&format!(
+            "script custom {{\n  run \"cat {path}\"\n  json_output true\n}}\nscript raw {{\n  run \"echo not json\"\n  json_output true\n}}\nreport stdout {{\n  include script_custom\n}}\n",
+            path = findings.display()
+        ))
+        .expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
+
+        assert!(!outcome.report.has_failures(), "{}", outcome.report);
+        let artifact = |name: &str| {
+            outcome
+                .artifacts
+                .iter()
+                .find(|artifact| artifact.name == name)
+                .unwrap_or_else(|| panic!("missing artifact {name}"))
+        };
+        assert_eq!(artifact("script_custom").data[0]["severity"], "high");
+        assert_eq!(artifact("script_raw").data["stdout"], "not json\n");
+        assert!(outcome.report.steps[1]
+            .message
+            .as_deref()
+            .is_some_and(|message| message.contains("stdout was not valid JSON")));
+        let table = &artifact("report:stdout").data["tables"]["script_custom"];
+        assert_eq!(table["rows"][0]["asset_id"], "10.0.0.5");
     }
-    Ok(resolved)
-}
 
-fn resolve_list(
-    items: &[String],
-    variables: &HashMap<String, LiteralValue>,
-    secrets: &mut SecretStore,
-) -> Result<Vec<String>, String> {
-    let mut resolved = Vec::with_capacity(items.len());
-    for item in items {
-        let substituted = substitute_variables(item, variables, secrets)?;
-        resolved.push(substituted);
+    #[test]
+    fn script_timeout_kills_the_process_and_keeps_partial_output() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = crate::scenario::parse_scenario_all(
+            "script slow {\n  run \"sh -c 'echo started; exec sleep 5'\"\n  timeout \"300ms\"\n}\n",
+        )
+        .expect("failed to parse scenario");
+        let timer = Instant::now();
+        let outcome = test_executor(&artifacts).execute(&scenario);
+
+        assert!(timer.elapsed() < Duration::from_secs(4));
+        let step = &outcome.report.steps[0];
+        assert_eq!(step.status, ExecutionStatus::Failed);
+        assert!(step
+            .message
+            .as_deref()
+            .is_some_and(|message| message.contains("timed out after 300ms and was killed")));
+        let artifact = &outcome.artifacts[0];
+        assert_eq!(artifact.data["stdout"], "started\n");
+        assert_eq!(artifact.data["timed_out"], true);
     }
-    Ok(resolved)
-}
 
-fn substitute_variables(
-    value: &str,
-    variables: &HashMap<String, LiteralValue>,
-    secrets: &mut SecretStore,
-) -> Result<String, String> {
-    let mut result = String::with_capacity(value.len());
-    let mut cursor = 0;
+    #[test]
+    fn script_timeout_kills_processes_the_script_started() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let work = tempfile::tempdir().expect("temp work dir");
+        let scenario = crate::scenario::parse_scenario_all(&format!(
+            "script spawner {{\n  run \"sh -c '(sleep 1; touch leaked) & sleep 5'\"\n  cwd \"{}\"\n  timeout \"200ms\"\n}}\n",
+            work.path().display()
+        ))
+        .expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
 
-    while let Some(start_offset) = value[cursor..].find("${") {
-        let start_idx = cursor + start_offset;
-        result.push_str(&value[cursor..start_idx]);
+        assert_eq!(outcome.report.steps[0].status, ExecutionStatus::Failed);
+        std::thread::sleep(Duration::from_millis(1500));
+        assert!(!work.path().join("leaked").exists());
+    }
 
-        let remainder = &value[start_idx + 2..];
-        let end_offset = remainder
-            .find('}')
-            .ok_or_else(|| "unterminated variable placeholder".to_string())?;
-        let end_idx = start_idx + 2 + end_offset;
-        let token = remainder[..end_offset].trim();
+    #[test]
+    fn failed_scripts_are_retried_until_they_succeed() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let work = tempfile::tempdir().expect("temp work dir");
+        let scenario = crate::scenario::parse_scenario_all(&format!(
+            "script flaky {{\n  run \"sh -c 'echo x >> attempts; test $(wc -l < attempts) -ge 3'\"\n  cwd \"{}\"\n  retry {{ count 3; delay \"10ms\"; backoff exponential }}\n}}\nscript broken {{\n  run \"false\"\n  retry 2\n}}\n",
+            work.path().display()
+        ))
+        .expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
+
+        let flaky = &outcome.report.steps[0];
+        assert_eq!(flaky.status, ExecutionStatus::Completed);
+        assert!(flaky
+            .message
+            .as_deref()
+            .is_some_and(|message| message.ends_with("(attempt 3 of 4)")));
+        let attempts = fs::read_to_string(work.path().join("attempts")).unwrap();
+        assert_eq!(attempts.lines().count(), 3);
+
+        let broken = &outcome.report.steps[1];
+        assert_eq!(broken.status, ExecutionStatus::Failed);
+        assert!(broken
+            .message
+            .as_deref()
+            .is_some_and(|message| message.ends_with("(attempt 3 of 3)")));
+        assert_eq!(
+            outcome
+                .artifacts
+                .iter()
+                .filter(|artifact| artifact.name == "script_broken")
+                .count(),
+            1
+        );
+    }
 
-        if token.is_empty() {
-            return Err("empty variable placeholder".to_string());
-        }
+    #[test]
+    fn on_failure_handlers_run_only_after_a_failed_step() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = crate::scenario::parse_scenario_all(
+            "script broken {\n  run \"false\"\n}\non_failure {\n  let recovered = true\n  script cleanup {\n    run \"echo cleaned\"\n  }\n}\nscript fine {\n  run \"true\"\n}\non_failure {\n  script fallback {\n    run \"true\"\n  }\n}\n",
+        )
+        .expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
 
-        if let Some(secret_name) = token.strip_prefix("secret:") {
-            let key = secret_name.trim();
-            if key.is_empty() {
-                return Err("empty secret placeholder".to_string());
-            }
-            let resolved = secrets
-                .resolve(key)
-                .ok_or_else(|| format!("undefined secret '{key}'"))?;
-            result.push_str(&resolved);
-        } else {
-            let replacement = variables
-                .get(token)
-                .ok_or_else(|| format!("undefined variable '{token}'"))?;
-            result.push_str(&literal_to_string(replacement));
-        }
-        cursor = end_idx + 1;
+        let steps: Vec<(&str, &ExecutionStatus)> = outcome
+            .report
+            .steps
+            .iter()
+            .map(|step| (step.name.as_str(), &step.status))
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                ("broken", &ExecutionStatus::Failed),
+                ("recovered", &ExecutionStatus::Completed),
+                ("cleanup", &ExecutionStatus::Completed),
+                ("fine", &ExecutionStatus::Completed),
+            ]
+        );
+        assert!(outcome
+            .artifacts
+            .iter()
+            .any(|artifact| artifact.name == "script_cleanup"));
     }
 
-    result.push_str(&value[cursor..]);
-    Ok(result)
-}
+    #[test]
+    fn report_include_patterns_expand_to_stored_artifacts() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = crate::scenario::parse_scenario_all(
+            "script one {\n  run \"echo 1\"\n} -> findings_host2\nscript two {\n  run \"echo 2\"\n} -> findings_host1\nscript other {\n  run \"echo 3\"\n}\nreport stdout {\n  include findings_*\n  include script_other\n}\n",
+        )
+        .expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
 
-fn resolve_iterable(
-    iterable: &LoopIterable,
-    variables: &HashMap<String, LiteralValue>,
-    secrets: &mut SecretStore,
-) -> Result<Vec<LiteralValue>, String> {
-    match iterable {
-        LoopIterable::Variable(name) => match variables.get(name) {
-            Some(LiteralValue::Array(items)) => Ok(items.clone()),
-            Some(LiteralValue::String(value)) => Ok(vec![LiteralValue::String(value.clone())]),
-            Some(other) => Err(format!(
-                "variable '{}' is not iterable (found {})",
-                name,
-                other.display()
-            )),
-            None => Err(format!("undefined variable '{}'", name)),
-        },
-        LoopIterable::Literal(literal) => {
-            let resolved = resolve_literal_value(literal, variables, secrets)?;
-            match resolved {
-                LiteralValue::Array(items) => Ok(items),
-                LiteralValue::String(value) => Ok(vec![LiteralValue::String(value)]),
-                other => Err(format!(
-                    "loop iterable must be array or string, found {}",
-                    other.display()
-                )),
-            }
-        }
+        assert!(!outcome.report.has_failures(), "{}", outcome.report);
+        let report = outcome
+            .artifacts
+            .iter()
+            .find(|artifact| artifact.name == "report:stdout")
+            .expect("report artifact");
+        let included: Vec<&String> = report.data["includes"]
+            .as_object()
+            .expect("includes object")
+            .keys()
+            .collect();
+        assert_eq!(
+            included,
+            ["findings_host1", "findings_host2", "script_other"]
+        );
+        assert!(outcome.report.steps[3]
+            .message
+            .as_deref()
+            .is_some_and(|message| message.contains("(2 artifact(s) matched include patterns)")));
     }
-}
 
-fn evaluate_condition(
-    expr: &ConditionExpr,
-    variables: &HashMap<String, LiteralValue>,
-    secrets: &mut SecretStore,
-) -> Result<bool, String> {
-    match expr {
-        ConditionExpr::Literal(value) => Ok(*value),
-        ConditionExpr::Variable(name) => match variables.get(name) {
-            Some(LiteralValue::Boolean(value)) => Ok(*value),
-            Some(other) => Err(format!(
-                "variable '{}' is not boolean (found {})",
-                name,
-                other.display()
-            )),
-            None => Err(format!("undefined variable '{}'", name)),
-        },
-        ConditionExpr::Not(inner) => Ok(!evaluate_condition(inner, variables, secrets)?),
-        ConditionExpr::Equals(left, right) => {
-            let lhs = evaluate_operand(left, variables, secrets)?;
-            let rhs = evaluate_operand(right, variables, secrets)?;
-            Ok(lhs == rhs)
-        }
-        ConditionExpr::NotEquals(left, right) => {
-            let lhs = evaluate_operand(left, variables, secrets)?;
-            let rhs = evaluate_operand(right, variables, secrets)?;
-            Ok(lhs != rhs)
-        }
+    #[test]
+    fn relative_cwd_resolves_against_scenario_directory() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let project = tempfile::tempdir().expect("temp project dir");
+        fs::create_dir(project.path().join("data")).unwrap();
+        fs::write(project.path().join("data/marker.txt"), "found\n").unwrap();
+        let scenario_path = project.path().join("scan.ax");
+        let source = "script probe {\n  run \"cat marker.txt\"\n  cwd \"data\"\n}\n";
+        fs::write(&scenario_path, source).unwrap();
+        let scenario = crate::scenario::parse_scenario_all(source).expect("scenario parses");
+
+        let outcome = test_executor(&artifacts)
+            .with_scenario_path(&scenario_path)
+            .execute(&scenario);
+
+        assert!(!outcome.report.has_failures(), "{}", outcome.report);
+        let artifact = outcome
+            .artifacts
+            .iter()
+            .find(|artifact| artifact.name == "script_probe")
+            .expect("script artifact");
+        assert_eq!(artifact.data["stdout"], "found\n");
     }
-}
 
-fn evaluate_operand(
-    operand: &ConditionOperand,
-    variables: &HashMap<String, LiteralValue>,
-    secrets: &mut SecretStore,
-) -> Result<LiteralValue, String> {
-    match operand {
-        ConditionOperand::Variable(name) => variables
-            .get(name)
-            .cloned()
-            .ok_or_else(|| format!("undefined variable '{}'", name)),
-        ConditionOperand::Literal(value) => resolve_literal_value(value, variables, secrets),
+    #[test]
+    fn tag_filters_skip_unselected_steps() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = crate::scenario::parse_scenario_all(
+            "let greeting = \"hi\"\nscript quick {\n  tags [\"fast\"]\n  run \"echo ${greeting}\"\n}\nscript slow {\n  tags [\"fast\", \"prod\"]\n  run \"echo slow\"\n}\nscript other {\n  run \"echo other\"\n}\n",
+        )
+        .expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts)
+            .with_only_tags(vec!["fast".to_string()])
+            .with_skip_tags(vec!["prod".to_string()])
+            .execute_with_vars(&scenario, &HashMap::new(), &HashMap::new());
+
+        let statuses: Vec<(&str, &ExecutionStatus, Option<&str>)> = outcome
+            .report
+            .steps
+            .iter()
+            .map(|step| (step.name.as_str(), &step.status, step.message.as_deref()))
+            .collect();
+        assert_eq!(statuses[0].1, &ExecutionStatus::Completed);
+        assert_eq!(statuses[1].1, &ExecutionStatus::Completed);
+        assert_eq!(
+            statuses[2],
+            (
+                "slow",
+                &ExecutionStatus::Skipped,
+                Some("[tags] excluded by skip tag 'prod'")
+            )
+        );
+        assert_eq!(
+            statuses[3],
+            (
+                "other",
+                &ExecutionStatus::Skipped,
+                Some("[tags] no tag in [fast]")
+            )
+        );
     }
-}
 
-fn build_table_from_scan(data: &Value) -> Option<TableArtifact> {
-    let findings = data.get("findings")?.as_array()?;
-    if findings.is_empty() {
-        return None;
+    #[test]
+    fn keyring_mock_env_reads_secrets_from_environment() {
+        assert_eq!(
+            keyring_env_var("axion-ci", "deploy.bot"),
+            "AXION_KEYRING_AXION_CI_DEPLOY_BOT"
+        );
+        std::env::set_var("AXION_KEYRING_RUNTIME_TEST_MOCK", "k3y");
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = crate::scenario::parse_scenario_all(
+            "secret token from keyring {\n  service = \"runtime-test\"\n  account = \"mock\"\n}\nsecret missing from keyring {\n  service = \"runtime-test\"\n  account = \"absent\"\n}\n",
+        )
+        .expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts)
+            .with_keyring_mock_env(true)
+            .execute_with_vars(&scenario, &HashMap::new(), &HashMap::new());
+
+        assert_eq!(
+            outcome.report.steps[0].message.as_deref(),
+            Some("secret 'token' loaded from environment variable AXION_KEYRING_RUNTIME_TEST_MOCK")
+        );
+        assert_eq!(
+            outcome.report.steps[1].message.as_deref(),
+            Some("keyring mock: environment variable AXION_KEYRING_RUNTIME_TEST_ABSENT is not set")
+        );
+    }
+
+    #[test]
+    fn placeholders_fall_back_to_defaults_or_custom_errors() {
+        let mut secrets = SecretStore::default();
+        let variables = HashMap::from([(
+            "target".to_string(),
+            LiteralValue::String("10.0.0.5".to_string()),
+        )]);
+
+        assert_eq!(
+            substitute_variables(
+                "${target:-localhost} ${port:-8080} ${path:-}",
+                &variables,
+                &mut secrets
+            ),
+            Ok("10.0.0.5 8080 ".to_string())
+        );
+        assert_eq!(
+            substitute_variables("${target:?set --var target}", &variables, &mut secrets),
+            Ok("10.0.0.5".to_string())
+        );
+        assert_eq!(
+            substitute_variables("${token:?set --var token}", &variables, &mut secrets),
+            Err("set --var token".to_string())
+        );
+        assert_eq!(
+            substitute_variables("${token:?}", &variables, &mut secrets),
+            Err("undefined variable 'token'".to_string())
+        );
     }
 
-    let columns = vec![
-        "asset_id".to_string(),
-        "port".to_string(),
-        "protocol".to_string(),
-        "service".to_string(),
-        "state".to_string(),
-        "severity".to_string(),
-        "description".to_string(),
-    ];
+    #[test]
+    fn placeholders_access_object_keys_and_array_elements() {
+        let mut secrets = SecretStore::default();
+        let literal = |source: &str| {
+            crate::scenario::parse_literal_expression(source).expect("literal parses")
+        };
+        let variables = HashMap::from([
+            (
+                "config".to_string(),
+                literal(r#"{host: "db.internal", ports: [5432, 6432], tags: {env: "prod"}}"#),
+            ),
+            ("hosts".to_string(), literal(r#"["a", "b", "c"]"#)),
+        ]);
+        let mut substitute = |value: &str| substitute_variables(value, &variables, &mut secrets);
 
-    let mut rows = Vec::new();
-    for finding in findings {
-        let mut row = BTreeMap::new();
-        row.insert(
-            "asset_id".to_string(),
-            finding.get("asset_id").cloned().unwrap_or(Value::Null),
-        );
-        row.insert(
-            "port".to_string(),
-            finding.get("port").cloned().unwrap_or(Value::Null),
+        assert_eq!(
+            substitute("${config.host}:${config.ports[1]} ${config.tags.env}"),
+            Ok("db.internal:6432 prod".to_string())
         );
-        row.insert(
-            "protocol".to_string(),
-            finding.get("protocol").cloned().unwrap_or(Value::Null),
+        assert_eq!(substitute("${hosts[0]}${hosts[-1]}"), Ok("ac".to_string()));
+        assert_eq!(substitute("${config.user:-admin}"), Ok("admin".to_string()));
+
+        assert_eq!(
+            substitute("${config.user}"),
+            Err("'config' has no key 'user'".to_string())
         );
-        row.insert(
-            "service".to_string(),
-            finding.get("service").cloned().unwrap_or(Value::Null),
+        assert_eq!(
+            substitute("${hosts[3]}"),
+            Err("index 3 is out of bounds for 'hosts' (length 3)".to_string())
         );
-        row.insert(
-            "state".to_string(),
-            finding.get("state").cloned().unwrap_or(Value::Null),
+        assert_eq!(
+            substitute("${hosts[-4]}"),
+            Err("index -4 is out of bounds for 'hosts' (length 3)".to_string())
         );
-        row.insert(
-            "severity".to_string(),
-            finding.get("severity").cloned().unwrap_or(Value::Null),
+        assert_eq!(
+            substitute("${config.host.name}"),
+            Err("cannot read key 'name' of 'config.host' (string)".to_string())
         );
-        row.insert(
-            "description".to_string(),
-            finding.get("description").cloned().unwrap_or(Value::Null),
+        assert_eq!(
+            substitute("${missing[0]}"),
+            Err("undefined variable 'missing'".to_string())
         );
-        rows.push(row);
     }
 
-    Some(TableArtifact { columns, rows })
-}
+    #[test]
+    fn null_values_substitute_as_empty_and_compare_equal() {
+        let mut secrets = SecretStore::default();
+        let variables = HashMap::from([
+            ("proxy".to_string(), LiteralValue::Null),
+            (
+                "ports".to_string(),
+                crate::scenario::parse_literal_expression("[80, null]").expect("literal parses"),
+            ),
+        ]);
 
-fn render_html_report(
-    title: &str,
-    generated_at: &str,
-    includes: &BTreeMap<String, Value>,
-    tables: &BTreeMap<String, TableArtifact>,
-) -> String {
-    let mut html = String::new();
-    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\" />\n");
-    html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\n");
-    html.push_str(&format!(
-        "<title>Axion Report – {}</title>\n",
-        escape_html(title)
-    ));
-    html.push_str("<style>");
-    html.push_str(
-        "body{font-family:system-ui,-apple-system,\"Segoe UI\",sans-serif;background:#0f172a;color:#e2e8f0;margin:0;padding:0;}\
-        header{background:#1e293b;padding:24px 32px;border-bottom:1px solid rgba(148,163,184,0.2);}\
-        h1{margin:0;font-size:28px;}\
-        h2{margin-top:32px;margin-bottom:12px;font-size:22px;}\
-        main{padding:32px;}\
-        section{margin-bottom:40px;background:#111c34;padding:24px;border-radius:12px;border:1px solid rgba(148,163,184,0.1);}\
-        table{width:100%;border-collapse:collapse;margin-top:16px;font-size:14px;}\
-        th,td{border:1px solid rgba(148,163,184,0.2);padding:8px 10px;text-align:left;}\
-        th{background:#1e293b;font-weight:600;}\
-        tr:nth-child(even){background:rgba(148,163,184,0.05);}\
-        code,pre{font-family:\"Fira Code\",Consolas,monospace;background:#0b1120;color:#f8fafc;border-radius:8px;}\
-        pre{padding:16px;overflow:auto;}\
-        details{margin-top:16px;}\
-        details>summary{cursor:pointer;color:#38bdf8;font-weight:600;}\
-        footer{padding:16px 32px;border-top:1px solid rgba(148,163,184,0.2);color:#94a3b8;font-size:13px;}",
-    );
-    html.push_str("</style>\n</head>\n<body>\n");
-    html.push_str("<header>");
-    html.push_str(&format!("<h1>Axion Report: {}</h1>", escape_html(title)));
-    html.push_str(&format!(
-        "<p>Generated at {}</p>",
-        escape_html(generated_at)
-    ));
-    html.push_str("</header>\n<main>\n");
+        assert_eq!(
+            substitute_variables("[${proxy}]", &variables, &mut secrets),
+            Ok("[]".to_string())
+        );
+        assert_eq!(
+            substitute_variables("${proxy:-direct}", &variables, &mut secrets),
+            Ok("direct".to_string())
+        );
+        assert_eq!(
+            substitute_variables("${proxy:?}", &variables, &mut secrets),
+            Err("variable 'proxy' is null".to_string())
+        );
 
-    for (name, value) in includes {
-        html.push_str("<section>");
-        html.push_str(&format!("<h2>{}</h2>", escape_html(name)));
-        if let Some(table) = tables.get(name) {
-            html.push_str(&render_html_table(table));
-        }
-        if let Ok(raw) = serde_json::to_string_pretty(value) {
-            html.push_str("<details><summary>Raw JSON</summary>");
-            html.push_str("<pre><code>");
-            html.push_str(&escape_html(&raw));
-            html.push_str("</code></pre></details>");
+        for (source, expected) in [
+            ("proxy == null", true),
+            ("proxy != null", false),
+            ("null == proxy", true),
+            (r#"proxy == "null""#, false),
+        ] {
+            assert_eq!(
+                evaluate_condition(&condition_from(source), &variables, &mut secrets),
+                Ok(expected),
+                "{source}"
+            );
         }
-        html.push_str("</section>\n");
     }
 
-    if includes.is_empty() {
-        html.push_str("<section><p>No artifacts were included in this report.</p></section>");
-    }
+    #[test]
+    fn placeholder_pipes_transform_values() {
+        let mut secrets = SecretStore::default();
+        let variables = HashMap::from([
+            (
+                "host".to_string(),
+                LiteralValue::String("  Web-01.Example.com \n".to_string()),
+            ),
+            (
+                "query".to_string(),
+                LiteralValue::String("a b&c=d/é~".to_string()),
+            ),
+        ]);
+        let mut substitute = |value: &str| substitute_variables(value, &variables, &mut secrets);
 
-    html.push_str("</main>\n<footer>Generated by axion-core</footer>\n</body>\n</html>");
-    html
-}
+        assert_eq!(
+            substitute("${host|trim}"),
+            Ok("Web-01.Example.com".to_string())
+        );
+        assert_eq!(
+            substitute("${host | trim | upper}"),
+            Ok("WEB-01.EXAMPLE.COM".to_string())
+        );
+        assert_eq!(
+            substitute("${host|trim|lower}"),
+            Ok("web-01.example.com".to_string())
+        );
+        assert_eq!(
+            substitute("${query|base64}"),
+            Ok("YSBiJmM9ZC/DqX4=".to_string())
+        );
+        assert_eq!(
+            substitute("${query|urlencode}"),
+            Ok("a%20b%26c%3Dd%2F%C3%A9~".to_string())
+        );
+        assert_eq!(substitute("${missing:-none|upper}"), Ok("NONE".to_string()));
 
-fn render_html_table(table: &TableArtifact) -> String {
-    let mut html = String::new();
-    html.push_str("<table>");
-    html.push_str("<thead><tr>");
-    for column in &table.columns {
-        html.push_str(&format!("<th>{}</th>", escape_html(column)));
+        let err = substitute("${host|reverse}").expect_err("unknown transform");
+        assert!(err.starts_with("unknown transform 'reverse'"), "{err}");
+        let err = substitute("${secret:api|upper}").expect_err("secret transform");
+        assert!(
+            err.contains("not supported on secret placeholders"),
+            "{err}"
+        );
     }
-    html.push_str("</tr></thead>");
-    html.push_str("<tbody>");
-    for row in &table.rows {
-        html.push_str("<tr>");
-        for column in &table.columns {
-            let value = value_to_string(row.get(column));
-            html.push_str(&format!("<td>{}</td>", escape_html(&value)));
-        }
-        html.push_str("</tr>");
+
+    #[test]
+    fn let_values_read_environment_variables_and_files() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let env_key = "AXION_TEST_LET_ENV_VALUE";
+        std::env::set_var(env_key, "eu-west-1");
+        let target_file = artifacts.path().join("target.txt");
+        fs::write(&target_file, "10.0.0.7\n").expect("write target file");
+
+        let source = format!(
+            r#"
+let region = env("{env_key}")
+let target = file("{}")
+let summary = "${{region}} ${{target|trim}}"
+let unset = env("AXION_TEST_LET_ENV_UNSET")
+let absent = file("{}/missing.txt")
+"#,
+            target_file.display(),
+            artifacts.path().display()
+        );
+        let scenario =
+            crate::scenario::parse_scenario_all(&source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute_with_vars(
+            &scenario,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        let step = |name: &str| {
+            outcome
+                .report
+                .steps
+                .iter()
+                .find(|step| step.name == name)
+                .expect("step present")
+        };
+
+        assert_eq!(
+            step("summary").message.as_deref(),
+            Some("summary = eu-west-1 10.0.0.7")
+        );
+        assert_eq!(step("unset").status, ExecutionStatus::Failed);
+        let message = step("unset").message.as_deref().unwrap_or_default();
+        assert!(
+            message.contains("environment variable 'AXION_TEST_LET_ENV_UNSET' is not set"),
+            "{message}"
+        );
+        assert_eq!(step("absent").status, ExecutionStatus::Failed);
+        let message = step("absent").message.as_deref().unwrap_or_default();
+        assert!(message.contains("failed to read file"), "{message}");
     }
-    html.push_str("</tbody></table>");
-    html
-}
 
-fn render_markdown_report(
-    title: &str,
-    generated_at: &str,
-    includes: &BTreeMap<String, Value>,
-    tables: &BTreeMap<String, TableArtifact>,
-) -> String {
-    let mut md = String::new();
-    md.push_str("# Axion Report\n\n");
-    md.push_str(&format!("**Title:** {}\n\n", title));
-    md.push_str(&format!("_Generated at {}_\n\n", generated_at));
+    #[test]
+    fn let_values_compute_arithmetic_expressions() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = crate::scenario::parse_scenario_all(
+            r#"
+let port = 8080
+let next = port + 1
+let total = next * 2 - 1
+let ratio = 7 / 2
+let url = "https://" + "example.com"
+let label = "port " + port
+let zero = 0
+let broken = port / zero
+"#,
+        )
+        .expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute_with_vars(
+            &scenario,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        let step = |name: &str| {
+            outcome
+                .report
+                .steps
+                .iter()
+                .find(|step| step.name == name)
+                .expect("step present")
+        };
 
-    if includes.is_empty() {
-        md.push_str("No artifacts were included in this report.\n");
-        return md;
+        assert_eq!(step("next").message.as_deref(), Some("next = 8081"));
+        assert_eq!(step("total").message.as_deref(), Some("total = 16161"));
+        assert_eq!(step("ratio").message.as_deref(), Some("ratio = 3.5"));
+        assert_eq!(
+            step("url").message.as_deref(),
+            Some("url = https://example.com")
+        );
+        assert_eq!(step("label").status, ExecutionStatus::Failed);
+        let message = step("label").message.as_deref().unwrap_or_default();
+        assert!(
+            message.contains("cannot apply '+' to string and number"),
+            "{message}"
+        );
+        assert_eq!(step("broken").status, ExecutionStatus::Failed);
+        let message = step("broken").message.as_deref().unwrap_or_default();
+        assert!(message.contains("division by zero"), "{message}");
     }
 
-    for (name, value) in includes {
-        md.push_str(&format!("## {}\n\n", name));
-        if let Some(table) = tables.get(name) {
-            if !table.columns.is_empty() {
-                md.push_str(&render_markdown_table(table));
-                md.push('\n');
-            }
-        }
-        if let Ok(raw) = serde_json::to_string_pretty(value) {
-            md.push_str("```json\n");
-            md.push_str(&raw);
-            md.push_str("\n```\n\n");
-        }
+    #[test]
+    fn resolves_secret_placeholders_and_masks_messages() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let env_key = "AXION_TEST_SECRET_VALUE";
+        std::env::set_var(env_key, "super-secret-token");
+
+        let source = format!(
+            r#"
+secret api from env {{
+  token = "{env_key}"
+}}
+
+let header = "Bearer ${{secret:api.token}}"
+"#
+        );
+
+        let scenario =
+            crate::scenario::parse_scenario_all(&source).expect("failed to parse scenario");
+        let executor = test_executor(&artifacts);
+        let overrides = HashMap::new();
+        let secret_overrides = HashMap::new();
+        let outcome = executor.execute_with_vars(&scenario, &overrides, &secret_overrides);
+
+        let secret_step = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.name == "api")
+            .expect("secret step present");
+        assert_eq!(secret_step.status, ExecutionStatus::Completed);
+
+        let variable_step = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.name == "header")
+            .expect("variable step present");
+        assert_eq!(variable_step.status, ExecutionStatus::Completed);
+        let message = variable_step.message.as_deref().unwrap_or_default();
+        assert!(message.contains("Bearer ***"));
+        assert!(!message.contains("super-secret-token"));
+
+        std::env::remove_var(env_key);
     }
 
-    md
+    #[test]
+    fn secret_override_allows_missing_env() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let source = r#"
+secret token from env {
+  value = "AXION_TEST_MISSING_ENV"
 }
 
-fn render_markdown_table(table: &TableArtifact) -> String {
-    let mut md = String::new();
-    md.push('|');
-    for column in &table.columns {
-        md.push(' ');
-        md.push_str(&sanitize_markdown_cell(column));
-        md.push_str(" |");
+let captured = "${secret:token.value}"
+"#;
+
+        std::env::remove_var("AXION_TEST_MISSING_ENV");
+
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let executor = test_executor(&artifacts);
+        let overrides = HashMap::new();
+        let mut secret_overrides = HashMap::new();
+        secret_overrides.insert("token.value".to_string(), "override-secret".to_string());
+
+        let outcome = executor.execute_with_vars(&scenario, &overrides, &secret_overrides);
+
+        let secret_step = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.name == "token")
+            .expect("secret step present");
+        assert_eq!(secret_step.status, ExecutionStatus::Completed);
+
+        let variable_step = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.name == "captured")
+            .expect("variable step present");
+        assert_eq!(variable_step.status, ExecutionStatus::Completed);
+        let message = variable_step.message.as_deref().unwrap_or_default();
+        assert!(message.contains("***"));
+        assert!(!message.contains("override-secret"));
     }
-    md.push('\n');
 
-    md.push('|');
-    for _ in &table.columns {
-        md.push_str(" --- |");
-    }
-    md.push('\n');
+    #[test]
+    fn compound_conditions_short_circuit() {
+        let variables: HashMap<String, LiteralValue> = [
+            ("is_prod".to_string(), LiteralValue::Boolean(false)),
+            ("force_run".to_string(), LiteralValue::Boolean(true)),
+            (
+                "severity".to_string(),
+                LiteralValue::String("high".to_string()),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let mut secrets = SecretStore::default();
 
-    for row in &table.rows {
-        md.push('|');
-        for column in &table.columns {
-            let value = value_to_string(row.get(column));
-            md.push(' ');
-            md.push_str(&sanitize_markdown_cell(&value));
-            md.push_str(" |");
+        let cases = [
+            ("is_prod || force_run", true),
+            (r#"force_run && severity == "high""#, true),
+            (r#"is_prod && severity == "high""#, false),
+            // The right-hand side references an undefined variable and must not be evaluated.
+            ("is_prod && missing", false),
+            ("force_run || missing", true),
+            ("defined force_run", true),
+            ("defined missing", false),
+            ("!defined missing && force_run", true),
+            ("defined missing && missing", false),
+        ];
+        for (source, expected) in cases {
+            let expr = condition_from(source);
+            let result = evaluate_condition(&expr, &variables, &mut secrets);
+            assert_eq!(result, Ok(expected), "condition: {source}");
         }
-        md.push('\n');
+
+        let expr = condition_from("force_run && missing");
+        assert!(evaluate_condition(&expr, &variables, &mut secrets).is_err());
     }
 
-    md
-}
+    #[test]
+    fn ordering_conditions_compare_numbers_and_strings() {
+        let variables: HashMap<String, LiteralValue> = [
+            ("port_count".to_string(), LiteralValue::Integer(12)),
+            ("host".to_string(), LiteralValue::String("beta".to_string())),
+        ]
+        .into_iter()
+        .collect();
+        let mut secrets = SecretStore::default();
 
-fn sanitize_markdown_cell(value: &str) -> String {
-    let replaced = value.replace('\n', "<br>");
-    replaced.replace('|', "\\|")
-}
+        let cases = [
+            ("port_count > 10", true),
+            ("port_count < 10", false),
+            ("port_count >= 12", true),
+            ("port_count <= 11.5", false),
+            ("port_count == 12.0", true),
+            ("port_count != 12", false),
+            (r#"host > "alpha""#, true),
+            (r#"host <= "beta""#, true),
+            (r#"host < "Beta""#, false),
+        ];
+        for (source, expected) in cases {
+            let expr = condition_from(source);
+            let result = evaluate_condition(&expr, &variables, &mut secrets);
+            assert_eq!(result, Ok(expected), "condition: {source}");
+        }
 
-fn render_sarif_report(
-    title: &str,
-    generated_at: &str,
-    includes: &BTreeMap<String, Value>,
-    options: &BTreeMap<String, String>,
-) -> Result<String, String> {
-    let tool_name = options
-        .get("tool_name")
-        .or_else(|| options.get("tool"))
-        .cloned()
-        .unwrap_or_else(|| "Axion".to_string());
-    let tool_version = options.get("tool_version").cloned();
-    let tool_uri = options.get("tool_uri").cloned();
-    let min_rank = options
-        .get("severity_threshold")
-        .map(|value| severity_rank(value))
-        .unwrap_or(0);
+        let mixed = condition_from(r#"port_count > "10""#);
+        let err = evaluate_condition(&mixed, &variables, &mut secrets)
+            .expect_err("mixed types should not compare");
+        assert!(err.contains("number and string"), "{err}");
+    }
 
-    let mut results = Vec::new();
-    let mut rules: BTreeMap<String, Value> = BTreeMap::new();
-    let mut artifacts = Vec::new();
-    let mut artifact_ids = HashSet::new();
+    #[test]
+    fn contains_checks_substrings_and_membership() {
+        let variables: HashMap<String, LiteralValue> = [
+            (
+                "hostname".to_string(),
+                LiteralValue::String("web01.corp.local".to_string()),
+            ),
+            (
+                "ports".to_string(),
+                LiteralValue::Array(vec![LiteralValue::Integer(22), LiteralValue::Integer(443)]),
+            ),
+            ("enabled".to_string(), LiteralValue::Boolean(true)),
+        ]
+        .into_iter()
+        .collect();
+        let mut secrets = SecretStore::default();
 
-    for value in includes.values() {
-        let scan: ScanArtifacts = match serde_json::from_value(value.clone()) {
-            Ok(scan) => scan,
-            Err(_) => continue,
-        };
+        let string_hit = condition_from(r#"hostname contains ".corp.local""#);
+        assert_eq!(
+            evaluate_condition(&string_hit, &variables, &mut secrets),
+            Ok(true)
+        );
 
-        rules.entry(scan.tool.clone()).or_insert_with(|| {
-            json!({
-                "id": scan.tool,
-                "name": scan.tool,
-                "shortDescription": {
-                    "text": format!("Findings emitted by {}", scan.tool)
-                }
-            })
-        });
+        let string_miss = condition_from(r#"hostname contains ".example.com""#);
+        assert_eq!(
+            evaluate_condition(&string_miss, &variables, &mut secrets),
+            Ok(false)
+        );
 
-        for finding in scan.findings {
-            let rank = severity_rank(&finding.severity);
-            if rank < min_rank {
-                continue;
-            }
+        let array_hit = condition_from("ports contains 443.0");
+        assert_eq!(
+            evaluate_condition(&array_hit, &variables, &mut secrets),
+            Ok(true)
+        );
 
-            if artifact_ids.insert(finding.asset_id.clone()) {
-                artifacts.push(json!({
-                    "location": {
-                        "uri": finding.asset_id
-                    }
-                }));
-            }
+        let array_miss = condition_from("contains(ports, 8080)");
+        assert_eq!(
+            evaluate_condition(&array_miss, &variables, &mut secrets),
+            Ok(false)
+        );
 
-            let level = sarif_level(&finding.severity);
-            let mut properties = serde_json::Map::new();
-            properties.insert("severity".to_string(), json!(finding.severity));
-            properties.insert("service".to_string(), json!(finding.service));
-            properties.insert("state".to_string(), json!(finding.state));
-            properties.insert("protocol".to_string(), json!(finding.protocol));
-            properties.insert("port".to_string(), json!(finding.port));
-            properties.insert("target".to_string(), json!(scan.target));
-            properties.insert("description".to_string(), json!(finding.description));
-            properties.insert("evidence".to_string(), json!(finding.evidence));
+        let undefined = condition_from(r#"missing contains "x""#);
+        let err = evaluate_condition(&undefined, &variables, &mut secrets)
+            .expect_err("undefined variable should error");
+        assert!(err.contains("undefined variable 'missing'"), "{err}");
 
-            results.push(json!({
-                "ruleId": scan.tool,
-                "level": level,
-                "message": {
-                    "text": finding.title
-                },
-                "locations": [{
-                    "physicalLocation": {
-                        "artifactLocation": {
-                            "uri": finding.asset_id
-                        }
-                    }
-                }],
-                "properties": properties
-            }));
-        }
+        let wrong_type = condition_from(r#"enabled contains "x""#);
+        let err = evaluate_condition(&wrong_type, &variables, &mut secrets)
+            .expect_err("boolean haystack should error");
+        assert!(err.contains("found boolean"), "{err}");
     }
 
-    let mut driver = serde_json::Map::new();
-    driver.insert("name".to_string(), json!(tool_name));
-    if let Some(version) = tool_version {
-        driver.insert("version".to_string(), json!(version));
-    }
-    if let Some(uri) = tool_uri {
-        driver.insert("informationUri".to_string(), json!(uri));
-    }
-    if !rules.is_empty() {
-        driver.insert(
-            "rules".to_string(),
-            json!(rules.into_values().collect::<Vec<_>>()),
+    #[test]
+    fn matches_condition_uses_regular_expressions() {
+        let variables: HashMap<String, LiteralValue> = [(
+            "target".to_string(),
+            LiteralValue::String("10.0.4.12".to_string()),
+        )]
+        .into_iter()
+        .collect();
+        let mut secrets = SecretStore::default();
+
+        let hit = condition_from(r#"target matches "^10\.""#);
+        assert_eq!(evaluate_condition(&hit, &variables, &mut secrets), Ok(true));
+
+        let miss = condition_from(r#"target matches "^192\.168\.""#);
+        assert_eq!(
+            evaluate_condition(&miss, &variables, &mut secrets),
+            Ok(false)
         );
-    }
 
-    let mut run = serde_json::Map::new();
-    run.insert("tool".to_string(), json!({ "driver": driver }));
-    run.insert("results".to_string(), json!(results));
-    if !artifacts.is_empty() {
-        run.insert("artifacts".to_string(), json!(artifacts));
+        let invalid = condition_from(r#"target matches "(unclosed""#);
+        let err = evaluate_condition(&invalid, &variables, &mut secrets)
+            .expect_err("invalid pattern should error");
+        assert!(err.contains("invalid regular expression"), "{err}");
     }
-    run.insert(
-        "invocations".to_string(),
-        json!([{
-            "executionSuccessful": true,
-            "endTimeUtc": generated_at
-        }]),
-    );
-    run.insert(
-        "automationDetails".to_string(),
-        json!({
-            "id": format!("axion::{}", title),
-            "description": {
-                "text": format!("Axion SARIF report {}", title)
-            }
-        }),
-    );
-
-    let sarif = json!({
-        "version": "2.1.0",
-        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
-        "runs": [run]
-    });
 
-    serde_json::to_string_pretty(&sarif).map_err(|err| err.to_string())
+    #[test]
+    fn invalid_regex_fails_conditional_step() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let source = r#"
+let target = "10.0.0.1"
+if target matches "[unclosed" {
+  let hit = true
 }
+"#;
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
 
-fn severity_rank(label: &str) -> u8 {
-    match label.to_lowercase().as_str() {
-        "critical" => 4,
-        "high" => 3,
-        "medium" => 2,
-        "moderate" => 2,
-        "low" => 1,
-        "informational" | "info" | "note" => 0,
-        _ => 0,
+        let step = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.kind == StepKind::Conditional)
+            .expect("conditional step present");
+        assert_eq!(step.status, ExecutionStatus::Failed);
+        assert!(step
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .contains("invalid regular expression"));
     }
-}
 
-fn sarif_level(label: &str) -> &'static str {
-    match label.to_lowercase().as_str() {
-        "critical" | "high" => "error",
-        "medium" | "moderate" => "warning",
-        "low" | "informational" | "info" => "note",
-        _ => "note",
-    }
+    #[test]
+    fn parallel_block_merges_branches_in_declaration_order() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let source = r#"
+let base = "corp"
+parallel enrich {
+  asset_group first {
+    scope corp-a
+  }
+  let extra = "${base}-added"
+  asset_group second {
+    scope corp-b
+  }
 }
+let after = "${extra}"
+"#;
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
 
-fn escape_html(input: &str) -> String {
-    let mut escaped = String::with_capacity(input.len());
-    for ch in input.chars() {
-        match ch {
-            '&' => escaped.push_str("&amp;"),
-            '<' => escaped.push_str("&lt;"),
-            '>' => escaped.push_str("&gt;"),
-            '"' => escaped.push_str("&quot;"),
-            '\'' => escaped.push_str("&#39;"),
-            _ => escaped.push(ch),
-        }
-    }
-    escaped
-}
+        let names: Vec<&str> = outcome
+            .report
+            .steps
+            .iter()
+            .map(|step| step.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "base",
+                "first",
+                "extra",
+                "second",
+                "parallel enrich",
+                "after"
+            ]
+        );
 
-fn render_table(table: &TableArtifact) -> String {
-    let mut display = Table::new();
-    display.load_preset(ASCII_FULL);
-    display.set_header(table.columns.clone());
+        let block = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.kind == StepKind::Parallel)
+            .expect("parallel step present");
+        assert_eq!(block.status, ExecutionStatus::Completed);
 
-    for row in &table.rows {
-        let cells: Vec<String> = table
-            .columns
+        let after = outcome
+            .report
+            .steps
             .iter()
-            .map(|column| value_to_string(row.get(column)))
-            .collect();
-        display.add_row(cells);
+            .find(|step| step.name == "after")
+            .expect("after step present");
+        assert_eq!(after.status, ExecutionStatus::Completed);
+
+        for name in ["asset_group:first", "asset_group:second", "parallel:enrich"] {
+            assert!(
+                outcome
+                    .artifacts
+                    .iter()
+                    .any(|artifact| artifact.name == name),
+                "missing artifact {name}"
+            );
+        }
     }
 
-    display.to_string()
+    #[test]
+    fn assert_steps_report_custom_failure_messages() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let source = r#"
+let open_ports = 3
+assert few_ports {
+  condition open_ports <= 5
 }
-
-fn value_to_string(value: Option<&Value>) -> String {
-    match value {
-        Some(Value::Null) | None => String::new(),
-        Some(Value::String(s)) => s.clone(),
-        Some(Value::Number(n)) => n.to_string(),
-        Some(Value::Bool(b)) => b.to_string(),
-        Some(other) => serde_json::to_string(other).unwrap_or_default(),
-    }
+assert no_ports {
+  condition open_ports == 0
+  message "unexpected open ports"
 }
+"#;
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
 
-fn parse_nmap_xml(xml: &[u8], target: &str) -> Result<ScanArtifacts, String> {
-    let mut reader = Reader::from_reader(xml);
-    reader.config_mut().trim_text(true);
+        let held = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.name == "assert few_ports")
+            .expect("first assert present");
+        assert_eq!(held.kind, StepKind::Assert);
+        assert_eq!(held.status, ExecutionStatus::Completed);
 
-    let mut buf = Vec::new();
-    let mut hosts = Vec::new();
-    let mut current_host: Option<HostBuilder> = None;
-    let mut current_port: Option<PortBuilder> = None;
+        let failed = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.name == "assert no_ports")
+            .expect("second assert present");
+        assert_eq!(failed.status, ExecutionStatus::Failed);
+        assert_eq!(failed.message.as_deref(), Some("unexpected open ports"));
+    }
 
-    loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(element)) => match element.name() {
-                QName(b"host") => {
-                    current_host = Some(HostBuilder::default());
-                }
-                QName(b"address") => {
-                    if let Some(host) = current_host.as_mut() {
-                        let mut address_value = None;
-                        let mut addr_type = None;
-                        for attr in element.attributes().flatten() {
-                            match attr.key.as_ref() {
-                                b"addr" => {
-                                    address_value =
-                                        Some(String::from_utf8_lossy(&attr.value).to_string())
-                                }
-                                b"addrtype" => {
-                                    addr_type =
-                                        Some(String::from_utf8_lossy(&attr.value).to_string())
-                                }
-                                _ => {}
-                            }
-                        }
-                        if let Some(addr) = address_value {
-                            host.addresses
-                                .push((addr, addr_type.unwrap_or_else(|| "unknown".to_string())));
-                        }
-                    }
-                }
-                QName(b"hostname") => {
-                    if let Some(host) = current_host.as_mut() {
-                        for attr in element.attributes().flatten() {
-                            if attr.key.as_ref() == b"name" {
-                                host.hostnames
-                                    .push(String::from_utf8_lossy(&attr.value).to_string());
-                            }
-                        }
-                    }
-                }
-                QName(b"port") => {
-                    let mut builder = PortBuilder::default();
-                    for attr in element.attributes().flatten() {
-                        match attr.key.as_ref() {
-                            b"protocol" => {
-                                builder.protocol =
-                                    Some(String::from_utf8_lossy(&attr.value).to_string())
-                            }
-                            b"portid" => {
-                                if let Ok(port) =
-                                    String::from_utf8_lossy(&attr.value).parse::<u16>()
-                                {
-                                    builder.port = Some(port);
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                    current_port = Some(builder);
-                }
-                QName(b"state") => {
-                    if let Some(port) = current_port.as_mut() {
-                        for attr in element.attributes().flatten() {
-                            if attr.key.as_ref() == b"state" {
-                                port.state = Some(String::from_utf8_lossy(&attr.value).to_string());
-                            }
-                        }
-                    }
-                }
-                QName(b"service") => {
-                    if let Some(port) = current_port.as_mut() {
-                        for attr in element.attributes().flatten() {
-                            if attr.key.as_ref() == b"name" {
-                                port.service =
-                                    Some(String::from_utf8_lossy(&attr.value).to_string());
-                            }
-                        }
-                    }
-                }
-                _ => {}
-            },
-            Ok(Event::Empty(element)) => match element.name() {
-                QName(b"address") => {
-                    if let Some(host) = current_host.as_mut() {
-                        let mut address_value = None;
-                        let mut addr_type = None;
-                        for attr in element.attributes().flatten() {
-                            match attr.key.as_ref() {
-                                b"addr" => {
-                                    address_value =
-                                        Some(String::from_utf8_lossy(&attr.value).to_string())
-                                }
-                                b"addrtype" => {
-                                    addr_type =
-                                        Some(String::from_utf8_lossy(&attr.value).to_string())
-                                }
-                                _ => {}
-                            }
-                        }
-                        if let Some(addr) = address_value {
-                            host.addresses
-                                .push((addr, addr_type.unwrap_or_else(|| "unknown".to_string())));
-                        }
-                    }
-                }
-                QName(b"hostname") => {
-                    if let Some(host) = current_host.as_mut() {
-                        for attr in element.attributes().flatten() {
-                            if attr.key.as_ref() == b"name" {
-                                host.hostnames
-                                    .push(String::from_utf8_lossy(&attr.value).to_string());
-                            }
-                        }
-                    }
-                }
-                _ => {}
-            },
-            Ok(Event::End(element)) => match element.name() {
-                QName(b"port") => {
-                    if let (Some(host), Some(port)) = (current_host.as_mut(), current_port.take()) {
-                        host.ports.push(port);
-                    }
-                }
-                QName(b"host") => {
-                    if let Some(host) = current_host.take() {
-                        hosts.push(host);
-                    }
-                }
-                _ => {}
+    #[test]
+    fn assert_resolves_artifact_paths() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let mut store = HashMap::new();
+        store.insert(
+            "findings_web".to_string(),
+            StoredArtifact {
+                name: "findings_web".to_string(),
+                kind: ArtifactKind::Scan,
+                path: None,
+                content_hash: None,
+                data: json!({
+                    "findings": [{ "port": 80 }, { "port": 443 }],
+                    "host": "10.0.0.5",
+                }),
             },
-            Ok(Event::Eof) => break,
-            Err(err) => return Err(format!("XML parsing error: {err}")),
-            _ => {}
-        }
-        buf.clear();
+        );
+
+        assert_eq!(
+            resolve_artifact_path(&store, "findings_web.findings.length"),
+            Some(LiteralValue::Integer(2))
+        );
+        assert_eq!(
+            resolve_artifact_path(&store, "findings_web.findings.1.port"),
+            Some(LiteralValue::Integer(443))
+        );
+        assert_eq!(resolve_artifact_path(&store, "findings_web.missing"), None);
+
+        let step = AssertStep {
+            name: "small_surface".to_string(),
+            condition: condition_from(
+                "findings_web.findings.length < 5 && findings_web.host == \"10.0.0.5\"",
+            ),
+            message: None,
+            tags: Vec::new(),
+            on_failure: Vec::new(),
+        };
+        let mut secrets = SecretStore::default();
+        let outcome =
+            test_executor(&artifacts).process_assert(&step, &HashMap::new(), &store, &mut secrets);
+        assert_eq!(outcome.execution.status, ExecutionStatus::Completed);
+
+        let missing = AssertStep {
+            name: "missing".to_string(),
+            condition: condition_from("findings_db.findings.length == 0"),
+            message: Some("database scan missing".to_string()),
+            tags: Vec::new(),
+            on_failure: Vec::new(),
+        };
+        let outcome = test_executor(&artifacts).process_assert(
+            &missing,
+            &HashMap::new(),
+            &store,
+            &mut secrets,
+        );
+        assert_eq!(outcome.execution.status, ExecutionStatus::Failed);
+        assert!(outcome
+            .execution
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .starts_with("database scan missing"));
+    }
+
+    #[test]
+    fn notify_posts_payload_and_masks_secrets() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let (address, server) = spawn_mock_http(vec![(200, r#"{"ok":true}"#.to_string())]);
+        let source = format!(
+            r#"
+notify slack {{
+  url "{address}/hooks/${{secret:hook}}"
+  body "scan finished"
+  headers {{
+    Content-Type = "text/plain"
+    X-Team "red"
+  }}
+}}
+"#
+        );
+        let scenario =
+            crate::scenario::parse_scenario_all(&source).expect("failed to parse scenario");
+        let mut secrets = HashMap::new();
+        secrets.insert("hook".to_string(), "T000-secret".to_string());
+        let outcome =
+            test_executor(&artifacts).execute_with_vars(&scenario, &HashMap::new(), &secrets);
+
+        let step = &outcome.report.steps[0];
+        assert_eq!(step.kind, StepKind::Notify);
+        assert_eq!(
+            step.status,
+            ExecutionStatus::Completed,
+            "{:?}",
+            step.message
+        );
+        let message = step.message.as_deref().unwrap_or_default();
+        assert!(message.contains("/hooks/***"));
+        assert!(message.contains("HTTP 200"));
+
+        let artifact = outcome
+            .artifacts
+            .iter()
+            .find(|artifact| artifact.name == "notify:slack")
+            .expect("notification artifact");
+        assert_eq!(artifact.kind, ArtifactKind::Notification);
+        assert_eq!(artifact.data["status"], json!(200));
+        assert!(!artifact.data.to_string().contains("T000-secret"));
+
+        let requests = server.join().expect("mock server finished");
+        assert!(requests[0].starts_with("POST /hooks/T000-secret"));
+        assert!(requests[0].to_ascii_lowercase().contains("x-team: red"));
+        assert!(requests[0].ends_with("scan finished"));
     }
 
-    let mut assets = Vec::new();
-    let mut findings = Vec::new();
+    #[test]
+    fn notify_fails_on_error_status_and_skips_in_dry_run() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let (address, server) = spawn_mock_http(vec![(500, "boom".to_string())]);
+        let source = format!("notify hook {{ url \"{address}/fail\"; method \"put\" }}\n");
+        let scenario =
+            crate::scenario::parse_scenario_all(&source).expect("failed to parse scenario");
+
+        let outcome = test_executor(&artifacts).execute(&scenario);
+        let step = &outcome.report.steps[0];
+        assert_eq!(step.status, ExecutionStatus::Failed);
+        assert!(step.message.as_deref().unwrap_or_default().contains("PUT"));
+        server.join().expect("mock server finished");
+
+        let outcome = test_executor(&artifacts)
+            .with_dry_run(true)
+            .execute(&scenario);
+        let step = &outcome.report.steps[0];
+        assert_eq!(step.status, ExecutionStatus::Skipped);
+        assert!(step
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .starts_with("[dry-run]"));
+    }
 
-    for host in hosts {
-        let addresses: Vec<String> = host
-            .addresses
+    #[test]
+    fn resumed_runs_skip_steps_completed_before_the_checkpoint() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = |first: &str| {
+            crate::scenario::parse_scenario_all(&format!(
+                "let auth = \"Bearer ${{secret:api_token}}\"\nscript first {{\n  run \"{first}\"\n}}\ncheckpoint halfway\nscript second {{\n  run \"echo second\"\n}}\n"
+            ))
+            .expect("failed to parse scenario")
+        };
+        let secrets = HashMap::from([("api_token".to_string(), "hunter2".to_string())]);
+        let statuses = |outcome: &ExecutionOutcome| -> Vec<(String, ExecutionStatus)> {
+            outcome
+                .report
+                .steps
+                .iter()
+                .filter(|step| step.kind == StepKind::Script)
+                .map(|step| (step.name.clone(), step.status.clone()))
+                .collect()
+        };
+
+        let outcome = test_executor(&artifacts).execute_with_vars(
+            &scenario("echo first"),
+            &HashMap::new(),
+            &secrets,
+        );
+        assert!(!outcome.report.has_failures(), "{}", outcome.report);
+        let path = artifacts.path().join("checkpoints/halfway.json");
+        let saved = fs::read_to_string(&path).expect("checkpoint file");
+        assert!(!saved.contains("hunter2"));
+        let checkpoint = Checkpoint::load(&path).expect("checkpoint parses");
+        assert_eq!(
+            checkpoint.completed.keys().collect::<Vec<_>>(),
+            vec!["first"]
+        );
+        assert_eq!(
+            checkpoint.variables.get("auth"),
+            Some(&LiteralValue::String("Bearer ***".to_string()))
+        );
+
+        let resumed = test_executor(&artifacts)
+            .with_resume(checkpoint.clone())
+            .execute_with_vars(&scenario("echo first"), &HashMap::new(), &secrets);
+        assert_eq!(
+            statuses(&resumed),
+            vec![
+                ("first".to_string(), ExecutionStatus::Skipped),
+                ("second".to_string(), ExecutionStatus::Completed),
+            ]
+        );
+        assert_eq!(
+            resumed.report.steps[1].message.as_deref(),
+            Some("[resume] completed before checkpoint 'halfway'")
+        );
+
+        // A changed definition or a missing artifact file runs the step again.
+        let changed = test_executor(&artifacts)
+            .with_resume(checkpoint.clone())
+            .execute_with_vars(&scenario("echo changed"), &HashMap::new(), &secrets);
+        assert_eq!(statuses(&changed)[0].1, ExecutionStatus::Completed);
+
+        let artifact = checkpoint
+            .artifacts
             .iter()
-            .map(|(addr, _)| addr.clone())
-            .collect();
-        if addresses.is_empty() {
-            continue;
-        }
-        let primary_addr = addresses.first().cloned().unwrap_or_default();
-        let asset_id = format!("asset://host/{}", primary_addr);
+            .find(|artifact| artifact.name == "script_first")
+            .and_then(|artifact| artifact.path.clone())
+            .expect("script artifact path");
+        fs::remove_file(artifact).expect("remove artifact");
+        let missing = test_executor(&artifacts)
+            .with_resume(checkpoint)
+            .execute_with_vars(&scenario("echo first"), &HashMap::new(), &secrets);
+        assert_eq!(statuses(&missing)[0].1, ExecutionStatus::Completed);
+    }
 
-        let asset = Asset {
-            id: asset_id.clone(),
-            addresses: addresses.clone(),
-            hostnames: host.hostnames.clone(),
-            labels: BTreeMap::new(),
-        };
+    #[test]
+    fn dry_run_stubs_external_steps_and_keeps_control_flow() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let source = r#"
+let hosts = ["10.0.0.1", "10.0.0.2"]
+for host in hosts {
+  scan web nmap {
+    target "${host}"
+    flags "-sV"
+  }
+}
+script probe {
+  run "definitely-not-installed-binary --flag"
+}
+report summary using html {
+  include findings_web
+  include script_probe
+}
+"#;
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let outcome =
+            test_executor(&artifacts).dry_run(&scenario, &HashMap::new(), &HashMap::new());
 
-        for port in host.ports {
-            if port.state.as_deref() != Some("open") {
-                continue;
-            }
+        let scans: Vec<&StepExecution> = outcome
+            .report
+            .steps
+            .iter()
+            .filter(|step| step.kind == StepKind::Scan)
+            .collect();
+        assert_eq!(scans.len(), 2);
+        assert!(scans
+            .iter()
+            .all(|step| step.status == ExecutionStatus::Skipped));
+        assert_eq!(
+            scans[1].message.as_deref(),
+            Some("[dry-run] would run nmap against 10.0.0.2")
+        );
 
-            let port_number = port.port.unwrap_or(0);
-            let protocol = port.protocol.clone().unwrap_or_else(|| "tcp".to_string());
-            let service = port.service.clone();
-            let finding_id = format!("finding://{}/{}-{}", primary_addr, protocol, port_number);
+        let script = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.kind == StepKind::Script)
+            .expect("script step");
+        assert_eq!(script.status, ExecutionStatus::Skipped);
+        assert!(!outcome.report.has_failures(), "{}", outcome.report);
 
-            let mut evidence = BTreeMap::new();
-            evidence.insert("port".to_string(), Value::from(port_number));
-            if let Some(state) = &port.state {
-                evidence.insert("state".to_string(), Value::String(state.clone()));
-            }
-            if let Some(svc) = &service {
-                evidence.insert("service".to_string(), Value::String(svc.clone()));
-            }
+        let placeholder = outcome
+            .artifacts
+            .iter()
+            .find(|artifact| artifact.name == "findings_web")
+            .expect("placeholder scan artifact");
+        assert_eq!(placeholder.data["target"], json!("10.0.0.2"));
+        assert!(placeholder.path.is_none());
+    }
 
-            findings.push(Finding {
-                id: finding_id,
-                asset_id: asset_id.clone(),
-                port: port_number,
-                protocol: protocol.clone(),
-                state: port.state.unwrap_or_else(|| "unknown".to_string()),
-                service: service.clone(),
-                title: format!("{}:{} {} open", primary_addr, port_number, protocol),
-                description: format!(
-                    "Port {} {} is open on asset {} with service {:?}",
-                    port_number, protocol, primary_addr, service
-                ),
-                severity: "informational".to_string(),
-                evidence,
-            });
+    #[test]
+    fn summary_by_status_counts_every_step() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = crate::scenario::parse_scenario_all(
+            "let a = \"1\"\nlet b = \"2\"\nif a == \"2\" {\n  let c = \"3\"\n}\nreport missing {\n  include nothing\n}\n",
+        )
+        .expect("failed to parse scenario");
+
+        let outcome = test_executor(&artifacts).execute(&scenario);
+        let summary = outcome.report.summary_by_status();
+        assert_eq!(summary.values().sum::<usize>(), outcome.report.steps.len());
+        for (status, count) in &summary {
+            let expected = outcome
+                .report
+                .steps
+                .iter()
+                .filter(|step| step.status.key() == status)
+                .count();
+            assert_eq!(*count, expected, "{status}");
         }
+        assert_eq!(summary.get("failed"), Some(&1), "{}", outcome.report);
+        assert!(outcome.report.has_failures());
+        assert_eq!(outcome.summary, summary);
 
-        assets.push(asset);
+        let json = serde_json::to_value(&outcome).expect("serialize outcome");
+        assert_eq!(json["summary"]["failed"], json!(1));
     }
 
-    Ok(ScanArtifacts {
-        tool: "nmap".to_string(),
-        target: target.to_string(),
-        assets,
-        findings,
-        raw_xml: String::from_utf8_lossy(xml).to_string(),
-    })
-}
+    #[test]
+    fn diff_reports_new_removed_and_changed_steps() {
+        let step = |name: &str, status: ExecutionStatus| StepExecution {
+            status,
+            ..StepExecution::completed(name.to_string(), StepKind::Scan, None)
+        };
+        let baseline = ExecutionReport {
+            steps: vec![
+                step("discovery", ExecutionStatus::Completed),
+                step("web", ExecutionStatus::Failed),
+                step("legacy", ExecutionStatus::Completed),
+                step("probe", ExecutionStatus::Completed),
+            ],
+        };
+        let current = ExecutionReport {
+            steps: vec![
+                step("discovery", ExecutionStatus::Completed),
+                step("web", ExecutionStatus::Completed),
+                step("probe", ExecutionStatus::Completed),
+                step("probe", ExecutionStatus::Skipped),
+            ],
+        };
 
-#[derive(Default)]
-struct HostBuilder {
-    addresses: Vec<(String, String)>,
-    hostnames: Vec<String>,
-    ports: Vec<PortBuilder>,
-}
+        let diff = ExecutionReport::diff(&baseline, &current);
+        assert!(diff.has_changes());
+        let names = |steps: &[StepExecution]| -> Vec<String> {
+            steps.iter().map(|step| step.name.clone()).collect()
+        };
+        assert_eq!(names(&diff.unchanged), vec!["discovery", "probe"]);
+        assert_eq!(names(&diff.removed_steps), vec!["legacy"]);
+        assert_eq!(names(&diff.new_steps), vec!["probe"]);
+        assert_eq!(diff.new_steps[0].status, ExecutionStatus::Skipped);
+        assert_eq!(diff.status_changed.len(), 1);
+        assert_eq!(diff.status_changed[0].name, "web");
+        assert_eq!(diff.status_changed[0].baseline, ExecutionStatus::Failed);
+
+        let rendered = diff.to_string();
+        assert!(
+            rendered.contains("- [completed] legacy (Scan)"),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains("~ [failed -> completed] web (Scan)"),
+            "{rendered}"
+        );
+        assert!(!ExecutionReport::diff(&current, &current).has_changes());
+    }
 
-#[derive(Default)]
-struct PortBuilder {
-    port: Option<u16>,
-    protocol: Option<String>,
-    state: Option<String>,
-    service: Option<String>,
-}
+    #[test]
+    fn steps_record_start_time_and_duration() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = crate::scenario::parse_scenario_all(
+            "let host = \"a\"\nscan pause sleep {\n  args \"0.05\"\n}\n",
+        )
+        .expect("failed to parse scenario");
+
+        let outcome = test_executor(&artifacts).execute(&scenario);
+        let scan = &outcome.report.steps[1];
+        assert_eq!(
+            scan.status,
+            ExecutionStatus::Completed,
+            "{:?}",
+            scan.message
+        );
+        assert!(scan.duration_ms.is_some_and(|ms| ms >= 50), "{scan:?}");
+        let started_at = scan.started_at.as_deref().expect("start timestamp");
+        assert!(
+            started_at.contains('T') && started_at.ends_with('Z'),
+            "{started_at}"
+        );
+        assert!(outcome.report.steps[0].duration_ms.is_some());
+        assert!(outcome.report.to_string().contains("pause (Scan) in "));
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::collections::HashMap;
-    use std::fs;
+    #[test]
+    fn sleep_records_elapsed_time_and_skips_in_dry_run() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = crate::scenario::parse_scenario_all("sleep pause 20ms\n")
+            .expect("failed to parse sleep");
+
+        let outcome = test_executor(&artifacts).execute(&scenario);
+        let step = &outcome.report.steps[0];
+        assert_eq!(step.kind, StepKind::Sleep);
+        assert_eq!(step.status, ExecutionStatus::Completed);
+        assert!(step
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .ends_with("(configured 20ms)"));
+
+        let outcome = test_executor(&artifacts)
+            .with_dry_run(true)
+            .execute(&scenario);
+        assert_eq!(outcome.report.steps[0].status, ExecutionStatus::Skipped);
+    }
 
     #[test]
-    fn resolves_secret_placeholders_and_masks_messages() {
-        let env_key = "AXION_TEST_SECRET_VALUE";
-        std::env::set_var(env_key, "super-secret-token");
+    fn loop_break_and_continue_control_iterations() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let source = r#"
+let visited = ""
+for host in ["a", "skip", "b", "stop", "c"] {
+  if host == "skip" { continue }
+  if host == "stop" { break }
+  let visited = "${visited}${host}"
+}
+"#;
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
 
-        let source = format!(
-            r#"
-secret api from env {{
-  token = "{env_key}"
-}}
+        let assignments: Vec<&str> = outcome
+            .report
+            .steps
+            .iter()
+            .filter(|step| step.kind == StepKind::Variable)
+            .filter_map(|step| step.message.as_deref())
+            .collect();
+        assert_eq!(assignments.len(), 3);
+        assert!(assignments[2].contains("ab"));
 
-let header = "Bearer ${{secret:api.token}}"
-"#
+        let loop_step = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.kind == StepKind::Loop)
+            .expect("loop step present");
+        assert_eq!(
+            loop_step.message.as_deref(),
+            Some(
+                "executed 4 iteration(s), 1 skipped via continue; stopped by break (1 of 5 item(s) not visited)"
+            )
         );
+    }
 
-        let scenario = crate::scenario::parse_scenario(&source).expect("failed to parse scenario");
-        let executor = Executor::new();
-        let overrides = HashMap::new();
-        let secret_overrides = HashMap::new();
-        let outcome = executor.execute_with_vars(&scenario, &overrides, &secret_overrides);
+    #[test]
+    fn loop_index_variables_reset_for_nested_loops() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let source = r#"
+for outer in ["a", "b"] {
+  for inner in ["x", "y", "z"] {
+    let label = "${outer}${loop.index1}-${inner}${loop.index}"
+  }
+  let after = "${outer}${loop.index}"
+}
+"#;
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
+        let messages: Vec<&str> = outcome
+            .report
+            .steps
+            .iter()
+            .filter(|step| step.kind == StepKind::Variable)
+            .filter_map(|step| step.message.as_deref())
+            .collect();
+        assert_eq!(messages.len(), 8);
+        assert!(messages[0].contains("a1-x0"));
+        assert!(messages[2].contains("a3-z2"));
+        assert!(messages[3].contains("a0"));
+        assert!(messages[4].contains("b1-x0"));
+        assert!(messages[7].contains("b1"));
+
+        let outside = crate::scenario::parse_scenario_all("let idx = \"${loop.index}\"\n")
+            .expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&outside);
+        assert_eq!(outcome.report.steps[0].status, ExecutionStatus::Failed);
+        assert!(outcome.report.steps[0]
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .contains("only available inside a for loop"));
+    }
 
-        let secret_step = outcome
+    #[test]
+    fn range_loops_iterate_numbers() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let source = r#"
+let total = ""
+for i in 1..=3 {
+  let total = "${total}${i}"
+}
+for j in 0..2 {
+  let last = "${j}"
+}
+"#;
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
+        let loops: Vec<&str> = outcome
             .report
             .steps
             .iter()
-            .find(|step| step.name == "api")
-            .expect("secret step present");
-        assert_eq!(secret_step.status, ExecutionStatus::Completed);
+            .filter(|step| step.kind == StepKind::Loop)
+            .filter_map(|step| step.message.as_deref())
+            .collect();
+        assert_eq!(
+            loops,
+            vec!["executed 3 iteration(s)", "executed 2 iteration(s)"]
+        );
+        let totals: Vec<&str> = outcome
+            .report
+            .steps
+            .iter()
+            .filter(|step| step.name == "total")
+            .filter_map(|step| step.message.as_deref())
+            .collect();
+        assert!(totals[3].contains("123"));
+    }
 
-        let variable_step = outcome
+    #[test]
+    fn object_loops_bind_keys_and_values() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let source = r#"
+let host = "outer"
+let tags = {web: "frontend", db: 5432, cache: true, meta: [1, 2]}
+for host, tag in tags {
+  let pair = "${host}=${tag}"
+}
+let after = "${host} ${tag:-unset}"
+for key, value in [1, 2] {
+}
+"#;
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
+        let pairs: Vec<&str> = outcome
             .report
             .steps
             .iter()
-            .find(|step| step.name == "header")
-            .expect("variable step present");
-        assert_eq!(variable_step.status, ExecutionStatus::Completed);
-        let message = variable_step.message.as_deref().unwrap_or_default();
-        assert!(message.contains("Bearer ***"));
-        assert!(!message.contains("super-secret-token"));
+            .filter(|step| step.name == "pair")
+            .filter_map(|step| step.message.as_deref())
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                "pair = cache=true",
+                "pair = db=5432",
+                "pair = meta=[1,2]",
+                "pair = web=frontend"
+            ]
+        );
+        let step = |name: &str| {
+            outcome
+                .report
+                .steps
+                .iter()
+                .find(|step| step.name == name)
+                .expect("step present")
+        };
+        assert_eq!(
+            step("after").message.as_deref(),
+            Some("after = outer unset")
+        );
+        let array_loop = step("for key, value in [1,2]");
+        assert_eq!(array_loop.status, ExecutionStatus::Failed);
+        assert!(array_loop
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .contains("requires an object"));
+    }
 
-        std::env::remove_var(env_key);
+    #[test]
+    fn calls_bind_arguments_in_their_own_scope() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let source = r#"
+let greeting = "hi"
+func greet(name, punctuation) {
+  let message = "${greeting} ${name}${punctuation}"
+}
+call greet("alice", "!")
+call greet(greeting, "?")
+let after = "${message:-unset} ${name:-unset}"
+call greet("bob")
+call missing()
+func ping() {
+  call pong()
+}
+func pong() {
+  call ping()
+}
+call ping()
+"#;
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
+        let messages: Vec<&str> = outcome
+            .report
+            .steps
+            .iter()
+            .filter(|step| step.name == "message")
+            .filter_map(|step| step.message.as_deref())
+            .collect();
+        assert_eq!(messages, vec!["message = hi alice!", "message = hi hi?"]);
+        let after = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.name == "after")
+            .expect("after step present");
+        assert_eq!(after.message.as_deref(), Some("after = unset unset"));
+
+        let calls: Vec<(&ExecutionStatus, &str)> = outcome
+            .report
+            .steps
+            .iter()
+            .filter(|step| step.kind == StepKind::Call)
+            .map(|step| (&step.status, step.message.as_deref().unwrap_or_default()))
+            .collect();
+        assert_eq!(calls.len(), 5, "{calls:?}");
+        assert_eq!(
+            calls[0],
+            (
+                &ExecutionStatus::Completed,
+                "name = \"alice\", punctuation = \"!\""
+            )
+        );
+        assert_eq!(
+            calls[2],
+            (
+                &ExecutionStatus::Failed,
+                "function 'greet' expects 2 argument(s), got 1"
+            )
+        );
+        assert_eq!(
+            calls[3],
+            (&ExecutionStatus::Failed, "undefined function 'missing'")
+        );
+        assert_eq!(
+            calls[4],
+            (
+                &ExecutionStatus::Failed,
+                "function 'ping' is recursive and cannot be called"
+            )
+        );
     }
 
     #[test]
-    fn secret_override_allows_missing_env() {
+    fn loop_when_guard_skips_iterations() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
         let source = r#"
-secret token from env {
-  value = "AXION_TEST_MISSING_ENV"
+let hosts = ["localhost", "10.0.0.1", "localhost", "10.0.0.2"]
+for host in hosts when host != "localhost" {
+  let seen = "${host}"
+}
+"#;
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute(&scenario);
+        let seen = outcome
+            .report
+            .steps
+            .iter()
+            .filter(|step| step.name == "seen")
+            .count();
+        assert_eq!(seen, 2);
+        let loop_step = outcome
+            .report
+            .steps
+            .iter()
+            .find(|step| step.kind == StepKind::Loop)
+            .expect("loop step present");
+        assert_eq!(loop_step.status, ExecutionStatus::Completed);
+        assert_eq!(
+            loop_step.message.as_deref(),
+            Some("executed 2 iteration(s), 2 skipped by when guard")
+        );
+    }
+
+    #[test]
+    fn loops_fail_when_exceeding_iteration_limit() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let source = r#"
+for i in 0..1000000000000 {
+  let never = "${i}"
+}
+for j in 0..20 {
+  option max_iter 50
+  let ok = "${j}"
 }
+"#;
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts)
+            .with_max_iterations(10)
+            .execute(&scenario);
+        let loops: Vec<&StepExecution> = outcome
+            .report
+            .steps
+            .iter()
+            .filter(|step| step.kind == StepKind::Loop)
+            .collect();
+        assert_eq!(loops[0].status, ExecutionStatus::Failed);
+        assert!(loops[0]
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .contains("exceeding the limit of 10"));
+        assert_eq!(loops[1].status, ExecutionStatus::Completed);
+        assert!(!outcome.report.steps.iter().any(|step| step.name == "never"));
+    }
+
+    #[test]
+    fn report_filter_drops_rows_and_counts_them() {
+        let mut debug = finding("example.com", 9000, "debug", "debug console");
+        debug.severity = "informational".to_string();
+        let artifact = scan_artifact(
+            "web",
+            "example.com",
+            vec![
+                finding("example.com", 443, "https", "tls"),
+                finding("example.com", 8443, "alt-https", "tls"),
+                debug,
+            ],
+        );
+        let mut table = build_table_from_scan(&artifact.data).expect("table");
+        let condition = parse_condition_expr(r#"(severity != "informational") && (port > 1024)"#)
+            .expect("filter parses");
+        let mut secrets = SecretStore::default();
 
-let captured = "${secret:token.value}"
-"#;
+        let removed = filter_table_rows(&mut table, &condition, &HashMap::new(), &mut secrets);
+        assert_eq!(removed, 2);
+        assert_eq!(table.rows.len(), 1);
+        assert_eq!(table.rows[0]["port"], json!(8443));
+    }
 
-        std::env::remove_var("AXION_TEST_MISSING_ENV");
+    #[test]
+    fn executor_writes_artifacts_through_custom_store() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let store = crate::store::InMemoryArtifactStore::new();
+        let scenario = crate::scenario::parse_scenario_all("report stdout {\n}\n")
+            .expect("failed to parse scenario");
+
+        let outcome = test_executor(&artifacts)
+            .with_store(store.clone())
+            .execute(&scenario);
+
+        assert_eq!(store.labels(), vec!["report:stdout".to_string()]);
+        let stored = store.read("report:stdout").expect("read succeeds");
+        assert_eq!(
+            stored.and_then(|data| data.get("target").cloned()),
+            Some(json!("stdout"))
+        );
+        let message = outcome.report.steps[0]
+            .message
+            .as_deref()
+            .unwrap_or_default();
+        assert!(message.ends_with("artifact: <memory>"), "{message}");
+    }
 
-        let scenario = crate::scenario::parse_scenario(source).expect("failed to parse scenario");
-        let executor = Executor::new();
-        let overrides = HashMap::new();
-        let mut secret_overrides = HashMap::new();
-        secret_overrides.insert("token.value".to_string(), "override-secret".to_string());
+    #[test]
+    fn content_addressable_runs_record_digest() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let scenario = crate::scenario::parse_scenario_all("report stdout {\n}\n")
+            .expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts)
+            .with_store(crate::store::InMemoryArtifactStore::new())
+            .with_content_addressable(true)
+            .execute(&scenario);
+
+        let artifact = &outcome.artifacts[0];
+        let expected = crate::store::content_digest(&artifact.data).expect("digest");
+        assert_eq!(artifact.content_hash.as_deref(), Some(expected.as_str()));
+    }
 
-        let outcome = executor.execute_with_vars(&scenario, &overrides, &secret_overrides);
+    #[test]
+    fn compressed_artifacts_are_gzipped_in_store() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let store = crate::store::InMemoryArtifactStore::new();
+        let scenario = crate::scenario::parse_scenario_all("report stdout {\n}\n")
+            .expect("failed to parse scenario");
+
+        test_executor(&artifacts)
+            .with_store(store.clone())
+            .with_compressed_artifacts(true)
+            .execute(&scenario);
+
+        let bytes = store.bytes("report:stdout").expect("artifact stored");
+        assert_eq!(bytes[..2], [0x1f, 0x8b]);
+        let stored = store.read("report:stdout").expect("read succeeds");
+        assert_eq!(
+            stored.and_then(|data| data.get("format").cloned()),
+            Some(json!("stdout"))
+        );
+    }
 
-        let secret_step = outcome
-            .report
-            .steps
-            .iter()
-            .find(|step| step.name == "token")
-            .expect("secret step present");
-        assert_eq!(secret_step.status, ExecutionStatus::Completed);
+    fn test_executor(artifacts: &tempfile::TempDir) -> Executor {
+        Executor::new().with_artifacts_dir(artifacts.path())
+    }
 
-        let variable_step = outcome
-            .report
-            .steps
-            .iter()
-            .find(|step| step.name == "captured")
-            .expect("variable step present");
-        assert_eq!(variable_step.status, ExecutionStatus::Completed);
-        let message = variable_step.message.as_deref().unwrap_or_default();
-        assert!(message.contains("***"));
-        assert!(!message.contains("override-secret"));
+    fn condition_from(source: &str) -> ConditionExpr {
+        crate::scenario::parse_scenario_all(&format!("if {source} {{\n}}"))
+            .ok()
+            .and_then(|scenario| match scenario.steps.into_iter().next() {
+                Some(Step::Conditional(block)) => Some(block.condition),
+                _ => None,
+            })
+            .expect("condition parses")
     }
 
     #[test]
     fn html_report_produces_file() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
         let source = r#"
 asset_group corp {
   scope demo
@@ -2146,8 +9201,9 @@ report summary using html {
 }
 "#;
 
-        let scenario = crate::scenario::parse_scenario(source).expect("failed to parse scenario");
-        let executor = Executor::new();
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let executor = test_executor(&artifacts);
         let overrides = HashMap::new();
         let secret_overrides = HashMap::new();
 
@@ -2179,8 +9235,57 @@ report summary using html {
         assert!(contents.contains("asset_group:corp"));
     }
 
+    #[test]
+    fn cidr_scope_expands_into_hosts_variable() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let source = r#"
+asset_group lab {
+  scope "192.168.1.0/24"
+  owner "netops"
+}
+for host in lab_hosts {
+  let last = "${host}"
+}
+"#;
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let outcome = test_executor(&artifacts).execute_with_vars(
+            &scenario,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        assert!(!outcome.report.has_failures(), "{}", outcome.report);
+
+        let artifact = outcome
+            .artifacts
+            .iter()
+            .find(|artifact| artifact.name == "asset_group:lab")
+            .expect("asset group artifact present");
+        let group: AssetGroupArtifact =
+            serde_json::from_value(artifact.data.clone()).expect("asset group artifact");
+        assert_eq!(group.hosts.len(), 256);
+        assert_eq!(group.hosts.first().map(String::as_str), Some("192.168.1.0"));
+        assert_eq!(
+            group.hosts.last().map(String::as_str),
+            Some("192.168.1.255")
+        );
+
+        let iterations = outcome
+            .report
+            .steps
+            .iter()
+            .filter(|step| step.name == "last")
+            .count();
+        assert_eq!(iterations, 256);
+
+        assert_eq!(cidr_address_count("10.0.0.0/8"), Some(1 << 24));
+        assert_eq!(cidr_address_count("::/0"), Some(u128::MAX));
+        assert_eq!(cidr_address_count("demo"), None);
+    }
+
     #[test]
     fn markdown_report_produces_file() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
         let source = r#"
 asset_group corp {
   scope demo
@@ -2191,8 +9296,9 @@ report summary_md using markdown {
 }
 "#;
 
-        let scenario = crate::scenario::parse_scenario(source).expect("failed to parse scenario");
-        let executor = Executor::new();
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let executor = test_executor(&artifacts);
         let overrides = HashMap::new();
         let secret_overrides = HashMap::new();
 
@@ -2226,6 +9332,7 @@ report summary_md using markdown {
 
     #[test]
     fn sarif_report_produces_file() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
         let source = r#"
 asset_group corp {
   scope demo
@@ -2237,8 +9344,9 @@ report summary_sarif using sarif {
 }
 "#;
 
-        let scenario = crate::scenario::parse_scenario(source).expect("failed to parse scenario");
-        let executor = Executor::new();
+        let scenario =
+            crate::scenario::parse_scenario_all(source).expect("failed to parse scenario");
+        let executor = test_executor(&artifacts);
         let overrides = HashMap::new();
         let secret_overrides = HashMap::new();
 
@@ -2269,4 +9377,1099 @@ report summary_sarif using sarif {
         assert!(contents.contains("\"version\": \"2.1.0\""));
         assert!(contents.contains("Axion Test Suite"));
     }
+
+    #[test]
+    fn sarif_and_tables_surface_cvss_scores() {
+        let mut scored = finding("10.0.0.1", 443, "Weak TLS", "TLS 1.0 enabled");
+        scored.cvss_score = Some(9.8);
+        scored.cvss_vector = Some("CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:H/I:H/A:N".to_string());
+        let artifact = scan_artifact(
+            "findings_tls",
+            "10.0.0.1",
+            vec![scored, finding("10.0.0.1", 80, "HTTP", "plain")],
+        );
+
+        let includes = BTreeMap::from([(artifact.name.clone(), artifact.data.clone())]);
+        let sarif = render_sarif_report("tls", "2024-01-01T00:00:00Z", &includes, &BTreeMap::new())
+            .expect("sarif renders");
+        let sarif: Value = serde_json::from_str(&sarif).expect("valid json");
+        let results = sarif["runs"][0]["results"].as_array().expect("results");
+        assert_eq!(results[0]["properties"]["security-severity"], json!("9.8"));
+        assert!(results[1]["properties"].get("security-severity").is_none());
+
+        let table = build_table_from_scan(&artifact.data).expect("table built");
+        let severity = table.columns.iter().position(|c| c == "severity").unwrap();
+        assert_eq!(table.columns[severity + 1], "cvss");
+        assert_eq!(table.rows[0]["cvss"], json!(9.8f32));
+        assert_eq!(table.rows[1]["cvss"], Value::Null);
+    }
+
+    #[test]
+    fn reports_link_cve_ids_to_nvd() {
+        let mut terrapin = finding("10.0.0.1", 22, "Terrapin", "prefix truncation");
+        terrapin.cve_ids = vec!["CVE-2023-48795".to_string()];
+        terrapin.cwe_ids = vec!["CWE-354".to_string()];
+        let artifact = scan_artifact(
+            "findings_ssh",
+            "10.0.0.1",
+            vec![terrapin, finding("10.0.0.1", 80, "HTTP", "plain")],
+        );
+        let includes = BTreeMap::from([(artifact.name.clone(), artifact.data.clone())]);
+        let table = build_table_from_scan(&artifact.data).expect("table built");
+        assert_eq!(table.columns.last().map(String::as_str), Some("cve"));
+        let tables = BTreeMap::from([(artifact.name.clone(), table)]);
+
+        let html = render_html_report("ssh", "now", &includes, &tables, &BTreeMap::new());
+        assert!(html.contains(
+            "<td><a href=\"https://nvd.nist.gov/vuln/detail/CVE-2023-48795\">CVE-2023-48795</a></td>"
+        ));
+        let markdown = render_markdown_report("ssh", "now", &includes, &tables);
+        assert!(markdown
+            .contains("| [CVE-2023-48795](https://nvd.nist.gov/vuln/detail/CVE-2023-48795) |"));
+
+        let sarif =
+            render_sarif_report("ssh", "now", &includes, &BTreeMap::new()).expect("sarif renders");
+        let sarif: Value = serde_json::from_str(&sarif).expect("valid json");
+        let properties = &sarif["runs"][0]["results"][0]["properties"];
+        assert_eq!(properties["cveIds"], json!(["CVE-2023-48795"]));
+        assert_eq!(properties["cweIds"], json!(["CWE-354"]));
+    }
+
+    #[test]
+    fn remediation_renders_in_every_format() {
+        let mut exposed = finding("10.0.0.1", 80, "Git config", "/.git/config readable");
+        exposed.severity = "high".to_string();
+        exposed.remediation = Some("Block access to /.git <paths>".to_string());
+        let artifact = scan_artifact(
+            "findings_web",
+            "10.0.0.1",
+            vec![exposed, finding("10.0.0.1", 443, "HTTPS", "plain")],
+        );
+        let includes = BTreeMap::from([(artifact.name.clone(), artifact.data.clone())]);
+        let table = build_table_from_scan(&artifact.data).expect("table built");
+        let tables = BTreeMap::from([(artifact.name.clone(), table)]);
+
+        let html = render_html_report("web", "now", &includes, &tables, &BTreeMap::new());
+        assert!(html.contains(
+            "<td><details><summary>Remediation</summary>Block access to /.git &lt;paths&gt;</details></td>"
+        ));
+
+        let markdown = render_markdown_report("web", "now", &includes, &tables);
+        assert!(markdown.contains("\n> **10.0.0.1:80** Block access to /.git <paths>\n"));
+        assert!(!markdown.contains("| remediation |"));
+
+        let sarif =
+            render_sarif_report("web", "now", &includes, &BTreeMap::new()).expect("sarif renders");
+        let sarif: Value = serde_json::from_str(&sarif).expect("valid json");
+        let results = &sarif["runs"][0]["results"];
+        assert_eq!(
+            results[0]["fixes"][0]["description"]["text"],
+            json!("Block access to /.git <paths>")
+        );
+        assert!(results[1].get("fixes").is_none());
+
+        let junit = render_junit_report("web", "now", &includes, &BTreeMap::new());
+        assert!(junit.contains(
+            "</failure>\n      <system-out>Block access to /.git &lt;paths&gt;</system-out>\n    </testcase>"
+        ));
+
+        let (csv, _) = render_csv_report(&includes);
+        assert!(csv.contains(",high,Block access to /.git <paths>\r\n"));
+    }
+
+    fn scan_artifact(name: &str, target: &str, findings: Vec<Finding>) -> StoredArtifact {
+        StoredArtifact {
+            name: name.to_string(),
+            kind: ArtifactKind::Scan,
+            path: None,
+            content_hash: None,
+            data: json!(ScanArtifacts {
+                tool: "nmap".to_string(),
+                target: target.to_string(),
+                assets: Vec::new(),
+                findings,
+                raw_xml: String::new(),
+                raw_output: String::new(),
+            }),
+        }
+    }
+
+    fn finding(asset: &str, port: u16, title: &str, description: &str) -> Finding {
+        Finding {
+            id: format!("{asset}:{port}"),
+            asset_id: asset.to_string(),
+            port,
+            protocol: "tcp".to_string(),
+            state: "open".to_string(),
+            service: Some("http".to_string()),
+            title: title.to_string(),
+            description: description.to_string(),
+            severity: "medium".to_string(),
+            evidence: BTreeMap::new(),
+            cvss_score: None,
+            cvss_vector: None,
+            cve_ids: Vec::new(),
+            cwe_ids: Vec::new(),
+            remediation: None,
+        }
+    }
+
+    #[test]
+    fn scan_deduplication_respects_no_dedup() {
+        let mut low = finding("10.0.0.1", 443, "Weak cipher", "/a");
+        low.severity = "low".to_string();
+        let mut high = finding("10.0.0.1", 443, "Weak cipher", "/b");
+        high.severity = "high".to_string();
+        let artifacts = ScanArtifacts {
+            tool: "nuclei".to_string(),
+            target: "10.0.0.1".to_string(),
+            assets: Vec::new(),
+            findings: vec![low, finding("10.0.0.1", 80, "HTTP", "/"), high],
+            raw_xml: String::new(),
+            raw_output: String::new(),
+        };
+
+        let mut deduped = artifacts.clone();
+        let note = deduplicate_scan(&mut deduped, &BTreeMap::new());
+        assert_eq!(note, " removed 1 duplicate finding(s).");
+        assert_eq!(deduped.findings.len(), 2);
+        assert_eq!(deduped.findings[0].severity, "high");
+        assert_eq!(deduped.findings[0].description, "/b");
+
+        let mut untouched = artifacts.clone();
+        let params = BTreeMap::from([("no_dedup".to_string(), "true".to_string())]);
+        let note = deduplicate_scan(&mut untouched, &params);
+        assert_eq!(note, " deduplication disabled.");
+        assert_eq!(untouched.findings.len(), 3);
+    }
+
+    #[test]
+    fn merge_step_combines_scan_artifacts() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let mut store = HashMap::new();
+        for artifact in [
+            scan_artifact(
+                "scan_tcp",
+                "10.0.0.1",
+                vec![finding("10.0.0.1", 80, "HTTP", "tcp pass")],
+            ),
+            scan_artifact(
+                "scan_udp",
+                "10.0.0.1",
+                vec![
+                    finding("10.0.0.1", 80, "HTTP", "duplicate"),
+                    finding("10.0.0.1", 161, "SNMP", "udp pass"),
+                ],
+            ),
+        ] {
+            store.insert(artifact.name.clone(), artifact);
+        }
+
+        let merge = MergeStep {
+            inputs: vec!["scan_tcp".to_string(), "scan_udp".to_string()],
+            output: "scan_all".to_string(),
+        };
+        let mut secrets = SecretStore::default();
+        let outcome =
+            test_executor(&artifacts).process_merge(&merge, &HashMap::new(), &store, &mut secrets);
+        assert_eq!(outcome.execution.status, ExecutionStatus::Completed);
+        assert_eq!(
+            outcome.execution.message.as_deref(),
+            Some("merged 2 artifact(s): 0 asset(s), 2 finding(s)")
+        );
+        let artifact = outcome.artifact.expect("merged artifact");
+        assert_eq!(artifact.name, "scan_all");
+        assert_eq!(
+            artifact.data["findings"][0]["description"],
+            json!("tcp pass")
+        );
+
+        let missing = MergeStep {
+            inputs: vec!["scan_tcp".to_string(), "scan_missing".to_string()],
+            output: "scan_all".to_string(),
+        };
+        let outcome = test_executor(&artifacts).process_merge(
+            &missing,
+            &HashMap::new(),
+            &store,
+            &mut secrets,
+        );
+        assert_eq!(outcome.execution.status, ExecutionStatus::Failed);
+        assert_eq!(
+            outcome.execution.message.as_deref(),
+            Some("artifact 'scan_missing' not found")
+        );
+    }
+
+    #[test]
+    fn transform_step_rewrites_artifact_data() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let mut high = finding("10.0.0.1", 443, "Outdated TLS", "old");
+        high.severity = "high".to_string();
+        let source = scan_artifact(
+            "scan_web",
+            "10.0.0.1",
+            vec![finding("10.0.0.1", 80, "HTTP", "plain"), high],
+        );
+        let store = HashMap::from([(source.name.clone(), source)]);
+        let scenario = crate::scenario::parse_scenario_all(
+            r#"
+transform triage from scan_web {
+    filter findings where severity == "${level}"
+    set findings[*].remediation "Update software"
+}
+transform slim from scan_web -> scan_slim {
+    rename findings[*].title to name
+    project findings[*] name, port
+}
+transform broken from scan_web { set hosts[*].owner "ops" }
+"#,
+        )
+        .expect("transform scenario parses");
+        let transform = |index: usize| match &scenario.steps[index] {
+            Step::Transform(transform) => transform,
+            other => panic!("expected transform step, got {:?}", other),
+        };
+        let variables = HashMap::from([(
+            "level".to_string(),
+            LiteralValue::String("high".to_string()),
+        )]);
+        let mut secrets = SecretStore::default();
+        let executor = test_executor(&artifacts);
+
+        let outcome = executor.process_transform(transform(0), &variables, &store, &mut secrets);
+        assert_eq!(outcome.execution.status, ExecutionStatus::Completed);
+        let artifact = outcome.artifact.expect("transformed artifact");
+        assert_eq!(artifact.name, "scan_web");
+        assert_eq!(artifact.kind, ArtifactKind::Transform);
+        let scan: ScanArtifacts =
+            serde_json::from_value(artifact.data).expect("still a scan artifact");
+        assert_eq!(scan.findings.len(), 1);
+        assert_eq!(scan.findings[0].title, "Outdated TLS");
+        assert_eq!(
+            scan.findings[0].remediation.as_deref(),
+            Some("Update software")
+        );
+
+        let outcome = executor.process_transform(transform(1), &variables, &store, &mut secrets);
+        let artifact = outcome.artifact.expect("projected artifact");
+        assert_eq!(artifact.name, "scan_slim");
+        assert_eq!(
+            artifact.data["findings"],
+            json!([{"name": "HTTP", "port": 80}, {"name": "Outdated TLS", "port": 443}])
+        );
+
+        let outcome = executor.process_transform(transform(2), &variables, &store, &mut secrets);
+        assert_eq!(outcome.execution.status, ExecutionStatus::Failed);
+        assert_eq!(
+            outcome.execution.message.as_deref(),
+            Some("set hosts[*].owner \"ops\": field 'hosts' not found")
+        );
+    }
+
+    #[test]
+    fn csv_report_quotes_fields_and_tags_sources() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let mut store = HashMap::new();
+        for artifact in [
+            scan_artifact(
+                "findings_web",
+                "10.0.0.1",
+                vec![finding("10.0.0.1", 80, "Open, unfiltered", "plain")],
+            ),
+            scan_artifact(
+                "findings_db",
+                "10.0.0.2",
+                vec![finding("10.0.0.2", 5432, "Postgres", "says \"hi\"\nthere")],
+            ),
+        ] {
+            store.insert(artifact.name.clone(), artifact);
+        }
+
+        let report = ReportStep {
+            name: "findings_csv".to_string(),
+            includes: vec!["findings_web".to_string(), "findings_db".to_string()],
+            format: ReportFormat::Csv,
+            output: None,
+            options: BTreeMap::new(),
+            tags: Vec::new(),
+            on_failure: Vec::new(),
+            comments: Default::default(),
+        };
+        let mut secrets = SecretStore::default();
+        let outcome = test_executor(&artifacts).process_report(
+            &report,
+            &store,
+            &HashMap::new(),
+            &mut secrets,
+        );
+        assert_eq!(outcome.execution.status, ExecutionStatus::Completed);
+
+        let artifact = outcome.artifact.expect("csv report artifact");
+        assert_eq!(artifact.data["row_count"], json!(2));
+        let path = artifact.path.expect("csv path present");
+        assert!(path.ends_with("reports/findings_csv.csv"), "{path}");
+
+        let contents = fs::read_to_string(&path).expect("csv file readable");
+        let mut lines = contents.split("\r\n");
+        assert_eq!(
+            lines.next(),
+            Some("source_artifact,id,asset_id,port,protocol,state,service,title,description,severity,remediation")
+        );
+        assert!(contents.contains("findings_db,10.0.0.2:5432,"));
+        assert!(contents.contains("\"says \"\"hi\"\"\nthere\""));
+        assert!(contents.contains(",\"Open, unfiltered\",plain,medium,\r\n"));
+    }
+
+    #[test]
+    fn report_deduplicates_findings_across_included_scans() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let mut tcp_high = finding("10.0.0.1", 443, "Weak cipher", "tcp scan");
+        tcp_high.severity = "high".to_string();
+        let mut store = HashMap::new();
+        for artifact in [
+            scan_artifact(
+                "findings_tcp",
+                "10.0.0.1",
+                vec![tcp_high, finding("10.0.0.1", 22, "SSH", "tcp scan")],
+            ),
+            scan_artifact(
+                "findings_udp",
+                "10.0.0.1",
+                vec![
+                    finding("10.0.0.1", 443, "Weak cipher", "udp scan"),
+                    finding("10.0.0.1", 22, "SSH banner", "udp scan"),
+                ],
+            ),
+        ] {
+            store.insert(artifact.name.clone(), artifact);
+        }
+        let report = |options: &[(&str, &str)]| ReportStep {
+            name: "combined".to_string(),
+            includes: vec!["findings_tcp".to_string(), "findings_udp".to_string()],
+            format: ReportFormat::Stdout,
+            output: None,
+            options: options
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            tags: Vec::new(),
+            on_failure: Vec::new(),
+            comments: Default::default(),
+        };
+        let executor = test_executor(&artifacts);
+        let mut secrets = SecretStore::default();
+
+        let outcome = executor.process_report(
+            &report(&[("deduplicate", "findings")]),
+            &store,
+            &HashMap::new(),
+            &mut secrets,
+        );
+        assert_eq!(outcome.execution.status, ExecutionStatus::Completed);
+        assert!(outcome.execution.message.as_deref().is_some_and(|message| {
+            message.contains("(1 duplicate finding(s) removed across 2 artifact(s))")
+        }));
+        let data = outcome.artifact.expect("report artifact").data;
+        let includes = data["includes"].as_object().expect("includes object");
+        assert_eq!(
+            includes.keys().collect::<Vec<_>>(),
+            ["report:combined:findings"]
+        );
+        let findings = includes["report:combined:findings"]["findings"]
+            .as_array()
+            .expect("merged findings");
+        assert_eq!(findings.len(), 3);
+        assert_eq!(findings[0]["severity"], "high");
+
+        let outcome = executor.process_report(
+            &report(&[
+                ("deduplicate", "findings"),
+                ("deduplicate_key", "asset_id, port"),
+            ]),
+            &store,
+            &HashMap::new(),
+            &mut secrets,
+        );
+        let data = outcome.artifact.expect("report artifact").data;
+        assert_eq!(
+            data["includes"]["report:combined:findings"]["findings"]
+                .as_array()
+                .map(Vec::len),
+            Some(2)
+        );
+
+        let outcome = executor.process_report(
+            &report(&[("deduplicate", "findings"), ("deduplicate_key", "banner")]),
+            &store,
+            &HashMap::new(),
+            &mut secrets,
+        );
+        assert_eq!(outcome.execution.status, ExecutionStatus::Failed);
+    }
+
+    #[test]
+    fn parses_nmap_service_versions_into_table_columns() {
+        let xml = br#"<nmaprun><host><address addr="192.0.2.20" addrtype="ipv4"/><ports><port protocol="tcp" portid="22"><state state="open"/><service name="ssh" product="OpenSSH" version="8.9p1 Ubuntu 3ubuntu0.6" extrainfo="Ubuntu Linux; protocol 2.0" method="probed" conf="10"/></port><port protocol="tcp" portid="443"><state state="open"/><service name="http" product="nginx" tunnel="ssl" method="probed" conf="10"/></port><port protocol="tcp" portid="9100"><state state="open"/><service name="jetdirect" method="table" conf="3"/></port></ports></host></nmaprun>"#;
+        let parsed = parse_nmap_xml(xml, "192.0.2.20").expect("nmap xml parses");
+
+        let ssh = &parsed.findings[0];
+        assert_eq!(ssh.evidence["service_product"], json!("OpenSSH"));
+        assert_eq!(
+            ssh.evidence["service_version"],
+            json!("8.9p1 Ubuntu 3ubuntu0.6")
+        );
+        assert_eq!(
+            ssh.evidence["service_extra"],
+            json!("Ubuntu Linux; protocol 2.0")
+        );
+        assert_eq!(parsed.findings[1].evidence["service_tunnel"], json!("ssl"));
+        assert!(!parsed.findings[2].evidence.contains_key("service_product"));
+
+        let table = build_table_from_scan(&json!(parsed)).expect("table built");
+        assert_eq!(
+            table.columns,
+            vec![
+                "asset_id",
+                "port",
+                "protocol",
+                "service",
+                "product",
+                "version",
+                "state",
+                "severity",
+                "description"
+            ]
+        );
+        assert_eq!(table.rows[0]["product"], json!("OpenSSH"));
+        assert_eq!(table.rows[1]["version"], Value::Null);
+
+        let plain = build_table_from_scan(&json!({
+            "findings": [{ "asset_id": "a", "port": 80, "evidence": {} }]
+        }))
+        .expect("table built");
+        assert!(!plain.columns.contains(&"product".to_string()));
+    }
+
+    #[test]
+    fn parses_nmap_os_detection_fixture() {
+        let fixture = include_str!("../tests/fixtures/nmap_os.xml");
+        let parsed = parse_nmap_xml(fixture.as_bytes(), "192.168.56.101").expect("fixture parses");
+
+        let asset = &parsed.assets[0];
+        assert_eq!(asset.hostnames, vec!["metasploitable.local".to_string()]);
+        let matches: Vec<(&str, u8)> = asset
+            .os_matches
+            .iter()
+            .map(|candidate| (candidate.name.as_str(), candidate.accuracy))
+            .collect();
+        assert_eq!(
+            matches,
+            vec![
+                ("Linux 2.6.32", 94),
+                ("Linux 3.2 - 4.9", 97),
+                ("OpenWrt Kamikaze 7.09 (Linux 2.6.22)", 88),
+            ]
+        );
+        assert_eq!(asset.labels.get("os"), Some(&"Linux 3.2 - 4.9".to_string()));
+        assert_eq!(parsed.findings.len(), 2);
+    }
+
+    #[test]
+    fn parses_nmap_nse_script_output() {
+        let xml = br#"<nmaprun><host><address addr="192.0.2.10" addrtype="ipv4"/><ports><port protocol="tcp" portid="443"><state state="open"/><service name="https"/><script id="http-title" output="Intranet &amp; Wiki"><elem key="title">Intranet &amp; Wiki</elem></script><script id="ssl-cert" output="Subject: commonName=intranet.example&#xa;Issuer: commonName=Example CA"/></port></ports><hostscript><script id="smb-os-discovery" output="OS: Windows Server 2019"/></hostscript></host></nmaprun>"#;
+        let parsed = parse_nmap_xml(xml, "192.0.2.10").expect("nmap xml parses");
+
+        let finding = &parsed.findings[0];
+        assert_eq!(finding.evidence["nse_http-title"], json!("Intranet & Wiki"));
+        assert_eq!(
+            finding.evidence["nse_ssl-cert"],
+            json!("Subject: commonName=intranet.example\nIssuer: commonName=Example CA")
+        );
+        assert_eq!(
+            parsed.assets[0].labels.get("nse_smb-os-discovery"),
+            Some(&"OS: Windows Server 2019".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_masscan_xml_fixture() {
+        let fixture = include_str!("../tests/fixtures/masscan.xml");
+        let parsed = parse_masscan_xml(fixture.as_bytes(), "10.0.0.0/24").expect("fixture parses");
+
+        assert_eq!(parsed.tool, "masscan");
+        assert_eq!(parsed.assets.len(), 2);
+        assert_eq!(parsed.assets[0].id, "asset://host/10.0.0.5");
+        assert_eq!(parsed.findings.len(), 3);
+
+        let ssh = parsed
+            .findings
+            .iter()
+            .find(|finding| finding.port == 22)
+            .expect("ssh finding");
+        assert_eq!(ssh.state, "open");
+        assert_eq!(ssh.service.as_deref(), Some("ssh"));
+        assert_eq!(
+            ssh.evidence["banners"]["ssh"],
+            json!("SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.6")
+        );
+
+        let http = parsed
+            .findings
+            .iter()
+            .find(|finding| finding.port == 80)
+            .expect("http finding");
+        assert_eq!(http.asset_id, "asset://host/10.0.0.9");
+        assert_eq!(http.service.as_deref(), Some("http"));
+        assert_eq!(
+            http.evidence["banners"]["title"],
+            json!("Welcome to nginx!")
+        );
+
+        assert!(masscan_emits_xml(Some("-p1-1000 --output-format xml")));
+        assert!(!masscan_emits_xml(Some("-p80 --output-format list")));
+    }
+
+    #[test]
+    fn tool_params_become_options_unless_already_given() {
+        let params: BTreeMap<String, String> = [
+            ("url", "https://example.com/FUZZ"),
+            ("wordlist", "common.txt"),
+            ("match_codes", "200"),
+            ("flags", "-w other.txt"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        let flags = vec!["-w".to_string(), "other.txt".to_string()];
+        assert_eq!(
+            tool_param_args("ffuf", &params, &flags),
+            vec!["-u", "https://example.com/FUZZ", "-mc", "200"]
+        );
+
+        let params: BTreeMap<String, String> = [("rate".to_string(), "500".to_string())].into();
+        assert!(tool_param_args("masscan", &params, &["--rate=100".to_string()]).is_empty());
+        assert_eq!(
+            tool_param_args("masscan", &params, &[]),
+            vec!["--rate", "500"]
+        );
+        assert!(tool_param_args("nmap", &params, &[]).is_empty());
+    }
+
+    #[test]
+    fn parses_self_closing_nmap_port_elements() {
+        let xml = br#"<nmaprun><host><address addr="192.0.2.1" addrtype="ipv4"/><hostnames><hostname name="gw.example" type="PTR"/></hostnames><ports><port protocol="tcp" portid="22"><state state="open" reason="syn-ack"/><service name="ssh" method="table"/></port><port protocol="tcp" portid="25"><state state="closed" reason="reset"/></port></ports></host></nmaprun>"#;
+        let parsed = parse_nmap_xml(xml, "192.0.2.1").expect("nmap xml parses");
+
+        assert_eq!(parsed.assets[0].hostnames, vec!["gw.example".to_string()]);
+        assert_eq!(parsed.findings.len(), 1);
+        assert_eq!(parsed.findings[0].port, 22);
+        assert_eq!(parsed.findings[0].service.as_deref(), Some("ssh"));
+    }
+
+    #[test]
+    fn parses_gobuster_plain_and_json_output() {
+        let fixture = include_str!("../tests/fixtures/gobuster.txt");
+        let parsed =
+            parse_gobuster_output(fixture, "http://10.10.11.5:8080", false).expect("plain parses");
+
+        assert_eq!(parsed.tool, "gobuster");
+        assert!(parsed.raw_xml.is_empty());
+        assert_eq!(parsed.raw_output, fixture);
+        assert_eq!(parsed.assets.len(), 1);
+        assert_eq!(parsed.assets[0].addresses, vec!["10.10.11.5".to_string()]);
+        assert_eq!(parsed.findings.len(), 4);
+
+        let admin = &parsed.findings[1];
+        assert_eq!(admin.asset_id, "asset://host/10.10.11.5");
+        assert_eq!(admin.port, 8080);
+        assert_eq!(admin.severity, "informational");
+        assert_eq!(admin.title, "/admin (301)");
+        assert_eq!(admin.description, "http://10.10.11.5:8080/admin");
+        assert_eq!(
+            admin.evidence["redirect"],
+            json!("http://10.10.11.5:8080/admin/")
+        );
+
+        let legacy = parse_gobuster_output(
+            "Status: 200 [Size: 1234] --> /admin",
+            "https://example.com",
+            false,
+        )
+        .expect("legacy parses");
+        assert_eq!(legacy.findings[0].port, 443);
+        assert_eq!(legacy.assets[0].hostnames, vec!["example.com".to_string()]);
+
+        let json_lines = "{\"path\":\"/api\",\"status\":200,\"size\":42}\nnoise\n";
+        let parsed =
+            parse_gobuster_output(json_lines, "http://example.com", true).expect("json parses");
+        assert_eq!(parsed.findings.len(), 1);
+        assert_eq!(parsed.findings[0].evidence["size"], json!(42));
+        assert!(gobuster_emits_json(&[
+            "dir".to_string(),
+            "-o".to_string(),
+            "json".to_string()
+        ]));
+    }
+
+    #[test]
+    fn parses_nuclei_jsonl_fixture() {
+        let fixture = include_str!("../tests/fixtures/nuclei.jsonl");
+        let output = format!("[INF] Current nuclei version: v3.1.10\n{fixture}");
+        let parsed = parse_nuclei_jsonl(&output, "scanme.sh").expect("fixture parses");
+
+        assert_eq!(parsed.tool, "nuclei");
+        assert_eq!(parsed.findings.len(), 4);
+        assert_eq!(parsed.assets.len(), 2);
+        assert_eq!(
+            parsed.assets[0].addresses,
+            vec!["128.199.158.128".to_string()]
+        );
+        assert_eq!(
+            parsed.assets[1].hostnames,
+            vec!["testphp.vulnweb.com".to_string()]
+        );
+
+        let tech = &parsed.findings[0];
+        assert_eq!(tech.title, "Wappalyzer Technology Detection");
+        assert_eq!(tech.severity, "informational");
+        assert_eq!(tech.asset_id, "scanme.sh");
+        assert_eq!(tech.port, 443);
+        assert_eq!(tech.service.as_deref(), Some("https"));
+
+        let git = &parsed.findings[2];
+        assert_eq!(git.severity, "medium");
+        assert_eq!(git.asset_id, "http://testphp.vulnweb.com");
+        assert_eq!(git.description, "http://testphp.vulnweb.com/.git/config");
+        assert_eq!(git.port, 80);
+        assert_eq!(git.cvss_score, Some(5.3));
+        assert_eq!(
+            git.cvss_vector.as_deref(),
+            Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:L/I:N/A:N")
+        );
+        assert_eq!(parsed.findings[0].cvss_score, None);
+        assert_eq!(git.cwe_ids, vec!["CWE-200".to_string()]);
+        assert!(git.cve_ids.is_empty());
+
+        let ssh = &parsed.findings[3];
+        assert_eq!(ssh.port, 22);
+        assert_eq!(ssh.protocol, "javascript");
+        assert_eq!(ssh.evidence["template_id"], json!("CVE-2023-48795"));
+
+        assert!(nuclei_emits_json(Some("-silent -jsonl")));
+        assert!(!nuclei_emits_json(Some("-silent")));
+        assert!(parse_nuclei_jsonl("{not json", "x").is_err());
+    }
+
+    #[test]
+    fn cyclonedx_report_maps_assets_and_findings() {
+        let mut critical = finding("10.0.0.1", 22, "SSH", "weak kex");
+        critical.severity = "critical".to_string();
+        let mut artifact = scan_artifact(
+            "findings_web",
+            "10.0.0.1",
+            vec![critical, finding("10.0.0.9", 80, "HTTP", "plain")],
+        );
+        artifact.data["assets"] = json!([{
+            "id": "10.0.0.1",
+            "addresses": ["10.0.0.1"],
+            "hostnames": ["web.internal"]
+        }]);
+        let includes = BTreeMap::from([(artifact.name.clone(), artifact.data)]);
+
+        let payload = render_cyclonedx_report("sbom", "2024-01-01T00:00:00Z", &includes)
+            .expect("cyclonedx renders");
+        let bom: Value = serde_json::from_str(&payload).expect("valid json");
+        assert_eq!(bom["specVersion"], json!("1.4"));
+        assert!(bom["serialNumber"]
+            .as_str()
+            .is_some_and(|serial| serial.starts_with("urn:uuid:")));
+        assert_eq!(
+            bom["metadata"]["tools"][0]["version"],
+            json!(env!("CARGO_PKG_VERSION"))
+        );
+
+        let components = bom["components"].as_array().expect("components");
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0]["name"], json!("web.internal"));
+        assert_eq!(components[1]["bom-ref"], json!("10.0.0.9"));
+
+        let vulnerabilities = bom["vulnerabilities"].as_array().expect("vulnerabilities");
+        assert_eq!(
+            vulnerabilities[0]["ratings"][0]["severity"],
+            json!("critical")
+        );
+        assert_eq!(vulnerabilities[0]["ratings"][0]["score"], json!(9.0));
+        assert_eq!(vulnerabilities[1]["affects"][0]["ref"], json!("10.0.0.9"));
+
+        let mut broken = bom.clone();
+        broken["vulnerabilities"][0]["affects"][0]["ref"] = json!("missing");
+        let err = validate_cyclonedx(&broken).expect_err("dangling ref rejected");
+        assert!(err.contains("unknown ref 'missing'"), "{err}");
+    }
+
+    #[test]
+    fn html_report_charts_findings_by_severity() {
+        let mut critical = finding("10.0.0.1", 22, "SSH", "weak kex");
+        critical.severity = "critical".to_string();
+        let mut high = finding("10.0.0.1", 443, "TLS", "old protocol");
+        high.severity = "high".to_string();
+        let first = scan_artifact("findings_a", "10.0.0.1", vec![critical, high.clone()]);
+        let second = scan_artifact("findings_b", "10.0.0.2", vec![high]);
+        let includes = BTreeMap::from([
+            (first.name.clone(), first.data),
+            (second.name.clone(), second.data),
+        ]);
+
+        let html = render_html_report("demo", "now", &includes, &BTreeMap::new(), &BTreeMap::new());
+        let chart = html.find("<svg").expect("severity chart rendered");
+        assert!(chart < html.find("<h2>findings_a</h2>").expect("artifact section"));
+        assert!(html.contains("aria-label=\"3 findings by severity\""));
+        assert!(html.contains("stroke=\"#dc2626\""));
+        assert!(html.contains(">critical (1)</text>"));
+        assert!(html.contains(">high (2)</text>"));
+        // The high slice covers two thirds of the ring, so it takes the large arc.
+        assert!(html.contains("A 70 70 0 1 1"));
+        assert!(!html.contains("<script"));
+
+        let single = BTreeMap::from([(
+            "findings_a".to_string(),
+            scan_artifact(
+                "findings_a",
+                "10.0.0.1",
+                vec![finding("10.0.0.1", 80, "HTTP", "")],
+            )
+            .data,
+        )]);
+        let html = render_html_report("demo", "now", &single, &BTreeMap::new(), &BTreeMap::new());
+        assert!(!html.contains("<svg"));
+    }
+
+    #[test]
+    fn html_report_search_is_inline_and_optional() {
+        let artifact = scan_artifact(
+            "findings_web",
+            "10.0.0.1",
+            vec![finding("10.0.0.1", 80, "HTTP", "plain")],
+        );
+        let includes = BTreeMap::from([(artifact.name.clone(), artifact.data.clone())]);
+        let table = build_table_from_scan(&artifact.data).expect("table built");
+        let tables = BTreeMap::from([(artifact.name.clone(), table)]);
+
+        let html = render_html_report("web", "now", &includes, &tables, &BTreeMap::new());
+        let header = &html
+            [html.find("<header>").expect("header")..html.find("</header>").expect("header end")];
+        assert!(header.contains("<input type=\"search\" id=\"axion-search\""));
+        assert!(html.contains("<script>"));
+        assert!(!html.contains("<script src"));
+        assert!(html.contains("toLowerCase()"));
+
+        let options = BTreeMap::from([("disable_search".to_string(), "true".to_string())]);
+        let html = render_html_report("web", "now", &includes, &tables, &options);
+        assert!(!html.contains("axion-search"));
+        assert!(!html.contains("<script"));
+    }
+
+    #[test]
+    fn html_report_theme_options_set_css_properties() {
+        let default = render_html_report(
+            "web",
+            "now",
+            &BTreeMap::new(),
+            &BTreeMap::new(),
+            &BTreeMap::new(),
+        );
+        assert!(!default.contains(":root{"));
+        assert!(default.contains("background:var(--primary, #0f172a)"));
+
+        let options = BTreeMap::from([
+            ("theme".to_string(), "light".to_string()),
+            ("primary_color".to_string(), "#123456".to_string()),
+            ("font".to_string(), "Inter, sans-serif".to_string()),
+        ]);
+        let html = render_html_report("web", "now", &BTreeMap::new(), &BTreeMap::new(), &options);
+        assert!(html.contains("--primary:#123456;"));
+        assert!(html.contains("--font:Inter, sans-serif;"));
+        assert!(html.contains("--text:#0f172a;"));
+
+        let hostile = BTreeMap::from([(
+            "primary_color".to_string(),
+            "red;}</style><script>".to_string(),
+        )]);
+        assert_eq!(render_html_theme(&hostile), "");
+    }
+
+    #[test]
+    fn sarif_baseline_marks_new_updated_and_absent_results() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let previous = scan_artifact(
+            "findings_web",
+            "10.0.0.0/24",
+            vec![
+                finding("10.0.0.1", 80, "HTTP", ""),
+                finding("10.0.0.2", 22, "SSH", ""),
+                finding("10.0.0.4", 21, "FTP", ""),
+            ],
+        );
+        let includes = BTreeMap::from([(previous.name.clone(), previous.data)]);
+        let baseline = render_sarif_report("web", "then", &includes, &BTreeMap::new())
+            .expect("baseline renders");
+        let baseline_path = dir.path().join("previous.sarif");
+        fs::write(&baseline_path, baseline).expect("baseline written");
+
+        let current = scan_artifact(
+            "findings_web",
+            "10.0.0.0/24",
+            vec![
+                finding("10.0.0.1", 80, "HTTP", ""),
+                finding("10.0.0.2", 22, "SSH (weak kex)", ""),
+                finding("10.0.0.3", 443, "HTTPS", ""),
+            ],
+        );
+        let includes = BTreeMap::from([(current.name.clone(), current.data)]);
+        let options = BTreeMap::from([(
+            "baseline_path".to_string(),
+            baseline_path.to_string_lossy().to_string(),
+        )]);
+        let sarif = render_sarif_report("web", "now", &includes, &options).expect("sarif renders");
+        let sarif: Value = serde_json::from_str(&sarif).expect("valid json");
+        let states: Vec<(&str, &str)> = sarif["runs"][0]["results"]
+            .as_array()
+            .expect("results")
+            .iter()
+            .map(|result| {
+                (
+                    result["message"]["text"].as_str().unwrap_or_default(),
+                    result["baselineState"].as_str().unwrap_or_default(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                ("HTTP", "unchanged"),
+                ("SSH (weak kex)", "updated"),
+                ("HTTPS", "new"),
+                ("FTP", "absent"),
+            ]
+        );
+
+        let missing = BTreeMap::from([(
+            "baseline_path".to_string(),
+            dir.path()
+                .join("missing.sarif")
+                .to_string_lossy()
+                .to_string(),
+        )]);
+        let err = render_sarif_report("web", "now", &includes, &missing).expect_err("missing file");
+        assert!(err.contains("failed to read SARIF baseline"), "{err}");
+    }
+
+    #[test]
+    fn sarif_suppressions_mark_matching_results() {
+        let artifact = scan_artifact(
+            "findings_web",
+            "10.0.0.0/24",
+            vec![
+                finding("dev-web", 80, "HTTP", ""),
+                finding("prod-web", 80, "HTTP", ""),
+            ],
+        );
+        let includes = BTreeMap::from([(artifact.name.clone(), artifact.data)]);
+        let options = BTreeMap::from([(
+            "suppress_dev".to_string(),
+            r#"finding where asset_id matches "^dev-" and rule_id == "nmap""#.to_string(),
+        )]);
+        let sarif = render_sarif_report("web", "now", &includes, &options).expect("sarif renders");
+        let sarif: Value = serde_json::from_str(&sarif).expect("valid json");
+        let results = sarif["runs"][0]["results"].as_array().expect("results");
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0]["suppressions"],
+            json!([{
+                "kind": "inSource",
+                "justification": "matched report option suppress_dev"
+            }])
+        );
+        assert!(results[1].get("suppressions").is_none());
+
+        let options = BTreeMap::from([(
+            "suppress".to_string(),
+            r#"tool == "nmap" or severity == "critical""#.to_string(),
+        )]);
+        let sarif = render_sarif_report("web", "now", &includes, &options).expect("sarif renders");
+        assert_eq!(sarif.matches("\"inSource\"").count(), 2);
+
+        let broken = BTreeMap::from([("suppress".to_string(), "port >".to_string())]);
+        let err = render_sarif_report("web", "now", &includes, &broken).expect_err("invalid rule");
+        assert!(err.starts_with("invalid suppress rule"), "{err}");
+        assert_eq!(
+            keyword_operators(r#"title == "a and b" and port > 1"#),
+            r#"title == "a and b" && port > 1"#
+        );
+    }
+
+    #[test]
+    fn sarif_report_emits_one_run_per_tool() {
+        let ports = scan_artifact(
+            "findings_ports",
+            "10.0.0.1",
+            vec![finding("10.0.0.1", 22, "SSH", "")],
+        );
+        let mut vulns = scan_artifact(
+            "findings_vulns",
+            "10.0.0.1",
+            vec![
+                finding("10.0.0.1", 443, "Weak TLS", ""),
+                finding("10.0.0.2", 443, "Expired cert", ""),
+            ],
+        );
+        vulns.data["tool"] = json!("nuclei");
+        let includes = BTreeMap::from([
+            (ports.name.clone(), ports.data),
+            (vulns.name.clone(), vulns.data),
+        ]);
+
+        let sarif = render_sarif_report("mixed", "now", &includes, &BTreeMap::new())
+            .expect("sarif renders");
+        let sarif: Value = serde_json::from_str(&sarif).expect("valid json");
+        let runs = sarif["runs"].as_array().expect("runs");
+        assert_eq!(runs.len(), 2);
+
+        let driver = &runs[0]["tool"]["driver"];
+        assert_eq!(driver["name"], json!("nmap"));
+        assert_eq!(driver["informationUri"], json!("https://nmap.org"));
+        assert_eq!(driver["rules"][0]["id"], json!("nmap"));
+        assert_eq!(runs[0]["results"].as_array().map(Vec::len), Some(1));
+
+        let driver = &runs[1]["tool"]["driver"];
+        assert_eq!(driver["name"], json!("nuclei"));
+        assert_eq!(
+            driver["informationUri"],
+            json!("https://github.com/projectdiscovery/nuclei")
+        );
+        assert_eq!(driver["rules"].as_array().map(Vec::len), Some(1));
+        assert_eq!(driver["rules"][0]["id"], json!("nuclei"));
+        let results = runs[1]["results"].as_array().expect("results");
+        assert!(results.iter().all(|result| result["ruleId"] == "nuclei"));
+        assert_eq!(runs[1]["artifacts"].as_array().map(Vec::len), Some(2));
+    }
+
+    #[test]
+    fn archive_option_bundles_report_and_includes() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let mut store = HashMap::new();
+        let artifact = scan_artifact(
+            "findings_web",
+            "10.0.0.1",
+            vec![finding("10.0.0.1", 80, "HTTP", "plain")],
+        );
+        store.insert(artifact.name.clone(), artifact);
+
+        let report = ReportStep {
+            name: "client".to_string(),
+            includes: vec!["findings_web".to_string()],
+            format: ReportFormat::Markdown,
+            output: None,
+            options: BTreeMap::from([("archive".to_string(), "true".to_string())]),
+            tags: Vec::new(),
+            on_failure: Vec::new(),
+            comments: Default::default(),
+        };
+        let outcome = test_executor(&artifacts).process_report(
+            &report,
+            &store,
+            &HashMap::new(),
+            &mut SecretStore::default(),
+        );
+        assert_eq!(outcome.execution.status, ExecutionStatus::Completed);
+        let artifact = outcome.artifact.expect("report artifact");
+        let path = artifact.path.expect("archive path");
+        assert!(path.ends_with("reports/client.zip"), "{path}");
+        assert_eq!(artifact.data["output_path"], json!(path));
+        let leftovers: Vec<_> = fs::read_dir(Path::new(&path).parent().unwrap())
+            .expect("reports dir")
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty(), "{leftovers:?}");
+
+        let file = fs::File::open(&path).expect("archive readable");
+        let mut archive = zip::ZipArchive::new(file).expect("valid zip");
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            vec!["artifacts/findings_web.json", "client.md", "manifest.json"]
+        );
+        let mut manifest = String::new();
+        archive
+            .by_name("manifest.json")
+            .expect("manifest entry")
+            .read_to_string(&mut manifest)
+            .expect("manifest readable");
+        let manifest: Value = serde_json::from_str(&manifest).expect("manifest json");
+        assert_eq!(manifest["report_file"], json!("client.md"));
+        assert_eq!(
+            manifest["artifacts"],
+            json!([{ "name": "findings_web", "path": "artifacts/findings_web.json" }])
+        );
+    }
+
+    #[test]
+    fn junit_report_fails_findings_at_threshold() {
+        let artifacts = tempfile::tempdir().expect("temp artifacts dir");
+        let mut critical = finding("10.0.0.1", 22, "SSH <weak>", "weak kex & ciphers");
+        critical.severity = "critical".to_string();
+        let mut store = HashMap::new();
+        let artifact = scan_artifact(
+            "findings_web",
+            "10.0.0.1",
+            vec![critical, finding("10.0.0.1", 80, "HTTP", "plain")],
+        );
+        store.insert(artifact.name.clone(), artifact);
+
+        let mut report = ReportStep {
+            name: "ci_gate".to_string(),
+            includes: vec!["findings_web".to_string()],
+            format: ReportFormat::JunitXml,
+            output: None,
+            options: BTreeMap::new(),
+            tags: Vec::new(),
+            on_failure: Vec::new(),
+            comments: Default::default(),
+        };
+        let mut secrets = SecretStore::default();
+        let outcome = test_executor(&artifacts).process_report(
+            &report,
+            &store,
+            &HashMap::new(),
+            &mut secrets,
+        );
+        assert_eq!(outcome.execution.status, ExecutionStatus::Completed);
+        let path = outcome.artifact.and_then(|a| a.path).expect("junit path");
+        assert!(path.ends_with("reports/ci_gate.xml"), "{path}");
+
+        let contents = fs::read_to_string(&path).expect("junit file readable");
+        assert!(contents.contains("<testsuites name=\"ci_gate\" tests=\"2\" failures=\"1\">"));
+        assert!(contents.contains("<testsuite name=\"findings_web\" tests=\"2\" failures=\"1\""));
+        assert!(contents.contains(
+            "<failure message=\"SSH &lt;weak&gt;\" type=\"critical\">weak kex &amp; ciphers</failure>"
+        ));
+        assert!(contents.contains("name=\"HTTP (80/tcp)\"/>"));
+
+        report.name = "ci_gate_medium".to_string();
+        report
+            .options
+            .insert("severity_threshold".to_string(), "medium".to_string());
+        let outcome = test_executor(&artifacts).process_report(
+            &report,
+            &store,
+            &HashMap::new(),
+            &mut secrets,
+        );
+        let path = outcome.artifact.and_then(|a| a.path).expect("junit path");
+        let contents = fs::read_to_string(&path).expect("junit file readable");
+        assert!(contents.contains("tests=\"2\" failures=\"2\">"));
+    }
 }
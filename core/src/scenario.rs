@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use std::collections::{BTreeMap, BTreeSet};
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::fmt;
 use std::str::FromStr;
 
@@ -9,6 +10,14 @@ pub struct Scenario {
     pub steps: Vec<Step>,
     #[serde(default)]
     pub imports: Vec<String>,
+    #[serde(default)]
+    pub aliases: Vec<ImportAlias>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportAlias {
+    pub alias: String,
+    pub imports: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,25 +32,64 @@ pub enum Step {
     Report(ReportStep),
     Conditional(ConditionalStep),
     Loop(LoopStep),
+    Parallel(ParallelStep),
+    Assert(AssertStep),
+    Notify(NotifyStep),
+    Sleep(SleepStep),
+    Merge(MergeStep),
+    Transform(TransformStep),
+    Checkpoint(CheckpointStep),
+    Function(FunctionDef),
+    Call(CallStep),
+    Break,
+    Continue,
+    Comment(CommentStep),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportStep {
     pub path: String,
+    #[serde(default)]
+    pub alias: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssetGroupStep {
     pub name: String,
     pub properties: BTreeMap<String, String>,
+    /// Labels from a `tags ["..."]` line, matched by `run --only-tags`/`--skip-tags`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Comment lines inside the block, keyed by the entry they precede (see
+    /// [`BlockComments`]).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub comments: BlockComments,
 }
 
+/// Comments inside a leaf block, keyed by the entry that follows them: a
+/// parameter or property name, `tags`, `retry`, `env`, `output`,
+/// `include <artifact>` or `option <key>`, or `}` for comments before the
+/// closing brace. `to_dsl` writes each group above its entry, so comments
+/// move with their entry when the formatter reorders a block.
+pub type BlockComments = BTreeMap<String, Vec<String>>;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanStep {
     pub name: String,
     pub tool: String,
     pub params: BTreeMap<String, String>,
     pub output: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// Steps from an `on_failure { ... }` block, run only when this step fails.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_failure: Vec<Step>,
+    /// Comment lines inside the block, keyed by the entry they precede (see
+    /// [`BlockComments`]).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub comments: BlockComments,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +97,63 @@ pub struct ScriptStep {
     pub name: String,
     pub params: BTreeMap<String, String>,
     pub output: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Variables from an `env { NAME = "value" }` block, set on the process.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_failure: Vec<Step>,
+    /// Comment lines inside the block, keyed by the entry they precede (see
+    /// [`BlockComments`]).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub comments: BlockComments,
+}
+
+/// `retry 3` or `retry { count 3; delay "5s"; backoff exponential }` on a scan
+/// or script: how often a failed run is attempted again.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub count: u32,
+    #[serde(default)]
+    pub delay_ms: u64,
+    #[serde(default)]
+    pub backoff: RetryBackoff,
+}
+
+impl RetryPolicy {
+    /// Milliseconds to wait after failed attempt number `attempt` (from 1).
+    pub fn wait_ms(&self, attempt: u32) -> u64 {
+        match self.backoff {
+            RetryBackoff::None => 0,
+            RetryBackoff::Fixed => self.delay_ms,
+            RetryBackoff::Exponential => self.delay_ms.saturating_mul(
+                1u64.checked_shl(attempt.saturating_sub(1))
+                    .unwrap_or(u64::MAX),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryBackoff {
+    #[default]
+    None,
+    Fixed,
+    Exponential,
+}
+
+impl RetryBackoff {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RetryBackoff::None => "none",
+            RetryBackoff::Fixed => "fixed",
+            RetryBackoff::Exponential => "exponential",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,6 +165,14 @@ pub struct ReportStep {
     pub output: Option<String>,
     #[serde(default)]
     pub options: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_failure: Vec<Step>,
+    /// Comment lines inside the block, keyed by the entry they precede (see
+    /// [`BlockComments`]).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub comments: BlockComments,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -69,6 +182,9 @@ pub enum ReportFormat {
     Html,
     Markdown,
     Sarif,
+    Csv,
+    JunitXml,
+    CycloneDx,
 }
 
 impl ReportFormat {
@@ -78,6 +194,9 @@ impl ReportFormat {
             ReportFormat::Html => "html",
             ReportFormat::Markdown => "markdown",
             ReportFormat::Sarif => "sarif",
+            ReportFormat::Csv => "csv",
+            ReportFormat::JunitXml => "junit",
+            ReportFormat::CycloneDx => "cyclonedx",
         }
     }
 }
@@ -91,6 +210,9 @@ impl std::str::FromStr for ReportFormat {
             "html" => Ok(ReportFormat::Html),
             "markdown" => Ok(ReportFormat::Markdown),
             "sarif" => Ok(ReportFormat::Sarif),
+            "csv" => Ok(ReportFormat::Csv),
+            "junit" => Ok(ReportFormat::JunitXml),
+            "cyclonedx" => Ok(ReportFormat::CycloneDx),
             other => Err(ParseError::InvalidSyntax(format!(
                 "unknown report format '{}'",
                 other
@@ -117,13 +239,40 @@ pub enum SecretSource {
     Env {
         mappings: BTreeMap<String, String>,
     },
+    /// A file read as plain text, or as JSON when `key` names a field
+    /// (dotted for nested objects) or is `*` for every top-level string.
     File {
         path: String,
+        #[serde(default)]
+        key: Option<String>,
     },
     Vault {
         path: String,
         field: Option<String>,
         namespace: Option<String>,
+        #[serde(default)]
+        mount: Option<String>,
+        #[serde(default)]
+        address: Option<String>,
+        #[serde(default)]
+        role_id: Option<String>,
+        #[serde(default)]
+        secret_id: Option<String>,
+    },
+    /// AWS Secrets Manager, authenticated through the standard AWS credential
+    /// chain. JSON secrets yield one value per field unless `key` picks one.
+    AwsSecretsManager {
+        secret_id: String,
+        #[serde(default)]
+        region: Option<String>,
+        #[serde(default)]
+        key: Option<String>,
+    },
+    /// An entry in the OS keychain (macOS Keychain, Secret Service, Windows
+    /// Credential Manager).
+    Keyring {
+        service: String,
+        account: String,
     },
 }
 
@@ -138,20 +287,195 @@ pub struct ConditionalStep {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoopStep {
     pub iterator: String,
+    /// The `v` of `for k, v in obj`: bound to each entry's value while `iterator` holds the key.
+    #[serde(default)]
+    pub value_iterator: Option<String>,
     pub iterable: LoopIterable,
+    #[serde(default)]
+    pub guard: Option<ConditionExpr>,
+    #[serde(default)]
+    pub max_iter: Option<usize>,
+    pub body: Vec<Step>,
+}
+
+impl LoopStep {
+    /// The loop variables as written in the header: `host` or `key, value`.
+    pub fn bindings(&self) -> String {
+        match &self.value_iterator {
+            Some(value) => format!("{}, {}", self.iterator, value),
+            None => self.iterator.clone(),
+        }
+    }
+}
+
+/// `func name(a, b) { ... }`: a step group run by `call`. Functions are
+/// defined at the top level and may be called before their definition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionDef {
+    pub name: String,
+    pub params: Vec<String>,
     pub body: Vec<Step>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallStep {
+    pub function: String,
+    pub args: Vec<ConditionOperand>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParallelStep {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssertStep {
+    pub name: String,
+    pub condition: ConditionExpr,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_failure: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotifyStep {
+    pub name: String,
+    pub url: String,
+    pub method: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_failure: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SleepStep {
+    pub name: String,
+    pub duration_ms: u64,
+}
+
+/// A `#` or `//` comment line between steps, kept so `axion fmt` can write it
+/// back. Comments inside a scan, script, report or asset group block are kept
+/// in the step's [`BlockComments`] instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentStep {
+    /// The comment line including its marker, without surrounding whitespace.
+    pub text: String,
+    /// Whether a blank line followed the comment in the source.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub detached: bool,
+}
+
+/// `checkpoint <name>` saves the run state so an interrupted run can resume.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointStep {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeStep {
+    pub inputs: Vec<String>,
+    pub output: String,
+}
+
+/// `transform <name> from <artifact> [-> <output>] { ... }` rewrites an
+/// artifact's data with a fixed set of operations. Without an output the
+/// source artifact is replaced in place.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformStep {
+    pub name: String,
+    pub source: String,
+    #[serde(default)]
+    pub output: Option<String>,
+    pub operations: Vec<TransformOp>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_failure: Vec<Step>,
+}
+
+/// Operations accepted inside a `transform` block. Paths are dotted field
+/// names where `field[*]` visits every element of an array.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TransformOp {
+    /// `filter <path> where <condition>` keeps the elements of the array at
+    /// `path` whose fields satisfy the condition.
+    Filter {
+        path: String,
+        condition: ConditionExpr,
+    },
+    /// `project <path> a, b` keeps only the listed fields of each object.
+    Project { path: String, fields: Vec<String> },
+    /// `set <path> <value>` assigns a field, creating it when missing.
+    Set { path: String, value: LiteralValue },
+    /// `rename <path> to <name>` renames a field wherever it is present.
+    Rename { path: String, to: String },
+}
+
+impl TransformOp {
+    pub fn path(&self) -> &str {
+        match self {
+            TransformOp::Filter { path, .. }
+            | TransformOp::Project { path, .. }
+            | TransformOp::Set { path, .. }
+            | TransformOp::Rename { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for TransformOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransformOp::Filter { path, condition } => write!(f, "filter {path} where {condition}"),
+            TransformOp::Project { path, fields } => {
+                write!(f, "project {path} {}", fields.join(", "))
+            }
+            TransformOp::Set { path, value } => write!(f, "set {path} {}", value.to_dsl()),
+            TransformOp::Rename { path, to } => write!(f, "rename {path} to {to}"),
+        }
+    }
+}
+
+/// Splits a transform path such as `findings[*].remediation` into field
+/// names paired with whether the field is an array to expand.
+pub fn split_transform_path(path: &str) -> Option<Vec<(&str, bool)>> {
+    path.split('.')
+        .map(|segment| match segment.strip_suffix("[*]") {
+            Some(field) => is_identifier(field).then_some((field, true)),
+            None => is_identifier(segment).then_some((segment, false)),
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ConditionExpr {
     Literal(bool),
     Variable(String),
+    /// `defined name`: whether the variable is set, without failing when it is not.
+    Defined(String),
     Not(Box<ConditionExpr>),
+    And(Box<ConditionExpr>, Box<ConditionExpr>),
+    Or(Box<ConditionExpr>, Box<ConditionExpr>),
     Equals(ConditionOperand, ConditionOperand),
     NotEquals(ConditionOperand, ConditionOperand),
+    LessThan(ConditionOperand, ConditionOperand),
+    GreaterThan(ConditionOperand, ConditionOperand),
+    LessThanOrEqual(ConditionOperand, ConditionOperand),
+    GreaterThanOrEqual(ConditionOperand, ConditionOperand),
+    Contains(ConditionOperand, ConditionOperand),
+    Matches(ConditionOperand, ConditionOperand),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ConditionOperand {
     Variable(String),
     Literal(LiteralValue),
@@ -161,6 +485,27 @@ pub enum ConditionOperand {
 pub enum LoopIterable {
     Variable(String),
     Literal(LiteralValue),
+    Range {
+        start: i64,
+        end: i64,
+        inclusive: bool,
+    },
+}
+
+impl LoopIterable {
+    pub fn range_len(&self) -> Option<u64> {
+        match self {
+            LoopIterable::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let span = i128::from(*end) - i128::from(*start) + i128::from(*inclusive);
+                Some(span.max(0) as u64)
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -172,10 +517,59 @@ pub struct VariableDecl {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum LiteralValue {
     String(String),
+    /// A whole number written without a decimal point or exponent.
+    Integer(i64),
     Number(f64),
     Boolean(bool),
     Array(Vec<LiteralValue>),
     Object(BTreeMap<String, LiteralValue>),
+    /// `null`: an explicitly absent value.
+    Null,
+    /// `env("NAME")`: the environment variable, read when the step runs.
+    Env(String),
+    /// `file("path")`: the file contents, read when the step runs.
+    File(String),
+    /// `left + right`: arithmetic on variables and literals, computed when the step runs.
+    Arithmetic {
+        operator: ArithmeticOperator,
+        left: Box<ConditionOperand>,
+        right: Box<ConditionOperand>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArithmeticOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl ArithmeticOperator {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            ArithmeticOperator::Add => "+",
+            ArithmeticOperator::Sub => "-",
+            ArithmeticOperator::Mul => "*",
+            ArithmeticOperator::Div => "/",
+        }
+    }
+
+    pub fn apply(self, lhs: &LiteralValue, rhs: &LiteralValue) -> Result<LiteralValue, String> {
+        match self {
+            ArithmeticOperator::Add => lhs.add(rhs),
+            ArithmeticOperator::Sub => lhs.sub(rhs),
+            ArithmeticOperator::Mul => lhs.mul(rhs),
+            ArithmeticOperator::Div => lhs.div(rhs),
+        }
+    }
+}
+
+impl fmt::Display for ArithmeticOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
 }
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -188,20 +582,141 @@ pub enum ParseError {
     InvalidSyntax(String),
     #[error("missing required value: {0}")]
     MissingValue(&'static str),
+    #[error("{location}: {source}")]
+    Located {
+        location: Location,
+        source: Box<ParseError>,
+    },
+}
+
+impl ParseError {
+    /// Source position attached by `parse_scenario`, if any.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            ParseError::Located { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// The error without its location.
+    pub fn inner(&self) -> &ParseError {
+        match self {
+            ParseError::Located { source, .. } => source.inner(),
+            other => other,
+        }
+    }
+}
+
+/// 1-based position of a parse error. `column` points at the first
+/// non-blank character of the offending line and is unset for errors raised
+/// at the end of input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    pub line: usize,
+    pub column: Option<usize>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "line {}, column {}", self.line, column),
+            None => write!(f, "line {}", self.line),
+        }
+    }
 }
 
+/// Result of [`parse_scenario_all`]: the scenario, or every error found.
+pub type ParseResult = Result<Scenario, Vec<ParseError>>;
+
+/// Parses a scenario, returning only the first error.
+#[deprecated(note = "use parse_scenario_all, which reports every parse error")]
 pub fn parse_scenario(source: &str) -> Result<Scenario, ParseError> {
-    let mut lines = source.lines().enumerate().peekable();
+    parse_scenario_all(source).map_err(|mut errors| errors.swap_remove(0))
+}
+
+/// Parses a scenario, collecting errors instead of stopping at the first
+/// one. After an error the parser skips to the next top-level directive (an
+/// unindented line other than `}`), so each directive reports at most one
+/// error.
+pub fn parse_scenario_all(source: &str) -> ParseResult {
+    // Index of the line most recently pulled from the source; errors are
+    // attributed to it.
+    let current = Cell::new(None::<usize>);
+    let mut lines = PeekableLines::new(
+        source
+            .lines()
+            .enumerate()
+            .inspect(|(idx, _)| current.set(Some(*idx))),
+    );
     let mut steps = Vec::new();
     let mut imports = Vec::new();
+    let mut errors = Vec::new();
 
-    while let Some((_, raw_line)) = next_non_empty(&mut lines) {
+    loop {
+        steps.extend(take_comments(&mut lines));
+        let Some((_, raw_line)) = next_non_empty(&mut lines) else {
+            break;
+        };
         let trimmed = raw_line.trim();
-        let step = parse_step_internal(trimmed, &mut lines, &mut imports)?;
-        steps.push(step);
+        let parsed = if is_on_failure(trimmed) {
+            parse_on_failure(trimmed, &mut steps, &mut lines, &mut imports)
+        } else {
+            parse_step_internal(trimmed, &mut lines, &mut imports).map(|step| steps.push(step))
+        };
+        match parsed {
+            Ok(()) => {}
+            Err(err) => {
+                let line = current.get().unwrap_or(0);
+                let column = match err {
+                    ParseError::UnexpectedEof(_) => None,
+                    _ => source
+                        .lines()
+                        .nth(line)
+                        .map(|text| text.chars().take_while(|c| c.is_whitespace()).count() + 1),
+                };
+                errors.push(ParseError::Located {
+                    location: Location {
+                        line: line + 1,
+                        column,
+                    },
+                    source: Box::new(err),
+                });
+                while let Some((_, line)) = lines.peek() {
+                    let top_level = !line.starts_with(char::is_whitespace)
+                        && !line.trim().is_empty()
+                        && !line.starts_with('}');
+                    if top_level {
+                        break;
+                    }
+                    lines.next();
+                }
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
     }
 
-    Ok(Scenario { steps, imports })
+    let aliases = steps
+        .iter()
+        .filter_map(|step| match step {
+            Step::Import(ImportStep {
+                path,
+                alias: Some(alias),
+            }) => Some(ImportAlias {
+                alias: alias.clone(),
+                imports: vec![path.clone()],
+            }),
+            _ => None,
+        })
+        .collect();
+
+    Ok(Scenario {
+        steps,
+        imports,
+        aliases,
+    })
 }
 
 fn parse_step_internal<'a, I>(
@@ -213,9 +728,9 @@ where
     I: Iterator<Item = (usize, &'a str)>,
 {
     if first_line.starts_with("import ") {
-        let path = parse_import(first_line)?;
+        let (path, alias) = parse_import(first_line)?;
         imports.push(path.clone());
-        Ok(Step::Import(ImportStep { path }))
+        Ok(Step::Import(ImportStep { path, alias }))
     } else if first_line.starts_with("asset_group ") || first_line.starts_with("group ") {
         let step = parse_asset_group(first_line, lines)?;
         Ok(Step::AssetGroup(step))
@@ -240,8 +755,42 @@ where
     } else if first_line.starts_with("for ") {
         let step = parse_for(first_line, lines, imports)?;
         Ok(Step::Loop(step))
-    } else if first_line.starts_with("else") {
-        Err(ParseError::InvalidDirective(first_line.to_string()))
+    } else if matches!(
+        first_line.trim_end_matches(';').trim(),
+        "break" | "continue"
+    ) {
+        if first_line.starts_with("break") {
+            Ok(Step::Break)
+        } else {
+            Ok(Step::Continue)
+        }
+    } else if first_line.starts_with("sleep ") {
+        let step = parse_sleep(first_line)?;
+        Ok(Step::Sleep(step))
+    } else if first_line.starts_with("merge_artifacts ") {
+        let step = parse_merge(first_line)?;
+        Ok(Step::Merge(step))
+    } else if first_line.starts_with("notify ") {
+        let step = parse_notify(first_line, lines)?;
+        Ok(Step::Notify(step))
+    } else if first_line.starts_with("assert ") {
+        let step = parse_assert(first_line, lines)?;
+        Ok(Step::Assert(step))
+    } else if first_line.starts_with("checkpoint ") {
+        let step = parse_checkpoint(first_line)?;
+        Ok(Step::Checkpoint(step))
+    } else if first_line.starts_with("transform ") {
+        let step = parse_transform(first_line, lines)?;
+        Ok(Step::Transform(step))
+    } else if first_line.starts_with("func ") {
+        let step = parse_function(first_line, lines, imports)?;
+        Ok(Step::Function(step))
+    } else if first_line.starts_with("call ") {
+        let step = parse_call(first_line)?;
+        Ok(Step::Call(step))
+    } else if first_line.starts_with("parallel ") || first_line.starts_with("parallel{") {
+        let step = parse_parallel(first_line, lines, imports)?;
+        Ok(Step::Parallel(step))
     } else {
         Err(ParseError::InvalidDirective(first_line.to_string()))
     }
@@ -262,6 +811,7 @@ where
 
     let name = tokens[1];
     let mut properties = BTreeMap::new();
+    let mut comments = BlockComments::new();
 
     if let Some(content) = body.take() {
         if let Some((segment, rest)) = content.split_once('}') {
@@ -269,9 +819,12 @@ where
             if !rest.trim().is_empty() {
                 return Err(ParseError::InvalidSyntax(rest.to_string()));
             }
+            let tags = take_tags(&mut properties)?;
             return Ok(AssetGroupStep {
                 name: name.to_string(),
                 properties,
+                tags,
+                comments,
             });
         } else {
             parse_properties_segment(content, &mut properties)?;
@@ -279,13 +832,15 @@ where
     }
 
     loop {
-        let (_, raw_line) = lines
-            .next()
-            .ok_or(ParseError::UnexpectedEof("asset_group block"))?;
+        let pending = take_block_comments(lines);
+        let (_, raw_line) =
+            next_non_empty(lines).ok_or(ParseError::UnexpectedEof("asset_group block"))?;
         let trimmed = raw_line.trim();
-        if trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with('#') {
-            continue;
-        }
+        let key = trimmed
+            .split(|c: char| c.is_whitespace() || c == ';')
+            .next()
+            .unwrap_or_default();
+        attach_block_comments(&mut comments, key, pending);
 
         if let Some((segment, rest)) = trimmed.split_once('}') {
             parse_properties_segment(segment, &mut properties)?;
@@ -298,9 +853,12 @@ where
         }
     }
 
+    let tags = take_tags(&mut properties)?;
     Ok(AssetGroupStep {
         name: name.to_string(),
         properties,
+        tags,
+        comments,
     })
 }
 
@@ -314,10 +872,19 @@ where
     let (name, tool) = parse_scan_header(first_line)?;
     let mut params = BTreeMap::new();
     let mut output = None;
+    let mut retry = None;
+    let mut comments = BlockComments::new();
 
     loop {
+        let pending = take_block_comments(lines);
         let (_, raw_line) = next_non_empty(lines).ok_or(ParseError::UnexpectedEof("scan block"))?;
         let trimmed = raw_line.trim();
+        attach_block_comments(&mut comments, block_entry_key(trimmed), pending);
+
+        if let Some(rest) = strip_retry(trimmed) {
+            retry = Some(parse_retry(rest, lines)?);
+            continue;
+        }
 
         if trimmed.starts_with('}') {
             if let Some(pos) = trimmed.find("->") {
@@ -336,15 +903,20 @@ where
         let value = parts
             .next()
             .ok_or_else(|| ParseError::InvalidSyntax(trimmed.to_string()))?;
-        let parsed_value = parse_quoted(value)?;
+        let parsed_value = parse_multiline_value(value, lines)?;
         params.insert(key.to_string(), parsed_value);
     }
 
+    let tags = take_tags(&mut params)?;
     Ok(ScanStep {
         name: name.to_string(),
         tool: tool.to_string(),
         params,
         output,
+        tags,
+        retry,
+        on_failure: Vec::new(),
+        comments,
     })
 }
 
@@ -372,7 +944,7 @@ fn parse_variable(line: &str) -> Result<VariableDecl, ParseError> {
     })
 }
 
-fn parse_import(line: &str) -> Result<String, ParseError> {
+fn parse_import(line: &str) -> Result<(String, Option<String>), ParseError> {
     let cleaned = line.trim_end_matches(';').trim();
     let rest = cleaned
         .strip_prefix("import")
@@ -381,7 +953,19 @@ fn parse_import(line: &str) -> Result<String, ParseError> {
     if rest.is_empty() {
         return Err(ParseError::MissingValue("import path"));
     }
-    parse_quoted(rest)
+    // A fully quoted path may itself contain " as ", so only split outside quotes.
+    let fully_quoted =
+        rest.len() > 1 && rest.starts_with(['"', '\'']) && rest.ends_with(['"', '\'']);
+    if let Some((path, alias)) = rest.rsplit_once(" as ").filter(|_| !fully_quoted) {
+        let alias = alias.trim();
+        if !is_identifier(alias) {
+            return Err(ParseError::InvalidSyntax(format!(
+                "invalid import alias '{alias}'"
+            )));
+        }
+        return Ok((parse_quoted(path)?, Some(alias.to_string())));
+    }
+    Ok((parse_quoted(rest)?, None))
 }
 
 fn parse_script<'a, I>(
@@ -399,12 +983,30 @@ where
 
     let name = tokens[1];
     let mut params = BTreeMap::new();
+    let mut env = BTreeMap::new();
     let mut output = None;
+    let mut retry = None;
+    let mut comments = BlockComments::new();
 
     loop {
+        let pending = take_block_comments(lines);
         let (_, raw_line) =
             next_non_empty(lines).ok_or(ParseError::UnexpectedEof("script block"))?;
         let trimmed = raw_line.trim();
+        attach_block_comments(&mut comments, block_entry_key(trimmed), pending);
+
+        if let Some(block) = trimmed
+            .strip_prefix("env")
+            .filter(|rest| rest.trim_start().starts_with('{'))
+        {
+            parse_env_block(block.trim_start(), lines, &mut env)?;
+            continue;
+        }
+
+        if let Some(rest) = strip_retry(trimmed) {
+            retry = Some(parse_retry(rest, lines)?);
+            continue;
+        }
 
         if trimmed.starts_with('}') {
             if let Some(pos) = trimmed.find("->") {
@@ -423,7 +1025,7 @@ where
         let value = parts
             .next()
             .ok_or_else(|| ParseError::InvalidSyntax(trimmed.to_string()))?;
-        let parsed_value = parse_quoted(value)?;
+        let parsed_value = parse_multiline_value(value, lines)?;
         params.insert(key.to_string(), parsed_value);
     }
 
@@ -431,10 +1033,117 @@ where
         return Err(ParseError::MissingValue("script run"));
     }
 
+    let tags = take_tags(&mut params)?;
     Ok(ScriptStep {
         name: name.to_string(),
         params,
         output,
+        tags,
+        env,
+        retry,
+        on_failure: Vec::new(),
+        comments,
+    })
+}
+
+// `env { A = "x"; B = "y" }` on one line, or one `NAME = "value"` entry per
+// line up to a closing `}`. The `=` is optional, as in notify headers.
+fn parse_env_block<'a, I>(
+    block: &str,
+    lines: &mut PeekableLines<'a, I>,
+    env: &mut BTreeMap<String, String>,
+) -> Result<(), ParseError>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    let body = block.strip_prefix('{').unwrap_or(block);
+    for entry in read_block_entries(Some(body), lines, "script env block")? {
+        for item in split_top_level(&entry, ';')? {
+            let (name, value) = parse_header_entry(item)?;
+            env.insert(name, value);
+        }
+    }
+    Ok(())
+}
+
+// `NAME = "value"` or `NAME "value"`, as used by notify headers and script env.
+fn parse_header_entry(entry: &str) -> Result<(String, String), ParseError> {
+    let (name, raw_value) = entry
+        .split_once(|c: char| c.is_whitespace() || c == '=')
+        .ok_or_else(|| ParseError::InvalidSyntax(entry.to_string()))?;
+    Ok((name.trim().to_string(), parse_secret_value(raw_value)?))
+}
+
+fn strip_retry(line: &str) -> Option<&str> {
+    line.strip_prefix("retry")
+        .filter(|rest| rest.starts_with([' ', '\t', '{']))
+        .map(str::trim)
+}
+
+// `retry 3`, or a block of `count`, `delay`, and `backoff` entries. A delay
+// without an explicit backoff waits the same time between every attempt.
+fn parse_retry<'a, I>(
+    rest: &str,
+    lines: &mut PeekableLines<'a, I>,
+) -> Result<RetryPolicy, ParseError>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    let parse_count = |value: &str| {
+        value
+            .parse::<u32>()
+            .map_err(|_| ParseError::InvalidSyntax(format!("invalid retry count '{value}'")))
+    };
+    if !rest.starts_with('{') {
+        return Ok(RetryPolicy {
+            count: parse_count(rest)?,
+            delay_ms: 0,
+            backoff: RetryBackoff::None,
+        });
+    }
+
+    let mut count = None;
+    let mut delay_ms = 0;
+    let mut backoff = None;
+    let body = rest.strip_prefix('{').unwrap_or(rest);
+    for entry in read_block_entries(Some(body), lines, "retry block")? {
+        for item in split_top_level(&entry, ';')? {
+            let (key, value) = item
+                .split_once(char::is_whitespace)
+                .map(|(key, value)| (key, value.trim()))
+                .ok_or_else(|| ParseError::InvalidSyntax(item.to_string()))?;
+            match key {
+                "count" => count = Some(parse_count(value)?),
+                "delay" => delay_ms = parse_duration_ms(&parse_quoted(value)?)?,
+                "backoff" => {
+                    backoff = Some(match value {
+                        "none" => RetryBackoff::None,
+                        "fixed" => RetryBackoff::Fixed,
+                        "exponential" => RetryBackoff::Exponential,
+                        other => {
+                            return Err(ParseError::InvalidSyntax(format!(
+                        "unknown retry backoff '{other}' (expected none, fixed, or exponential)"
+                    )))
+                        }
+                    })
+                }
+                other => {
+                    return Err(ParseError::InvalidSyntax(format!(
+                        "unknown retry option '{other}'"
+                    )))
+                }
+            }
+        }
+    }
+    let default_backoff = if delay_ms > 0 {
+        RetryBackoff::Fixed
+    } else {
+        RetryBackoff::None
+    };
+    Ok(RetryPolicy {
+        count: count.ok_or(ParseError::MissingValue("retry count"))?,
+        delay_ms,
+        backoff: backoff.unwrap_or(default_backoff),
     })
 }
 
@@ -449,28 +1158,42 @@ where
     let mut includes = Vec::new();
     let mut output = None;
     let mut options = BTreeMap::new();
+    let mut tags = Vec::new();
+    let mut comments = BlockComments::new();
 
     loop {
+        let pending = take_block_comments(lines);
         let (_, raw_line) =
             next_non_empty(lines).ok_or(ParseError::UnexpectedEof("report block"))?;
         let trimmed = raw_line.trim();
+        attach_block_comments(&mut comments, &report_entry_key(trimmed)?, pending);
 
         if trimmed.starts_with('}') {
             break;
         }
 
-        if trimmed.starts_with("include ") {
-            includes.push(trimmed["include ".len()..].trim().to_string());
-        } else if trimmed.starts_with("output ") {
+        if let Some(include) = trimmed.strip_prefix("include ") {
+            includes.push(include.trim().to_string());
+        } else if let Some(raw_tags) = trimmed.strip_prefix("tags ") {
+            tags = parse_tags(raw_tags)?;
+        } else if let Some(raw_output) = trimmed.strip_prefix("output ") {
             if output.is_some() {
                 return Err(ParseError::InvalidSyntax(
                     "duplicate output directive".to_string(),
                 ));
             }
-            let value = parse_quoted(trimmed["output ".len()..].trim())?;
+            let value = parse_quoted(raw_output.trim())?;
             output = Some(value);
-        } else if trimmed.starts_with("option ") {
-            let remainder = trimmed["option ".len()..].trim();
+        } else if let Some(raw_filter) = trimmed.strip_prefix("filter ") {
+            let expr = raw_filter.trim();
+            parse_condition_expr(expr)?;
+            let combined = match options.remove("filter") {
+                Some(existing) => format!("({existing}) && ({expr})"),
+                None => expr.to_string(),
+            };
+            options.insert("filter".to_string(), combined);
+        } else if let Some(raw_option) = trimmed.strip_prefix("option ") {
+            let remainder = raw_option.trim();
             let (key, raw_value) = parse_report_option(remainder)?;
             if options.contains_key(key) {
                 return Err(ParseError::InvalidSyntax(format!(
@@ -498,9 +1221,61 @@ where
         format,
         output,
         options,
+        tags,
+        on_failure: Vec::new(),
+        comments,
     })
 }
 
+// The `BlockComments` key of a scan or script line: its first word, with
+// `retry {` and `env {` reduced to the keyword and any closing line to `}`.
+fn block_entry_key(line: &str) -> &str {
+    if line.starts_with('}') {
+        return "}";
+    }
+    line.split(|c: char| c.is_whitespace() || c == '{')
+        .next()
+        .unwrap_or_default()
+}
+
+// The `BlockComments` key of a report line, matching what `to_dsl` writes:
+// `include <artifact>` per include and `option <key>` for options and filters.
+fn report_entry_key(line: &str) -> Result<String, ParseError> {
+    if line.starts_with('}') {
+        return Ok("}".to_string());
+    }
+    if let Some(include) = line.strip_prefix("include ") {
+        return Ok(format!("include {}", include.trim()));
+    }
+    if line.starts_with("filter ") {
+        return Ok("option filter".to_string());
+    }
+    if let Some(option) = line.strip_prefix("option ") {
+        let (key, _) = parse_report_option(option)?;
+        return Ok(format!("option {key}"));
+    }
+    Ok(block_entry_key(line).to_string())
+}
+
+fn take_block_comments<'a, I>(lines: &mut PeekableLines<'a, I>) -> Vec<String>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    take_comments(lines)
+        .into_iter()
+        .filter_map(|step| match step {
+            Step::Comment(comment) => Some(comment.text),
+            _ => None,
+        })
+        .collect()
+}
+
+fn attach_block_comments(comments: &mut BlockComments, key: &str, pending: Vec<String>) {
+    if !pending.is_empty() {
+        comments.entry(key.to_string()).or_default().extend(pending);
+    }
+}
+
 fn parse_secret<'a, I>(
     first_line: &str,
     lines: &mut PeekableLines<'a, I>,
@@ -543,18 +1318,35 @@ where
             SecretSource::Env { mappings }
         }
         "file" => {
-            let path = if !remainder.is_empty() {
-                parse_secret_value(remainder)?
+            if !remainder.is_empty() {
+                SecretSource::File {
+                    path: parse_secret_value(remainder)?,
+                    key: None,
+                }
             } else {
                 let entries = parse_secret_block_lines(inline_body, lines, "file")?;
-                if entries.len() != 1 {
-                    return Err(ParseError::InvalidSyntax(
-                        "file secret expects a single path".to_string(),
-                    ));
+                match entries.as_slice() {
+                    [entry] if entry.starts_with('"') => SecretSource::File {
+                        path: parse_secret_value(entry)?,
+                        key: None,
+                    },
+                    [] => {
+                        return Err(ParseError::InvalidSyntax(
+                            "file secret expects a single path".to_string(),
+                        ))
+                    }
+                    _ => {
+                        let params = parse_secret_map(entries)?;
+                        SecretSource::File {
+                            path: params
+                                .get("path")
+                                .cloned()
+                                .ok_or(ParseError::MissingValue("file.path"))?,
+                            key: params.get("key").cloned(),
+                        }
+                    }
                 }
-                parse_secret_value(&entries[0])?
-            };
-            SecretSource::File { path }
+            }
         }
         "vault" => {
             let entries = parse_secret_block_lines(inline_body, lines, "vault")?;
@@ -562,13 +1354,48 @@ where
             let path = params
                 .get("path")
                 .cloned()
-                .ok_or_else(|| ParseError::MissingValue("vault.path"))?;
+                .ok_or(ParseError::MissingValue("vault.path"))?;
             let field = params.get("field").cloned();
             let namespace = params.get("namespace").cloned();
+            let mount = params.get("mount").cloned();
+            let address = params.get("address").cloned();
+            let role_id = params.get("role_id").cloned();
+            let secret_id = params.get("secret_id").cloned();
             SecretSource::Vault {
                 path,
                 field,
                 namespace,
+                mount,
+                address,
+                role_id,
+                secret_id,
+            }
+        }
+        "aws_secrets_manager" => {
+            let entries = parse_secret_block_lines(inline_body, lines, "aws_secrets_manager")?;
+            let params = parse_secret_map(entries)?;
+            let secret_id = params
+                .get("secret_id")
+                .cloned()
+                .ok_or(ParseError::MissingValue("aws_secrets_manager.secret_id"))?;
+            SecretSource::AwsSecretsManager {
+                secret_id,
+                region: params.get("region").cloned(),
+                key: params.get("key").cloned(),
+            }
+        }
+        "keyring" => {
+            let entries = parse_secret_block_lines(inline_body, lines, "keyring")?;
+            let params = parse_secret_map(entries)?;
+            SecretSource::Keyring {
+                service: params
+                    .get("service")
+                    .cloned()
+                    .ok_or(ParseError::MissingValue("keyring.service"))?,
+                account: params
+                    .get("account")
+                    .cloned()
+                    .ok_or(ParseError::MissingValue("keyring.account"))?,
             }
         }
         _ => {
@@ -613,9 +1440,9 @@ where
         let (_, raw_line) =
             next_non_empty(lines).ok_or(ParseError::UnexpectedEof("secret block"))?;
         let trimmed = raw_line.trim();
-        if trimmed.starts_with('}') {
-            if trimmed.len() > 1 && !trimmed[1..].trim().is_empty() {
-                return Err(ParseError::InvalidSyntax(trimmed[1..].trim().to_string()));
+        if let Some(after_brace) = trimmed.strip_prefix('}') {
+            if !after_brace.trim().is_empty() {
+                return Err(ParseError::InvalidSyntax(after_brace.trim().to_string()));
             }
             break;
         }
@@ -707,26 +1534,30 @@ where
     if condition_raw.is_empty() {
         return Err(ParseError::InvalidSyntax(first_line.to_string()));
     }
-    if let Some(content) = body {
-        if !content.is_empty() {
-            return Err(ParseError::InvalidSyntax(content.to_string()));
-        }
-    }
-
     let condition = parse_condition_expr(condition_raw)?;
-    let (then_steps, trailing) = parse_block_steps(lines, imports)?;
+    let (then_steps, trailing) = parse_branch_steps(body, lines, imports)?;
 
     let mut else_steps = Vec::new();
     let mut remaining = trailing;
 
+    // Comments between `}` and `else` move into the else branch; when no
+    // `else` follows they are left for the next step.
     if remaining.is_none() {
-        if let Some((_, peek_line)) = peek_non_empty(lines) {
-            let trimmed = peek_line.trim();
-            if trimmed.starts_with("else") {
-                let (_, consumed) =
-                    next_non_empty(lines).ok_or(ParseError::UnexpectedEof("else block"))?;
-                remaining = Some(consumed.trim().to_string());
-            }
+        let mut offset = 0;
+        while lines.peek_nth(offset).is_some_and(|(_, line)| {
+            let trimmed = line.trim();
+            trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with('#')
+        }) {
+            offset += 1;
+        }
+        let has_else = lines
+            .peek_nth(offset)
+            .is_some_and(|(_, line)| line.trim().starts_with("else"));
+        if has_else {
+            else_steps = take_comments(lines);
+            let (_, consumed) =
+                next_non_empty(lines).ok_or(ParseError::UnexpectedEof("else block"))?;
+            remaining = Some(consumed.trim().to_string());
         }
     }
 
@@ -740,16 +1571,11 @@ where
             if header != "else" {
                 return Err(ParseError::InvalidSyntax(clause));
             }
-            if let Some(content) = remainder {
-                if !content.is_empty() {
-                    return Err(ParseError::InvalidSyntax(content.to_string()));
-                }
-            }
-            let (steps, trailing_after_else) = parse_block_steps(lines, imports)?;
+            let (steps, trailing_after_else) = parse_branch_steps(remainder, lines, imports)?;
             if let Some(rest) = trailing_after_else {
                 return Err(ParseError::InvalidSyntax(rest));
             }
-            else_steps = steps;
+            else_steps.extend(steps);
         } else {
             return Err(ParseError::InvalidSyntax(clause));
         }
@@ -762,6 +1588,29 @@ where
     })
 }
 
+// Branch bodies either span lines or hold a single inline step (`if done { break }`).
+fn parse_branch_steps<'a, I>(
+    body: Option<&str>,
+    lines: &mut PeekableLines<'a, I>,
+    imports: &mut Vec<String>,
+) -> Result<(Vec<Step>, Option<String>), ParseError>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    match body.map(str::trim).filter(|content| !content.is_empty()) {
+        Some(content) => {
+            let inner = content
+                .strip_suffix('}')
+                .map(str::trim)
+                .filter(|inner| !inner.is_empty())
+                .ok_or_else(|| ParseError::InvalidSyntax(content.to_string()))?;
+            let step = parse_step_internal(inner, lines, imports)?;
+            Ok((vec![step], None))
+        }
+        None => parse_block_steps(lines, imports),
+    }
+}
+
 fn parse_for<'a, I>(
     first_line: &str,
     lines: &mut PeekableLines<'a, I>,
@@ -779,12 +1628,31 @@ where
     let in_pos = rest
         .find(" in ")
         .ok_or_else(|| ParseError::InvalidSyntax(first_line.to_string()))?;
-    let iterator = rest[..in_pos].trim();
-    let iterable_raw = rest[in_pos + 4..].trim();
+    let (iterator, value_iterator) = match rest[..in_pos].split_once(',') {
+        Some((key, value)) => (key.trim(), Some(value.trim())),
+        None => (rest[..in_pos].trim(), None),
+    };
+    let mut iterable_raw = rest[in_pos + 4..].trim();
+    let mut guard = None;
+    if let Some(when_pos) = find_operator(iterable_raw, " when ") {
+        let guard_raw = iterable_raw[when_pos + 6..].trim();
+        if guard_raw.is_empty() {
+            return Err(ParseError::InvalidSyntax(first_line.to_string()));
+        }
+        guard = Some(parse_condition_expr(guard_raw)?);
+        iterable_raw = iterable_raw[..when_pos].trim();
+    }
 
     if iterator.is_empty() || !is_identifier(iterator) {
         return Err(ParseError::InvalidSyntax(iterator.to_string()));
     }
+    if let Some(value) = value_iterator {
+        if !is_identifier(value) || value == iterator {
+            return Err(ParseError::InvalidSyntax(format!(
+                "invalid loop variables '{iterator}, {value}'"
+            )));
+        }
+    }
     if iterable_raw.is_empty() {
         return Err(ParseError::InvalidSyntax(first_line.to_string()));
     }
@@ -796,18 +1664,512 @@ where
     }
 
     let iterable = parse_loop_iterable(iterable_raw)?;
-    let (body_steps, trailing) = parse_block_steps(lines, imports)?;
+
+    // Loop options must precede the body steps. Comments around them move to
+    // the start of the body.
+    let mut max_iter = None;
+    let mut leading_comments = Vec::new();
+    loop {
+        leading_comments.extend(take_comments(lines));
+        let Some((_, line)) = peek_non_empty(lines) else {
+            break;
+        };
+        let Some(option) = line.trim().strip_prefix("option ") else {
+            break;
+        };
+        let mut parts = option.split_whitespace();
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some("max_iter"), Some(value), None) => {
+                let limit = value.parse::<usize>().map_err(|_| {
+                    ParseError::InvalidSyntax(format!("invalid max_iter value '{value}'"))
+                })?;
+                max_iter = Some(limit);
+            }
+            _ => return Err(ParseError::InvalidSyntax(line.trim().to_string())),
+        }
+        lines.next();
+    }
+
+    let (mut body_steps, trailing) = parse_block_steps(lines, imports)?;
+    leading_comments.append(&mut body_steps);
+    let body_steps = leading_comments;
     if let Some(rest) = trailing {
         return Err(ParseError::InvalidSyntax(rest));
     }
 
     Ok(LoopStep {
         iterator: iterator.to_string(),
+        value_iterator: value_iterator.map(str::to_string),
         iterable,
+        guard,
+        max_iter,
         body: body_steps,
     })
 }
 
+fn parse_assert<'a, I>(
+    first_line: &str,
+    lines: &mut PeekableLines<'a, I>,
+) -> Result<AssertStep, ParseError>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    let (header, body) = split_header_body(first_line)?;
+    let tokens: Vec<&str> = header.split_whitespace().collect();
+    if tokens.len() != 2 || tokens[0] != "assert" || !is_identifier(tokens[1]) {
+        return Err(ParseError::InvalidSyntax(first_line.to_string()));
+    }
+
+    let entries = read_block_entries(body, lines, "assert block")?;
+
+    let mut condition = None;
+    let mut message = None;
+    let mut tags = Vec::new();
+    for entry in &entries {
+        for item in split_top_level(entry, ';')? {
+            if let Some(raw_tags) = item.strip_prefix("tags ") {
+                tags = parse_tags(raw_tags)?;
+            } else if let Some(expr) = item.strip_prefix("condition ") {
+                if condition.is_some() {
+                    return Err(ParseError::InvalidSyntax(
+                        "duplicate condition directive".to_string(),
+                    ));
+                }
+                condition = Some(parse_condition_expr(expr)?);
+            } else if let Some(text) = item.strip_prefix("message ") {
+                message = Some(parse_quoted(text)?);
+            } else {
+                return Err(ParseError::InvalidSyntax(item.to_string()));
+            }
+        }
+    }
+
+    Ok(AssertStep {
+        name: tokens[1].to_string(),
+        condition: condition.ok_or(ParseError::MissingValue("assert condition"))?,
+        message,
+        tags,
+        on_failure: Vec::new(),
+    })
+}
+
+fn parse_sleep(line: &str) -> Result<SleepStep, ParseError> {
+    let tokens: Vec<&str> = line
+        .trim()
+        .trim_end_matches(';')
+        .split_whitespace()
+        .collect();
+    if tokens.len() != 3 || tokens[0] != "sleep" || !is_identifier(tokens[1]) {
+        return Err(ParseError::InvalidSyntax(line.to_string()));
+    }
+    Ok(SleepStep {
+        name: tokens[1].to_string(),
+        duration_ms: parse_duration_ms(tokens[2])?,
+    })
+}
+
+fn parse_checkpoint(line: &str) -> Result<CheckpointStep, ParseError> {
+    let tokens: Vec<&str> = line
+        .trim()
+        .trim_end_matches(';')
+        .split_whitespace()
+        .collect();
+    match tokens.as_slice() {
+        ["checkpoint", name] if is_identifier(name) => Ok(CheckpointStep {
+            name: name.to_string(),
+        }),
+        _ => Err(ParseError::InvalidSyntax(line.to_string())),
+    }
+}
+
+fn parse_merge(line: &str) -> Result<MergeStep, ParseError> {
+    let rest = line
+        .trim()
+        .trim_end_matches(';')
+        .trim_start_matches("merge_artifacts")
+        .trim();
+    let (inputs_raw, output_raw) = rest
+        .split_once("->")
+        .ok_or(ParseError::MissingValue("merge_artifacts output"))?;
+    let inputs = shell_words::split(inputs_raw)
+        .map_err(|err| ParseError::InvalidSyntax(format!("{line}: {err}")))?;
+    let output = match shell_words::split(output_raw)
+        .map_err(|err| ParseError::InvalidSyntax(format!("{line}: {err}")))?
+        .as_slice()
+    {
+        [single] => single.clone(),
+        _ => return Err(ParseError::InvalidSyntax(line.to_string())),
+    };
+    if inputs.is_empty() {
+        return Err(ParseError::MissingValue("merge_artifacts inputs"));
+    }
+    Ok(MergeStep { inputs, output })
+}
+
+fn parse_transform<'a, I>(
+    first_line: &str,
+    lines: &mut PeekableLines<'a, I>,
+) -> Result<TransformStep, ParseError>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    let (header, body) = split_header_body(first_line)?;
+    let (header, output) = match header.split_once("->") {
+        Some((header, output)) => {
+            let output = output.trim();
+            if output.is_empty() || output.contains(char::is_whitespace) {
+                return Err(ParseError::InvalidSyntax(first_line.to_string()));
+            }
+            (header, Some(output.to_string()))
+        }
+        None => (header, None),
+    };
+    let tokens: Vec<&str> = header.split_whitespace().collect();
+    let (name, source) = match tokens.as_slice() {
+        ["transform", name, "from", source] if is_identifier(name) => (*name, *source),
+        _ => return Err(ParseError::InvalidSyntax(first_line.to_string())),
+    };
+
+    let mut operations = Vec::new();
+    let mut tags = Vec::new();
+    for entry in read_block_entries(body, lines, "transform block")? {
+        for item in split_top_level(&entry, ';')? {
+            match item.strip_prefix("tags ") {
+                Some(raw_tags) => tags = parse_tags(raw_tags)?,
+                None => operations.push(parse_transform_op(item)?),
+            }
+        }
+    }
+    if operations.is_empty() {
+        return Err(ParseError::MissingValue("transform operations"));
+    }
+
+    Ok(TransformStep {
+        name: name.to_string(),
+        source: source.to_string(),
+        output,
+        operations,
+        tags,
+        on_failure: Vec::new(),
+    })
+}
+
+fn parse_transform_op(item: &str) -> Result<TransformOp, ParseError> {
+    let invalid = || ParseError::InvalidSyntax(item.to_string());
+    // Filters and projections act on whole values, while `set` and `rename`
+    // need a field name at the end of the path.
+    let path = |path: &str, field_last: bool| {
+        let segments = split_transform_path(path).ok_or_else(invalid)?;
+        match segments.last() {
+            Some((_, true)) if field_last => Err(invalid()),
+            _ => Ok(path.to_string()),
+        }
+    };
+
+    if let Some(rest) = item.strip_prefix("filter ") {
+        let (target, condition) = rest.split_once(" where ").ok_or_else(invalid)?;
+        Ok(TransformOp::Filter {
+            path: path(target.trim(), true)?,
+            condition: parse_condition_expr(condition.trim())?,
+        })
+    } else if let Some(rest) = item.strip_prefix("project ") {
+        let (target, fields) = rest.trim().split_once(' ').ok_or_else(invalid)?;
+        let fields: Vec<String> = fields
+            .split(',')
+            .map(|field| field.trim().to_string())
+            .collect();
+        if !fields.iter().all(|field| is_identifier(field)) {
+            return Err(invalid());
+        }
+        Ok(TransformOp::Project {
+            path: path(target, false)?,
+            fields,
+        })
+    } else if let Some(rest) = item.strip_prefix("set ") {
+        let (target, value) = rest.trim().split_once(' ').ok_or_else(invalid)?;
+        Ok(TransformOp::Set {
+            path: path(target, true)?,
+            value: parse_literal(value.trim())?,
+        })
+    } else if let Some(rest) = item.strip_prefix("rename ") {
+        let (target, to) = rest.split_once(" to ").ok_or_else(invalid)?;
+        let to = to.trim();
+        if !is_identifier(to) {
+            return Err(invalid());
+        }
+        Ok(TransformOp::Rename {
+            path: path(target.trim(), true)?,
+            to: to.to_string(),
+        })
+    } else {
+        Err(invalid())
+    }
+}
+
+pub(crate) fn parse_duration_ms(value: &str) -> Result<u64, ParseError> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| ParseError::InvalidSyntax(format!("duration '{value}' needs a unit")))?;
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| ParseError::InvalidSyntax(format!("invalid duration '{value}'")))?;
+    let factor = match unit {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        _ => {
+            return Err(ParseError::InvalidSyntax(format!(
+                "unknown duration unit '{unit}' (expected ms, s, m, or h)"
+            )))
+        }
+    };
+    amount
+        .checked_mul(factor)
+        .ok_or_else(|| ParseError::InvalidSyntax(format!("duration '{value}' is too large")))
+}
+
+pub fn format_duration_ms(duration_ms: u64) -> String {
+    const UNITS: [(u64, &str); 3] = [(3_600_000, "h"), (60_000, "m"), (1_000, "s")];
+    for (factor, unit) in UNITS {
+        if duration_ms >= factor && duration_ms.is_multiple_of(factor) {
+            return format!("{}{}", duration_ms / factor, unit);
+        }
+    }
+    format!("{duration_ms}ms")
+}
+
+fn parse_notify<'a, I>(
+    first_line: &str,
+    lines: &mut PeekableLines<'a, I>,
+) -> Result<NotifyStep, ParseError>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    let (header, body) = split_header_body(first_line)?;
+    let tokens: Vec<&str> = header.split_whitespace().collect();
+    if tokens.len() != 2 || tokens[0] != "notify" || !is_identifier(tokens[1]) {
+        return Err(ParseError::InvalidSyntax(first_line.to_string()));
+    }
+
+    let mut url = None;
+    let mut method = None;
+    let mut payload = None;
+    let mut headers = BTreeMap::new();
+    let mut tags = Vec::new();
+    for entry in read_block_entries(body, lines, "notify block")? {
+        for item in split_top_level(&entry, ';')? {
+            let (key, value) = item
+                .split_once(char::is_whitespace)
+                .map(|(key, value)| (key, value.trim()))
+                .unwrap_or((item, ""));
+            match key {
+                "url" => url = Some(parse_secret_value(value)?),
+                "method" => method = Some(parse_secret_value(value)?.to_ascii_uppercase()),
+                "body" => payload = Some(parse_secret_value(value)?),
+                "tags" => tags = parse_tags(value)?,
+                "headers" => {
+                    let inner = value
+                        .strip_prefix('{')
+                        .and_then(|rest| rest.strip_suffix('}'))
+                        .ok_or_else(|| ParseError::InvalidSyntax(item.to_string()))?;
+                    for header_entry in split_top_level(inner, ';')? {
+                        let (name, value) = parse_header_entry(header_entry)?;
+                        headers.insert(name, value);
+                    }
+                }
+                _ => return Err(ParseError::InvalidSyntax(item.to_string())),
+            }
+        }
+    }
+
+    Ok(NotifyStep {
+        name: tokens[1].to_string(),
+        url: url.ok_or(ParseError::MissingValue("notify url"))?,
+        method: method.unwrap_or_else(|| "POST".to_string()),
+        body: payload,
+        headers,
+        tags,
+        on_failure: Vec::new(),
+    })
+}
+
+// Reads the body of a `{ ... }` block, returning one entry per top-level line.
+// Nested blocks are folded into a single `;`-separated entry and braces inside
+// quotes (e.g. `"${secret:token}"`) are ignored.
+fn read_block_entries<'a, I>(
+    initial: Option<&str>,
+    lines: &mut PeekableLines<'a, I>,
+    context: &'static str,
+) -> Result<Vec<String>, ParseError>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    let mut depth = 1usize;
+    let mut pending = initial.map(|content| content.trim().to_string());
+
+    loop {
+        let line = match pending.take() {
+            Some(line) => line,
+            None => {
+                let (_, raw_line) =
+                    next_non_empty(lines).ok_or(ParseError::UnexpectedEof(context))?;
+                raw_line.trim().to_string()
+            }
+        };
+
+        let mut in_quote: Option<char> = None;
+        let mut escaped = false;
+        for (idx, c) in line.char_indices() {
+            if let Some(q) = in_quote {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == q {
+                    in_quote = None;
+                }
+            } else if c == '"' || c == '\'' {
+                in_quote = Some(c);
+            } else if c == '{' {
+                depth += 1;
+            } else if c == '}' {
+                depth -= 1;
+                if depth == 0 {
+                    let rest = line[idx + 1..].trim();
+                    if !rest.is_empty() {
+                        return Err(ParseError::InvalidSyntax(rest.to_string()));
+                    }
+                    current.push_str(line[..idx].trim());
+                    if !current.trim().is_empty() {
+                        entries.push(current.trim().to_string());
+                    }
+                    return Ok(entries);
+                }
+            }
+        }
+
+        current.push_str(&line);
+        if depth == 1 {
+            if !current.trim().is_empty() {
+                entries.push(current.trim().to_string());
+            }
+            current.clear();
+        } else {
+            if !line.ends_with('{') {
+                current.push(';');
+            }
+            current.push(' ');
+        }
+    }
+}
+
+fn parse_parallel<'a, I>(
+    first_line: &str,
+    lines: &mut PeekableLines<'a, I>,
+    imports: &mut Vec<String>,
+) -> Result<ParallelStep, ParseError>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    let (header, body) = split_header_body(first_line)?;
+    let tokens: Vec<&str> = header.split_whitespace().collect();
+    let name = match tokens.as_slice() {
+        ["parallel"] => None,
+        ["parallel", name] if is_identifier(name) => Some(name.to_string()),
+        _ => return Err(ParseError::InvalidSyntax(first_line.to_string())),
+    };
+    if let Some(content) = body {
+        if !content.is_empty() {
+            return Err(ParseError::InvalidSyntax(content.to_string()));
+        }
+    }
+
+    let (steps, trailing) = parse_block_steps(lines, imports)?;
+    if let Some(rest) = trailing {
+        return Err(ParseError::InvalidSyntax(rest));
+    }
+
+    Ok(ParallelStep { name, steps })
+}
+
+fn parse_function<'a, I>(
+    first_line: &str,
+    lines: &mut PeekableLines<'a, I>,
+    imports: &mut Vec<String>,
+) -> Result<FunctionDef, ParseError>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    let (header, body) = split_header_body(first_line)?;
+    let signature = header
+        .strip_prefix("func ")
+        .ok_or_else(|| ParseError::InvalidSyntax(first_line.to_string()))?;
+    let (name, params_raw) = split_invocation(signature)
+        .ok_or_else(|| ParseError::InvalidSyntax(first_line.to_string()))?;
+    let mut params: Vec<String> = Vec::new();
+    for param in split_top_level(params_raw, ',')? {
+        let param = param.trim();
+        if !is_identifier(param) || params.iter().any(|existing| existing == param) {
+            return Err(ParseError::InvalidSyntax(format!(
+                "invalid parameter '{param}' in {header}"
+            )));
+        }
+        params.push(param.to_string());
+    }
+    if let Some(content) = body {
+        if !content.is_empty() {
+            return Err(ParseError::InvalidSyntax(content.to_string()));
+        }
+    }
+
+    let (body, trailing) = parse_block_steps(lines, imports)?;
+    if let Some(rest) = trailing {
+        return Err(ParseError::InvalidSyntax(rest));
+    }
+
+    Ok(FunctionDef {
+        name: name.to_string(),
+        params,
+        body,
+    })
+}
+
+fn parse_call(line: &str) -> Result<CallStep, ParseError> {
+    let invocation = line
+        .trim()
+        .trim_end_matches(';')
+        .strip_prefix("call ")
+        .ok_or_else(|| ParseError::InvalidSyntax(line.to_string()))?;
+    let (function, args_raw) =
+        split_invocation(invocation).ok_or_else(|| ParseError::InvalidSyntax(line.to_string()))?;
+    let mut args = Vec::new();
+    for arg in split_top_level(args_raw, ',')? {
+        if arg.trim().is_empty() {
+            return Err(ParseError::InvalidSyntax(format!(
+                "empty argument in {line}"
+            )));
+        }
+        args.push(parse_condition_operand(arg, 0)?);
+    }
+    Ok(CallStep {
+        function: function.to_string(),
+        args,
+    })
+}
+
+/// Splits `name(a, b)` into the name and the text between the parentheses.
+/// `name()` yields an empty argument list.
+fn split_invocation(value: &str) -> Option<(&str, &str)> {
+    let (name, rest) = value.trim().split_once('(')?;
+    let name = name.trim();
+    let inner = rest.trim_end().strip_suffix(')')?;
+    is_identifier(name).then_some((name, inner))
+}
+
 fn parse_block_steps<'a, I>(
     lines: &mut PeekableLines<'a, I>,
     imports: &mut Vec<String>,
@@ -817,21 +2179,89 @@ where
 {
     let mut steps = Vec::new();
     loop {
+        steps.extend(take_comments(lines));
         let (_, raw_line) = next_non_empty(lines).ok_or(ParseError::UnexpectedEof("block"))?;
         let trimmed = raw_line.trim();
-        if trimmed.starts_with('}') {
-            let remainder = trimmed[1..].trim();
+        if let Some(after_brace) = trimmed.strip_prefix('}') {
+            let remainder = after_brace.trim();
             if remainder.is_empty() {
                 return Ok((steps, None));
             } else {
                 return Ok((steps, Some(remainder.to_string())));
             }
         }
+        if is_on_failure(trimmed) {
+            parse_on_failure(trimmed, &mut steps, lines, imports)?;
+            continue;
+        }
         let step = parse_step_internal(trimmed, lines, imports)?;
         steps.push(step);
     }
 }
 
+fn is_on_failure(line: &str) -> bool {
+    line.strip_prefix("on_failure")
+        .is_some_and(|rest| rest.trim_start().starts_with('{'))
+}
+
+// `on_failure { ... }` on the lines after a step attaches its steps to that
+// step as failure handlers.
+fn parse_on_failure<'a, I>(
+    first_line: &str,
+    steps: &mut [Step],
+    lines: &mut PeekableLines<'a, I>,
+    imports: &mut Vec<String>,
+) -> Result<(), ParseError>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    let (_, body) = split_header_body(first_line)?;
+    let (handlers, trailing) = parse_branch_steps(body, lines, imports)?;
+    if let Some(rest) = trailing {
+        return Err(ParseError::InvalidSyntax(rest));
+    }
+    let target = steps
+        .last_mut()
+        .and_then(Step::on_failure_mut)
+        .ok_or_else(|| {
+            ParseError::InvalidSyntax(
+                "on_failure must directly follow a scan, script, report, assert, notify, or transform step"
+                    .to_string(),
+            )
+        })?;
+    if !target.is_empty() {
+        return Err(ParseError::InvalidSyntax(
+            "a step can have only one on_failure block".to_string(),
+        ));
+    }
+    *target = handlers;
+    Ok(())
+}
+
+/// Consumes blank and comment lines ahead of the next step, returning the
+/// comments as [`Step::Comment`] nodes.
+fn take_comments<'a, I>(lines: &mut PeekableLines<'a, I>) -> Vec<Step>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    let mut comments = Vec::new();
+    while let Some((_, line)) = lines.peek() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            lines.next();
+            continue;
+        }
+        if !trimmed.starts_with("//") && !trimmed.starts_with('#') {
+            break;
+        }
+        let text = trimmed.to_string();
+        lines.next();
+        let detached = matches!(lines.peek(), Some((_, next)) if next.trim().is_empty());
+        comments.push(Step::Comment(CommentStep { text, detached }));
+    }
+    comments
+}
+
 fn peek_non_empty<'a, I>(lines: &mut PeekableLines<'a, I>) -> Option<(usize, &'a str)>
 where
     I: Iterator<Item = (usize, &'a str)>,
@@ -847,66 +2277,201 @@ where
     None
 }
 
-fn parse_condition_expr(expr: &str) -> Result<ConditionExpr, ParseError> {
+pub(crate) fn parse_condition_expr(expr: &str) -> Result<ConditionExpr, ParseError> {
+    parse_condition_at(expr, 0)
+}
+
+fn parse_condition_at(expr: &str, depth: usize) -> Result<ConditionExpr, ParseError> {
+    check_nesting(depth)?;
     let trimmed = expr.trim();
     if trimmed.is_empty() {
         return Err(ParseError::InvalidSyntax(expr.to_string()));
     }
 
+    if let Some(inner) = strip_enclosing_parens(trimmed) {
+        return parse_condition_at(inner, depth + 1);
+    }
+
+    // `||` binds looser than `&&`, so split on it first.
+    if let Some(pos) = find_operator(trimmed, "||") {
+        let left = parse_condition_at(&trimmed[..pos], depth + 1)?;
+        let right = parse_condition_at(&trimmed[pos + 2..], depth + 1)?;
+        return Ok(ConditionExpr::Or(Box::new(left), Box::new(right)));
+    }
+    if let Some(pos) = find_operator(trimmed, "&&") {
+        let left = parse_condition_at(&trimmed[..pos], depth + 1)?;
+        let right = parse_condition_at(&trimmed[pos + 2..], depth + 1)?;
+        return Ok(ConditionExpr::And(Box::new(left), Box::new(right)));
+    }
+
     if let Some(pos) = find_operator(trimmed, "==") {
         let left = &trimmed[..pos];
         let right = &trimmed[pos + 2..];
-        let left_operand = parse_condition_operand(left)?;
-        let right_operand = parse_condition_operand(right)?;
+        let left_operand = parse_condition_operand(left, depth + 1)?;
+        let right_operand = parse_condition_operand(right, depth + 1)?;
         return Ok(ConditionExpr::Equals(left_operand, right_operand));
     }
     if let Some(pos) = find_operator(trimmed, "!=") {
         let left = &trimmed[..pos];
         let right = &trimmed[pos + 2..];
-        let left_operand = parse_condition_operand(left)?;
-        let right_operand = parse_condition_operand(right)?;
+        let left_operand = parse_condition_operand(left, depth + 1)?;
+        let right_operand = parse_condition_operand(right, depth + 1)?;
         return Ok(ConditionExpr::NotEquals(left_operand, right_operand));
     }
-
-    if trimmed.starts_with('!') {
-        let inner = parse_condition_expr(trimmed[1..].trim())?;
-        return Ok(ConditionExpr::Not(Box::new(inner)));
+    // Two-character operators must be matched before their single-character prefixes.
+    for (operator, build) in [
+        ("<=", ConditionExpr::LessThanOrEqual as ComparisonBuilder),
+        (">=", ConditionExpr::GreaterThanOrEqual),
+        ("<", ConditionExpr::LessThan),
+        (">", ConditionExpr::GreaterThan),
+    ] {
+        if let Some(pos) = find_operator(trimmed, operator) {
+            let left_operand = parse_condition_operand(&trimmed[..pos], depth + 1)?;
+            let right_operand =
+                parse_condition_operand(&trimmed[pos + operator.len()..], depth + 1)?;
+            return Ok(build(left_operand, right_operand));
+        }
     }
-
-    if trimmed.eq("true") {
-        return Ok(ConditionExpr::Literal(true));
+    if let Some(pos) = find_operator(trimmed, " matches ") {
+        let left_operand = parse_condition_operand(&trimmed[..pos], depth + 1)?;
+        let right_operand =
+            parse_condition_operand(&trimmed[pos + " matches ".len()..], depth + 1)?;
+        return Ok(ConditionExpr::Matches(left_operand, right_operand));
     }
-    if trimmed.eq("false") {
-        return Ok(ConditionExpr::Literal(false));
+    if let Some(pos) = find_operator(trimmed, " contains ") {
+        let left_operand = parse_condition_operand(&trimmed[..pos], depth + 1)?;
+        let right_operand =
+            parse_condition_operand(&trimmed[pos + " contains ".len()..], depth + 1)?;
+        return Ok(ConditionExpr::Contains(left_operand, right_operand));
     }
-    if is_identifier(trimmed) {
-        return Ok(ConditionExpr::Variable(trimmed.to_string()));
+    if let Some(arguments) = trimmed
+        .strip_prefix("contains(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let parts = split_top_level(arguments, ',')?;
+        if parts.len() != 2 {
+            return Err(ParseError::InvalidSyntax(format!(
+                "contains() expects two arguments: {}",
+                trimmed
+            )));
+        }
+        let left_operand = parse_condition_operand(parts[0], depth + 1)?;
+        let right_operand = parse_condition_operand(parts[1], depth + 1)?;
+        return Ok(ConditionExpr::Contains(left_operand, right_operand));
     }
 
-    if let Ok(literal) = parse_literal(trimmed) {
-        if let LiteralValue::Boolean(value) = literal {
-            return Ok(ConditionExpr::Literal(value));
+    if let Some(name) = trimmed.strip_prefix("defined ") {
+        let name = name.trim();
+        if !is_identifier(name) {
+            return Err(ParseError::InvalidSyntax(format!(
+                "defined expects a variable name: {}",
+                trimmed
+            )));
         }
+        return Ok(ConditionExpr::Defined(name.to_string()));
+    }
+
+    if let Some(negated) = trimmed.strip_prefix('!') {
+        let inner = parse_condition_at(negated.trim(), depth + 1)?;
+        return Ok(ConditionExpr::Not(Box::new(inner)));
+    }
+
+    if trimmed.eq("true") {
+        return Ok(ConditionExpr::Literal(true));
+    }
+    if trimmed.eq("false") {
+        return Ok(ConditionExpr::Literal(false));
+    }
+    if is_variable_path(trimmed) {
+        return Ok(ConditionExpr::Variable(trimmed.to_string()));
+    }
+
+    if let Ok(LiteralValue::Boolean(value)) = parse_literal_at(trimmed, depth + 1) {
+        return Ok(ConditionExpr::Literal(value));
     }
 
     Err(ParseError::InvalidSyntax(trimmed.to_string()))
 }
 
-fn parse_condition_operand(value: &str) -> Result<ConditionOperand, ParseError> {
+type ComparisonBuilder = fn(ConditionOperand, ConditionOperand) -> ConditionExpr;
+
+fn strip_enclosing_parens(expr: &str) -> Option<&str> {
+    let inner = expr.strip_prefix('(')?.strip_suffix(')')?;
+    let mut depth = 0i32;
+    let mut in_quote: Option<char> = None;
+    let mut escaped = false;
+    for c in inner.chars() {
+        if let Some(q) = in_quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                in_quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => in_quote = Some(c),
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth < 0 {
+                    // The leading '(' closes before the end, e.g. `(a) && (b)`.
+                    return None;
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(inner.trim())
+}
+
+fn parse_condition_operand(value: &str, depth: usize) -> Result<ConditionOperand, ParseError> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
         return Err(ParseError::InvalidSyntax(value.to_string()));
     }
 
-    if is_identifier(trimmed) {
+    if let Some(number) = parse_number_literal(trimmed) {
+        return Ok(ConditionOperand::Literal(number));
+    }
+    if matches!(trimmed, "true" | "false") {
+        return Ok(ConditionOperand::Literal(LiteralValue::Boolean(
+            trimmed == "true",
+        )));
+    }
+    if trimmed == "null" {
+        return Ok(ConditionOperand::Literal(LiteralValue::Null));
+    }
+
+    if is_variable_path(trimmed) {
         Ok(ConditionOperand::Variable(trimmed.to_string()))
     } else {
-        let literal = parse_literal(trimmed)?;
+        let literal = parse_literal_at(trimmed, depth)?;
         Ok(ConditionOperand::Literal(literal))
     }
 }
 
 fn parse_loop_iterable(value: &str) -> Result<LoopIterable, ParseError> {
+    let is_quoted_or_array = value.starts_with(['"', '\'', '[', '{']);
+    if let Some((start, end)) = value.split_once("..").filter(|_| !is_quoted_or_array) {
+        let (end, inclusive) = match end.strip_prefix('=') {
+            Some(end) => (end, true),
+            None => (end, false),
+        };
+        let parse_bound = |bound: &str| {
+            bound
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| ParseError::InvalidSyntax(format!("invalid range bound '{bound}'")))
+        };
+        return Ok(LoopIterable::Range {
+            start: parse_bound(start)?,
+            end: parse_bound(end)?,
+            inclusive,
+        });
+    }
     if is_identifier(value) {
         Ok(LoopIterable::Variable(value.to_string()))
     } else {
@@ -948,19 +2513,16 @@ fn parse_report_header(line: &str) -> Result<(String, Option<String>), ParseErro
     }
 }
 
+/// Splits `key value` or `key = value`. The key ends at the first space or
+/// `=`, so quoted values may themselves contain `=` (`'severity == "low"'`).
 fn parse_report_option(remainder: &str) -> Result<(&str, &str), ParseError> {
-    let (key_part, value_part) = if let Some((key, value)) = remainder.split_once('=') {
-        (key.trim(), value.trim())
-    } else {
-        let mut parts = remainder.splitn(2, char::is_whitespace);
-        let key = parts
-            .next()
-            .ok_or_else(|| ParseError::InvalidSyntax(remainder.to_string()))?;
-        let value = parts
-            .next()
-            .ok_or_else(|| ParseError::InvalidSyntax(remainder.to_string()))?;
-        (key.trim(), value.trim())
-    };
+    let remainder = remainder.trim();
+    let key_end = remainder
+        .find(|c: char| c == '=' || c.is_whitespace())
+        .ok_or_else(|| ParseError::InvalidSyntax(remainder.to_string()))?;
+    let key_part = &remainder[..key_end];
+    let rest = remainder[key_end..].trim_start();
+    let value_part = rest.strip_prefix('=').unwrap_or(rest).trim();
 
     if key_part.is_empty() {
         return Err(ParseError::InvalidSyntax("missing option key".to_string()));
@@ -1000,13 +2562,42 @@ fn parse_properties_segment(
             .ok_or_else(|| ParseError::InvalidSyntax(trimmed.to_string()))?;
         let value = parts
             .next()
-            .ok_or_else(|| ParseError::MissingValue("asset_group value"))?;
+            .ok_or(ParseError::MissingValue("asset_group value"))?;
         let parsed_value = parse_quoted(value)?;
         properties.insert(key.to_string(), parsed_value);
     }
     Ok(())
 }
 
+/// Parses a `tags ["a", "b"]` value into its labels.
+fn parse_tags(value: &str) -> Result<Vec<String>, ParseError> {
+    let invalid = || ParseError::InvalidSyntax(format!("tags expects a list of strings: {value}"));
+    match parse_literal(value)? {
+        LiteralValue::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                LiteralValue::String(tag) if !tag.is_empty() => Ok(tag),
+                _ => Err(invalid()),
+            })
+            .collect(),
+        _ => Err(invalid()),
+    }
+}
+
+// Blocks that store `key value` lines in a map pick up `tags [...]` there.
+// Only a list counts, so a tool parameter such as nuclei's `tags "cve"`
+// stays a parameter.
+fn take_tags(params: &mut BTreeMap<String, String>) -> Result<Vec<String>, ParseError> {
+    match params.get("tags") {
+        Some(value) if value.trim_start().starts_with('[') => {
+            let tags = parse_tags(value)?;
+            params.remove("tags");
+            Ok(tags)
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
 fn parse_quoted(value: &str) -> Result<String, ParseError> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
@@ -1031,13 +2622,116 @@ fn parse_quoted(value: &str) -> Result<String, ParseError> {
     Ok(trimmed.to_string())
 }
 
-type PeekableLines<'a, I> = std::iter::Peekable<I>;
+/// Parses a block parameter value. `<<MARKER` reads the following lines up
+/// to a line containing only `MARKER`, and `"""` reads up to the closing
+/// `"""`; both keep the text verbatim (no escapes, quotes allowed). Anything
+/// else goes through `parse_quoted`.
+fn parse_multiline_value<'a, I>(
+    value: &str,
+    lines: &mut PeekableLines<'a, I>,
+) -> Result<String, ParseError>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    let trimmed = value.trim();
+    if let Some(marker) = trimmed.strip_prefix("<<") {
+        let marker = marker.trim();
+        if marker.is_empty() || marker.contains(char::is_whitespace) {
+            return Err(ParseError::InvalidSyntax(value.to_string()));
+        }
+        let mut body = Vec::new();
+        loop {
+            let (_, line) = lines.next().ok_or(ParseError::UnexpectedEof("heredoc"))?;
+            if line.trim() == marker {
+                return Ok(body.join("\n"));
+            }
+            body.push(line);
+        }
+    }
+
+    if let Some(rest) = trimmed.strip_prefix(TRIPLE_QUOTE) {
+        if let Some(end) = rest.find(TRIPLE_QUOTE) {
+            if !rest[end + TRIPLE_QUOTE.len()..].trim().is_empty() {
+                return Err(ParseError::InvalidSyntax(value.to_string()));
+            }
+            return Ok(rest[..end].to_string());
+        }
+        let mut body = Vec::new();
+        if !rest.is_empty() {
+            body.push(rest);
+        }
+        loop {
+            let (_, line) = lines
+                .next()
+                .ok_or(ParseError::UnexpectedEof("triple-quoted string"))?;
+            if let Some(end) = line.find(TRIPLE_QUOTE) {
+                if !line[end + TRIPLE_QUOTE.len()..].trim().is_empty() {
+                    return Err(ParseError::InvalidSyntax(line.to_string()));
+                }
+                if !line[..end].trim().is_empty() {
+                    body.push(&line[..end]);
+                }
+                return Ok(body.join("\n"));
+            }
+            body.push(line);
+        }
+    }
+
+    parse_quoted(value)
+}
+
+const TRIPLE_QUOTE: &str = "\"\"\"";
+
+/// Source lines with arbitrary lookahead, so `parse_if` can look past
+/// comments for an `else`.
+struct PeekableLines<'a, I>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    inner: I,
+    ahead: VecDeque<(usize, &'a str)>,
+}
+
+impl<'a, I> PeekableLines<'a, I>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    fn new(inner: I) -> Self {
+        Self {
+            inner,
+            ahead: VecDeque::new(),
+        }
+    }
+
+    fn peek(&mut self) -> Option<&(usize, &'a str)> {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&mut self, n: usize) -> Option<&(usize, &'a str)> {
+        while self.ahead.len() <= n {
+            let line = self.inner.next()?;
+            self.ahead.push_back(line);
+        }
+        self.ahead.get(n)
+    }
+}
+
+impl<'a, I> Iterator for PeekableLines<'a, I>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+{
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.ahead.pop_front().or_else(|| self.inner.next())
+    }
+}
 
 fn next_non_empty<'a, I>(lines: &mut PeekableLines<'a, I>) -> Option<(usize, &'a str)>
 where
     I: Iterator<Item = (usize, &'a str)>,
 {
-    while let Some((idx, line)) = lines.next() {
+    for (idx, line) in lines.by_ref() {
         let trimmed = line.trim_start();
         if trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with('#') {
             continue;
@@ -1054,16 +2748,34 @@ pub fn parse_literal_expression(value: &str) -> Result<LiteralValue, ParseError>
 }
 
 fn parse_literal(value: &str) -> Result<LiteralValue, ParseError> {
+    parse_literal_at(value, 0)
+}
+
+/// Literals and conditions nested deeper than this are rejected instead of
+/// recursing until the stack overflows.
+const MAX_NESTING_DEPTH: usize = 64;
+
+fn check_nesting(depth: usize) -> Result<(), ParseError> {
+    if depth > MAX_NESTING_DEPTH {
+        return Err(ParseError::InvalidSyntax(format!(
+            "expression nested more than {MAX_NESTING_DEPTH} levels deep"
+        )));
+    }
+    Ok(())
+}
+
+fn parse_literal_at(value: &str, depth: usize) -> Result<LiteralValue, ParseError> {
+    check_nesting(depth)?;
     let trimmed = value.trim();
     if trimmed.is_empty() {
         return Ok(LiteralValue::String(String::new()));
     }
 
     if trimmed.starts_with('[') {
-        return parse_array_literal(trimmed);
+        return parse_array_literal(trimmed, depth);
     }
     if trimmed.starts_with('{') {
-        return parse_object_literal(trimmed);
+        return parse_object_literal(trimmed, depth);
     }
     if trimmed.eq("true") {
         return Ok(LiteralValue::Boolean(true));
@@ -1071,8 +2783,32 @@ fn parse_literal(value: &str) -> Result<LiteralValue, ParseError> {
     if trimmed.eq("false") {
         return Ok(LiteralValue::Boolean(false));
     }
+    if trimmed.eq("null") {
+        return Ok(LiteralValue::Null);
+    }
+    for (function, build) in [
+        ("env", LiteralValue::Env as fn(String) -> LiteralValue),
+        ("file", LiteralValue::File),
+    ] {
+        let Some(argument) = trimmed
+            .strip_prefix(function)
+            .and_then(|rest| rest.trim_start().strip_prefix('('))
+            .and_then(|rest| rest.strip_suffix(')'))
+        else {
+            continue;
+        };
+        return match parse_literal_at(argument, depth + 1)? {
+            LiteralValue::String(argument) if !argument.is_empty() => Ok(build(argument)),
+            _ => Err(ParseError::InvalidSyntax(format!(
+                "{function}() expects a string argument: {trimmed}"
+            ))),
+        };
+    }
+    if let Some(expression) = parse_arithmetic(trimmed, depth)? {
+        return Ok(expression);
+    }
     if let Some(num) = parse_number_literal(trimmed) {
-        return Ok(LiteralValue::Number(num));
+        return Ok(num);
     }
     if let Some(stripped) = trimmed
         .strip_prefix('"')
@@ -1089,7 +2825,103 @@ fn parse_literal(value: &str) -> Result<LiteralValue, ParseError> {
     Ok(LiteralValue::String(trimmed.to_string()))
 }
 
-fn parse_array_literal(value: &str) -> Result<LiteralValue, ParseError> {
+/// Operators need surrounding spaces so that `-1`, `a/b` paths and `*.txt` globs stay plain
+/// values. Anything whose operands are not numbers, quoted strings or variable paths is left
+/// to the string fallback.
+fn parse_arithmetic(value: &str, depth: usize) -> Result<Option<LiteralValue>, ParseError> {
+    let levels: [&[(&str, ArithmeticOperator)]; 2] = [
+        &[
+            (" + ", ArithmeticOperator::Add),
+            (" - ", ArithmeticOperator::Sub),
+        ],
+        &[
+            (" * ", ArithmeticOperator::Mul),
+            (" / ", ArithmeticOperator::Div),
+        ],
+    ];
+    for operators in levels {
+        // The rightmost operator of the loosest level splits first, so `a - b - c` is `(a - b) - c`.
+        let Some((pos, token, operator)) = operators
+            .iter()
+            .filter_map(|(token, operator)| {
+                find_last_operator(value, token).map(|pos| (pos, *token, *operator))
+            })
+            .max_by_key(|(pos, _, _)| *pos)
+        else {
+            continue;
+        };
+        let (Some(left), Some(right)) = (
+            parse_arithmetic_operand(&value[..pos], depth + 1)?,
+            parse_arithmetic_operand(&value[pos + token.len()..], depth + 1)?,
+        ) else {
+            return Ok(None);
+        };
+        if operator == ArithmeticOperator::Div
+            && matches!(&right, ConditionOperand::Literal(divisor) if divisor.as_f64() == Some(0.0))
+        {
+            return Err(ParseError::InvalidSyntax(format!(
+                "division by zero: {value}"
+            )));
+        }
+        return Ok(Some(LiteralValue::Arithmetic {
+            operator,
+            left: Box::new(left),
+            right: Box::new(right),
+        }));
+    }
+    Ok(None)
+}
+
+fn parse_arithmetic_operand(
+    value: &str,
+    depth: usize,
+) -> Result<Option<ConditionOperand>, ParseError> {
+    check_nesting(depth)?;
+    let trimmed = value.trim();
+    if let Some(expression) = parse_arithmetic(trimmed, depth)? {
+        return Ok(Some(ConditionOperand::Literal(expression)));
+    }
+    if let Some(number) = parse_number_literal(trimmed) {
+        return Ok(Some(ConditionOperand::Literal(number)));
+    }
+    match trimmed {
+        "true" | "false" => {
+            return Ok(Some(ConditionOperand::Literal(LiteralValue::Boolean(
+                trimmed == "true",
+            ))))
+        }
+        "null" => return Ok(Some(ConditionOperand::Literal(LiteralValue::Null))),
+        _ => {}
+    }
+    for quote in ['"', '\''] {
+        if let Some(inner) = trimmed
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+            .filter(|inner| !inner.contains(quote))
+        {
+            return Ok(Some(ConditionOperand::Literal(LiteralValue::String(
+                inner.to_string(),
+            ))));
+        }
+    }
+    if is_variable_path(trimmed) {
+        return Ok(Some(ConditionOperand::Variable(trimmed.to_string())));
+    }
+    Ok(None)
+}
+
+/// Byte offset of the last top-level occurrence of `operator`.
+fn find_last_operator(input: &str, operator: &str) -> Option<usize> {
+    let mut last = None;
+    let mut offset = 0;
+    while let Some(pos) = find_operator(&input[offset..], operator) {
+        last = Some(offset + pos);
+        offset += pos + operator.len();
+    }
+    last
+}
+
+fn parse_array_literal(value: &str, depth: usize) -> Result<LiteralValue, ParseError> {
     if !value.ends_with(']') {
         return Err(ParseError::InvalidSyntax(value.to_string()));
     }
@@ -1100,12 +2932,12 @@ fn parse_array_literal(value: &str) -> Result<LiteralValue, ParseError> {
         if trimmed.is_empty() {
             continue;
         }
-        items.push(parse_literal(trimmed)?);
+        items.push(parse_literal_at(trimmed, depth + 1)?);
     }
     Ok(LiteralValue::Array(items))
 }
 
-fn parse_object_literal(value: &str) -> Result<LiteralValue, ParseError> {
+fn parse_object_literal(value: &str, depth: usize) -> Result<LiteralValue, ParseError> {
     if !value.ends_with('}') {
         return Err(ParseError::InvalidSyntax(value.to_string()));
     }
@@ -1118,19 +2950,24 @@ fn parse_object_literal(value: &str) -> Result<LiteralValue, ParseError> {
         }
         let (raw_key, raw_value) = split_key_value(trimmed)?;
         let key = parse_object_key(raw_key)?;
-        let value = parse_literal(raw_value)?;
+        let value = parse_literal_at(raw_value, depth + 1)?;
         map.insert(key, value);
     }
     Ok(LiteralValue::Object(map))
 }
 
-fn parse_number_literal(value: &str) -> Option<f64> {
+fn parse_number_literal(value: &str) -> Option<LiteralValue> {
     if value
         .chars()
         .all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+'))
     {
+        if !value.contains('.') {
+            if let Ok(integer) = value.parse::<i64>() {
+                return Some(LiteralValue::Integer(integer));
+            }
+        }
         if let Ok(number) = value.parse::<f64>() {
-            return Some(number);
+            return Some(LiteralValue::Number(number));
         }
     }
     None
@@ -1183,7 +3020,8 @@ fn find_operator(input: &str, operator: &str) -> Option<usize> {
                 }
             }
             if matches {
-                return Some(idx);
+                // Callers slice with the result, so return a byte offset.
+                return input.char_indices().nth(idx).map(|(byte, _)| byte);
             }
         }
         idx += 1;
@@ -1195,15 +3033,20 @@ fn is_identifier(value: &str) -> bool {
     !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
 }
 
+/// Accepts plain identifiers as well as dotted paths such as `scan_web.findings.length`.
+fn is_variable_path(value: &str) -> bool {
+    !value.is_empty() && value.split('.').all(is_identifier)
+}
+
 fn split_top_level(input: &str, delimiter: char) -> Result<Vec<&str>, ParseError> {
     let mut items = Vec::new();
     let mut depth = 0i32;
     let mut in_quote: Option<char> = None;
     let mut start = 0usize;
-    let chars: Vec<char> = input.chars().collect();
+    let chars: Vec<(usize, char)> = input.char_indices().collect();
     let mut idx = 0usize;
     while idx < chars.len() {
-        let c = chars[idx];
+        let (byte, c) = chars[idx];
         if let Some(q) = in_quote {
             if c == '\\' {
                 idx += 1;
@@ -1221,8 +3064,8 @@ fn split_top_level(input: &str, delimiter: char) -> Result<Vec<&str>, ParseError
                     }
                 }
                 _ if c == delimiter && depth == 0 => {
-                    items.push(input[start..idx].trim());
-                    start = idx + 1;
+                    items.push(input[start..byte].trim());
+                    start = byte + c.len_utf8();
                 }
                 _ => {}
             }
@@ -1241,19 +3084,18 @@ fn split_top_level(input: &str, delimiter: char) -> Result<Vec<&str>, ParseError
 fn split_key_value(entry: &str) -> Result<(&str, &str), ParseError> {
     let mut depth = 0i32;
     let mut in_quote: Option<char> = None;
-    let chars: Vec<char> = entry.chars().collect();
-    for (idx, c) in chars.iter().enumerate() {
+    for (idx, c) in entry.char_indices() {
         if let Some(q) = in_quote {
-            if *c == '\\' {
+            if c == '\\' {
                 continue;
             }
-            if *c == q {
+            if c == q {
                 in_quote = None;
             }
             continue;
         }
         match c {
-            '"' | '\'' => in_quote = Some(*c),
+            '"' | '\'' => in_quote = Some(c),
             '[' | '{' => depth += 1,
             ']' | '}' => depth -= 1,
             ':' if depth == 0 => {
@@ -1284,6 +3126,7 @@ impl LiteralValue {
     pub fn to_json(&self) -> JsonValue {
         match self {
             LiteralValue::String(s) => JsonValue::String(s.clone()),
+            LiteralValue::Integer(n) => JsonValue::Number(serde_json::Number::from(*n)),
             LiteralValue::Number(n) => JsonValue::from(*n),
             LiteralValue::Boolean(b) => JsonValue::Bool(*b),
             LiteralValue::Array(items) => {
@@ -1296,12 +3139,105 @@ impl LiteralValue {
                 }
                 JsonValue::Object(obj)
             }
+            LiteralValue::Null => JsonValue::Null,
+            LiteralValue::Env(_) | LiteralValue::File(_) | LiteralValue::Arithmetic { .. } => {
+                JsonValue::String(self.to_dsl())
+            }
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            LiteralValue::String(_) => "string",
+            // Integers are numbers to the type checks; only the representation differs.
+            LiteralValue::Integer(_) | LiteralValue::Number(_) => "number",
+            LiteralValue::Boolean(_) => "boolean",
+            LiteralValue::Array(_) => "array",
+            LiteralValue::Object(_) => "object",
+            LiteralValue::Null => "null",
+            LiteralValue::Env(_) => "env()",
+            LiteralValue::File(_) => "file()",
+            LiteralValue::Arithmetic { .. } => "expression",
+        }
+    }
+
+    /// The numeric value of an integer or float, `None` for anything else.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            LiteralValue::Integer(n) => Some(*n as f64),
+            LiteralValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Numeric addition, or concatenation when both sides are strings.
+    pub fn add(&self, other: &LiteralValue) -> Result<LiteralValue, String> {
+        if let (LiteralValue::String(a), LiteralValue::String(b)) = (self, other) {
+            return Ok(LiteralValue::String(format!("{a}{b}")));
+        }
+        self.numeric_op(
+            other,
+            ArithmeticOperator::Add,
+            Some(i64::checked_add),
+            |a, b| a + b,
+        )
+    }
+
+    pub fn sub(&self, other: &LiteralValue) -> Result<LiteralValue, String> {
+        self.numeric_op(
+            other,
+            ArithmeticOperator::Sub,
+            Some(i64::checked_sub),
+            |a, b| a - b,
+        )
+    }
+
+    pub fn mul(&self, other: &LiteralValue) -> Result<LiteralValue, String> {
+        self.numeric_op(
+            other,
+            ArithmeticOperator::Mul,
+            Some(i64::checked_mul),
+            |a, b| a * b,
+        )
+    }
+
+    /// Division always yields a float, so `7 / 2` is `3.5`.
+    pub fn div(&self, other: &LiteralValue) -> Result<LiteralValue, String> {
+        if other.as_f64() == Some(0.0) && self.as_f64().is_some() {
+            return Err("division by zero".to_string());
+        }
+        self.numeric_op(other, ArithmeticOperator::Div, None, |a, b| a / b)
+    }
+
+    fn numeric_op(
+        &self,
+        other: &LiteralValue,
+        operator: ArithmeticOperator,
+        integer: Option<fn(i64, i64) -> Option<i64>>,
+        float: fn(f64, f64) -> f64,
+    ) -> Result<LiteralValue, String> {
+        if let (Some(integer), LiteralValue::Integer(a), LiteralValue::Integer(b)) =
+            (integer, self, other)
+        {
+            return integer(*a, *b)
+                .map(LiteralValue::Integer)
+                .ok_or_else(|| format!("integer overflow in {a} {operator} {b}"));
+        }
+        match (self.as_f64(), other.as_f64()) {
+            (Some(a), Some(b)) => Ok(LiteralValue::Number(float(a, b))),
+            _ => Err(format!(
+                "cannot apply '{}' to {} and {}",
+                operator,
+                self.type_name(),
+                other.type_name()
+            )),
         }
     }
 
     pub fn display(&self) -> String {
         match self {
             LiteralValue::String(s) => s.clone(),
+            LiteralValue::Integer(n) => n.to_string(),
             LiteralValue::Number(n) => {
                 if (n.fract() - 0.0).abs() < f64::EPSILON {
                     format!("{:.0}", n)
@@ -1310,7 +3246,11 @@ impl LiteralValue {
                 }
             }
             LiteralValue::Boolean(b) => b.to_string(),
+            LiteralValue::Null => "null".to_string(),
             LiteralValue::Array(_) | LiteralValue::Object(_) => self.to_json().to_string(),
+            LiteralValue::Env(_) | LiteralValue::File(_) | LiteralValue::Arithmetic { .. } => {
+                self.to_dsl()
+            }
         }
     }
 }
@@ -1321,295 +3261,2147 @@ impl fmt::Display for LiteralValue {
     }
 }
 
+impl ConditionExpr {
+    pub fn variable_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_variable_names(&mut names);
+        names
+    }
+
+    fn collect_variable_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        let push_operand = |operand: &'a ConditionOperand, names: &mut Vec<&'a str>| {
+            if let ConditionOperand::Variable(name) = operand {
+                names.push(name.as_str());
+            }
+        };
+        match self {
+            ConditionExpr::Literal(_) | ConditionExpr::Defined(_) => {}
+            ConditionExpr::Variable(name) => names.push(name.as_str()),
+            ConditionExpr::Not(inner) => inner.collect_variable_names(names),
+            ConditionExpr::And(left, right) | ConditionExpr::Or(left, right) => {
+                left.collect_variable_names(names);
+                right.collect_variable_names(names);
+            }
+            ConditionExpr::Equals(left, right)
+            | ConditionExpr::NotEquals(left, right)
+            | ConditionExpr::LessThan(left, right)
+            | ConditionExpr::GreaterThan(left, right)
+            | ConditionExpr::LessThanOrEqual(left, right)
+            | ConditionExpr::GreaterThanOrEqual(left, right)
+            | ConditionExpr::Contains(left, right)
+            | ConditionExpr::Matches(left, right) => {
+                push_operand(left, names);
+                push_operand(right, names);
+            }
+        }
+    }
+}
+
 impl fmt::Display for ConditionExpr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ConditionExpr::Literal(value) => write!(f, "{value}"),
             ConditionExpr::Variable(name) => write!(f, "{name}"),
-            ConditionExpr::Not(inner) => write!(f, "!{}", inner),
+            ConditionExpr::Defined(name) => write!(f, "defined {name}"),
+            ConditionExpr::Not(inner) => match inner.as_ref() {
+                ConditionExpr::Literal(_)
+                | ConditionExpr::Variable(_)
+                | ConditionExpr::Defined(_) => write!(f, "!{}", inner),
+                _ => write!(f, "!({})", inner),
+            },
+            // The parser splits at the first `&&`/`||`, so chains associate to
+            // the right and a left operand using the same operator needs
+            // parentheses to parse back into the same tree.
+            ConditionExpr::And(left, right) => {
+                let left_grouped = matches!(
+                    left.as_ref(),
+                    ConditionExpr::And(..) | ConditionExpr::Or(..)
+                );
+                write_grouped(f, left, left_grouped)?;
+                write!(f, " && ")?;
+                write_grouped(f, right, matches!(right.as_ref(), ConditionExpr::Or(..)))
+            }
+            ConditionExpr::Or(left, right) => {
+                write_grouped(f, left, matches!(left.as_ref(), ConditionExpr::Or(..)))?;
+                write!(f, " || {}", right)
+            }
             ConditionExpr::Equals(left, right) => write!(f, "{} == {}", left, right),
             ConditionExpr::NotEquals(left, right) => write!(f, "{} != {}", left, right),
+            ConditionExpr::LessThan(left, right) => write!(f, "{} < {}", left, right),
+            ConditionExpr::GreaterThan(left, right) => write!(f, "{} > {}", left, right),
+            ConditionExpr::LessThanOrEqual(left, right) => write!(f, "{} <= {}", left, right),
+            ConditionExpr::GreaterThanOrEqual(left, right) => write!(f, "{} >= {}", left, right),
+            ConditionExpr::Contains(left, right) => write!(f, "{} contains {}", left, right),
+            ConditionExpr::Matches(left, right) => write!(f, "{} matches {}", left, right),
         }
     }
 }
 
+fn write_grouped(f: &mut fmt::Formatter<'_>, expr: &ConditionExpr, grouped: bool) -> fmt::Result {
+    if grouped {
+        write!(f, "({})", expr)
+    } else {
+        write!(f, "{}", expr)
+    }
+}
+
 impl fmt::Display for ConditionOperand {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ConditionOperand::Variable(name) => write!(f, "{name}"),
-            ConditionOperand::Literal(value) => write!(f, "{}", value.display()),
+            ConditionOperand::Literal(value) => write!(f, "{}", value.to_dsl()),
         }
     }
 }
 
+fn quote_string(value: &str) -> String {
+    // Escapes are not interpreted by the parser, so pick a quote style the value does not use.
+    if value.contains('"') && !value.contains('\'') {
+        format!("'{}'", value)
+    } else {
+        format!("\"{}\"", value)
+    }
+}
+
 impl fmt::Display for LoopIterable {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             LoopIterable::Variable(name) => write!(f, "{name}"),
             LoopIterable::Literal(value) => write!(f, "{}", value.display()),
+            LoopIterable::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let operator = if *inclusive { "..=" } else { ".." };
+                write!(f, "{start}{operator}{end}")
+            }
         }
     }
 }
 
-impl Scenario {
-    pub fn summary(&self) -> ScenarioSummary {
-        let import_list: BTreeSet<String> = self.imports.iter().cloned().collect();
-        let mut accumulator = SummaryAccumulator::default();
-        collect_summary_steps(&self.steps, &mut accumulator);
-        ScenarioSummary {
-            total_steps: accumulator.total_steps,
-            imports: import_list.into_iter().collect(),
-            variables: accumulator.variables,
-            secrets: accumulator.secrets,
-            asset_groups: accumulator.asset_groups,
-            scans: accumulator.scans,
-            scripts: accumulator.scripts,
-            reports: accumulator.reports,
+impl Step {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Step::AssetGroup(group) => Some(&group.name),
+            Step::Scan(scan) => Some(&scan.name),
+            Step::Variable(var) => Some(&var.name),
+            Step::Secret(secret) => Some(&secret.name),
+            Step::Script(script) => Some(&script.name),
+            Step::Report(report) => Some(&report.name),
+            Step::Parallel(block) => block.name.as_deref(),
+            Step::Assert(step) => Some(&step.name),
+            Step::Notify(step) => Some(&step.name),
+            Step::Sleep(step) => Some(&step.name),
+            Step::Merge(step) => Some(&step.output),
+            Step::Transform(step) => Some(&step.name),
+            Step::Checkpoint(step) => Some(&step.name),
+            Step::Import(_)
+            | Step::Conditional(_)
+            | Step::Loop(_)
+            | Step::Function(_)
+            | Step::Call(_)
+            | Step::Break
+            | Step::Continue
+            | Step::Comment(_) => None,
         }
     }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ScenarioSummary {
-    pub total_steps: usize,
-    pub imports: Vec<String>,
-    pub variables: Vec<VariableSummary>,
-    pub secrets: Vec<SecretSummary>,
-    pub asset_groups: Vec<AssetGroupSummary>,
-    pub scans: Vec<ScanSummary>,
-    pub scripts: Vec<ScriptSummary>,
-    pub reports: Vec<ReportSummary>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AssetGroupSummary {
-    pub name: String,
-    pub properties: BTreeMap<String, String>,
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ScanSummary {
-    pub name: String,
-    pub tool: String,
-    pub output: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ScriptSummary {
-    pub name: String,
-    pub run: String,
-    pub output: Option<String>,
-}
+    /// Tags declared on the step. Only block steps (asset groups, scans,
+    /// scripts, reports, asserts, notifications, transforms) carry tags.
+    pub fn tags(&self) -> &[String] {
+        match self {
+            Step::AssetGroup(group) => &group.tags,
+            Step::Scan(scan) => &scan.tags,
+            Step::Script(script) => &script.tags,
+            Step::Report(report) => &report.tags,
+            Step::Assert(step) => &step.tags,
+            Step::Notify(step) => &step.tags,
+            Step::Transform(step) => &step.tags,
+            _ => &[],
+        }
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ReportSummary {
-    pub name: String,
-    pub includes: Vec<String>,
-    pub format: ReportFormat,
-    pub output: Option<String>,
-    pub options: BTreeMap<String, String>,
-}
+    /// Handler steps from an `on_failure` block. Scans, scripts, reports,
+    /// asserts, notifications, and transforms can carry one.
+    pub fn on_failure(&self) -> &[Step] {
+        match self {
+            Step::Scan(scan) => &scan.on_failure,
+            Step::Script(script) => &script.on_failure,
+            Step::Report(report) => &report.on_failure,
+            Step::Assert(step) => &step.on_failure,
+            Step::Notify(step) => &step.on_failure,
+            Step::Transform(step) => &step.on_failure,
+            _ => &[],
+        }
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VariableSummary {
-    pub name: String,
-    pub value: LiteralValue,
+    fn on_failure_mut(&mut self) -> Option<&mut Vec<Step>> {
+        match self {
+            Step::Scan(scan) => Some(&mut scan.on_failure),
+            Step::Script(script) => Some(&mut script.on_failure),
+            Step::Report(report) => Some(&mut report.on_failure),
+            Step::Assert(step) => Some(&mut step.on_failure),
+            Step::Notify(step) => Some(&mut step.on_failure),
+            Step::Transform(step) => Some(&mut step.on_failure),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SecretSummary {
-    pub name: String,
-    pub provider: String,
+/// Prefixes the names of imported steps with `<alias>.` and rewrites report
+/// includes that point at artifacts produced by the renamed steps. Variables
+/// and secrets keep their names so `${...}` references continue to resolve.
+pub fn prefix_step_names(steps: &mut [Step], alias: &str) {
+    let mut renamed = BTreeMap::new();
+    rename_steps(steps, alias, &mut renamed);
+    rewrite_includes(steps, &renamed);
 }
 
-#[derive(Default)]
-struct SummaryAccumulator {
-    total_steps: usize,
-    variables: Vec<VariableSummary>,
-    secrets: Vec<SecretSummary>,
-    asset_groups: Vec<AssetGroupSummary>,
-    scans: Vec<ScanSummary>,
-    scripts: Vec<ScriptSummary>,
-    reports: Vec<ReportSummary>,
+fn rename_steps(steps: &mut [Step], alias: &str, renamed: &mut BTreeMap<String, String>) {
+    let prefixed = |name: &str| format!("{alias}.{name}");
+    for step in steps {
+        match step {
+            Step::AssetGroup(group) => {
+                let name = prefixed(&group.name);
+                renamed.insert(
+                    format!("asset_group:{}", group.name),
+                    format!("asset_group:{name}"),
+                );
+                group.name = name;
+            }
+            Step::Scan(scan) => {
+                let name = prefixed(&scan.name);
+                match &mut scan.output {
+                    Some(label) => {
+                        let new_label = prefixed(label);
+                        renamed.insert(label.clone(), new_label.clone());
+                        *label = new_label;
+                    }
+                    None => {
+                        for kind in ["scan", "findings"] {
+                            renamed
+                                .insert(format!("{kind}_{}", scan.name), format!("{kind}_{name}"));
+                        }
+                    }
+                }
+                scan.name = name;
+            }
+            Step::Script(script) => {
+                let name = prefixed(&script.name);
+                match &mut script.output {
+                    Some(label) => {
+                        let new_label = prefixed(label);
+                        renamed.insert(label.clone(), new_label.clone());
+                        *label = new_label;
+                    }
+                    None => {
+                        renamed.insert(format!("script_{}", script.name), format!("script_{name}"));
+                    }
+                }
+                script.name = name;
+            }
+            Step::Report(report) => {
+                let name = prefixed(&report.name);
+                renamed.insert(format!("report:{}", report.name), format!("report:{name}"));
+                report.name = name;
+            }
+            Step::Notify(notify) => {
+                let name = prefixed(&notify.name);
+                renamed.insert(format!("notify:{}", notify.name), format!("notify:{name}"));
+                notify.name = name;
+            }
+            Step::Assert(assert_step) => assert_step.name = prefixed(&assert_step.name),
+            Step::Sleep(sleep) => sleep.name = prefixed(&sleep.name),
+            Step::Checkpoint(checkpoint) => checkpoint.name = prefixed(&checkpoint.name),
+            Step::Merge(merge) => {
+                let output = prefixed(&merge.output);
+                renamed.insert(merge.output.clone(), output.clone());
+                merge.output = output;
+            }
+            Step::Transform(transform) => {
+                if let Some(label) = &mut transform.output {
+                    let new_label = prefixed(label);
+                    renamed.insert(label.clone(), new_label.clone());
+                    *label = new_label;
+                }
+                transform.name = prefixed(&transform.name);
+            }
+            Step::Parallel(block) => {
+                if let Some(block_name) = &mut block.name {
+                    let name = prefixed(block_name);
+                    renamed.insert(format!("parallel:{block_name}"), format!("parallel:{name}"));
+                    *block_name = name;
+                }
+                rename_steps(&mut block.steps, alias, renamed);
+            }
+            Step::Conditional(block) => {
+                rename_steps(&mut block.then_steps, alias, renamed);
+                rename_steps(&mut block.else_steps, alias, renamed);
+            }
+            Step::Loop(loop_step) => rename_steps(&mut loop_step.body, alias, renamed),
+            // Like variables, function names stay global so calls keep resolving.
+            Step::Function(function) => rename_steps(&mut function.body, alias, renamed),
+            Step::Import(_)
+            | Step::Variable(_)
+            | Step::Secret(_)
+            | Step::Call(_)
+            | Step::Break
+            | Step::Continue
+            | Step::Comment(_) => {}
+        }
+        if let Some(handlers) = step.on_failure_mut() {
+            rename_steps(handlers, alias, renamed);
+        }
+    }
 }
 
-fn collect_summary_steps(steps: &[Step], acc: &mut SummaryAccumulator) {
+fn rewrite_includes(steps: &mut [Step], renamed: &BTreeMap<String, String>) {
     for step in steps {
-        acc.total_steps += 1;
         match step {
-            Step::Import(_) => {}
-            Step::Variable(var) => acc.variables.push(VariableSummary {
-                name: var.name.clone(),
-                value: var.value.clone(),
-            }),
-            Step::Secret(secret) => acc.secrets.push(SecretSummary {
-                name: secret.name.clone(),
-                provider: match &secret.source {
-                    SecretSource::Env { .. } => "env".to_string(),
-                    SecretSource::File { .. } => "file".to_string(),
-                    SecretSource::Vault { .. } => "vault".to_string(),
-                },
-            }),
-            Step::AssetGroup(group) => acc.asset_groups.push(AssetGroupSummary {
-                name: group.name.clone(),
-                properties: group.properties.clone(),
-            }),
-            Step::Scan(scan) => acc.scans.push(ScanSummary {
-                name: scan.name.clone(),
-                tool: scan.tool.clone(),
-                output: scan.output.clone(),
-            }),
-            Step::Script(script) => {
-                if let Some(run) = script.params.get("run").cloned() {
-                    acc.scripts.push(ScriptSummary {
-                        name: script.name.clone(),
-                        run,
-                        output: script.output.clone(),
-                    });
+            Step::Report(report) => {
+                for include in &mut report.includes {
+                    if let Some(new_name) = renamed.get(include.as_str()) {
+                        *include = new_name.clone();
+                    }
                 }
             }
-            Step::Report(report) => acc.reports.push(ReportSummary {
-                name: report.name.clone(),
-                includes: report.includes.clone(),
-                format: report.format.clone(),
-                output: report.output.clone(),
-                options: report.options.clone(),
-            }),
+            Step::Merge(merge) => {
+                for input in &mut merge.inputs {
+                    if let Some(new_name) = renamed.get(input.as_str()) {
+                        *input = new_name.clone();
+                    }
+                }
+            }
+            Step::Transform(transform) => {
+                if let Some(new_name) = renamed.get(transform.source.as_str()) {
+                    transform.source = new_name.clone();
+                }
+            }
+            Step::Parallel(block) => rewrite_includes(&mut block.steps, renamed),
             Step::Conditional(block) => {
-                collect_summary_steps(&block.then_steps, acc);
-                collect_summary_steps(&block.else_steps, acc);
+                rewrite_includes(&mut block.then_steps, renamed);
+                rewrite_includes(&mut block.else_steps, renamed);
             }
-            Step::Loop(loop_step) => {
-                collect_summary_steps(&loop_step.body, acc);
+            Step::Loop(loop_step) => rewrite_includes(&mut loop_step.body, renamed),
+            Step::Function(function) => rewrite_includes(&mut function.body, renamed),
+            _ => {}
+        }
+        if let Some(handlers) = step.on_failure_mut() {
+            rewrite_includes(handlers, renamed);
+        }
+    }
+}
+
+impl Scenario {
+    /// Combines two scenarios: `b`'s steps run after `a`'s, imports keep
+    /// their first occurrence, and aliases with the same name pool their
+    /// imports. Nothing is re-ordered.
+    pub fn merge(a: Scenario, b: Scenario) -> Scenario {
+        let mut seen = BTreeSet::new();
+        let imports = a
+            .imports
+            .into_iter()
+            .chain(b.imports)
+            .filter(|import| seen.insert(import.clone()))
+            .collect();
+
+        let mut aliases: Vec<ImportAlias> = Vec::new();
+        for alias in a.aliases.into_iter().chain(b.aliases) {
+            match aliases.iter_mut().find(|entry| entry.alias == alias.alias) {
+                Some(entry) => {
+                    for import in alias.imports {
+                        if !entry.imports.contains(&import) {
+                            entry.imports.push(import);
+                        }
+                    }
+                }
+                None => aliases.push(alias),
+            }
+        }
+
+        let mut steps = a.steps;
+        steps.extend(b.steps);
+        Scenario {
+            steps,
+            imports,
+            aliases,
+        }
+    }
+
+    /// Returns a scenario with only the top-level steps for which `f` holds.
+    /// Imports and aliases are kept, since they record where the steps came
+    /// from rather than what runs.
+    pub fn filter_steps(&self, f: impl Fn(&Step) -> bool) -> Scenario {
+        Scenario {
+            steps: self.steps.iter().filter(|step| f(step)).cloned().collect(),
+            imports: self.imports.clone(),
+            aliases: self.aliases.clone(),
+        }
+    }
+
+    pub fn summary(&self) -> ScenarioSummary {
+        let import_list: BTreeSet<String> = self.imports.iter().cloned().collect();
+        let mut accumulator = SummaryAccumulator::default();
+        collect_summary_steps(&self.steps, &mut accumulator);
+        let mut import_aliases: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for alias in &self.aliases {
+            let entry = import_aliases.entry(alias.alias.clone()).or_default();
+            entry.extend(alias.imports.iter().cloned());
+            entry.sort();
+            entry.dedup();
+        }
+        ScenarioSummary {
+            total_steps: accumulator.total_steps,
+            imports: import_list.into_iter().collect(),
+            import_aliases,
+            variables: accumulator.variables,
+            secrets: accumulator.secrets,
+            asset_groups: accumulator.asset_groups,
+            scans: accumulator.scans,
+            scripts: accumulator.scripts,
+            reports: accumulator.reports,
+            sleeps: accumulator.sleeps,
+            merges: accumulator.merges,
+            transforms: accumulator.transforms,
+            checkpoints: accumulator.checkpoints,
+            on_failure_handlers: accumulator.on_failure_handlers,
+            functions: accumulator
+                .functions
+                .into_iter()
+                .map(|mut function| {
+                    function.call_sites = accumulator
+                        .calls
+                        .iter()
+                        .filter(|(callee, _)| *callee == function.name)
+                        .map(|(_, site)| site.clone())
+                        .collect();
+                    function
+                })
+                .collect(),
+        }
+    }
+
+    /// Call cycles among top-level functions, each as the path that closes
+    /// it (`["a", "b", "a"]`). A function calling itself yields `["a", "a"]`.
+    pub fn function_cycles(&self) -> Vec<Vec<String>> {
+        let graph: BTreeMap<&str, Vec<&str>> = self
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                Step::Function(function) => {
+                    let mut calls = Vec::new();
+                    collect_calls(&function.body, &mut calls);
+                    calls.sort_unstable();
+                    calls.dedup();
+                    Some((function.name.as_str(), calls))
+                }
+                _ => None,
+            })
+            .collect();
+
+        fn visit<'a>(
+            name: &'a str,
+            graph: &BTreeMap<&'a str, Vec<&'a str>>,
+            path: &mut Vec<&'a str>,
+            done: &mut BTreeSet<&'a str>,
+            cycles: &mut Vec<Vec<String>>,
+        ) {
+            if let Some(start) = path.iter().position(|entry| *entry == name) {
+                let mut cycle: Vec<String> = path[start..].iter().map(|s| s.to_string()).collect();
+                cycle.push(name.to_string());
+                cycles.push(cycle);
+                return;
+            }
+            if done.contains(name) {
+                return;
+            }
+            path.push(name);
+            for callee in graph.get(name).into_iter().flatten() {
+                visit(callee, graph, path, done, cycles);
+            }
+            path.pop();
+            done.insert(name);
+        }
+
+        let mut cycles = Vec::new();
+        let mut done = BTreeSet::new();
+        for name in graph.keys() {
+            visit(name, &graph, &mut Vec::new(), &mut done, &mut cycles);
+        }
+        cycles
+    }
+
+    /// Renders the scenario as canonical DSL source: two-space indentation,
+    /// one directive per line, and top-level steps separated by a blank line
+    /// unless they are consecutive one-line steps of the same kind (a run of
+    /// `let`s stays together). Comments stay attached to the step below them,
+    /// and a blank line after a comment is kept. Parsing the result yields the
+    /// same steps; other layout is not preserved.
+    pub fn to_dsl(&self) -> String {
+        let mut out = String::new();
+        let mut previous: Option<(&Step, bool)> = None;
+        for step in &self.steps {
+            let rendered = step.to_dsl();
+            let multiline = rendered.trim_end().contains('\n');
+            if let Some((previous_step, previous_multiline)) = previous {
+                let separated = match (previous_step, step) {
+                    (Step::Comment(comment), _) => comment.detached,
+                    (_, Step::Comment(_)) => true,
+                    _ => {
+                        multiline
+                            || previous_multiline
+                            || std::mem::discriminant(previous_step) != std::mem::discriminant(step)
+                    }
+                };
+                if separated {
+                    out.push('\n');
+                }
+            }
+            out.push_str(&rendered);
+            previous = Some((step, multiline));
+        }
+        out
+    }
+}
+
+impl Step {
+    /// Renders a single step as DSL source, ending with a newline.
+    pub fn to_dsl(&self) -> String {
+        let mut out = String::new();
+        write_step_dsl(&mut out, self, 0);
+        out
+    }
+}
+
+impl LiteralValue {
+    /// Renders the value as a DSL literal accepted by `let` and conditions.
+    /// Unlike [`LiteralValue::display`], strings are quoted.
+    pub fn to_dsl(&self) -> String {
+        match self {
+            LiteralValue::String(value) => quote_string(value),
+            // Keep the decimal point so whole floats do not reparse as integers.
+            LiteralValue::Number(n) if n.fract() == 0.0 => format!("{n:.1}"),
+            LiteralValue::Integer(_)
+            | LiteralValue::Number(_)
+            | LiteralValue::Boolean(_)
+            | LiteralValue::Null => self.display(),
+            LiteralValue::Array(items) => {
+                let items: Vec<String> = items.iter().map(LiteralValue::to_dsl).collect();
+                format!("[{}]", items.join(", "))
+            }
+            LiteralValue::Object(map) => {
+                let entries: Vec<String> = map
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = if is_identifier(key) {
+                            key.clone()
+                        } else {
+                            quote_string(key)
+                        };
+                        format!("{key}: {}", value.to_dsl())
+                    })
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            LiteralValue::Env(name) => format!("env({})", quote_string(name)),
+            LiteralValue::File(path) => format!("file({})", quote_string(path)),
+            LiteralValue::Arithmetic {
+                operator,
+                left,
+                right,
+            } => format!("{left} {operator} {right}"),
+        }
+    }
+}
+
+const DSL_INDENT: &str = "  ";
+
+fn write_dsl_line(out: &mut String, depth: usize, text: &str) {
+    for _ in 0..depth {
+        out.push_str(DSL_INDENT);
+    }
+    out.push_str(text);
+    out.push('\n');
+}
+
+fn write_tags_dsl(out: &mut String, depth: usize, tags: &[String], comments: &BlockComments) {
+    if tags.is_empty() {
+        return;
+    }
+    write_block_comments_dsl(out, depth, comments, "tags");
+    let tags: Vec<String> = tags.iter().map(|tag| quote_string(tag)).collect();
+    write_dsl_line(out, depth, &format!("tags [{}]", tags.join(", ")));
+}
+
+fn write_retry_dsl(
+    out: &mut String,
+    depth: usize,
+    retry: Option<&RetryPolicy>,
+    comments: &BlockComments,
+) {
+    let Some(retry) = retry else {
+        return;
+    };
+    write_block_comments_dsl(out, depth, comments, "retry");
+    if retry.delay_ms == 0 && retry.backoff == RetryBackoff::None {
+        write_dsl_line(out, depth, &format!("retry {}", retry.count));
+        return;
+    }
+    write_dsl_line(out, depth, "retry {");
+    write_dsl_line(out, depth + 1, &format!("count {}", retry.count));
+    if retry.delay_ms > 0 {
+        write_dsl_line(
+            out,
+            depth + 1,
+            &format!(
+                "delay {}",
+                quote_string(&format_duration_ms(retry.delay_ms))
+            ),
+        );
+    }
+    write_dsl_line(
+        out,
+        depth + 1,
+        &format!("backoff {}", retry.backoff.as_str()),
+    );
+    write_dsl_line(out, depth, "}");
+}
+
+fn write_steps_dsl(out: &mut String, steps: &[Step], depth: usize) {
+    for step in steps {
+        write_step_dsl(out, step, depth);
+    }
+}
+
+fn write_step_dsl(out: &mut String, step: &Step, depth: usize) {
+    let inner = depth + 1;
+    match step {
+        Step::Import(import) => {
+            let mut line = format!("import {}", quote_string(&import.path));
+            if let Some(alias) = &import.alias {
+                line.push_str(&format!(" as {alias}"));
+            }
+            write_dsl_line(out, depth, &line);
+        }
+        Step::AssetGroup(group) => {
+            let comments = &group.comments;
+            write_dsl_line(out, depth, &format!("asset_group {} {{", group.name));
+            write_tags_dsl(out, inner, &group.tags, comments);
+            for (key, value) in &group.properties {
+                write_block_comments_dsl(out, inner, comments, key);
+                write_dsl_line(out, inner, &format!("{key} {}", quote_string(value)));
+            }
+            write_closing_dsl(out, depth, "}", comments, |key| {
+                (key == "tags" && !group.tags.is_empty()) || group.properties.contains_key(key)
+            });
+        }
+        Step::Scan(scan) => {
+            let comments = &scan.comments;
+            write_dsl_line(out, depth, &format!("scan {} {} {{", scan.name, scan.tool));
+            write_tags_dsl(out, inner, &scan.tags, comments);
+            write_block_params_dsl(out, inner, &scan.params, comments);
+            write_retry_dsl(out, inner, scan.retry.as_ref(), comments);
+            let closing = closing_with_output(scan.output.as_deref());
+            write_closing_dsl(out, depth, &closing, comments, |key| match key {
+                "tags" => !scan.tags.is_empty(),
+                "retry" => scan.retry.is_some(),
+                _ => scan.params.contains_key(key),
+            });
+        }
+        Step::Variable(var) => {
+            write_dsl_line(
+                out,
+                depth,
+                &format!("let {} = {}", var.name, var.value.to_dsl()),
+            );
+        }
+        Step::Secret(secret) => write_secret_dsl(out, secret, depth),
+        Step::Script(script) => {
+            let comments = &script.comments;
+            write_dsl_line(out, depth, &format!("script {} {{", script.name));
+            write_tags_dsl(out, inner, &script.tags, comments);
+            write_block_params_dsl(out, inner, &script.params, comments);
+            if !script.env.is_empty() {
+                write_block_comments_dsl(out, inner, comments, "env");
+                write_dsl_line(out, inner, "env {");
+                for (name, value) in &script.env {
+                    write_dsl_line(out, inner + 1, &format!("{name} = {}", quote_string(value)));
+                }
+                write_dsl_line(out, inner, "}");
+            }
+            write_retry_dsl(out, inner, script.retry.as_ref(), comments);
+            let closing = closing_with_output(script.output.as_deref());
+            write_closing_dsl(out, depth, &closing, comments, |key| match key {
+                "tags" => !script.tags.is_empty(),
+                "retry" => script.retry.is_some(),
+                "env" => !script.env.is_empty(),
+                _ => script.params.contains_key(key),
+            });
+        }
+        Step::Report(report) => {
+            let implied = ReportFormat::from_str(&report.name).unwrap_or(ReportFormat::Stdout);
+            let header = if implied == report.format {
+                format!("report {} {{", report.name)
+            } else {
+                format!("report {} using {} {{", report.name, report.format)
+            };
+            let comments = &report.comments;
+            write_dsl_line(out, depth, &header);
+            write_tags_dsl(out, inner, &report.tags, comments);
+            for include in &report.includes {
+                let line = format!("include {include}");
+                write_block_comments_dsl(out, inner, comments, &line);
+                write_dsl_line(out, inner, &line);
+            }
+            if let Some(output) = &report.output {
+                write_block_comments_dsl(out, inner, comments, "output");
+                write_dsl_line(out, inner, &format!("output {}", quote_string(output)));
+            }
+            for (key, value) in &report.options {
+                write_block_comments_dsl(out, inner, comments, &format!("option {key}"));
+                let line = if key == "filter" && parse_condition_expr(value).is_ok() {
+                    format!("filter {value}")
+                } else if value.contains('=') {
+                    format!("option {key} = {}", quote_string(value))
+                } else {
+                    format!("option {key} {}", quote_string(value))
+                };
+                write_dsl_line(out, inner, &line);
+            }
+            write_closing_dsl(out, depth, "}", comments, |key| match key {
+                "tags" => !report.tags.is_empty(),
+                "output" => report.output.is_some(),
+                _ => {
+                    key.strip_prefix("include ")
+                        .is_some_and(|include| report.includes.iter().any(|i| i == include))
+                        || key
+                            .strip_prefix("option ")
+                            .is_some_and(|option| report.options.contains_key(option))
+                }
+            });
+        }
+        Step::Conditional(conditional) => write_conditional_dsl(out, conditional, depth, ""),
+        Step::Loop(loop_step) => {
+            let iterable = match &loop_step.iterable {
+                LoopIterable::Literal(value) => value.to_dsl(),
+                other => other.to_string(),
+            };
+            let mut header = format!("for {} in {iterable}", loop_step.bindings());
+            if let Some(guard) = &loop_step.guard {
+                header.push_str(&format!(" when {guard}"));
+            }
+            header.push_str(" {");
+            write_dsl_line(out, depth, &header);
+            if let Some(limit) = loop_step.max_iter {
+                write_dsl_line(out, inner, &format!("option max_iter {limit}"));
+            }
+            write_steps_dsl(out, &loop_step.body, inner);
+            write_dsl_line(out, depth, "}");
+        }
+        Step::Parallel(block) => {
+            let header = match &block.name {
+                Some(name) => format!("parallel {name} {{"),
+                None => "parallel {".to_string(),
+            };
+            write_dsl_line(out, depth, &header);
+            write_steps_dsl(out, &block.steps, inner);
+            write_dsl_line(out, depth, "}");
+        }
+        Step::Assert(assert_step) => {
+            write_dsl_line(out, depth, &format!("assert {} {{", assert_step.name));
+            write_tags_dsl(out, inner, &assert_step.tags);
+            write_dsl_line(out, inner, &format!("condition {}", assert_step.condition));
+            if let Some(message) = &assert_step.message {
+                write_dsl_line(out, inner, &format!("message {}", quote_string(message)));
+            }
+            write_dsl_line(out, depth, "}");
+        }
+        Step::Notify(notify) => {
+            write_dsl_line(out, depth, &format!("notify {} {{", notify.name));
+            write_tags_dsl(out, inner, &notify.tags);
+            write_dsl_line(out, inner, &format!("url {}", quote_string(&notify.url)));
+            write_dsl_line(out, inner, &format!("method {}", notify.method));
+            if let Some(body) = &notify.body {
+                write_dsl_line(out, inner, &format!("body {}", quote_string(body)));
+            }
+            if !notify.headers.is_empty() {
+                write_dsl_line(out, inner, "headers {");
+                for (name, value) in &notify.headers {
+                    write_dsl_line(out, inner + 1, &format!("{name} {}", quote_string(value)));
+                }
+                write_dsl_line(out, inner, "}");
+            }
+            write_dsl_line(out, depth, "}");
+        }
+        Step::Sleep(sleep) => {
+            write_dsl_line(
+                out,
+                depth,
+                &format!(
+                    "sleep {} {}",
+                    sleep.name,
+                    format_duration_ms(sleep.duration_ms)
+                ),
+            );
+        }
+        Step::Checkpoint(checkpoint) => {
+            write_dsl_line(out, depth, &format!("checkpoint {}", checkpoint.name));
+        }
+        Step::Merge(merge) => {
+            let inputs: Vec<_> = merge
+                .inputs
+                .iter()
+                .map(|input| shell_words::quote(input))
+                .collect();
+            write_dsl_line(
+                out,
+                depth,
+                &format!(
+                    "merge_artifacts {} -> {}",
+                    inputs.join(" "),
+                    shell_words::quote(&merge.output)
+                ),
+            );
+        }
+        Step::Transform(transform) => {
+            let output = transform
+                .output
+                .as_ref()
+                .map(|output| format!(" -> {output}"))
+                .unwrap_or_default();
+            write_dsl_line(
+                out,
+                depth,
+                &format!(
+                    "transform {} from {}{} {{",
+                    transform.name, transform.source, output
+                ),
+            );
+            write_tags_dsl(out, inner, &transform.tags);
+            for operation in &transform.operations {
+                write_dsl_line(out, inner, &operation.to_string());
+            }
+            write_dsl_line(out, depth, "}");
+        }
+        Step::Function(function) => {
+            write_dsl_line(
+                out,
+                depth,
+                &format!("func {}({}) {{", function.name, function.params.join(", ")),
+            );
+            write_steps_dsl(out, &function.body, inner);
+            write_dsl_line(out, depth, "}");
+        }
+        Step::Call(call) => {
+            let args: Vec<String> = call.args.iter().map(ToString::to_string).collect();
+            write_dsl_line(
+                out,
+                depth,
+                &format!("call {}({})", call.function, args.join(", ")),
+            );
+        }
+        Step::Break => write_dsl_line(out, depth, "break"),
+        Step::Continue => write_dsl_line(out, depth, "continue"),
+        Step::Comment(comment) => write_dsl_line(out, depth, &comment.text),
+    }
+    let handlers = step.on_failure();
+    if !handlers.is_empty() {
+        write_dsl_line(out, depth, "on_failure {");
+        write_steps_dsl(out, handlers, inner);
+        write_dsl_line(out, depth, "}");
+    }
+}
+
+// `lead` carries the `} else ` of an enclosing conditional so `else if`
+// chains render on one line.
+fn write_conditional_dsl(out: &mut String, step: &ConditionalStep, depth: usize, lead: &str) {
+    write_dsl_line(out, depth, &format!("{lead}if {} {{", step.condition));
+    write_steps_dsl(out, &step.then_steps, depth + 1);
+    match step.else_steps.as_slice() {
+        [] => write_dsl_line(out, depth, "}"),
+        [Step::Conditional(nested)] => write_conditional_dsl(out, nested, depth, "} else "),
+        steps => {
+            write_dsl_line(out, depth, "} else {");
+            write_steps_dsl(out, steps, depth + 1);
+            write_dsl_line(out, depth, "}");
+        }
+    }
+}
+
+fn write_secret_dsl(out: &mut String, secret: &SecretStep, depth: usize) {
+    let header = format!("secret {} from", secret.name);
+    match &secret.source {
+        SecretSource::Env { mappings } => {
+            write_dsl_line(out, depth, &format!("{header} env {{"));
+            for (alias, variable) in mappings {
+                write_dsl_line(
+                    out,
+                    depth + 1,
+                    &format!("{alias} = {}", quote_string(variable)),
+                );
+            }
+            write_dsl_line(out, depth, "}");
+        }
+        SecretSource::File { path, key: None } => {
+            write_dsl_line(out, depth, &format!("{header} file {}", quote_string(path)));
+        }
+        SecretSource::File {
+            path,
+            key: Some(key),
+        } => {
+            write_dsl_line(out, depth, &format!("{header} file {{"));
+            write_dsl_line(out, depth + 1, &format!("path = {}", quote_string(path)));
+            write_dsl_line(out, depth + 1, &format!("key = {}", quote_string(key)));
+            write_dsl_line(out, depth, "}");
+        }
+        SecretSource::Vault {
+            path,
+            field,
+            namespace,
+            mount,
+            address,
+            role_id,
+            secret_id,
+        } => {
+            write_dsl_line(out, depth, &format!("{header} vault {{"));
+            write_dsl_line(out, depth + 1, &format!("path = {}", quote_string(path)));
+            for (key, value) in [
+                ("field", field),
+                ("namespace", namespace),
+                ("mount", mount),
+                ("address", address),
+                ("role_id", role_id),
+                ("secret_id", secret_id),
+            ] {
+                if let Some(value) = value {
+                    write_dsl_line(out, depth + 1, &format!("{key} = {}", quote_string(value)));
+                }
+            }
+            write_dsl_line(out, depth, "}");
+        }
+        SecretSource::AwsSecretsManager {
+            secret_id,
+            region,
+            key,
+        } => {
+            write_dsl_line(out, depth, &format!("{header} aws_secrets_manager {{"));
+            write_dsl_line(
+                out,
+                depth + 1,
+                &format!("secret_id = {}", quote_string(secret_id)),
+            );
+            for (name, value) in [("region", region), ("key", key)] {
+                if let Some(value) = value {
+                    write_dsl_line(out, depth + 1, &format!("{name} = {}", quote_string(value)));
+                }
+            }
+            write_dsl_line(out, depth, "}");
+        }
+        SecretSource::Keyring { service, account } => {
+            write_dsl_line(out, depth, &format!("{header} keyring {{"));
+            write_dsl_line(
+                out,
+                depth + 1,
+                &format!("service = {}", quote_string(service)),
+            );
+            write_dsl_line(
+                out,
+                depth + 1,
+                &format!("account = {}", quote_string(account)),
+            );
+            write_dsl_line(out, depth, "}");
+        }
+    }
+}
+
+// Scan and script parameters fall back to a heredoc when the value spans
+// lines or would otherwise open a triple-quoted string.
+fn write_block_params_dsl(
+    out: &mut String,
+    depth: usize,
+    params: &BTreeMap<String, String>,
+    comments: &BlockComments,
+) {
+    for (key, value) in params {
+        write_block_comments_dsl(out, depth, comments, key);
+        let quoted = format!("\"{value}\"");
+        if !value.contains('\n') && !quoted.starts_with(TRIPLE_QUOTE) {
+            write_dsl_line(out, depth, &format!("{key} {quoted}"));
+            continue;
+        }
+        let mut marker = "EOF".to_string();
+        let mut suffix = 0;
+        while value.split('\n').any(|line| line.trim() == marker) {
+            suffix += 1;
+            marker = format!("EOF{suffix}");
+        }
+        write_dsl_line(out, depth, &format!("{key} <<{marker}"));
+        out.push_str(value);
+        out.push('\n');
+        out.push_str(&marker);
+        out.push('\n');
+    }
+}
+
+fn write_block_comments_dsl(out: &mut String, depth: usize, comments: &BlockComments, key: &str) {
+    for comment in comments.get(key).into_iter().flatten() {
+        write_dsl_line(out, depth, comment);
+    }
+}
+
+// Comments keyed to an entry that was not written (such as an empty `env`
+// block) go above the closing line with those before `}`, so reformatting
+// never loses them.
+fn write_closing_dsl(
+    out: &mut String,
+    depth: usize,
+    closing: &str,
+    comments: &BlockComments,
+    written: impl Fn(&str) -> bool,
+) {
+    for (key, group) in comments {
+        if key != "}" && !written(key) {
+            for comment in group {
+                write_dsl_line(out, depth + 1, comment);
+            }
+        }
+    }
+    write_block_comments_dsl(out, depth + 1, comments, "}");
+    write_dsl_line(out, depth, closing);
+}
+
+fn closing_with_output(output: Option<&str>) -> String {
+    match output {
+        Some(label) => format!("}} -> {label}"),
+        None => "}".to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioSummary {
+    pub total_steps: usize,
+    pub imports: Vec<String>,
+    pub import_aliases: BTreeMap<String, Vec<String>>,
+    pub variables: Vec<VariableSummary>,
+    pub secrets: Vec<SecretSummary>,
+    pub asset_groups: Vec<AssetGroupSummary>,
+    pub scans: Vec<ScanSummary>,
+    pub scripts: Vec<ScriptSummary>,
+    pub reports: Vec<ReportSummary>,
+    pub sleeps: Vec<SleepSummary>,
+    pub merges: Vec<MergeSummary>,
+    #[serde(default)]
+    pub transforms: Vec<TransformSummary>,
+    #[serde(default)]
+    pub checkpoints: Vec<String>,
+    #[serde(default)]
+    pub functions: Vec<FunctionSummary>,
+    /// Steps that carry an `on_failure` block.
+    #[serde(default)]
+    pub on_failure_handlers: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetGroupSummary {
+    pub name: String,
+    pub properties: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanSummary {
+    pub name: String,
+    pub tool: String,
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptSummary {
+    pub name: String,
+    pub run: String,
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportSummary {
+    pub name: String,
+    pub includes: Vec<String>,
+    pub format: ReportFormat,
+    pub output: Option<String>,
+    pub options: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariableSummary {
+    pub name: String,
+    pub value: LiteralValue,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SleepSummary {
+    pub name: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionSummary {
+    pub name: String,
+    pub params: Vec<String>,
+    /// One entry per `call`: `main` for calls outside any function,
+    /// otherwise the calling function's name.
+    pub call_sites: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeSummary {
+    pub inputs: Vec<String>,
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformSummary {
+    pub name: String,
+    pub source: String,
+    pub output: String,
+    pub operations: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretSummary {
+    pub name: String,
+    pub provider: String,
+}
+
+#[derive(Default)]
+struct SummaryAccumulator {
+    total_steps: usize,
+    variables: Vec<VariableSummary>,
+    secrets: Vec<SecretSummary>,
+    asset_groups: Vec<AssetGroupSummary>,
+    scans: Vec<ScanSummary>,
+    scripts: Vec<ScriptSummary>,
+    reports: Vec<ReportSummary>,
+    sleeps: Vec<SleepSummary>,
+    merges: Vec<MergeSummary>,
+    transforms: Vec<TransformSummary>,
+    checkpoints: Vec<String>,
+    functions: Vec<FunctionSummary>,
+    on_failure_handlers: usize,
+    // (callee, call site) pairs, attached to `functions` once all are known.
+    calls: Vec<(String, String)>,
+    current_function: Option<String>,
+}
+
+fn collect_calls<'a>(steps: &'a [Step], calls: &mut Vec<&'a str>) {
+    for step in steps {
+        match step {
+            Step::Call(call) => calls.push(&call.function),
+            Step::Conditional(block) => {
+                collect_calls(&block.then_steps, calls);
+                collect_calls(&block.else_steps, calls);
+            }
+            Step::Loop(loop_step) => collect_calls(&loop_step.body, calls),
+            Step::Parallel(block) => collect_calls(&block.steps, calls),
+            Step::Function(function) => collect_calls(&function.body, calls),
+            _ => {}
+        }
+        collect_calls(step.on_failure(), calls);
+    }
+}
+
+fn collect_summary_steps(steps: &[Step], acc: &mut SummaryAccumulator) {
+    for step in steps {
+        if matches!(step, Step::Comment(_)) {
+            continue;
+        }
+        acc.total_steps += 1;
+        match step {
+            Step::Import(_) | Step::Comment(_) => {}
+            Step::Variable(var) => acc.variables.push(VariableSummary {
+                name: var.name.clone(),
+                value: var.value.clone(),
+            }),
+            Step::Secret(secret) => acc.secrets.push(SecretSummary {
+                name: secret.name.clone(),
+                provider: match &secret.source {
+                    SecretSource::Env { .. } => "env".to_string(),
+                    SecretSource::File { .. } => "file".to_string(),
+                    SecretSource::Vault { .. } => "vault".to_string(),
+                    SecretSource::AwsSecretsManager { .. } => "aws_secrets_manager".to_string(),
+                    SecretSource::Keyring { .. } => "keyring".to_string(),
+                },
+            }),
+            Step::AssetGroup(group) => acc.asset_groups.push(AssetGroupSummary {
+                name: group.name.clone(),
+                properties: group.properties.clone(),
+            }),
+            Step::Scan(scan) => acc.scans.push(ScanSummary {
+                name: scan.name.clone(),
+                tool: scan.tool.clone(),
+                output: scan.output.clone(),
+            }),
+            Step::Script(script) => {
+                if let Some(run) = script.params.get("run").cloned() {
+                    acc.scripts.push(ScriptSummary {
+                        name: script.name.clone(),
+                        run,
+                        output: script.output.clone(),
+                    });
+                }
+            }
+            Step::Report(report) => acc.reports.push(ReportSummary {
+                name: report.name.clone(),
+                includes: report.includes.clone(),
+                format: report.format.clone(),
+                output: report.output.clone(),
+                options: report.options.clone(),
+            }),
+            Step::Conditional(block) => {
+                collect_summary_steps(&block.then_steps, acc);
+                collect_summary_steps(&block.else_steps, acc);
+            }
+            Step::Loop(loop_step) => {
+                collect_summary_steps(&loop_step.body, acc);
+            }
+            Step::Parallel(block) => {
+                collect_summary_steps(&block.steps, acc);
+            }
+            Step::Sleep(sleep) => acc.sleeps.push(SleepSummary {
+                name: sleep.name.clone(),
+                duration_ms: sleep.duration_ms,
+            }),
+            Step::Merge(merge) => acc.merges.push(MergeSummary {
+                inputs: merge.inputs.clone(),
+                output: merge.output.clone(),
+            }),
+            Step::Transform(transform) => acc.transforms.push(TransformSummary {
+                name: transform.name.clone(),
+                source: transform.source.clone(),
+                output: transform
+                    .output
+                    .clone()
+                    .unwrap_or_else(|| transform.source.clone()),
+                operations: transform.operations.len(),
+            }),
+            Step::Checkpoint(checkpoint) => acc.checkpoints.push(checkpoint.name.clone()),
+            Step::Function(function) => {
+                acc.functions.push(FunctionSummary {
+                    name: function.name.clone(),
+                    params: function.params.clone(),
+                    call_sites: Vec::new(),
+                });
+                let outer = acc.current_function.replace(function.name.clone());
+                collect_summary_steps(&function.body, acc);
+                acc.current_function = outer;
+            }
+            Step::Call(call) => {
+                let site = acc.current_function.as_deref().unwrap_or("main");
+                acc.calls.push((call.function.clone(), site.to_string()));
+            }
+            Step::Assert(_) | Step::Notify(_) | Step::Break | Step::Continue => {}
+        }
+        let handlers = step.on_failure();
+        if !handlers.is_empty() {
+            acc.on_failure_handlers += 1;
+            collect_summary_steps(handlers, acc);
+        }
+    }
+}
+
+impl fmt::Display for ScenarioSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Steps: {}", self.total_steps)?;
+        let aliased: BTreeSet<&String> = self.import_aliases.values().flatten().collect();
+        let plain: Vec<&String> = self
+            .imports
+            .iter()
+            .filter(|import| !aliased.contains(import))
+            .collect();
+        if !plain.is_empty() {
+            writeln!(f, "Imports:")?;
+            for import in plain {
+                writeln!(f, "  - {}", import)?;
+            }
+        }
+        for (alias, imports) in &self.import_aliases {
+            writeln!(f, "Imports as {}:", alias)?;
+            for import in imports {
+                writeln!(f, "  - {}", import)?;
+            }
+        }
+        if !self.variables.is_empty() {
+            writeln!(f, "Variables:")?;
+            for var in &self.variables {
+                writeln!(f, "  - {} = {}", var.name, var.value)?;
+            }
+        }
+        if !self.asset_groups.is_empty() {
+            writeln!(f, "Asset groups:")?;
+            for group in &self.asset_groups {
+                let props: Vec<String> = group
+                    .properties
+                    .iter()
+                    .map(|(k, v)| format!("{k}={v}"))
+                    .collect();
+                writeln!(f, "  - {} ({})", group.name, props.join(", "))?;
+            }
+        }
+        if !self.scans.is_empty() {
+            writeln!(f, "Scans:")?;
+            for scan in &self.scans {
+                let output = scan.output.as_deref().unwrap_or("<none>");
+                writeln!(f, "  - {} via {} -> {}", scan.name, scan.tool, output)?;
+            }
+        }
+        if !self.scripts.is_empty() {
+            writeln!(f, "Scripts:")?;
+            for script in &self.scripts {
+                let output = script.output.as_deref().unwrap_or("<none>");
+                writeln!(f, "  - {} run {} -> {}", script.name, script.run, output)?;
+            }
+        }
+        if !self.reports.is_empty() {
+            writeln!(f, "Reports:")?;
+            for report in &self.reports {
+                let includes = if report.includes.is_empty() {
+                    "<none>".to_string()
+                } else {
+                    report.includes.join(", ")
+                };
+                let output_display = report
+                    .output
+                    .as_deref()
+                    .map(|value| format!(" -> {}", value))
+                    .unwrap_or_default();
+                let options_display = if report.options.is_empty() {
+                    String::new()
+                } else {
+                    let opts: Vec<String> = report
+                        .options
+                        .iter()
+                        .map(|(k, v)| format!("{k}={v}"))
+                        .collect();
+                    format!(" options: {}", opts.join(", "))
+                };
+                writeln!(
+                    f,
+                    "  - {} [{}] (includes: {}){}{}",
+                    report.name,
+                    report.format.as_str(),
+                    includes,
+                    output_display,
+                    options_display
+                )?;
+            }
+        }
+        if !self.sleeps.is_empty() {
+            writeln!(f, "Sleeps:")?;
+            for sleep in &self.sleeps {
+                writeln!(
+                    f,
+                    "  - {} ({})",
+                    sleep.name,
+                    format_duration_ms(sleep.duration_ms)
+                )?;
+            }
+        }
+        if !self.merges.is_empty() {
+            writeln!(f, "Merges:")?;
+            for merge in &self.merges {
+                writeln!(f, "  - {} -> {}", merge.inputs.join(" + "), merge.output)?;
+            }
+        }
+        if !self.transforms.is_empty() {
+            writeln!(f, "Transforms:")?;
+            for transform in &self.transforms {
+                writeln!(
+                    f,
+                    "  - {}: {} -> {} ({} operation(s))",
+                    transform.name, transform.source, transform.output, transform.operations
+                )?;
+            }
+        }
+        if !self.checkpoints.is_empty() {
+            writeln!(f, "Checkpoints: {}", self.checkpoints.join(", "))?;
+        }
+        if self.on_failure_handlers > 0 {
+            writeln!(f, "On-failure handlers: {}", self.on_failure_handlers)?;
+        }
+        if !self.functions.is_empty() {
+            writeln!(f, "Functions:")?;
+            for function in &self.functions {
+                let sites = if function.call_sites.is_empty() {
+                    "never called".to_string()
+                } else {
+                    format!("called from {}", function.call_sites.join(", "))
+                };
+                writeln!(
+                    f,
+                    "  - {}({}) {}",
+                    function.name,
+                    function.params.join(", "),
+                    sites
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_heredoc_and_triple_quoted_values() {
+        let source = r#"script pipeline {
+  run <<EOF
+grep -E "a|b" 'input.txt' \
+  | sed 's/"//g'
+# not a comment
+EOF
+  shell "/bin/sh"
+}
+scan web custom-scanner {
+  args """--header "X-Test: 1"
+--path 'C:\tmp'"""
+  target """10.0.0.1"""
+  flags """
+-v "quoted"
+  """
+}
+"#;
+        let scenario = parse_scenario_all(source).expect("failed to parse multi-line values");
+        let Step::Script(script) = &scenario.steps[0] else {
+            panic!("expected script step");
+        };
+        assert_eq!(
+            script.params["run"],
+            "grep -E \"a|b\" 'input.txt' \\\n  | sed 's/\"//g'\n# not a comment"
+        );
+        assert_eq!(script.params["shell"], "/bin/sh");
+
+        let Step::Scan(scan) = &scenario.steps[1] else {
+            panic!("expected scan step");
+        };
+        assert_eq!(
+            scan.params["args"],
+            "--header \"X-Test: 1\"\n--path 'C:\\tmp'"
+        );
+        assert_eq!(scan.params["target"], "10.0.0.1");
+        assert_eq!(scan.params["flags"], "-v \"quoted\"");
+
+        assert!(parse_scenario_all("script s {\n  run <<EOF\necho hi\n}\n").is_err());
+        assert!(parse_scenario_all("script s {\n  run \"\"\"a\"\"\" trailing\n}\n").is_err());
+    }
+
+    #[test]
+    fn parse_errors_are_collected_per_directive() {
+        let source = "let a = \"1\"\nfrobnicate {\n  inner line\n}\nlet b = \"2\"\nscan web {\n  target \"x\"\n}\n\nlet c = \"3\"\nbogus\n";
+        let errors = parse_scenario_all(source).unwrap_err();
+        let lines: Vec<usize> = errors
+            .iter()
+            .filter_map(|err| err.location().map(|loc| loc.line))
+            .collect();
+        assert_eq!(lines, vec![2, 6, 11]);
+
+        #[allow(deprecated)]
+        let first = parse_scenario(source).unwrap_err();
+        assert_eq!(first.location().map(|loc| loc.line), Some(2));
+        assert!(parse_scenario_all("let a = \"1\"\n").is_ok());
+    }
+
+    #[test]
+    fn parse_errors_carry_line_and_column() {
+        let err = parse_scenario_all("let a = \"1\"\n\n  frobnicate now\n")
+            .unwrap_err()
+            .remove(0);
+        assert_eq!(
+            err.location(),
+            Some(&Location {
+                line: 3,
+                column: Some(3)
+            })
+        );
+        assert!(matches!(err.inner(), ParseError::InvalidDirective(_)));
+        assert!(err
+            .to_string()
+            .starts_with("line 3, column 3: invalid directive"));
+
+        let nested =
+            parse_scenario_all("let a = true\nif a {\n  let b = \"1\"\n    frobnicate\n}\n")
+                .unwrap_err()
+                .remove(0);
+        assert_eq!(nested.location().map(|loc| loc.line), Some(4));
+        assert_eq!(nested.location().and_then(|loc| loc.column), Some(5));
+
+        let eof = parse_scenario_all("scan web nmap {\n  target \"10.0.0.1\"\n")
+            .unwrap_err()
+            .remove(0);
+        assert_eq!(
+            eof.location(),
+            Some(&Location {
+                line: 2,
+                column: None
+            })
+        );
+        assert!(matches!(eof.inner(), ParseError::UnexpectedEof(_)));
+    }
+
+    #[test]
+    fn parses_env_secret_block() {
+        let source = r#"
+secret db_creds from env {
+  username = "DB_USER"
+  password = "DB_PASS"
+}
+"#;
+        let scenario = parse_scenario_all(source).expect("failed to parse env secret");
+        assert_eq!(scenario.steps.len(), 1);
+
+        match &scenario.steps[0] {
+            Step::Secret(step) => {
+                assert_eq!(step.name, "db_creds");
+                match &step.source {
+                    SecretSource::Env { mappings } => {
+                        assert_eq!(mappings.get("username"), Some(&"DB_USER".to_string()));
+                        assert_eq!(mappings.get("password"), Some(&"DB_PASS".to_string()));
+                    }
+                    other => panic!("expected env secret, got {:?}", other),
+                }
+            }
+            other => panic!("expected secret step, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_inline_file_secret() {
+        let source = r#"secret api_key from file ".secrets/api.key""#;
+        let scenario = parse_scenario_all(source).expect("failed to parse file secret");
+        assert_eq!(scenario.steps.len(), 1);
+
+        match &scenario.steps[0] {
+            Step::Secret(step) => {
+                assert_eq!(step.name, "api_key");
+                match &step.source {
+                    SecretSource::File { path, key } => {
+                        assert_eq!(path, ".secrets/api.key");
+                        assert!(key.is_none());
+                    }
+                    other => panic!("expected file secret, got {:?}", other),
+                }
+            }
+            other => panic!("expected secret step, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn deeply_nested_expressions_are_rejected() {
+        let nested = |open: &str, inner: &str, close: &str, levels: usize| {
+            format!("{}{inner}{}", open.repeat(levels), close.repeat(levels))
+        };
+        assert!(parse_literal_expression(&nested("[", "1", "]", 32)).is_ok());
+        assert!(parse_condition_expr(&nested("(", "!a", ")", 32)).is_ok());
+
+        for result in [
+            parse_literal_expression(&nested("[", "", "]", 5000)).map(|_| ()),
+            parse_literal_expression(&nested("{a: ", "1", "}", 5000)).map(|_| ()),
+            parse_condition_expr(&nested("(", "a", ")", 5000)).map(|_| ()),
+            parse_condition_expr(&nested("!", "a", "", 5000)).map(|_| ()),
+        ] {
+            let err = result.expect_err("nesting limit");
+            assert!(err.to_string().contains("nested more than"), "{err}");
+        }
+    }
+
+    #[test]
+    fn multibyte_text_before_delimiters_is_sliced_on_char_boundaries() {
+        let literal = parse_literal_expression(r#"["ééé", {"ключ": "значение"}, "a"]"#)
+            .expect("literal parses");
+        let string = |value: &str| LiteralValue::String(value.to_string());
+        assert_eq!(
+            literal,
+            LiteralValue::Array(vec![
+                string("ééé"),
+                LiteralValue::Object(BTreeMap::from([("ключ".to_string(), string("значение"))])),
+                string("a"),
+            ])
+        );
+
+        let condition =
+            parse_condition_expr(r#""ééé" == name && name != "ü""#).expect("condition parses");
+        let ConditionExpr::And(left, right) = condition else {
+            panic!("expected && at the top level");
+        };
+        assert!(matches!(
+            *left,
+            ConditionExpr::Equals(ConditionOperand::Literal(LiteralValue::String(ref value)), _)
+                if value == "ééé"
+        ));
+        assert!(matches!(*right, ConditionExpr::NotEquals(_, _)));
+    }
+
+    #[test]
+    fn parses_step_tags() {
+        let source = r#"
+scan discovery nmap {
+  tags ["scan", "prod"]
+  target "10.0.0.1"
+}
+scan templates nuclei {
+  tags "cve"
+  target "10.0.0.1"
+}
+report stdout {
+  tags ["report"]
+  include discovery
+}
+"#;
+        let scenario = parse_scenario_all(source).expect("failed to parse tags");
+        assert_eq!(scenario.steps[0].tags(), ["scan", "prod"]);
+        match &scenario.steps[1] {
+            Step::Scan(scan) => {
+                assert!(scan.tags.is_empty());
+                assert_eq!(scan.params.get("tags").map(String::as_str), Some("cve"));
+            }
+            other => panic!("expected scan step, got {:?}", other),
+        }
+        assert_eq!(scenario.steps[2].tags(), ["report"]);
+        assert!(parse_scenario_all("script s {\n  tags [1]\n  run \"x\"\n}\n").is_err());
+    }
+
+    #[test]
+    fn parses_keyed_file_secret_block() {
+        let source = "secret creds from file {\n  path = \".secrets/creds.json\"\n  key = \"api.token\"\n}\n";
+        let scenario = parse_scenario_all(source).expect("failed to parse keyed file secret");
+        match &scenario.steps[0] {
+            Step::Secret(step) => match &step.source {
+                SecretSource::File { path, key } => {
+                    assert_eq!(path, ".secrets/creds.json");
+                    assert_eq!(key.as_deref(), Some("api.token"));
+                }
+                other => panic!("expected file secret, got {:?}", other),
+            },
+            other => panic!("expected secret step, got {:?}", other),
+        }
+        let err = parse_scenario_all("secret creds from file {\n  key = \"*\"\n}\n")
+            .unwrap_err()
+            .remove(0);
+        assert!(matches!(err.inner(), ParseError::MissingValue("file.path")));
+    }
+
+    #[test]
+    fn parses_vault_secret_block() {
+        let source = r#"
+secret db from vault {
+  path = "app/db"
+  mount = "kv"
+  field = "password"
+  role_id = "axion-ci"
+}
+"#;
+        let scenario = parse_scenario_all(source).expect("failed to parse vault secret");
+
+        match &scenario.steps[0] {
+            Step::Secret(step) => match &step.source {
+                SecretSource::Vault {
+                    path,
+                    field,
+                    mount,
+                    role_id,
+                    secret_id,
+                    ..
+                } => {
+                    assert_eq!(path, "app/db");
+                    assert_eq!(mount.as_deref(), Some("kv"));
+                    assert_eq!(field.as_deref(), Some("password"));
+                    assert_eq!(role_id.as_deref(), Some("axion-ci"));
+                    assert!(secret_id.is_none());
+                }
+                other => panic!("expected vault secret, got {:?}", other),
+            },
+            other => panic!("expected secret step, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_aws_secrets_manager_secret() {
+        let source = r#"
+secret db from aws_secrets_manager {
+  secret_id = "prod/db"
+  region = "eu-west-1"
+  key = "password"
+}
+secret token from aws_secrets_manager { secret_id = "ci/token" }
+"#;
+        let scenario = parse_scenario_all(source).expect("failed to parse aws secrets");
+        let sources: Vec<&SecretSource> = scenario
+            .steps
+            .iter()
+            .map(|step| match step {
+                Step::Secret(step) => &step.source,
+                other => panic!("expected secret step, got {:?}", other),
+            })
+            .collect();
+        assert!(matches!(
+            sources[0],
+            SecretSource::AwsSecretsManager { secret_id, region: Some(region), key: Some(key) }
+                if secret_id == "prod/db" && region == "eu-west-1" && key == "password"
+        ));
+        assert!(matches!(
+            sources[1],
+            SecretSource::AwsSecretsManager { secret_id, region: None, key: None }
+                if secret_id == "ci/token"
+        ));
+        assert_eq!(
+            scenario.summary().secrets[0].provider,
+            "aws_secrets_manager"
+        );
+
+        assert!(parse_scenario_all(
+            "secret db from aws_secrets_manager {\n  region = \"us-east-1\"\n}\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parses_keyring_secret() {
+        let scenario = parse_scenario_all(
+            "secret gh from keyring {\n  service = \"axion\"\n  account = \"github\"\n}\n",
+        )
+        .expect("failed to parse keyring secret");
+        match &scenario.steps[0] {
+            Step::Secret(SecretStep {
+                source: SecretSource::Keyring { service, account },
+                ..
+            }) => {
+                assert_eq!(service, "axion");
+                assert_eq!(account, "github");
+            }
+            other => panic!("expected keyring secret, got {:?}", other),
+        }
+        assert!(
+            parse_scenario_all("secret gh from keyring {\n  service = \"axion\"\n}\n").is_err()
+        );
+    }
+
+    #[test]
+    fn parses_compound_conditions_with_precedence() {
+        let expr = parse_condition_expr(r#"is_prod || scan_done && severity == "high""#)
+            .expect("failed to parse condition");
+        match &expr {
+            ConditionExpr::Or(left, right) => {
+                assert!(
+                    matches!(left.as_ref(), ConditionExpr::Variable(name) if name == "is_prod")
+                );
+                match right.as_ref() {
+                    ConditionExpr::And(inner_left, inner_right) => {
+                        assert!(matches!(inner_left.as_ref(), ConditionExpr::Variable(_)));
+                        assert!(matches!(inner_right.as_ref(), ConditionExpr::Equals(_, _)));
+                    }
+                    other => panic!("expected && on the right, got {:?}", other),
+                }
+            }
+            other => panic!("expected || at the top level, got {:?}", other),
+        }
+
+        let grouped = parse_condition_expr("(a || b) && !c").expect("failed to parse grouping");
+        assert!(matches!(grouped, ConditionExpr::And(_, _)));
+        assert_eq!(grouped.to_string(), "(a || b) && !c");
+        let reparsed = parse_condition_expr(&grouped.to_string()).expect("round trip");
+        assert_eq!(reparsed.to_string(), grouped.to_string());
+
+        let quoted = parse_condition_expr(r#"label == "a || b""#).expect("quoted operator");
+        assert!(matches!(quoted, ConditionExpr::Equals(_, _)));
+    }
+
+    #[test]
+    fn parses_contains_infix_and_call_forms() {
+        let infix = parse_condition_expr(r#"hostname contains ".corp.local""#)
+            .expect("failed to parse infix contains");
+        let call = parse_condition_expr(r#"contains(hostname, ".corp.local")"#)
+            .expect("failed to parse contains call");
+        for expr in [&infix, &call] {
+            match expr {
+                ConditionExpr::Contains(ConditionOperand::Variable(name), _) => {
+                    assert_eq!(name, "hostname")
+                }
+                other => panic!("expected contains, got {:?}", other),
+            }
+        }
+        assert_eq!(infix.to_string(), call.to_string());
+        assert!(parse_condition_expr("contains(a)").is_err());
+    }
+
+    #[test]
+    fn matches_condition_round_trips_through_display() {
+        let expr =
+            parse_condition_expr(r#"target matches "^10\..*""#).expect("failed to parse matches");
+        match &expr {
+            ConditionExpr::Matches(
+                ConditionOperand::Variable(name),
+                ConditionOperand::Literal(LiteralValue::String(pattern)),
+            ) => {
+                assert_eq!(name, "target");
+                assert_eq!(pattern, r"^10\..*");
+            }
+            other => panic!("expected matches, got {:?}", other),
+        }
+
+        let rendered = expr.to_string();
+        assert_eq!(rendered, r#"target matches "^10\..*""#);
+        let reparsed = parse_condition_expr(&rendered).expect("display output parses");
+        assert_eq!(reparsed.to_string(), rendered);
+
+        let word = parse_condition_expr(r#"role matches "admin""#).expect("word pattern");
+        let reparsed = parse_condition_expr(&word.to_string()).expect("word round trip");
+        assert!(matches!(
+            reparsed,
+            ConditionExpr::Matches(_, ConditionOperand::Literal(LiteralValue::String(_)))
+        ));
+    }
+
+    #[test]
+    fn parses_parallel_block() {
+        let source = r#"
+parallel discovery {
+  scan web nmap {
+    target "10.0.0.1"
+  }
+  script banner {
+    run "echo hi"
+  }
+}
+"#;
+        let scenario = parse_scenario_all(source).expect("failed to parse parallel block");
+        match &scenario.steps[0] {
+            Step::Parallel(block) => {
+                assert_eq!(block.name.as_deref(), Some("discovery"));
+                assert_eq!(block.steps.len(), 2);
+            }
+            other => panic!("expected parallel block, got {:?}", other),
+        }
+
+        let anonymous = parse_scenario_all("parallel {\n}\n").expect("anonymous parallel block");
+        assert!(matches!(&anonymous.steps[0], Step::Parallel(block) if block.name.is_none()));
+    }
+
+    #[test]
+    fn parses_break_and_continue_in_loops() {
+        let source = r#"
+for host in ["a", "b", "c"] {
+  if host == "b" { continue }
+  if host == "c" {
+    break
+  } else { let last = host }
+}
+"#;
+        let scenario = parse_scenario_all(source).expect("failed to parse loop controls");
+        let Step::Loop(loop_step) = &scenario.steps[0] else {
+            panic!("expected loop step");
+        };
+        match &loop_step.body[0] {
+            Step::Conditional(block) => {
+                assert!(matches!(block.then_steps.as_slice(), [Step::Continue]));
+                assert!(block.else_steps.is_empty());
+            }
+            other => panic!("expected conditional, got {:?}", other),
+        }
+        match &loop_step.body[1] {
+            Step::Conditional(block) => {
+                assert!(matches!(block.then_steps.as_slice(), [Step::Break]));
+                assert!(matches!(block.else_steps.as_slice(), [Step::Variable(_)]));
+            }
+            other => panic!("expected conditional, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_loop_when_guard() {
+        let source = "for host in hosts when host != \"localhost\" && host != \"a when b\" {\n}\n";
+        let scenario = parse_scenario_all(source).expect("failed to parse guarded loop");
+        match &scenario.steps[0] {
+            Step::Loop(loop_step) => {
+                assert!(
+                    matches!(&loop_step.iterable, LoopIterable::Variable(name) if name == "hosts")
+                );
+                let guard = loop_step.guard.as_ref().expect("guard parsed");
+                assert_eq!(
+                    guard.to_string(),
+                    "host != \"localhost\" && host != \"a when b\""
+                );
+            }
+            other => panic!("expected loop, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_key_value_loops() {
+        let scenario = parse_scenario_all("for host, tag in tags {\n}\n").expect("key, value loop");
+        match &scenario.steps[0] {
+            Step::Loop(loop_step) => {
+                assert_eq!(loop_step.iterator, "host");
+                assert_eq!(loop_step.value_iterator.as_deref(), Some("tag"));
+                assert_eq!(loop_step.bindings(), "host, tag");
+            }
+            other => panic!("expected loop, got {:?}", other),
+        }
+        assert!(parse_scenario_all("for k, k in tags {\n}\n").is_err());
+        assert!(parse_scenario_all("for k, in tags {\n}\n").is_err());
+    }
+
+    #[test]
+    fn summarises_functions_and_call_sites() {
+        let scenario = parse_scenario_all(
+            "func probe(target, port) {\n  let url = \"${target}:${port}\"\n}\nfunc sweep() {\n  call probe(\"10.0.0.1\", 80)\n}\ncall probe(host, 443)\ncall sweep()\nfunc unused() {\n}\n",
+        )
+        .expect("functions parse");
+        match &scenario.steps[3] {
+            Step::Call(call) => {
+                assert_eq!(call.function, "sweep");
+                assert!(call.args.is_empty());
             }
+            other => panic!("expected call, got {other:?}"),
         }
+        let summary = scenario.summary();
+        let rendered = summary.to_string();
+        assert!(
+            rendered.contains(
+                "Functions:\n  - probe(target, port) called from sweep, main\n  - sweep() called from main\n  - unused() never called\n"
+            ),
+            "{rendered}"
+        );
+        assert!(scenario.function_cycles().is_empty());
+
+        let recursive =
+            parse_scenario_all("func again() {\n  call again()\n}\n").expect("recursion parses");
+        assert_eq!(recursive.function_cycles(), vec![vec!["again", "again"]]);
+        assert!(parse_scenario_all("func twice(a, a) {\n}\n").is_err());
+        assert!(parse_scenario_all("call missing_paren(\n").is_err());
     }
-}
 
-impl fmt::Display for ScenarioSummary {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "Steps: {}", self.total_steps)?;
-        if !self.imports.is_empty() {
-            writeln!(f, "Imports:")?;
-            for import in &self.imports {
-                writeln!(f, "  - {}", import)?;
+    #[test]
+    fn parses_loop_max_iter_option() {
+        let source = "for ip in ips {\n  option max_iter 50000\n  let seen = ip\n}\n";
+        let scenario = parse_scenario_all(source).expect("failed to parse loop option");
+        match &scenario.steps[0] {
+            Step::Loop(loop_step) => {
+                assert_eq!(loop_step.max_iter, Some(50_000));
+                assert_eq!(loop_step.body.len(), 1);
             }
+            other => panic!("expected loop, got {:?}", other),
         }
-        if !self.variables.is_empty() {
-            writeln!(f, "Variables:")?;
-            for var in &self.variables {
-                writeln!(f, "  - {} = {}", var.name, var.value)?;
-            }
+
+        assert!(parse_scenario_all("for ip in ips {\n  option retries 3\n}\n").is_err());
+    }
+
+    #[test]
+    fn parses_range_loops() {
+        let scenario = parse_scenario_all("for i in 1..10 {\n}\nfor j in -2..=2 {\n}\n")
+            .expect("failed to parse range loops");
+        let iterables: Vec<String> = scenario
+            .steps
+            .iter()
+            .map(|step| match step {
+                Step::Loop(loop_step) => loop_step.iterable.to_string(),
+                other => panic!("expected loop, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(iterables, vec!["1..10", "-2..=2"]);
+        match &scenario.steps[1] {
+            Step::Loop(loop_step) => assert_eq!(loop_step.iterable.range_len(), Some(5)),
+            other => panic!("expected loop, got {:?}", other),
         }
-        if !self.asset_groups.is_empty() {
-            writeln!(f, "Asset groups:")?;
-            for group in &self.asset_groups {
-                let props: Vec<String> = group
-                    .properties
-                    .iter()
-                    .map(|(k, v)| format!("{k}={v}"))
-                    .collect();
-                writeln!(f, "  - {} ({})", group.name, props.join(", "))?;
+
+        assert!(parse_scenario_all("for i in 1..x {\n}\n").is_err());
+        let literal = parse_scenario_all("for v in [\"1..2\"] {\n}\n").expect("array with dots");
+        assert!(matches!(
+            &literal.steps[0],
+            Step::Loop(LoopStep {
+                iterable: LoopIterable::Literal(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn parses_import_alias_and_prefixes_steps() {
+        let scenario =
+            parse_scenario_all("import \"base.dsl\" as base\nimport \"plain as file.dsl\"\n")
+                .expect("failed to parse imports");
+        match &scenario.steps[0] {
+            Step::Import(import) => {
+                assert_eq!(import.path, "base.dsl");
+                assert_eq!(import.alias.as_deref(), Some("base"));
             }
+            other => panic!("expected import, got {:?}", other),
         }
-        if !self.scans.is_empty() {
-            writeln!(f, "Scans:")?;
-            for scan in &self.scans {
-                let output = scan.output.as_deref().unwrap_or("<none>");
-                writeln!(f, "  - {} via {} -> {}", scan.name, scan.tool, output)?;
+        match &scenario.steps[1] {
+            Step::Import(import) => {
+                assert_eq!(import.path, "plain as file.dsl");
+                assert!(import.alias.is_none());
             }
+            other => panic!("expected import, got {:?}", other),
         }
-        if !self.scripts.is_empty() {
-            writeln!(f, "Scripts:")?;
-            for script in &self.scripts {
-                let output = script.output.as_deref().unwrap_or("<none>");
-                writeln!(f, "  - {} run {} -> {}", script.name, script.run, output)?;
-            }
+        let summary = scenario.summary();
+        assert_eq!(summary.import_aliases["base"], vec!["base.dsl".to_string()]);
+        let rendered = summary.to_string();
+        assert!(rendered.contains("Imports:\n  - plain as file.dsl"));
+        assert!(rendered.contains("Imports as base:\n  - base.dsl"));
+
+        let mut imported = parse_scenario_all(
+            r#"
+scan web nmap {
+  target "10.0.0.1"
+}
+report summary {
+  include findings_web
+}
+"#,
+        )
+        .expect("failed to parse imported scenario");
+        prefix_step_names(&mut imported.steps, "base");
+        let names: Vec<&str> = imported.steps.iter().filter_map(Step::name).collect();
+        assert_eq!(names, vec!["base.web", "base.summary"]);
+        match &imported.steps[1] {
+            Step::Report(report) => assert_eq!(report.includes, vec!["findings_base.web"]),
+            other => panic!("expected report, got {:?}", other),
         }
-        if !self.reports.is_empty() {
-            writeln!(f, "Reports:")?;
-            for report in &self.reports {
-                let includes = if report.includes.is_empty() {
-                    "<none>".to_string()
-                } else {
-                    report.includes.join(", ")
-                };
-                let output_display = report
-                    .output
-                    .as_deref()
-                    .map(|value| format!(" -> {}", value))
-                    .unwrap_or_default();
-                let options_display = if report.options.is_empty() {
-                    String::new()
-                } else {
-                    let opts: Vec<String> = report
-                        .options
-                        .iter()
-                        .map(|(k, v)| format!("{k}={v}"))
-                        .collect();
-                    format!(" options: {}", opts.join(", "))
-                };
-                writeln!(
-                    f,
-                    "  - {} [{}] (includes: {}){}{}",
-                    report.name,
-                    report.format.as_str(),
-                    includes,
-                    output_display,
-                    options_display
-                )?;
+    }
+
+    #[test]
+    fn parses_merge_artifacts_steps() {
+        let source =
+            "merge_artifacts \"scan_tcp\" \"scan_udp\" -> \"scan_all\"\nmerge_artifacts a b -> c\n";
+        let scenario = parse_scenario_all(source).expect("failed to parse merge steps");
+        match &scenario.steps[0] {
+            Step::Merge(merge) => {
+                assert_eq!(merge.inputs, vec!["scan_tcp", "scan_udp"]);
+                assert_eq!(merge.output, "scan_all");
             }
+            other => panic!("expected merge step, got {:?}", other),
         }
-        Ok(())
+        let summary = scenario.summary();
+        assert!(summary
+            .to_string()
+            .contains("Merges:\n  - scan_tcp + scan_udp -> scan_all\n"));
+
+        assert!(parse_scenario_all("merge_artifacts a b\n").is_err());
+        assert!(parse_scenario_all("merge_artifacts -> c\n").is_err());
+        assert!(parse_scenario_all("merge_artifacts a -> c d\n").is_err());
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
-    fn parses_env_secret_block() {
+    fn parses_transform_steps() {
         let source = r#"
-secret db_creds from env {
-  username = "DB_USER"
-  password = "DB_PASS"
+transform triage from scan_web -> scan_triaged {
+    filter findings where severity == "high"
+    set findings[*].remediation "Update software"
+    rename findings[*].cve to cve_id
+    project findings[*] cve_id, severity, remediation
 }
+transform tidy from scan_web { rename tool to scanner }
 "#;
-        let scenario = parse_scenario(source).expect("failed to parse env secret");
-        assert_eq!(scenario.steps.len(), 1);
-
+        let scenario = parse_scenario_all(source).expect("failed to parse transform steps");
         match &scenario.steps[0] {
-            Step::Secret(step) => {
-                assert_eq!(step.name, "db_creds");
-                match &step.source {
-                    SecretSource::Env { mappings } => {
-                        assert_eq!(mappings.get("username"), Some(&"DB_USER".to_string()));
-                        assert_eq!(mappings.get("password"), Some(&"DB_PASS".to_string()));
-                    }
-                    other => panic!("expected env secret, got {:?}", other),
-                }
+            Step::Transform(transform) => {
+                assert_eq!(transform.source, "scan_web");
+                assert_eq!(transform.output.as_deref(), Some("scan_triaged"));
+                assert_eq!(transform.operations.len(), 4);
+                assert!(matches!(
+                    &transform.operations[1],
+                    TransformOp::Set { path, value: LiteralValue::String(text) }
+                        if path == "findings[*].remediation" && text == "Update software"
+                ));
+                assert_eq!(
+                    transform.operations[0].to_string(),
+                    "filter findings where severity == \"high\""
+                );
             }
-            other => panic!("expected secret step, got {:?}", other),
+            other => panic!("expected transform step, got {:?}", other),
+        }
+        let summary = scenario.summary().to_string();
+        assert!(summary
+            .contains("Transforms:\n  - triage: scan_web -> scan_triaged (4 operation(s))\n"));
+        assert!(summary.contains("  - tidy: scan_web -> scan_web (1 operation(s))\n"));
+
+        for invalid in [
+            "transform t from a {\n}\n",
+            "transform t from a {\n filter findings[*] where x\n}\n",
+            "transform t from a {\n set findings[*] 1\n}\n",
+            "transform t from a {\n rename a to b c\n}\n",
+            "transform t from a {\n delete findings\n}\n",
+            "transform t a {\n rename a to b\n}\n",
+        ] {
+            assert!(parse_scenario_all(invalid).is_err(), "{invalid}");
         }
     }
 
     #[test]
-    fn parses_inline_file_secret() {
-        let source = r#"secret api_key from file ".secrets/api.key""#;
-        let scenario = parse_scenario(source).expect("failed to parse file secret");
-        assert_eq!(scenario.steps.len(), 1);
+    fn parses_checkpoint_steps() {
+        let scenario =
+            parse_scenario_all("checkpoint after_scans\ncheckpoint done;\n").expect("checkpoints");
+        let names: Vec<Option<&str>> = scenario.steps.iter().map(Step::name).collect();
+        assert_eq!(names, vec![Some("after_scans"), Some("done")]);
+        assert!(scenario
+            .summary()
+            .to_string()
+            .contains("Checkpoints: after_scans, done\n"));
+
+        assert!(parse_scenario_all("checkpoint\n").is_err());
+        assert!(parse_scenario_all("checkpoint a b\n").is_err());
+        assert!(parse_scenario_all("checkpoint artifacts/x\n").is_err());
+    }
 
+    #[test]
+    fn parses_sleep_durations() {
+        let source = "sleep pause 30s\nsleep short 500ms\nsleep cooldown 2m\n";
+        let scenario = parse_scenario_all(source).expect("failed to parse sleep steps");
+        let durations: Vec<u64> = scenario
+            .steps
+            .iter()
+            .map(|step| match step {
+                Step::Sleep(sleep) => sleep.duration_ms,
+                other => panic!("expected sleep step, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(durations, vec![30_000, 500, 120_000]);
+
+        let summary = scenario.summary();
+        assert_eq!(summary.sleeps.len(), 3);
+        assert!(summary.to_string().contains("  - cooldown (2m)"));
+
+        assert!(parse_scenario_all("sleep pause 30\n").is_err());
+        assert!(parse_scenario_all("sleep pause 5d\n").is_err());
+    }
+
+    #[test]
+    fn parses_assert_blocks() {
+        let source = r#"
+assert few_findings {
+  condition findings_web.findings.length < 50
+  message "too many findings"
+}
+assert inline { condition ready; message "not ready" }
+"#;
+        let scenario = parse_scenario_all(source).expect("failed to parse assert blocks");
         match &scenario.steps[0] {
-            Step::Secret(step) => {
-                assert_eq!(step.name, "api_key");
-                match &step.source {
-                    SecretSource::File { path } => assert_eq!(path, ".secrets/api.key"),
-                    other => panic!("expected file secret, got {:?}", other),
+            Step::Assert(step) => {
+                assert_eq!(step.name, "few_findings");
+                assert_eq!(step.message.as_deref(), Some("too many findings"));
+                match &step.condition {
+                    ConditionExpr::LessThan(ConditionOperand::Variable(path), _) => {
+                        assert_eq!(path, "findings_web.findings.length")
+                    }
+                    other => panic!("expected comparison, got {:?}", other),
                 }
             }
-            other => panic!("expected secret step, got {:?}", other),
+            other => panic!("expected assert step, got {:?}", other),
         }
+        assert!(matches!(&scenario.steps[1], Step::Assert(step) if step.name == "inline"));
+
+        let missing = parse_scenario_all("assert empty {\n  message \"x\"\n}\n");
+        assert!(missing.is_err());
     }
 
     #[test]
@@ -1622,7 +5414,7 @@ report summary using html {
 }
 "#;
 
-        let scenario = parse_scenario(source).expect("failed to parse report");
+        let scenario = parse_scenario_all(source).expect("failed to parse report");
         assert_eq!(scenario.steps.len(), 1);
 
         match &scenario.steps[0] {
@@ -1639,4 +5431,978 @@ report summary using html {
             other => panic!("expected report step, got {:?}", other),
         }
     }
+
+    #[test]
+    fn report_filters_are_combined_with_and() {
+        let source = r#"
+report triage using html {
+  include scan_web
+  filter severity != "informational"
+  filter port > 1024
+}
+"#;
+
+        let scenario = parse_scenario_all(source).expect("failed to parse report");
+        match &scenario.steps[0] {
+            Step::Report(report) => assert_eq!(
+                report.options.get("filter").map(String::as_str),
+                Some(r#"(severity != "informational") && (port > 1024)"#)
+            ),
+            other => panic!("expected report step, got {:?}", other),
+        }
+
+        let invalid = "report triage {\n  filter severity ==\n}\n";
+        assert!(parse_scenario_all(invalid).is_err());
+    }
+
+    #[test]
+    fn to_dsl_renders_canonical_source() {
+        let source = r#"import "modules/common.ax" as common
+
+let hosts = ["10.0.0.1", "10.0.0.2"]
+let retries = 3
+let settings = {depth: 2, "user agent": "axion"}
+
+secret token from env {
+  api = "API_TOKEN"
+}
+
+asset_group dmz {
+  cidr "10.0.0.0/24"
+  owner "ops"
+}
+
+scan web nmap {
+  flags "-sV"
+  target "${dmz.cidr}"
+} -> web_scan
+
+script pipeline {
+  run <<EOF
+grep -E "a|b" input.txt
+  | sort
+EOF
+} -> filtered
+
+for host in hosts when host != "10.0.0.2" {
+  option max_iter 5
+  if retries > 2 && host matches "^10\." {
+    sleep pause 5s
+  } else if retries == 0 {
+    break
+  } else {
+    continue
+  }
+}
+
+parallel probes {
+  notify hook {
+    url "https://hooks.example.com/${secret:token}"
+    method POST
+    body "done"
+    headers {
+      X-Token "${secret:token}"
+    }
+  }
+  merge_artifacts web_scan filtered -> combined
+}
+
+assert enough {
+  condition (retries >= 1 || hosts) || !ready
+  message "need retries"
+}
+
+report summary using html {
+  include combined
+  output "reports/summary.html"
+  filter severity != "informational"
+  option query = "a=b"
+  option title "Executive Summary"
+}
+"#;
+
+        let scenario = parse_scenario_all(source).expect("canonical source parses");
+        assert_eq!(scenario.to_dsl(), source);
+    }
+
+    #[test]
+    fn parses_script_env_blocks() {
+        let scenario = parse_scenario_all(
+            "script a {\n  run \"env\"\n  env { API_KEY = \"${secret:api.key}\"; LOG_LEVEL = \"debug\" }\n}\nscript b {\n  run \"env\"\n  env {\n    HOME_DIR \"/tmp\"\n    MODE = \"fast\"\n  }\n} -> b_out\nscript c {\n  run \"env\"\n  env {\n    A = \"x\" }\n}\n",
+        )
+        .expect("scenario parses");
+        let envs: Vec<Vec<(&str, &str)>> = scenario
+            .steps
+            .iter()
+            .map(|step| match step {
+                Step::Script(script) => {
+                    assert!(!script.params.contains_key("env"));
+                    script
+                        .env
+                        .iter()
+                        .map(|(name, value)| (name.as_str(), value.as_str()))
+                        .collect()
+                }
+                other => panic!("expected script, got {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            envs,
+            [
+                vec![("API_KEY", "${secret:api.key}"), ("LOG_LEVEL", "debug")],
+                vec![("HOME_DIR", "/tmp"), ("MODE", "fast")],
+                vec![("A", "x")],
+            ]
+        );
+        assert!(scenario
+            .to_dsl()
+            .contains("  env {\n    API_KEY = \"${secret:api.key}\"\n"));
+    }
+
+    #[test]
+    fn parses_retry_shorthand_and_blocks() {
+        let scenario = parse_scenario_all(
+            "scan web nmap {\n  target \"10.0.0.1\"\n  retry 3\n}\nscript a {\n  run \"true\"\n  retry { count 2; delay \"5s\"; backoff exponential }\n}\nscript b {\n  run \"true\"\n  retry {\n    count 4\n    delay \"500ms\"\n  }\n}\n",
+        )
+        .expect("scenario parses");
+        let retries: Vec<&RetryPolicy> = scenario
+            .steps
+            .iter()
+            .map(|step| match step {
+                Step::Scan(scan) => scan.retry.as_ref(),
+                Step::Script(script) => script.retry.as_ref(),
+                other => panic!("unexpected step {other:?}"),
+            })
+            .map(|retry| retry.expect("retry is set"))
+            .collect();
+        let expected = [
+            (3, 0, RetryBackoff::None),
+            (2, 5_000, RetryBackoff::Exponential),
+            (4, 500, RetryBackoff::Fixed),
+        ];
+        for (retry, (count, delay_ms, backoff)) in retries.iter().zip(expected) {
+            assert_eq!(
+                (retry.count, retry.delay_ms, retry.backoff),
+                (count, delay_ms, backoff)
+            );
+        }
+        assert_eq!(retries[1].wait_ms(3), 20_000);
+        assert!(scenario
+            .to_dsl()
+            .contains("  retry {\n    count 2\n    delay \"5s\"\n    backoff exponential\n  }\n"));
+
+        let err = parse_scenario_all(
+            "script a {\n  run \"true\"\n  retry { count 2; backoff linear }\n}\n",
+        )
+        .unwrap_err();
+        assert!(err[0]
+            .to_string()
+            .contains("unknown retry backoff 'linear'"));
+    }
+
+    #[test]
+    fn parses_on_failure_handlers() {
+        let source = "scan web nmap {\n  target \"10.0.0.1\"\n} -> web\non_failure {\n  notify ops {\n    url \"https://hooks.example/ops\"\n    method POST\n  }\n  script cleanup {\n    run \"rm -f /tmp/web.lock\"\n  }\n}\nfor host in [\"a\"] {\n  script probe {\n    run \"true\"\n  }\n  on_failure { let probe_failed = true }\n}\n";
+        let scenario = parse_scenario_all(source).expect("scenario parses");
+        let Step::Scan(scan) = &scenario.steps[0] else {
+            panic!("expected scan, got {:?}", scenario.steps[0]);
+        };
+        assert!(matches!(
+            scan.on_failure.as_slice(),
+            [Step::Notify(_), Step::Script(_)]
+        ));
+        let Step::Loop(loop_step) = &scenario.steps[1] else {
+            panic!("expected loop, got {:?}", scenario.steps[1]);
+        };
+        assert!(matches!(
+            loop_step.body[0].on_failure(),
+            [Step::Variable(_)]
+        ));
+
+        let summary = scenario.summary();
+        assert_eq!(summary.on_failure_handlers, 2);
+        assert_eq!(summary.scripts.len(), 2);
+        assert!(scenario
+            .to_dsl()
+            .contains("} -> web\non_failure {\n  notify ops {\n"));
+
+        for source in [
+            "let a = 1\non_failure {\n  let b = 2\n}\n",
+            "script a {\n  run \"true\"\n}\non_failure { let b = 1 }\non_failure { let c = 1 }\n",
+        ] {
+            assert!(parse_scenario_all(source).is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn comments_before_else_join_the_else_branch() {
+        let source = "let ready = true\nif ready {\n  sleep a 1s\n}\n\n# fallback\nelse {\n  sleep b 2s\n}\n";
+        let scenario = parse_scenario_all(source).expect("scenario parses");
+        let Some(Step::Conditional(conditional)) = scenario.steps.get(1) else {
+            panic!("expected conditional, got {:?}", scenario.steps.get(1));
+        };
+        assert!(matches!(
+            conditional.else_steps.as_slice(),
+            [Step::Comment(c), Step::Sleep(_)] if c.text == "# fallback"
+        ));
+
+        let source = "let ready = true\nif ready {\n  sleep a 1s\n}\n# next\nsleep b 2s\n";
+        let scenario = parse_scenario_all(source).expect("scenario parses");
+        let Some(Step::Conditional(conditional)) = scenario.steps.get(1) else {
+            panic!("expected conditional, got {:?}", scenario.steps.get(1));
+        };
+        assert!(conditional.else_steps.is_empty());
+        assert!(matches!(scenario.steps.get(2), Some(Step::Comment(c)) if c.text == "# next"));
+    }
+
+    #[test]
+    fn keeps_comments_between_steps() {
+        let source = r#"#!/usr/bin/env axion
+
+# Sweep the lab network
+let a = 1
+  // about b
+let b = 2
+for host in ["a"] {
+  # c1
+  option max_iter 3
+  # c2
+  sleep pause 1s
+}
+# after the loop
+report stdout {
+  # inside a leaf block
+  include asset_group:hello
+}
+"#;
+        let scenario = parse_scenario_all(source).expect("scenario parses");
+        let comments: Vec<(&str, bool)> = scenario
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                Step::Comment(comment) => Some((comment.text.as_str(), comment.detached)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            comments,
+            [
+                ("#!/usr/bin/env axion", true),
+                ("# Sweep the lab network", false),
+                ("// about b", false),
+                ("# after the loop", false),
+            ]
+        );
+        let Some(Step::Loop(loop_step)) = scenario.steps.get(5) else {
+            panic!("expected loop, got {:?}", scenario.steps.get(5));
+        };
+        assert_eq!(loop_step.max_iter, Some(3));
+        assert!(matches!(
+            loop_step.body.as_slice(),
+            [Step::Comment(c1), Step::Comment(c2), Step::Sleep(_)]
+                if c1.text == "# c1" && c2.text == "# c2"
+        ));
+
+        assert_eq!(
+            scenario.to_dsl(),
+            r#"#!/usr/bin/env axion
+
+# Sweep the lab network
+let a = 1
+
+// about b
+let b = 2
+
+for host in ["a"] {
+  option max_iter 3
+  # c1
+  # c2
+  sleep pause 1s
+}
+
+# after the loop
+report stdout {
+  # inside a leaf block
+  include asset_group:hello
+}
+"#
+        );
+    }
+
+    #[test]
+    fn block_comments_follow_their_entry() {
+        let source = r#"asset_group lab {
+  # the lab range
+  scope "10.0.0.0/24"
+  # owner
+  owner "infra"
+}
+scan web nmap {
+  # checked last
+  target "10.0.0.1"
+  // flags first
+  flags "-sV"
+  retry 2
+  # trailing note
+} -> web_scan
+script notes {
+  run "true"
+  # empty env is dropped, its comment is not
+  env {
+  }
+}
+report summary using markdown {
+  option title "Weekly"
+  # only high
+  filter severity == "high"
+  // the scan
+  include web_scan
+}
+"#;
+        let scenario = parse_scenario_all(source).expect("scenario parses");
+        let Step::Scan(scan) = &scenario.steps[1] else {
+            panic!("expected scan, got {:?}", scenario.steps[1]);
+        };
+        assert_eq!(scan.comments["target"], ["# checked last"]);
+        assert_eq!(scan.comments["}"], ["# trailing note"]);
+
+        let formatted = scenario.to_dsl();
+        assert_eq!(
+            formatted,
+            r#"asset_group lab {
+  # owner
+  owner "infra"
+  # the lab range
+  scope "10.0.0.0/24"
+}
+
+scan web nmap {
+  // flags first
+  flags "-sV"
+  # checked last
+  target "10.0.0.1"
+  retry 2
+  # trailing note
+} -> web_scan
+
+script notes {
+  run "true"
+  # empty env is dropped, its comment is not
+}
+
+report summary using markdown {
+  // the scan
+  include web_scan
+  # only high
+  filter severity == "high"
+  option title "Weekly"
+}
+"#
+        );
+        let reparsed = parse_scenario_all(&formatted).expect("formatted source parses");
+        assert_eq!(reparsed.to_dsl(), formatted);
+    }
+
+    #[test]
+    fn parses_builtin_calls_and_null_in_let_values() {
+        let scenario = parse_scenario_all(
+            "let region = env(\"AWS_REGION\")\nlet hosts = [file('hosts.txt'), \"extra\"]\n",
+        )
+        .expect("scenario parses");
+        let values: Vec<&LiteralValue> = scenario
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                Step::Variable(var) => Some(&var.value),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(values[0], &LiteralValue::Env("AWS_REGION".to_string()));
+        assert_eq!(
+            values[1],
+            &LiteralValue::Array(vec![
+                LiteralValue::File("hosts.txt".to_string()),
+                LiteralValue::String("extra".to_string()),
+            ])
+        );
+
+        let scenario = parse_scenario_all(
+            "let proxy = null\nlet opts = {retries: null, tags: [null, \"a\"]}\n",
+        )
+        .expect("null literals parse");
+        match (&scenario.steps[0], &scenario.steps[1]) {
+            (Step::Variable(proxy), Step::Variable(opts)) => {
+                assert_eq!(proxy.value, LiteralValue::Null);
+                assert_eq!(proxy.value.display(), "null");
+                assert_eq!(
+                    opts.value.to_json(),
+                    serde_json::json!({"retries": null, "tags": [null, "a"]})
+                );
+            }
+            other => panic!("expected variables, got {other:?}"),
+        }
+
+        let scenario =
+            parse_scenario_all("let retries = 3\nlet ratio = 3.0\n").expect("numbers parse");
+        match (&scenario.steps[0], &scenario.steps[1]) {
+            (Step::Variable(retries), Step::Variable(ratio)) => {
+                assert_eq!(retries.value, LiteralValue::Integer(3));
+                assert_eq!(retries.value.display(), "3");
+                assert_eq!(ratio.value, LiteralValue::Number(3.0));
+                assert_eq!(ratio.value.to_dsl(), "3.0");
+            }
+            other => panic!("expected variables, got {other:?}"),
+        }
+
+        let scenario = parse_scenario_all(
+            "let a = count - 1 - offset\nlet b = base + step * 2\nlet c = Web scan - prod\n",
+        )
+        .expect("arithmetic parses");
+        let values: Vec<&LiteralValue> = scenario
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                Step::Variable(var) => Some(&var.value),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            values[0],
+            &LiteralValue::Arithmetic {
+                operator: ArithmeticOperator::Sub,
+                left: Box::new(ConditionOperand::Literal(LiteralValue::Arithmetic {
+                    operator: ArithmeticOperator::Sub,
+                    left: Box::new(ConditionOperand::Variable("count".to_string())),
+                    right: Box::new(ConditionOperand::Literal(LiteralValue::Integer(1))),
+                })),
+                right: Box::new(ConditionOperand::Variable("offset".to_string())),
+            }
+        );
+        assert_eq!(values[1].to_dsl(), "base + step * 2");
+        assert!(
+            matches!(
+                values[1],
+                LiteralValue::Arithmetic {
+                    operator: ArithmeticOperator::Add,
+                    ..
+                }
+            ),
+            "{:?}",
+            values[1]
+        );
+        assert_eq!(
+            values[2],
+            &LiteralValue::String("Web scan - prod".to_string())
+        );
+
+        let errors = parse_scenario_all("let rate = total / 0\n").expect_err("division by zero");
+        assert!(
+            errors[0].to_string().contains("division by zero"),
+            "{}",
+            errors[0]
+        );
+
+        let errors = parse_scenario_all("let region = env([1])\n").expect_err("array argument");
+        assert!(
+            errors[0]
+                .to_string()
+                .contains("env() expects a string argument"),
+            "{}",
+            errors[0]
+        );
+    }
+
+    #[test]
+    fn merge_concatenates_steps_and_deduplicates_imports() {
+        let mut first = parse_scenario_all("let a = 1\nscan web nmap {\n  target \"x\"\n}\n")
+            .expect("first scenario parses");
+        first.imports = vec!["common.ax".to_string(), "web.ax".to_string()];
+        first.aliases = vec![ImportAlias {
+            alias: "lib".to_string(),
+            imports: vec!["common.ax".to_string()],
+        }];
+        let mut second = parse_scenario_all("let b = 2\nreport stdout {\n  include scan_web\n}\n")
+            .expect("second scenario parses");
+        second.imports = vec!["web.ax".to_string(), "extra.ax".to_string()];
+        second.aliases = vec![ImportAlias {
+            alias: "lib".to_string(),
+            imports: vec!["common.ax".to_string(), "extra.ax".to_string()],
+        }];
+
+        let merged = Scenario::merge(first, second);
+        let names: Vec<_> = merged.steps.iter().filter_map(Step::name).collect();
+        assert_eq!(names, vec!["a", "web", "b", "stdout"]);
+        assert_eq!(merged.imports, vec!["common.ax", "web.ax", "extra.ax"]);
+        assert_eq!(merged.aliases.len(), 1);
+        assert_eq!(merged.aliases[0].imports, vec!["common.ax", "extra.ax"]);
+
+        let scans = merged.filter_steps(|step| matches!(step, Step::Scan(_)));
+        assert_eq!(scans.steps.len(), 1);
+        assert_eq!(scans.steps[0].name(), Some("web"));
+        assert_eq!(scans.imports, merged.imports);
+    }
+
+    mod round_trip {
+        use super::*;
+        use proptest::prelude::*;
+
+        const KEYWORDS: [&str; 10] = [
+            "true", "false", "null", "contains", "matches", "when", "in", "if", "else", "defined",
+        ];
+
+        fn ident() -> impl Strategy<Value = String> {
+            "[a-z][a-z0-9_]{0,6}".prop_filter("keyword", |name| !KEYWORDS.contains(&name.as_str()))
+        }
+
+        fn variable_path() -> impl Strategy<Value = String> {
+            (ident(), prop::option::of(ident())).prop_map(|(head, tail)| match tail {
+                Some(tail) => format!("{head}.{tail}"),
+                None => head,
+            })
+        }
+
+        fn text() -> impl Strategy<Value = String> {
+            "[a-zA-Z0-9 _./:$-]{0,12}"
+        }
+
+        // Scan and script parameters may span lines and contain quotes.
+        fn block_text() -> impl Strategy<Value = String> {
+            "[a-zEOF \"'\n]{0,16}"
+        }
+
+        fn scalar() -> impl Strategy<Value = LiteralValue> {
+            prop_oneof![
+                text().prop_map(LiteralValue::String),
+                any::<i64>().prop_map(LiteralValue::Integer),
+                any::<i32>().prop_map(|n| LiteralValue::Number(n.into())),
+                (-1.0e6..1.0e6f64).prop_map(LiteralValue::Number),
+                any::<bool>().prop_map(LiteralValue::Boolean),
+                Just(LiteralValue::Null),
+            ]
+        }
+
+        fn literal() -> impl Strategy<Value = LiteralValue> {
+            let leaf = prop_oneof![
+                4 => scalar(),
+                1 => "[A-Z_]{1,8}".prop_map(LiteralValue::Env),
+                1 => "[a-z/._-]{1,12}".prop_map(LiteralValue::File),
+                1 => (operand(), operand(), 0..4u8)
+                    .prop_filter("division by a literal zero is a parse error", |(_, right, op)| {
+                        *op != 3
+                            || !matches!(right, ConditionOperand::Literal(value) if value.as_f64() == Some(0.0))
+                    })
+                    .prop_map(|(left, right, op)| LiteralValue::Arithmetic {
+                        operator: [
+                            ArithmeticOperator::Add,
+                            ArithmeticOperator::Sub,
+                            ArithmeticOperator::Mul,
+                            ArithmeticOperator::Div,
+                        ][op as usize],
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    }),
+            ];
+            leaf.prop_recursive(2, 12, 4, |inner| {
+                prop_oneof![
+                    prop::collection::vec(inner.clone(), 0..4).prop_map(LiteralValue::Array),
+                    prop::collection::btree_map(text(), inner, 0..3).prop_map(LiteralValue::Object),
+                ]
+            })
+        }
+
+        fn operand() -> impl Strategy<Value = ConditionOperand> {
+            prop_oneof![
+                variable_path().prop_map(ConditionOperand::Variable),
+                scalar().prop_map(ConditionOperand::Literal),
+            ]
+        }
+
+        fn condition() -> impl Strategy<Value = ConditionExpr> {
+            let comparison = (operand(), operand(), 0..8u8).prop_map(|(left, right, op)| {
+                let build: ComparisonBuilder = match op {
+                    0 => ConditionExpr::Equals,
+                    1 => ConditionExpr::NotEquals,
+                    2 => ConditionExpr::LessThan,
+                    3 => ConditionExpr::GreaterThan,
+                    4 => ConditionExpr::LessThanOrEqual,
+                    5 => ConditionExpr::GreaterThanOrEqual,
+                    6 => ConditionExpr::Contains,
+                    _ => ConditionExpr::Matches,
+                };
+                build(left, right)
+            });
+            let leaf = prop_oneof![
+                any::<bool>().prop_map(ConditionExpr::Literal),
+                variable_path().prop_map(ConditionExpr::Variable),
+                ident().prop_map(ConditionExpr::Defined),
+                comparison,
+            ];
+            leaf.prop_recursive(3, 12, 2, |inner| {
+                prop_oneof![
+                    inner
+                        .clone()
+                        .prop_map(|expr| ConditionExpr::Not(Box::new(expr))),
+                    (inner.clone(), inner.clone()).prop_map(|(left, right)| {
+                        ConditionExpr::And(Box::new(left), Box::new(right))
+                    }),
+                    (inner.clone(), inner).prop_map(|(left, right)| ConditionExpr::Or(
+                        Box::new(left),
+                        Box::new(right)
+                    )),
+                ]
+            })
+        }
+
+        fn field_path() -> impl Strategy<Value = String> {
+            (
+                prop::collection::vec((ident(), any::<bool>()), 0..3),
+                ident(),
+            )
+                .prop_map(|(parents, field)| {
+                    let mut segments: Vec<String> = parents
+                        .into_iter()
+                        .map(|(name, expand)| if expand { format!("{name}[*]") } else { name })
+                        .collect();
+                    segments.push(field);
+                    segments.join(".")
+                })
+        }
+
+        fn transform_op() -> impl Strategy<Value = TransformOp> {
+            prop_oneof![
+                (field_path(), condition())
+                    .prop_map(|(path, condition)| TransformOp::Filter { path, condition }),
+                (
+                    field_path(),
+                    any::<bool>(),
+                    prop::collection::vec(ident(), 1..3)
+                )
+                    .prop_map(|(path, expand, fields)| TransformOp::Project {
+                        path: if expand { format!("{path}[*]") } else { path },
+                        fields,
+                    }),
+                (field_path(), literal())
+                    .prop_map(|(path, value)| TransformOp::Set { path, value }),
+                (field_path(), ident()).prop_map(|(path, to)| TransformOp::Rename { path, to }),
+            ]
+        }
+
+        fn report_format() -> impl Strategy<Value = ReportFormat> {
+            prop_oneof![
+                Just(ReportFormat::Stdout),
+                Just(ReportFormat::Html),
+                Just(ReportFormat::Markdown),
+                Just(ReportFormat::Sarif),
+                Just(ReportFormat::Csv),
+                Just(ReportFormat::JunitXml),
+                Just(ReportFormat::CycloneDx),
+            ]
+        }
+
+        fn secret_source() -> impl Strategy<Value = SecretSource> {
+            prop_oneof![
+                prop::collection::btree_map(ident(), text(), 1..3)
+                    .prop_map(|mappings| SecretSource::Env { mappings }),
+                (text(), prop::option::of(text()))
+                    .prop_map(|(path, key)| SecretSource::File { path, key }),
+                (
+                    text(),
+                    prop::option::of(text()),
+                    prop::option::of(text()),
+                    prop::option::of(text()),
+                )
+                    .prop_map(|(path, field, namespace, role_id)| {
+                        SecretSource::Vault {
+                            path,
+                            field,
+                            namespace,
+                            mount: None,
+                            address: None,
+                            role_id,
+                            secret_id: None,
+                        }
+                    }),
+                (text(), prop::option::of(text()), prop::option::of(text())).prop_map(
+                    |(secret_id, region, key)| SecretSource::AwsSecretsManager {
+                        secret_id,
+                        region,
+                        key,
+                    }
+                ),
+                (text(), text())
+                    .prop_map(|(service, account)| SecretSource::Keyring { service, account }),
+            ]
+        }
+
+        fn leaf_step() -> impl Strategy<Value = Step> {
+            // `tags` is reserved for step tags when it holds a list.
+            let params = || {
+                prop::collection::btree_map(ident(), block_text(), 0..3).prop_map(|mut params| {
+                    params.remove("tags");
+                    params.remove("retry");
+                    params
+                })
+            };
+            let retry = || {
+                (
+                    0..20u32,
+                    0..100_000u64,
+                    prop_oneof![
+                        Just(RetryBackoff::None),
+                        Just(RetryBackoff::Fixed),
+                        Just(RetryBackoff::Exponential),
+                    ],
+                )
+                    .prop_map(|(count, delay_ms, backoff)| RetryPolicy {
+                        count,
+                        delay_ms,
+                        backoff,
+                    })
+            };
+            let tags = || prop::collection::vec("[a-z][a-z0-9_-]{0,6}", 0..3);
+            prop_oneof![
+                (text(), prop::option::of(ident()))
+                    .prop_map(|(path, alias)| Step::Import(ImportStep { path, alias })),
+                (ident(), literal())
+                    .prop_map(|(name, value)| Step::Variable(VariableDecl { name, value })),
+                (ident(), prop::collection::vec(operand(), 0..3))
+                    .prop_map(|(function, args)| Step::Call(CallStep { function, args })),
+                (
+                    ident(),
+                    prop::collection::btree_map(ident(), text(), 0..3),
+                    tags()
+                )
+                    .prop_map(|(name, mut properties, tags)| {
+                        properties.remove("tags");
+                        Step::AssetGroup(AssetGroupStep {
+                            name,
+                            properties,
+                            tags,
+                            comments: BlockComments::new(),
+                        })
+                    }),
+                (
+                    ident(),
+                    ident(),
+                    params(),
+                    prop::option::of(ident()),
+                    tags(),
+                    prop::option::of(retry()),
+                )
+                    .prop_map(|(name, tool, params, output, tags, retry)| {
+                        Step::Scan(ScanStep {
+                            name,
+                            tool,
+                            params,
+                            output,
+                            tags,
+                            retry,
+                            on_failure: Vec::new(),
+                            comments: BlockComments::new(),
+                        })
+                    }),
+                (
+                    ident(),
+                    block_text(),
+                    params(),
+                    prop::option::of(ident()),
+                    tags(),
+                    prop::collection::btree_map("[A-Z][A-Z0-9_]{0,6}", text(), 0..3),
+                    prop::option::of(retry()),
+                )
+                    .prop_map(
+                        |(name, run, mut params, output, tags, env, retry)| {
+                            params.insert("run".to_string(), run);
+                            Step::Script(ScriptStep {
+                                name,
+                                params,
+                                output,
+                                tags,
+                                env,
+                                retry,
+                                on_failure: Vec::new(),
+                                comments: BlockComments::new(),
+                            })
+                        }
+                    ),
+                (
+                    ident(),
+                    report_format(),
+                    prop::collection::vec("[a-z_]{1,6}(:[a-z_]{1,6})?", 0..3),
+                    prop::option::of(text()),
+                    prop::collection::btree_map(ident(), "[a-z =]{0,8}", 0..2),
+                    prop::option::of(condition()),
+                    tags(),
+                )
+                    .prop_map(
+                        |(name, format, includes, output, mut options, filter, tags)| {
+                            options.remove("filter");
+                            if let Some(filter) = filter {
+                                options.insert("filter".to_string(), filter.to_string());
+                            }
+                            Step::Report(ReportStep {
+                                name,
+                                includes,
+                                format,
+                                output,
+                                options,
+                                tags,
+                                on_failure: Vec::new(),
+                                comments: BlockComments::new(),
+                            })
+                        }
+                    ),
+                (ident(), secret_source())
+                    .prop_map(|(name, source)| Step::Secret(SecretStep { name, source })),
+                (ident(), condition(), prop::option::of(text()), tags()).prop_map(
+                    |(name, condition, message, tags)| Step::Assert(AssertStep {
+                        name,
+                        condition,
+                        message,
+                        tags,
+                        on_failure: Vec::new(),
+                    })
+                ),
+                (
+                    ident(),
+                    text(),
+                    prop_oneof![Just("GET"), Just("POST"), Just("PUT")],
+                    prop::option::of(text()),
+                    prop::collection::btree_map("[A-Za-z][A-Za-z-]{0,6}", text(), 0..2),
+                    tags(),
+                )
+                    .prop_map(|(name, url, method, body, headers, tags)| {
+                        Step::Notify(NotifyStep {
+                            name,
+                            url,
+                            method: method.to_string(),
+                            body,
+                            headers,
+                            tags,
+                            on_failure: Vec::new(),
+                        })
+                    }),
+                (ident(), 0..10_000_000u64)
+                    .prop_map(|(name, duration_ms)| Step::Sleep(SleepStep { name, duration_ms })),
+                ident().prop_map(|name| Step::Checkpoint(CheckpointStep { name })),
+                (prop::collection::vec(text(), 1..3), text())
+                    .prop_map(|(inputs, output)| Step::Merge(MergeStep { inputs, output })),
+                (
+                    ident(),
+                    ident(),
+                    prop::option::of(ident()),
+                    prop::collection::vec(transform_op(), 1..4),
+                    tags(),
+                )
+                    .prop_map(|(name, source, output, operations, tags)| {
+                        Step::Transform(TransformStep {
+                            name,
+                            source,
+                            output,
+                            operations,
+                            tags,
+                            on_failure: Vec::new(),
+                        })
+                    }),
+                Just(Step::Break),
+                Just(Step::Continue),
+                "(#|//)( [a-z0-9]{1,6}){0,3}".prop_map(|text| Step::Comment(CommentStep {
+                    text,
+                    detached: false,
+                })),
+            ]
+        }
+
+        fn iterable() -> impl Strategy<Value = LoopIterable> {
+            prop_oneof![
+                ident().prop_map(LoopIterable::Variable),
+                (-50..50i64, -50..50i64, any::<bool>()).prop_map(|(start, end, inclusive)| {
+                    LoopIterable::Range {
+                        start,
+                        end,
+                        inclusive,
+                    }
+                }),
+                text().prop_map(|value| LoopIterable::Literal(LiteralValue::String(value))),
+                prop::collection::vec(scalar(), 0..4)
+                    .prop_map(|items| LoopIterable::Literal(LiteralValue::Array(items))),
+            ]
+        }
+
+        fn step() -> impl Strategy<Value = Step> {
+            leaf_step().prop_recursive(3, 24, 4, |inner| {
+                let block = prop::collection::vec(inner.clone(), 0..4);
+                prop_oneof![
+                    (leaf_step(), prop::collection::vec(inner, 1..3)).prop_map(
+                        |(mut step, handlers)| {
+                            if let Some(on_failure) = step.on_failure_mut() {
+                                *on_failure = handlers;
+                            }
+                            step
+                        }
+                    ),
+                    (condition(), block.clone(), block.clone()).prop_map(
+                        |(condition, then_steps, else_steps)| Step::Conditional(ConditionalStep {
+                            condition,
+                            then_steps,
+                            else_steps,
+                        })
+                    ),
+                    (
+                        (ident(), prop::option::of(ident()))
+                            .prop_filter("distinct loop variables", |(key, value)| {
+                                value.as_ref() != Some(key)
+                            }),
+                        iterable(),
+                        prop::option::of(condition()),
+                        prop::option::of(1..100usize),
+                        block.clone(),
+                    )
+                        .prop_map(
+                            |((iterator, value_iterator), iterable, guard, max_iter, body)| {
+                                Step::Loop(LoopStep {
+                                    iterator,
+                                    value_iterator,
+                                    iterable,
+                                    guard,
+                                    max_iter,
+                                    body,
+                                })
+                            }
+                        ),
+                    (
+                        ident(),
+                        prop::collection::btree_set(ident(), 0..3),
+                        block.clone()
+                    )
+                        .prop_map(|(name, params, body)| Step::Function(
+                            FunctionDef {
+                                name,
+                                params: params.into_iter().collect(),
+                                body,
+                            }
+                        )),
+                    (prop::option::of(ident()), block)
+                        .prop_map(|(name, steps)| Step::Parallel(ParallelStep { name, steps })),
+                ]
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn to_dsl_round_trips_through_the_parser(steps in prop::collection::vec(step(), 0..6)) {
+                let scenario = Scenario { steps, ..Scenario::default() };
+                let rendered = scenario.to_dsl();
+                let reparsed = parse_scenario_all(&rendered)
+                    .map_err(|errors| TestCaseError::fail(format!("{errors:?}\n{rendered}")))?;
+                prop_assert_eq!(
+                    serde_json::to_value(&reparsed.steps).unwrap(),
+                    serde_json::to_value(&scenario.steps).unwrap(),
+                    "{}",
+                    rendered
+                );
+                prop_assert_eq!(reparsed.to_dsl(), rendered);
+            }
+        }
+    }
 }
@@ -0,0 +1,746 @@
+use crate::runtime::sanitize_label;
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::error::DisplayErrorContext;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::ServerSideEncryption;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Destination for artifact JSON produced while a scenario runs.
+///
+/// `write` returns the location the artifact was stored at, if it has one
+/// (for example a file path or object URL); step messages fall back to
+/// `<memory>` when it is `None`. `read` accepts artifacts stored by either
+/// write method and decompresses gzip payloads transparently.
+pub trait ArtifactStore: fmt::Debug + Send + Sync {
+    fn write(&self, label: &str, data: &Value) -> Result<Option<String>, String>;
+    fn read(&self, label: &str) -> Result<Option<Value>, String>;
+
+    /// Stores a gzip-compressed copy of the artifact. Stores that cannot hold
+    /// compressed data fall back to `write`.
+    fn write_compressed(&self, label: &str, data: &Value) -> Result<Option<String>, String> {
+        self.write(label, data)
+    }
+
+    /// Stores the artifact under its content `digest` (see [`content_digest`])
+    /// and keeps `label` resolvable for `read`. Stores without a content
+    /// addressed layout fall back to a plain write under `label`.
+    fn write_content_addressed(
+        &self,
+        label: &str,
+        _digest: &str,
+        data: &Value,
+        compress: bool,
+    ) -> Result<Option<String>, String> {
+        if compress {
+            self.write_compressed(label, data)
+        } else {
+            self.write(label, data)
+        }
+    }
+}
+
+/// Hex-encoded SHA-256 of the artifact's serialised JSON.
+pub fn content_digest(data: &Value) -> Result<String, String> {
+    let bytes = serde_json::to_vec_pretty(data)
+        .map_err(|err| format!("failed to serialize artifact for hashing: {err}"))?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const CAS_DIRECTORY: &str = "cas";
+
+fn encode_artifact(label: &str, data: &Value, compress: bool) -> Result<Vec<u8>, String> {
+    let bytes = serde_json::to_vec_pretty(data)
+        .map_err(|err| format!("failed to serialize artifact '{}': {err}", label))?;
+    if !compress {
+        return Ok(bytes);
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&bytes)
+        .and_then(|_| encoder.finish())
+        .map_err(|err| format!("failed to compress artifact '{}': {err}", label))
+}
+
+fn decode_artifact(location: &str, bytes: &[u8]) -> Result<Value, String> {
+    if bytes.starts_with(&GZIP_MAGIC) {
+        let mut json = Vec::new();
+        GzDecoder::new(bytes)
+            .read_to_end(&mut json)
+            .map_err(|err| format!("failed to decompress artifact {location}: {err}"))?;
+        return serde_json::from_slice(&json)
+            .map_err(|err| format!("failed to parse artifact {location}: {err}"));
+    }
+    serde_json::from_slice(bytes)
+        .map_err(|err| format!("failed to parse artifact {location}: {err}"))
+}
+
+fn artifact_file_name(label: &str, compressed: bool) -> String {
+    let extension = if compressed { "json.gz" } else { "json" };
+    format!("{}.{extension}", sanitize_label(label))
+}
+
+/// Writes each artifact to `<root>/<label>.json` (or `<label>.json.gz` when
+/// compressed), with the label sanitised for use as a file name. This is the
+/// executor's default store.
+#[derive(Debug, Clone)]
+pub struct FilesystemArtifactStore {
+    root: PathBuf,
+}
+
+impl FilesystemArtifactStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &PathBuf {
+        &self.root
+    }
+
+    fn store(&self, label: &str, data: &Value, compress: bool) -> Result<Option<String>, String> {
+        let path = self.root.join(artifact_file_name(label, compress));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| {
+                format!("failed to create artifact directory {:?}: {err}", parent)
+            })?;
+        }
+        let bytes = encode_artifact(label, data, compress)?;
+        write_atomically(&path, |file| file.write_all(&bytes))
+            .map_err(|err| format!("failed to write artifact {:?}: {err}", path))?;
+        // Drop the copy in the other encoding so `read` never sees a stale file.
+        let _ = fs::remove_file(self.root.join(artifact_file_name(label, !compress)));
+        Ok(Some(path.to_string_lossy().to_string()))
+    }
+}
+
+impl ArtifactStore for FilesystemArtifactStore {
+    fn write(&self, label: &str, data: &Value) -> Result<Option<String>, String> {
+        self.store(label, data, false)
+    }
+
+    fn write_compressed(&self, label: &str, data: &Value) -> Result<Option<String>, String> {
+        self.store(label, data, true)
+    }
+
+    // Content lives in `cas/<digest>.json`; `<label>.json` becomes a relative
+    // symlink to it (a copy on platforms without symlinks). Identical content
+    // is written once.
+    fn write_content_addressed(
+        &self,
+        label: &str,
+        digest: &str,
+        data: &Value,
+        compress: bool,
+    ) -> Result<Option<String>, String> {
+        let cas_dir = self.root.join(CAS_DIRECTORY);
+        fs::create_dir_all(&cas_dir)
+            .map_err(|err| format!("failed to create artifact directory {:?}: {err}", cas_dir))?;
+        let extension = if compress { "json.gz" } else { "json" };
+        let object_name = format!("{digest}.{extension}");
+        let object = cas_dir.join(&object_name);
+        if !object.exists() {
+            let bytes = encode_artifact(label, data, compress)?;
+            write_atomically(&object, |file| file.write_all(&bytes))
+                .map_err(|err| format!("failed to write artifact {:?}: {err}", object))?;
+        }
+
+        let link = self.root.join(artifact_file_name(label, compress));
+        link_atomically(&Path::new(CAS_DIRECTORY).join(&object_name), &object, &link)
+            .map_err(|err| format!("failed to link artifact {:?}: {err}", link))?;
+        let _ = fs::remove_file(self.root.join(artifact_file_name(label, !compress)));
+        Ok(Some(object.to_string_lossy().to_string()))
+    }
+
+    fn read(&self, label: &str) -> Result<Option<Value>, String> {
+        for compressed in [false, true] {
+            let path = self.root.join(artifact_file_name(label, compressed));
+            if !path.exists() {
+                continue;
+            }
+            let contents = fs::read(&path)
+                .map_err(|err| format!("failed to read artifact {:?}: {err}", path))?;
+            return decode_artifact(&path.to_string_lossy(), &contents).map(Some);
+        }
+        Ok(None)
+    }
+}
+
+// Writes through a temp file in the destination directory, syncs it, and
+// renames it over `path`, so a failed or interrupted write never leaves a
+// truncated artifact behind. The temp file is removed on drop if the write
+// fails.
+pub(crate) fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> io::Result<()>,
+) -> io::Result<()> {
+    let directory = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut temp = tempfile::Builder::new()
+        .prefix(&format!("{file_name}."))
+        .suffix(".tmp")
+        .tempfile_in(directory)?;
+    write(temp.as_file_mut())?;
+    temp.as_file_mut().flush()?;
+    temp.as_file().sync_all()?;
+    temp.persist(path).map_err(|err| err.error)?;
+    Ok(())
+}
+
+// Points `link` at `target` (relative to the link's directory) by creating the
+// link under a temporary name and renaming it over any previous one.
+fn link_atomically(target: &Path, resolved: &Path, link: &Path) -> io::Result<()> {
+    let staging = link.with_extension(format!("link-{}.tmp", uuid::Uuid::new_v4()));
+    #[cfg(unix)]
+    let created = {
+        let _ = resolved;
+        std::os::unix::fs::symlink(target, &staging)
+    };
+    #[cfg(not(unix))]
+    let created = {
+        let _ = target;
+        fs::copy(resolved, &staging).map(|_| ())
+    };
+    created?;
+    fs::rename(&staging, link).inspect_err(|_| {
+        let _ = fs::remove_file(&staging);
+    })
+}
+
+/// Keeps serialised artifacts in memory. Clones share the same entries, so a
+/// test can hand one clone to the executor and inspect another afterwards.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryArtifactStore {
+    entries: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl InMemoryArtifactStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn labels(&self) -> Vec<String> {
+        let mut labels: Vec<String> = self
+            .entries
+            .lock()
+            .map(|entries| entries.keys().cloned().collect())
+            .unwrap_or_default();
+        labels.sort();
+        labels
+    }
+
+    /// Returns the stored bytes for `label`, gzip-compressed if the artifact
+    /// was written with `write_compressed`.
+    pub fn bytes(&self, label: &str) -> Option<Vec<u8>> {
+        self.entries.lock().ok()?.get(label).cloned()
+    }
+
+    fn store(&self, label: &str, data: &Value, compress: bool) -> Result<Option<String>, String> {
+        let bytes = encode_artifact(label, data, compress)?;
+        self.entries
+            .lock()
+            .map_err(|_| "artifact store lock poisoned".to_string())?
+            .insert(label.to_string(), bytes);
+        Ok(None)
+    }
+}
+
+impl ArtifactStore for InMemoryArtifactStore {
+    fn write(&self, label: &str, data: &Value) -> Result<Option<String>, String> {
+        self.store(label, data, false)
+    }
+
+    fn write_compressed(&self, label: &str, data: &Value) -> Result<Option<String>, String> {
+        self.store(label, data, true)
+    }
+
+    fn read(&self, label: &str) -> Result<Option<Value>, String> {
+        self.bytes(label)
+            .map(|bytes| decode_artifact(label, &bytes))
+            .transpose()
+    }
+}
+
+/// Connection settings for [`S3ArtifactStore`]. Credentials fall back to the
+/// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, and `AWS_SESSION_TOKEN`
+/// environment variables when unset; `endpoint` targets S3-compatible services
+/// (MinIO, LocalStack) and switches to path-style addressing.
+#[derive(Clone, Default)]
+pub struct S3ArtifactStoreConfig {
+    pub bucket: String,
+    pub prefix: String,
+    pub region: String,
+    pub endpoint: Option<String>,
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    pub session_token: Option<String>,
+    /// Server-side encryption applied to every object: `AES256` or `aws:kms`.
+    pub sse: Option<String>,
+}
+
+impl S3ArtifactStoreConfig {
+    pub fn new(bucket: impl Into<String>, region: impl Into<String>) -> Self {
+        Self {
+            bucket: bucket.into(),
+            region: region.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Applies a store option by name. Recognised keys are `prefix`,
+    /// `endpoint`, and `sse`.
+    pub fn with_option(mut self, key: &str, value: impl Into<String>) -> Result<Self, String> {
+        let value = value.into();
+        match key {
+            "prefix" => self.prefix = value,
+            "endpoint" => self.endpoint = Some(value),
+            "sse" => self.sse = Some(value),
+            other => return Err(format!("unknown S3 artifact store option '{other}'")),
+        }
+        Ok(self)
+    }
+}
+
+impl fmt::Debug for S3ArtifactStoreConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3ArtifactStoreConfig")
+            .field("bucket", &self.bucket)
+            .field("prefix", &self.prefix)
+            .field("region", &self.region)
+            .field("endpoint", &self.endpoint)
+            .field("access_key_id", &self.access_key_id)
+            .field(
+                "secret_access_key",
+                &self.secret_access_key.as_ref().map(|_| "***"),
+            )
+            .field("session_token", &self.session_token.as_ref().map(|_| "***"))
+            .field("sse", &self.sse)
+            .finish()
+    }
+}
+
+/// Stores artifacts as `<prefix>/<label>.json` objects in an S3 bucket and
+/// reports their `s3://` URI as the artifact path. Requests run on a private
+/// current-thread Tokio runtime so the executor stays synchronous.
+#[derive(Debug)]
+pub struct S3ArtifactStore {
+    bucket: String,
+    prefix: String,
+    sse: Option<ServerSideEncryption>,
+    client: aws_sdk_s3::Client,
+    runtime: tokio::runtime::Runtime,
+}
+
+impl S3ArtifactStore {
+    pub fn new(config: S3ArtifactStoreConfig) -> Result<Self, String> {
+        if config.bucket.trim().is_empty() {
+            return Err("S3 artifact store requires a bucket".to_string());
+        }
+        if config.region.trim().is_empty() {
+            return Err("S3 artifact store requires a region".to_string());
+        }
+        let sse = match config.sse.as_deref() {
+            None => None,
+            Some(value @ ("AES256" | "aws:kms")) => Some(ServerSideEncryption::from(value)),
+            Some(other) => {
+                return Err(format!(
+                    "unsupported S3 server-side encryption '{other}' (expected AES256 or aws:kms)"
+                ))
+            }
+        };
+
+        let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let access_key_id = config
+            .access_key_id
+            .clone()
+            .or_else(|| env("AWS_ACCESS_KEY_ID"))
+            .ok_or_else(|| "S3 artifact store requires an access key id".to_string())?;
+        let secret_access_key = config
+            .secret_access_key
+            .clone()
+            .or_else(|| env("AWS_SECRET_ACCESS_KEY"))
+            .ok_or_else(|| "S3 artifact store requires a secret access key".to_string())?;
+        let session_token = config
+            .session_token
+            .clone()
+            .or_else(|| env("AWS_SESSION_TOKEN"));
+
+        let mut builder = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new(config.region.clone()))
+            .credentials_provider(Credentials::new(
+                access_key_id,
+                secret_access_key,
+                session_token,
+                None,
+                "axion",
+            ));
+        if let Some(endpoint) = &config.endpoint {
+            builder = builder.endpoint_url(endpoint).force_path_style(true);
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| format!("failed to start S3 runtime: {err}"))?;
+
+        Ok(Self {
+            bucket: config.bucket,
+            prefix: config.prefix.trim_matches('/').to_string(),
+            sse,
+            client: aws_sdk_s3::Client::from_conf(builder.build()),
+            runtime,
+        })
+    }
+
+    fn key_for(&self, label: &str, compressed: bool) -> String {
+        let name = artifact_file_name(label, compressed);
+        if self.prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", self.prefix, name)
+        }
+    }
+
+    fn upload(&self, label: &str, data: &Value, compress: bool) -> Result<Option<String>, String> {
+        let key = self.key_for(label, compress);
+        let bytes = encode_artifact(label, data, compress)?;
+        let content_type = if compress {
+            "application/gzip"
+        } else {
+            "application/json"
+        };
+        let request = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .content_type(content_type)
+            .set_server_side_encryption(self.sse.clone())
+            .body(ByteStream::from(bytes));
+        self.runtime.block_on(request.send()).map_err(|err| {
+            format!(
+                "failed to upload artifact to s3://{}/{key}: {}",
+                self.bucket,
+                DisplayErrorContext(&err)
+            )
+        })?;
+        Ok(Some(format!("s3://{}/{key}", self.bucket)))
+    }
+
+    fn download(&self, key: &str) -> Result<Option<Value>, String> {
+        let location = format!("s3://{}/{key}", self.bucket);
+        let request = self.client.get_object().bucket(&self.bucket).key(key);
+        let bytes = self.runtime.block_on(async {
+            match request.send().await {
+                Ok(output) => output
+                    .body
+                    .collect()
+                    .await
+                    .map(|body| Some(body.into_bytes()))
+                    .map_err(|err| format!("failed to read {location}: {err}")),
+                Err(err) if err.as_service_error().is_some_and(|e| e.is_no_such_key()) => Ok(None),
+                Err(err) => Err(format!(
+                    "failed to download {location}: {}",
+                    DisplayErrorContext(&err)
+                )),
+            }
+        })?;
+        bytes
+            .map(|bytes| decode_artifact(&location, &bytes))
+            .transpose()
+    }
+}
+
+impl ArtifactStore for S3ArtifactStore {
+    fn write(&self, label: &str, data: &Value) -> Result<Option<String>, String> {
+        self.upload(label, data, false)
+    }
+
+    fn write_compressed(&self, label: &str, data: &Value) -> Result<Option<String>, String> {
+        self.upload(label, data, true)
+    }
+
+    fn read(&self, label: &str) -> Result<Option<Value>, String> {
+        match self.download(&self.key_for(label, false))? {
+            Some(value) => Ok(Some(value)),
+            None => self.download(&self.key_for(label, true)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::thread;
+
+    // Serves one canned S3 response per connection and returns each request's
+    // head and body.
+    fn spawn_mock_s3(
+        responses: Vec<(u16, &'static str, String)>,
+    ) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let address = format!("http://{}", listener.local_addr().expect("mock address"));
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, content_type, body) in responses {
+                let (stream, _) = listener.accept().expect("accept mock connection");
+                let mut reader = BufReader::new(stream);
+                let mut head = String::new();
+                let mut content_length = 0usize;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("read request line");
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap_or(0);
+                    }
+                    head.push_str(&line);
+                }
+                let mut payload = vec![0u8; content_length];
+                reader.read_exact(&mut payload).expect("read request body");
+                head.push_str(&String::from_utf8_lossy(&payload));
+                requests.push(head);
+
+                let response = format!(
+                    "HTTP/1.1 {status} Mock\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nETag: \"mock\"\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let mut stream = reader.into_inner();
+                stream
+                    .write_all(response.as_bytes())
+                    .expect("write mock response");
+            }
+            requests
+        });
+        (address, handle)
+    }
+
+    #[test]
+    fn s3_store_puts_and_gets_objects_under_prefix() {
+        let missing = "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Error><Code>NoSuchKey</Code><Message>missing</Message></Error>";
+        let (address, server) = spawn_mock_s3(vec![
+            (200, "application/xml", String::new()),
+            (200, "application/json", r#"{"rows":3}"#.to_string()),
+            (404, "application/xml", missing.to_string()),
+            (404, "application/xml", missing.to_string()),
+        ]);
+        let config = S3ArtifactStoreConfig {
+            access_key_id: Some("AKIDEXAMPLE".to_string()),
+            secret_access_key: Some("secret".to_string()),
+            ..S3ArtifactStoreConfig::new("axion-artifacts", "us-east-1")
+        }
+        .with_option("prefix", "runs/42/")
+        .and_then(|config| config.with_option("endpoint", address))
+        .and_then(|config| config.with_option("sse", "AES256"))
+        .expect("valid options");
+        let store = S3ArtifactStore::new(config).expect("store builds");
+
+        let location = store
+            .write("report:weekly", &json!({ "rows": 3 }))
+            .expect("upload succeeds");
+        assert_eq!(
+            location.as_deref(),
+            Some("s3://axion-artifacts/runs/42/report_weekly.json")
+        );
+        assert_eq!(
+            store.read("report:weekly").expect("download succeeds"),
+            Some(json!({ "rows": 3 }))
+        );
+        assert_eq!(
+            store.read("missing").expect("missing key is not an error"),
+            None
+        );
+
+        let requests = server.join().expect("mock server");
+        let put = requests[0].to_ascii_lowercase();
+        assert!(
+            put.starts_with("put /axion-artifacts/runs/42/report_weekly.json"),
+            "{put}"
+        );
+        assert!(
+            put.contains("x-amz-server-side-encryption: aes256"),
+            "{put}"
+        );
+        assert!(put.contains("\"rows\": 3"), "{put}");
+        assert!(requests[2].starts_with("GET /axion-artifacts/runs/42/missing.json?"));
+        assert!(requests[3].starts_with("GET /axion-artifacts/runs/42/missing.json.gz?"));
+    }
+
+    #[test]
+    fn s3_store_rejects_unknown_encryption() {
+        let config = S3ArtifactStoreConfig {
+            access_key_id: Some("AKIDEXAMPLE".to_string()),
+            secret_access_key: Some("secret".to_string()),
+            sse: Some("rot13".to_string()),
+            ..S3ArtifactStoreConfig::new("axion-artifacts", "us-east-1")
+        };
+        let err = S3ArtifactStore::new(config).expect_err("sse is validated");
+        assert!(err.contains("rot13"), "{err}");
+    }
+
+    #[test]
+    fn filesystem_store_round_trips_sanitised_labels() {
+        let root = std::env::temp_dir().join(format!("axion-store-{}", uuid::Uuid::new_v4()));
+        let store = FilesystemArtifactStore::new(&root);
+
+        let location = store
+            .write("report:weekly", &json!({ "rows": 3 }))
+            .expect("write succeeds");
+        assert_eq!(
+            location,
+            Some(
+                root.join("report_weekly.json")
+                    .to_string_lossy()
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            store.read("report:weekly").expect("read succeeds"),
+            Some(json!({ "rows": 3 }))
+        );
+        assert_eq!(store.read("missing").expect("read succeeds"), None);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn filesystem_store_compresses_with_gzip() {
+        let root = std::env::temp_dir().join(format!("axion-store-{}", uuid::Uuid::new_v4()));
+        let store = FilesystemArtifactStore::new(&root);
+
+        store
+            .write("findings_web", &json!({ "findings": [] }))
+            .expect("plain write succeeds");
+        let location = store
+            .write_compressed("findings_web", &json!({ "findings": [1] }))
+            .expect("compressed write succeeds")
+            .expect("filesystem path");
+        assert!(location.ends_with("findings_web.json.gz"), "{location}");
+
+        let bytes = fs::read(&location).expect("compressed file exists");
+        assert_eq!(bytes[..2], GZIP_MAGIC);
+        assert!(!root.join("findings_web.json").exists());
+        assert_eq!(
+            store.read("findings_web").expect("read succeeds"),
+            Some(json!({ "findings": [1] }))
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn failed_atomic_write_keeps_previous_artifact() {
+        let root = std::env::temp_dir().join(format!("axion-store-{}", uuid::Uuid::new_v4()));
+        let store = FilesystemArtifactStore::new(&root);
+        store
+            .write("scan", &json!({ "findings": [1, 2] }))
+            .expect("initial write succeeds");
+        let path = root.join("scan.json");
+
+        let result = write_atomically(&path, |file| {
+            file.write_all(b"{\"findings\": [")?;
+            Err(io::Error::other("disk full"))
+        });
+        assert!(result.is_err());
+        assert_eq!(
+            store.read("scan").expect("artifact still parses"),
+            Some(json!({ "findings": [1, 2] }))
+        );
+
+        let fresh = root.join("fresh.json");
+        let result = write_atomically(&fresh, |file| {
+            file.write_all(b"{\"find")?;
+            Err(io::Error::other("interrupted"))
+        });
+        assert!(result.is_err());
+        assert!(!fresh.exists());
+
+        let leftovers: Vec<_> = fs::read_dir(&root)
+            .expect("list artifacts")
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty(), "{leftovers:?}");
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn content_addressed_writes_share_one_object() {
+        let root = std::env::temp_dir().join(format!("axion-store-{}", uuid::Uuid::new_v4()));
+        let store = FilesystemArtifactStore::new(&root);
+        let data = json!({ "tool": "nmap", "findings": [] });
+
+        let first_digest = content_digest(&data).expect("digest");
+        let second_digest = content_digest(&data.clone()).expect("digest");
+        assert_eq!(first_digest, second_digest);
+        assert_eq!(first_digest.len(), 64);
+
+        let first = store
+            .write_content_addressed("findings_a", &first_digest, &data, false)
+            .expect("first write succeeds");
+        let second = store
+            .write_content_addressed("findings_b", &second_digest, &data, false)
+            .expect("second write succeeds");
+        let expected = root
+            .join("cas")
+            .join(format!("{first_digest}.json"))
+            .to_string_lossy()
+            .to_string();
+        assert_eq!(first.as_deref(), Some(expected.as_str()));
+        assert_eq!(first, second);
+
+        assert_eq!(fs::read_dir(root.join("cas")).expect("cas dir").count(), 1);
+        assert_eq!(
+            store.read("findings_a").expect("read a"),
+            Some(data.clone())
+        );
+        assert_eq!(store.read("findings_b").expect("read b"), Some(data));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn in_memory_store_keeps_compressed_bytes() {
+        let store = InMemoryArtifactStore::new();
+        store
+            .write_compressed("scan", &json!({ "tool": "nmap" }))
+            .expect("compressed write succeeds");
+
+        let bytes = store.bytes("scan").expect("stored bytes");
+        assert_eq!(bytes[..2], GZIP_MAGIC);
+        assert_eq!(store.read("scan"), Ok(Some(json!({ "tool": "nmap" }))));
+    }
+
+    #[test]
+    fn in_memory_store_shares_entries_between_clones() {
+        let store = InMemoryArtifactStore::new();
+        let handle = store.clone();
+
+        assert_eq!(store.write("scan", &json!([1, 2])), Ok(None));
+        assert_eq!(handle.read("scan"), Ok(Some(json!([1, 2]))));
+        assert_eq!(handle.labels(), vec!["scan".to_string()]);
+    }
+}
@@ -1,9 +1,18 @@
+use crate::runtime::{
+    is_include_pattern, masscan_emits_xml, nuclei_emits_json, parse_deduplicate_options,
+    parse_sort_option, placeholder_root, split_placeholder, PlaceholderFallback, SuppressionRule,
+    DEFAULT_MAX_ITERATIONS, LOOP_INDEX1_VAR, LOOP_INDEX_VAR, LOOP_VAR_PREFIX, MAX_EXPANDED_HOSTS,
+    PLACEHOLDER_TRANSFORMS, SCAN_TABLE_COLUMNS,
+};
 use crate::scenario::{
-    LiteralValue, LoopIterable, LoopStep, ReportStep, ScanStep, Scenario, ScriptStep, SecretSource,
-    SecretStep, Step,
+    format_duration_ms, parse_condition_expr, parse_duration_ms, AssertStep, ConditionExpr,
+    ConditionOperand, LiteralValue, LoopIterable, LoopStep, MergeStep, NotifyStep, ParallelStep,
+    ReportFormat, ReportStep, RetryPolicy, ScanStep, Scenario, ScriptStep, SecretSource,
+    SecretStep, Step, TransformOp, TransformStep,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashSet};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -11,6 +20,7 @@ use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 pub enum DiagnosticLevel {
     Error,
     Warning,
+    Info,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,25 +47,255 @@ impl Diagnostic {
         }
     }
 
+    fn info(location: Option<String>, message: impl Into<String>) -> Self {
+        Self {
+            level: DiagnosticLevel::Info,
+            location,
+            message: message.into(),
+        }
+    }
+
     pub fn is_error(&self) -> bool {
         matches!(self.level, DiagnosticLevel::Error)
     }
+
+    pub fn is_warning(&self) -> bool {
+        matches!(self.level, DiagnosticLevel::Warning)
+    }
+
+    pub fn is_info(&self) -> bool {
+        matches!(self.level, DiagnosticLevel::Info)
+    }
 }
 
 pub fn validate_scenario(scenario: &Scenario) -> Vec<Diagnostic> {
+    validate_scenario_with_vars(scenario, &HashMap::new())
+}
+
+/// Like [`validate_scenario`], treating `overrides` (e.g. `--var` values) as
+/// declared before the first step.
+pub fn validate_scenario_with_vars(
+    scenario: &Scenario,
+    overrides: &HashMap<String, LiteralValue>,
+) -> Vec<Diagnostic> {
+    validate_scenario_with_schemas(scenario, overrides, &[])
+}
+
+/// Like [`validate_scenario_with_vars`], checking tool parameters against
+/// `schemas` (e.g. from [`parse_schema_plugin`]) before the builtin schemas.
+pub fn validate_scenario_with_schemas(
+    scenario: &Scenario,
+    overrides: &HashMap<String, LiteralValue>,
+    schemas: &[ToolSchema],
+) -> Vec<Diagnostic> {
     let mut ctx = ValidationContext::new();
+    ctx.plugin_schemas = schemas.to_vec();
+    ctx.variables.extend(overrides.keys().cloned());
+    ctx.overrides.extend(overrides.keys().cloned());
+    ctx.known_tags = scenario.steps.iter().find_map(|step| match step {
+        Step::Variable(var) if var.name == "known_tags" => match &var.value {
+            LiteralValue::Array(items) => Some(
+                items
+                    .iter()
+                    .filter_map(|item| match item {
+                        LiteralValue::String(tag) => Some(tag.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        },
+        _ => None,
+    });
+    validate_import_aliases(scenario, &mut ctx);
+    validate_functions(scenario, &mut ctx);
     validate_steps(&scenario.steps, &mut ctx);
     ctx.finish()
 }
 
+fn validate_import_aliases(scenario: &Scenario, ctx: &mut ValidationContext) {
+    let mut seen = HashSet::new();
+    for alias in &scenario.aliases {
+        if !seen.insert(alias.alias.as_str()) {
+            ctx.error(format!(
+                "import alias '{}' is used by more than one import",
+                alias.alias
+            ));
+        }
+    }
+
+    let mut local_names = HashSet::new();
+    collect_step_names(&scenario.steps, &mut local_names);
+    for alias in seen {
+        if local_names.contains(alias) {
+            ctx.warning(format!(
+                "import alias '{alias}' shadows a local step with the same name"
+            ));
+        }
+    }
+}
+
+// Functions may be called before their definition, so register them all up front.
+fn validate_functions(scenario: &Scenario, ctx: &mut ValidationContext) {
+    for step in &scenario.steps {
+        if let Step::Function(function) = step {
+            if ctx.functions.contains_key(&function.name) {
+                ctx.error(format!(
+                    "function '{}' is defined more than once",
+                    function.name
+                ));
+            }
+            ctx.functions
+                .insert(function.name.clone(), (function.params.len(), Vec::new()));
+        }
+    }
+    for cycle in scenario.function_cycles() {
+        ctx.error(format!(
+            "recursive function call: {}; functions cannot call themselves",
+            cycle.join(" -> ")
+        ));
+    }
+}
+
+fn collect_step_names<'a>(steps: &'a [Step], names: &mut HashSet<&'a str>) {
+    for step in steps {
+        if let Some(name) = step.name() {
+            names.insert(name);
+        }
+        match step {
+            Step::Conditional(block) => {
+                collect_step_names(&block.then_steps, names);
+                collect_step_names(&block.else_steps, names);
+            }
+            Step::Loop(loop_step) => collect_step_names(&loop_step.body, names),
+            Step::Parallel(block) => collect_step_names(&block.steps, names),
+            Step::Function(function) => collect_step_names(&function.body, names),
+            _ => {}
+        }
+        collect_step_names(step.on_failure(), names);
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolSchema {
     pub name: String,
     pub kind: Option<String>,
     pub description: Option<String>,
+    #[serde(default)]
     pub required: Vec<String>,
+    #[serde(default)]
     pub optional: Vec<String>,
+    #[serde(default)]
     pub allow_additional: bool,
+    #[serde(default)]
+    pub examples: Vec<ToolSchemaExample>,
+}
+
+/// A sample parameter set for a tool, shown by `axion schema`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolSchemaExample {
+    pub description: String,
+    pub params: BTreeMap<String, String>,
+}
+
+impl ToolSchema {
+    fn allows(&self, key: &str) -> bool {
+        self.required
+            .iter()
+            .chain(self.optional.iter())
+            .any(|candidate| candidate == key)
+    }
+
+    /// The tool's parameters as a JSON Schema object. Scenario parameters
+    /// are always strings, so every property is typed `string`.
+    pub fn to_json_schema(&self) -> Value {
+        let properties: serde_json::Map<String, Value> = self
+            .required
+            .iter()
+            .chain(self.optional.iter())
+            .map(|param| (param.clone(), json!({ "type": "string" })))
+            .collect();
+        let mut schema = json!({
+            "title": self.name,
+            "type": "object",
+            "required": self.required,
+            "properties": properties,
+            "additionalProperties": self.allow_additional,
+        });
+        if let Some(description) = &self.description {
+            schema["description"] = json!(description);
+        }
+        if !self.examples.is_empty() {
+            let examples: Vec<&BTreeMap<String, String>> = self
+                .examples
+                .iter()
+                .map(|example| &example.params)
+                .collect();
+            schema["examples"] = json!(examples);
+        }
+        schema
+    }
+}
+
+/// Dialect declared by `tool_json_schema` documents.
+pub const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// A draft 2020-12 JSON Schema document with one `$defs` entry per tool.
+/// `definitions` points at the same entries for draft-07 consumers such as
+/// vscode-yaml.
+pub fn tool_json_schema(bundle: &ToolSchemaBundle) -> Value {
+    let defs: serde_json::Map<String, Value> = bundle
+        .tools
+        .iter()
+        .map(|schema| (schema.name.clone(), schema.to_json_schema()))
+        .collect();
+    let definitions: serde_json::Map<String, Value> = bundle
+        .tools
+        .iter()
+        .map(|schema| {
+            let reference = format!("#/$defs/{}", schema.name);
+            (schema.name.clone(), json!({ "$ref": reference }))
+        })
+        .collect();
+    json!({
+        "$schema": JSON_SCHEMA_DIALECT,
+        "title": "Axion tool parameters",
+        "description": format!("Tool parameter schemas, version {}", bundle.version),
+        "$defs": defs,
+        "definitions": definitions,
+    })
+}
+
+// A schema plugin file: `[[tool]]` tables with the `ToolSchema` fields.
+#[derive(Deserialize)]
+struct SchemaPlugin {
+    #[serde(default)]
+    tool: Vec<ToolSchema>,
+}
+
+/// Parses a TOML schema plugin, one `[[tool]]` table per tool:
+///
+/// ```toml
+/// [[tool]]
+/// name = "semgrep"
+/// kind = "scan"
+/// description = "Semgrep static analysis"
+/// required = ["target", "config"]
+/// optional = ["flags", "timeout"]
+/// allow_additional = false
+/// ```
+pub fn parse_schema_plugin(source: &str) -> Result<Vec<ToolSchema>, String> {
+    let plugin: SchemaPlugin =
+        toml::from_str(source).map_err(|err| format!("invalid schema plugin: {err}"))?;
+    for (index, schema) in plugin.tool.iter().enumerate() {
+        if schema.name.trim().is_empty() {
+            return Err(format!(
+                "schema plugin tool #{} has an empty name",
+                index + 1
+            ));
+        }
+    }
+    Ok(plugin.tool)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,30 +308,60 @@ pub struct ToolSchemaBundle {
 pub fn builtin_tool_schemas() -> Vec<ToolSchema> {
     BUILTIN_SCHEMAS
         .iter()
-        .map(|schema| ToolSchema {
-            name: schema.name.to_string(),
-            kind: Some(schema.kind.to_string()),
-            description: Some(schema.description.to_string()),
-            required: schema.required.iter().map(|s| (*s).to_string()).collect(),
-            optional: schema.optional.iter().map(|s| (*s).to_string()).collect(),
-            allow_additional: schema.allow_additional,
-        })
+        .map(ToolSchemaDef::to_schema)
         .collect()
 }
 
 pub fn builtin_tool_schema_bundle() -> ToolSchemaBundle {
+    tool_schema_bundle(&[])
+}
+
+/// The builtin schemas plus `plugins`, which replace builtins of the same name.
+pub fn tool_schema_bundle(plugins: &[ToolSchema]) -> ToolSchemaBundle {
+    let mut tools = plugins.to_vec();
+    tools.extend(
+        builtin_tool_schemas()
+            .into_iter()
+            .filter(|schema| !plugins.iter().any(|plugin| plugin.name == schema.name)),
+    );
     ToolSchemaBundle {
         version: SCHEMA_VERSION.to_string(),
         generated_at: OffsetDateTime::now_utc()
             .format(&Rfc3339)
             .unwrap_or_else(|_| "unknown".to_string()),
-        tools: builtin_tool_schemas(),
+        tools,
     }
 }
 
 struct ValidationContext {
     stack: Vec<String>,
     diagnostics: Vec<Diagnostic>,
+    // Names produced by the steps validated so far.
+    artifacts: HashSet<String>,
+    // Artifacts produced only on some paths (one conditional branch, a loop
+    // body that may not run).
+    maybe_artifacts: HashSet<String>,
+    // Scan artifact names mapped to the tool that produces them.
+    scan_tools: HashMap<String, String>,
+    variables: HashSet<String>,
+    // Variables supplied from outside the scenario (e.g. `--var`).
+    overrides: HashSet<String>,
+    // Variables declared on some but not all paths (one conditional branch,
+    // a loop body that may not run).
+    maybe_variables: HashSet<String>,
+    // Step names declared so far, keyed by kind, with their location and
+    // whether they sit in a loop body. Each kind stores its artifact under
+    // its own prefix (`scan_`, `script_`, `asset_group:`...), so only names
+    // of the same kind collide.
+    step_names: HashMap<(&'static str, String), (String, bool)>,
+    loop_depth: usize,
+    // Top-level functions mapped to their parameter count and, once the
+    // definition has been validated, the artifacts a call produces.
+    functions: HashMap<String, (usize, Vec<String>)>,
+    // Tags from a top-level `let known_tags = [...]`, if declared.
+    known_tags: Option<HashSet<String>>,
+    // User-provided schemas, consulted before `BUILTIN_SCHEMAS`.
+    plugin_schemas: Vec<ToolSchema>,
 }
 
 impl ValidationContext {
@@ -99,6 +369,17 @@ impl ValidationContext {
         Self {
             stack: Vec::new(),
             diagnostics: Vec::new(),
+            artifacts: HashSet::new(),
+            maybe_artifacts: HashSet::new(),
+            scan_tools: HashMap::new(),
+            variables: HashSet::new(),
+            overrides: HashSet::new(),
+            maybe_variables: HashSet::new(),
+            step_names: HashMap::new(),
+            loop_depth: 0,
+            functions: HashMap::new(),
+            known_tags: None,
+            plugin_schemas: Vec::new(),
         }
     }
 
@@ -128,6 +409,56 @@ impl ValidationContext {
         self.diagnostics.push(diag);
     }
 
+    fn info(&mut self, message: impl Into<String>) {
+        let diag = Diagnostic::info(self.location(), message);
+        self.diagnostics.push(diag);
+    }
+
+    fn produce(&mut self, label: String) {
+        self.maybe_artifacts.remove(&label);
+        self.artifacts.insert(label);
+    }
+
+    // Call after pushing the step's own label so the recorded location
+    // points at it.
+    fn declare_step(&mut self, kind: &'static str, name: &str) {
+        let location = self.location().unwrap_or_default();
+        let in_loop = self.loop_depth > 0;
+        let key = (kind, name.to_string());
+        if let Some((previous_location, previous_in_loop)) = self.step_names.get(&key) {
+            let message = format!(
+                "duplicate step name '{name}': {kind} reuses the name of the {kind} at '{previous_location}'"
+            );
+            // Inside a loop the name may be made unique by the loop index.
+            if in_loop || *previous_in_loop {
+                self.warning(message);
+            } else {
+                self.error(message);
+            }
+        } else {
+            self.step_names.insert(key, (location, in_loop));
+        }
+        if in_loop {
+            self.warning(format!(
+                "{kind} '{name}' is inside a for loop and runs once per iteration; its artifact is overwritten each time unless the name includes the loop index"
+            ));
+        }
+    }
+
+    fn check_tags(&mut self, tags: &[String]) {
+        let Some(known) = &self.known_tags else {
+            return;
+        };
+        let unknown: Vec<String> = tags
+            .iter()
+            .filter(|tag| !known.contains(*tag))
+            .cloned()
+            .collect();
+        for tag in unknown {
+            self.warning(format!("tag '{tag}' is not listed in known_tags"));
+        }
+    }
+
     fn finish(self) -> Vec<Diagnostic> {
         self.diagnostics
     }
@@ -135,9 +466,17 @@ impl ValidationContext {
 
 fn validate_steps(steps: &[Step], ctx: &mut ValidationContext) {
     for step in steps {
+        let handlers = step.on_failure();
+        let artifacts_before = (!handlers.is_empty()).then(|| ctx.artifacts.clone());
         match step {
-            Step::Import(_) => {}
-            Step::Variable(_) => {}
+            Step::Import(_) | Step::Comment(_) => {}
+            Step::Variable(var) => {
+                ctx.push(format!("let {}", var.name));
+                check_cve_literals(&var.value, ctx);
+                check_literal_placeholders(&var.value, ctx);
+                ctx.pop();
+                ctx.variables.insert(var.name.clone());
+            }
             Step::Secret(secret) => {
                 ctx.push(format!("secret {}", secret.name));
                 validate_secret(secret, ctx);
@@ -145,49 +484,377 @@ fn validate_steps(steps: &[Step], ctx: &mut ValidationContext) {
             }
             Step::AssetGroup(group) => {
                 ctx.push(format!("asset_group {}", group.name));
-                // Asset group properties are free-form.
+                ctx.declare_step("asset_group", &group.name);
+                ctx.check_tags(&group.tags);
+                // Asset group properties are free-form, apart from a CIDR `scope`.
+                check_map_placeholders(&group.properties, ctx);
+                let cidr_size = group
+                    .properties
+                    .get("scope")
+                    .and_then(|scope| crate::runtime::cidr_address_count(scope));
+                // The runtime only binds `<group>_hosts` for scopes it expands.
+                let expanded = cidr_size.filter(|size| *size <= MAX_EXPANDED_HOSTS);
+                match cidr_size {
+                    Some(size) if size > MAX_EXPANDED_HOSTS => ctx.warning(format!(
+                        "scope spans {size} addresses (more than {MAX_EXPANDED_HOSTS}); {}_hosts will not be defined",
+                        group.name
+                    )),
+                    Some(size) if size > DEFAULT_MAX_ITERATIONS as u128 => ctx.warning(format!(
+                        "scope spans {size} addresses; loops over {}_hosts exceed the {DEFAULT_MAX_ITERATIONS}-iteration limit unless they raise `max_iter`",
+                        group.name
+                    )),
+                    _ => {}
+                }
                 ctx.pop();
+                ctx.produce(format!("asset_group:{}", group.name));
+                if expanded.is_some() {
+                    ctx.variables.insert(format!("{}_hosts", group.name));
+                }
             }
             Step::Scan(scan) => {
                 ctx.push(format!("scan {}", scan.name));
+                ctx.declare_step("scan", &scan.name);
+                ctx.check_tags(&scan.tags);
                 validate_scan(scan, ctx);
                 ctx.pop();
+                let labels = match &scan.output {
+                    Some(label) => vec![label.clone()],
+                    None => vec![
+                        format!("scan_{}", scan.name),
+                        format!("findings_{}", scan.name),
+                    ],
+                };
+                for label in labels {
+                    ctx.scan_tools.insert(label.clone(), scan.tool.clone());
+                    ctx.produce(label);
+                }
             }
             Step::Script(script) => {
                 ctx.push(format!("script {}", script.name));
+                ctx.declare_step("script", &script.name);
+                ctx.check_tags(&script.tags);
                 validate_script(script, ctx);
                 ctx.pop();
+                ctx.produce(
+                    script
+                        .output
+                        .clone()
+                        .unwrap_or_else(|| format!("script_{}", script.name)),
+                );
             }
             Step::Report(report) => {
                 ctx.push(format!("report {}", report.name));
+                ctx.declare_step("report", &report.name);
+                ctx.check_tags(&report.tags);
                 validate_report(report, ctx);
                 ctx.pop();
+                ctx.produce(format!("report:{}", report.name));
             }
             Step::Conditional(block) => {
+                let before = ctx.variables.clone();
+                let names_before = ctx.step_names.clone();
+                let artifacts_before = ctx.artifacts.clone();
+                let maybe_before = ctx.maybe_artifacts.clone();
                 ctx.push(format!("if {}", block.condition));
+                validate_condition(&block.condition, ctx);
                 validate_steps(&block.then_steps, ctx);
                 ctx.pop();
+                let then_vars = std::mem::replace(&mut ctx.variables, before);
+                // Branches are exclusive, so they may reuse each other's names.
+                let then_names = std::mem::replace(&mut ctx.step_names, names_before);
+                let then_artifacts = std::mem::replace(&mut ctx.artifacts, artifacts_before);
+                let then_maybe = std::mem::replace(&mut ctx.maybe_artifacts, maybe_before);
 
                 if !block.else_steps.is_empty() {
                     ctx.push("else".to_string());
                     validate_steps(&block.else_steps, ctx);
                     ctx.pop();
                 }
+
+                // Only variables declared on both paths are guaranteed afterwards.
+                let else_vars = std::mem::take(&mut ctx.variables);
+                ctx.maybe_variables
+                    .extend(then_vars.symmetric_difference(&else_vars).cloned());
+                ctx.variables = then_vars.intersection(&else_vars).cloned().collect();
+                for (key, declared) in then_names {
+                    ctx.step_names.entry(key).or_insert(declared);
+                }
+                ctx.maybe_artifacts.extend(then_maybe);
+                let one_branch: Vec<String> = then_artifacts
+                    .symmetric_difference(&ctx.artifacts)
+                    .cloned()
+                    .collect();
+                ctx.maybe_artifacts.extend(one_branch);
+                ctx.artifacts.extend(then_artifacts);
             }
             Step::Loop(loop_step) => {
+                let before = ctx.variables.clone();
+                let artifacts_before = ctx.artifacts.clone();
+                let maybe_before = ctx.maybe_artifacts.clone();
                 ctx.push(format!("for {}", loop_step.iterator));
+                ctx.variables.extend([
+                    loop_step.iterator.clone(),
+                    LOOP_INDEX_VAR.to_string(),
+                    LOOP_INDEX1_VAR.to_string(),
+                ]);
+                ctx.variables.extend(loop_step.value_iterator.clone());
                 validate_loop(loop_step, ctx);
+                ctx.loop_depth += 1;
                 validate_steps(&loop_step.body, ctx);
+                ctx.loop_depth -= 1;
+                ctx.pop();
+
+                // The iterator is scoped to the body, and the body may not run at all.
+                let body_vars = std::mem::replace(&mut ctx.variables, before);
+                ctx.maybe_variables
+                    .extend(body_vars.into_iter().filter(|name| {
+                        !ctx.variables.contains(name)
+                            && *name != loop_step.iterator
+                            && loop_step.value_iterator.as_ref() != Some(name)
+                            && !name.starts_with(LOOP_VAR_PREFIX)
+                    }));
+                ctx.maybe_artifacts.extend(maybe_before);
+                let body_artifacts: Vec<String> = ctx
+                    .artifacts
+                    .difference(&artifacts_before)
+                    .cloned()
+                    .collect();
+                ctx.maybe_artifacts.extend(body_artifacts);
+            }
+            Step::Parallel(block) => {
+                ctx.push(match &block.name {
+                    Some(name) => format!("parallel {}", name),
+                    None => "parallel".to_string(),
+                });
+                validate_parallel(block, ctx);
+                // Branches run on their own threads and cannot steer an enclosing loop.
+                let loop_depth = std::mem::take(&mut ctx.loop_depth);
+                // Each branch starts from the variables declared before the block.
+                let before = ctx.variables.clone();
+                let mut declared = before.clone();
+                for branch in &block.steps {
+                    ctx.variables = before.clone();
+                    validate_steps(std::slice::from_ref(branch), ctx);
+                    declared.extend(ctx.variables.drain());
+                }
+                ctx.variables = declared;
+                ctx.loop_depth = loop_depth;
+                ctx.pop();
+                ctx.produce(format!(
+                    "parallel:{}",
+                    block.name.as_deref().unwrap_or("block")
+                ));
+            }
+            Step::Function(function) => {
+                ctx.push(format!("func {}", function.name));
+                if ctx.stack.len() > 1 {
+                    ctx.error("functions must be defined at the top level");
+                }
+                // The body runs at each call site: validate it once, with the
+                // parameters declared, and keep nothing it declares.
+                let before = ctx.variables.clone();
+                let names_before = ctx.step_names.clone();
+                let artifacts_before = ctx.artifacts.clone();
+                let maybe_before = ctx.maybe_artifacts.clone();
+                let loop_depth = std::mem::take(&mut ctx.loop_depth);
+                ctx.variables.extend(function.params.iter().cloned());
+                validate_steps(&function.body, ctx);
+                ctx.loop_depth = loop_depth;
+                ctx.pop();
+                let produced: Vec<String> = ctx
+                    .artifacts
+                    .difference(&artifacts_before)
+                    .cloned()
+                    .collect();
+                if let Some((_, artifacts)) = ctx.functions.get_mut(&function.name) {
+                    *artifacts = produced;
+                }
+                ctx.variables = before;
+                ctx.step_names = names_before;
+                ctx.artifacts = artifacts_before;
+                ctx.maybe_artifacts = maybe_before;
+            }
+            Step::Call(call) => {
+                ctx.push(format!("call {}", call.function));
+                for arg in &call.args {
+                    if let ConditionOperand::Literal(value) = arg {
+                        check_literal_placeholders(value, ctx);
+                    }
+                }
+                match ctx.functions.get(&call.function).cloned() {
+                    None => ctx.error(format!("undefined function '{}'", call.function)),
+                    Some((arity, _)) if arity != call.args.len() => ctx.error(format!(
+                        "function '{}' expects {} argument(s), got {}",
+                        call.function,
+                        arity,
+                        call.args.len()
+                    )),
+                    Some((_, artifacts)) => {
+                        for artifact in artifacts {
+                            ctx.produce(artifact);
+                        }
+                    }
+                }
+                ctx.pop();
+            }
+            Step::Notify(notify) => {
+                ctx.push(format!("notify {}", notify.name));
+                ctx.check_tags(&notify.tags);
+                validate_notify(notify, ctx);
+                ctx.pop();
+                ctx.produce(format!("notify:{}", notify.name));
+            }
+            Step::Break | Step::Continue => {
+                if ctx.loop_depth == 0 {
+                    let keyword = if matches!(step, Step::Break) {
+                        "break"
+                    } else {
+                        "continue"
+                    };
+                    ctx.error(format!(
+                        "'{keyword}' can only be used inside a for loop body"
+                    ));
+                }
+            }
+            Step::Merge(merge) => {
+                ctx.push(format!("merge_artifacts -> {}", merge.output));
+                validate_merge(merge, ctx);
+                ctx.pop();
+                if let Some(tool) = merge
+                    .inputs
+                    .first()
+                    .and_then(|input| ctx.scan_tools.get(input))
+                    .cloned()
+                {
+                    ctx.scan_tools.insert(merge.output.clone(), tool);
+                }
+                ctx.produce(merge.output.clone());
+            }
+            Step::Checkpoint(checkpoint) => {
+                let in_parallel = ctx.stack.iter().any(|frame| frame.starts_with("parallel"));
+                ctx.push(format!("checkpoint {}", checkpoint.name));
+                ctx.declare_step("checkpoint", &checkpoint.name);
+                if in_parallel {
+                    ctx.warning(
+                        "checkpoint inside a parallel block only saves the state of its own branch",
+                    );
+                }
+                ctx.pop();
+            }
+            Step::Transform(transform) => {
+                ctx.push(format!("transform {}", transform.name));
+                ctx.declare_step("transform", &transform.name);
+                ctx.check_tags(&transform.tags);
+                validate_transform(transform, ctx);
                 ctx.pop();
+                let output = transform.output.as_ref().unwrap_or(&transform.source);
+                if let Some(tool) = ctx.scan_tools.get(&transform.source).cloned() {
+                    ctx.scan_tools.insert(output.clone(), tool);
+                }
+                ctx.produce(output.clone());
+            }
+            Step::Sleep(sleep) => {
+                if sleep.duration_ms > 3_600_000 {
+                    ctx.push(format!("sleep {}", sleep.name));
+                    ctx.warning(format!(
+                        "sleep of {} exceeds one hour",
+                        format_duration_ms(sleep.duration_ms)
+                    ));
+                    ctx.pop();
+                }
+            }
+            Step::Assert(assert_step) => {
+                ctx.push(format!("assert {}", assert_step.name));
+                ctx.check_tags(&assert_step.tags);
+                validate_assert(assert_step, ctx);
+                ctx.pop();
+            }
+        }
+        if let Some(artifacts_before) = artifacts_before {
+            validate_on_failure(step, handlers, artifacts_before, ctx);
+        }
+    }
+}
+
+// Handlers run only after their step failed: the step's own artifacts may be
+// missing, and whatever the handlers declare or produce may be too.
+fn validate_on_failure(
+    step: &Step,
+    handlers: &[Step],
+    artifacts_before: HashSet<String>,
+    ctx: &mut ValidationContext,
+) {
+    let name = step.name().unwrap_or_default();
+    ctx.push(format!("on_failure {name}"));
+    let failed: Vec<&String> = ctx.artifacts.difference(&artifacts_before).collect();
+    let mut reports = Vec::new();
+    collect_reports(handlers, &mut reports);
+    let mut missing = Vec::new();
+    for report in reports {
+        for include in &report.includes {
+            if failed.contains(&include) {
+                missing.push(format!(
+                    "report '{}' includes '{include}', which '{name}' did not produce when it failed",
+                    report.name
+                ));
+            }
+        }
+    }
+    for message in missing {
+        ctx.error(message);
+    }
+
+    let before = ctx.variables.clone();
+    let artifacts_before = ctx.artifacts.clone();
+    validate_steps(handlers, ctx);
+    ctx.pop();
+    let handler_vars = std::mem::replace(&mut ctx.variables, before);
+    ctx.maybe_variables.extend(
+        handler_vars
+            .into_iter()
+            .filter(|name| !ctx.variables.contains(name)),
+    );
+    let handler_artifacts: Vec<String> = ctx
+        .artifacts
+        .difference(&artifacts_before)
+        .cloned()
+        .collect();
+    ctx.maybe_artifacts.extend(handler_artifacts);
+}
+
+fn collect_reports<'a>(steps: &'a [Step], reports: &mut Vec<&'a ReportStep>) {
+    for step in steps {
+        match step {
+            Step::Report(report) => reports.push(report),
+            Step::Conditional(block) => {
+                collect_reports(&block.then_steps, reports);
+                collect_reports(&block.else_steps, reports);
             }
+            Step::Loop(loop_step) => collect_reports(&loop_step.body, reports),
+            Step::Parallel(block) => collect_reports(&block.steps, reports),
+            _ => {}
         }
+        collect_reports(step.on_failure(), reports);
     }
 }
 
 fn validate_scan(scan: &ScanStep, ctx: &mut ValidationContext) {
     let params = &scan.params;
-    if let Some(schema) = lookup_schema(scan.tool.as_str()) {
-        validate_with_schema(&scan.tool, params, schema, ctx);
+    check_map_placeholders(params, ctx);
+    validate_retry(scan.retry.as_ref(), ctx);
+    if let Some(target) = params.get("target") {
+        for range in target.split([',', ' ']).filter(|part| !part.is_empty()) {
+            if let Some(count) = ipv4_range_size(range) {
+                if count >= LARGE_TARGET_ADDRESSES {
+                    ctx.info(format!(
+                        "target range {range} covers {count} addresses; expect a long scan and a large artifact"
+                    ));
+                }
+            }
+        }
+    }
+    if let Some(schema) = lookup_schema(scan.tool.as_str(), &ctx.plugin_schemas) {
+        validate_with_schema(&scan.tool, params, &schema, ctx);
     } else {
         if let Some(value) = params.get("target") {
             if value.trim().is_empty() {
@@ -222,44 +889,613 @@ fn validate_secret(secret: &SecretStep, ctx: &mut ValidationContext) {
                 }
             }
         }
-        SecretSource::File { path } => {
+        SecretSource::File { path, key } => {
             if path.trim().is_empty() {
                 ctx.error("file secret path cannot be empty");
             }
+            if key
+                .as_deref()
+                .is_some_and(|key| key.split('.').any(|part| part.trim().is_empty()))
+            {
+                ctx.error("file secret key cannot be empty or contain empty segments");
+            }
         }
-        SecretSource::Vault { path, .. } => {
+        SecretSource::Vault {
+            path,
+            field,
+            role_id,
+            secret_id,
+            ..
+        } => {
             if path.trim().is_empty() {
                 ctx.error("vault secret requires a path");
             }
+            if let Some(field) = field {
+                if field.trim().is_empty() {
+                    ctx.error("vault secret field cannot be empty");
+                }
+            }
+            if role_id.is_some() != secret_id.is_some() {
+                ctx.warning(
+                    "vault role_id and secret_id should be set together; the missing value is read from VAULT_ROLE_ID/VAULT_SECRET_ID",
+                );
+            }
+            if secret_id.is_some() {
+                ctx.warning(
+                    "vault secret_id is embedded in the scenario; prefer the VAULT_SECRET_ID environment variable",
+                );
+            }
+        }
+        SecretSource::AwsSecretsManager { secret_id, key, .. } => {
+            if secret_id.trim().is_empty() {
+                ctx.error("aws_secrets_manager secret requires a secret_id");
+                return;
+            }
+            if let Some(key) = key {
+                if key.trim().is_empty() {
+                    ctx.error("aws_secrets_manager secret key cannot be empty");
+                }
+            }
             ctx.warning(
-                "vault provider is not implemented yet; this step will be skipped at runtime",
+                "aws_secrets_manager secrets are fetched from AWS at run time; the step fails when the API is unreachable",
             );
+            ctx.info(format!(
+                "AWS credentials need secretsmanager:GetSecretValue on '{secret_id}' (and kms:Decrypt if the secret uses a customer managed key)"
+            ));
+        }
+        SecretSource::Keyring { service, account } => {
+            if service.trim().is_empty() {
+                ctx.error("keyring secret requires a service");
+            }
+            if account.trim().is_empty() {
+                ctx.error("keyring secret requires an account");
+            }
+            ctx.warning(format!(
+                "keyring secrets need an OS keychain, which headless CI runners usually lack; run with --keyring-mock-env and set {}",
+                crate::runtime::keyring_env_var(service, account)
+            ));
         }
     }
 }
 
+// Ranges at least this large (a /16) get an informational note.
+const LARGE_TARGET_ADDRESSES: u64 = 1 << 16;
+
+fn ipv4_range_size(range: &str) -> Option<u64> {
+    let (address, prefix) = range.split_once('/')?;
+    address.parse::<std::net::Ipv4Addr>().ok()?;
+    let prefix: u32 = prefix.parse().ok().filter(|prefix| *prefix <= 32)?;
+    Some(1u64 << (32 - prefix))
+}
+
 fn validate_script(script: &ScriptStep, ctx: &mut ValidationContext) {
     let params = &script.params;
-    if let Some(schema) = lookup_schema("script") {
-        validate_with_schema("script", params, schema, ctx);
+    check_map_placeholders(params, ctx);
+    check_map_placeholders(&script.env, ctx);
+    for name in script.env.keys() {
+        if name.is_empty() || name.contains(['=', '\0']) {
+            ctx.error(format!("invalid environment variable name '{name}'"));
+        }
+    }
+    validate_retry(script.retry.as_ref(), ctx);
+    if let Some(schema) = lookup_schema("script", &ctx.plugin_schemas) {
+        validate_with_schema("script", params, &schema, ctx);
+    }
+}
+
+const MAX_RETRIES: u32 = 10;
+
+fn validate_retry(retry: Option<&RetryPolicy>, ctx: &mut ValidationContext) {
+    if let Some(retry) = retry {
+        if !(1..=MAX_RETRIES).contains(&retry.count) {
+            ctx.error(format!(
+                "retry count must be between 1 and {MAX_RETRIES}, found {}",
+                retry.count
+            ));
+        }
     }
 }
 
+// Parsers that populate `Finding::cvss_score`.
+const CVSS_SCAN_TOOLS: &[&str] = &["nuclei"];
+
 fn validate_report(report: &ReportStep, ctx: &mut ValidationContext) {
     if report.includes.is_empty() {
         ctx.warning("report does not include any artifacts");
     }
+    for include in &report.includes {
+        if include.contains("${") {
+            check_placeholders(include, ctx);
+        } else if is_include_pattern(include) {
+            match glob::Pattern::new(include) {
+                Ok(_) => ctx.warning(format!(
+                    "include '{include}' is a pattern matched against artifacts at run time; which artifacts it picks up is not checked"
+                )),
+                Err(err) => ctx.error(format!("invalid include pattern '{include}': {err}")),
+            }
+        } else if !ctx.artifacts.contains(include) {
+            ctx.error(format!(
+                "include '{}' does not match any artifact produced by an earlier step",
+                include
+            ));
+        } else if ctx.maybe_artifacts.contains(include) {
+            ctx.warning(format!(
+                "include '{}' is only produced inside a conditional branch or loop body and may be missing at runtime",
+                include
+            ));
+        }
+    }
+
+    if let Some(raw) = report.options.get("filter") {
+        if let Err(err) = parse_condition_expr(raw) {
+            ctx.error(format!("invalid filter expression: {err}"));
+        }
+    }
+    let renders_tables = matches!(
+        report.format,
+        ReportFormat::Stdout | ReportFormat::Html | ReportFormat::Markdown
+    );
+    if !renders_tables
+        && (report.options.contains_key("filter") || report.options.contains_key("sort_by"))
+    {
+        ctx.warning(format!(
+            "{} reports do not render tables; filter and sort_by have no effect",
+            report.format.as_str()
+        ));
+    }
+    if let Some(raw) = report.options.get("sort_by") {
+        match parse_sort_option(raw) {
+            Ok((column, _)) => {
+                if !column.contains("${") && !SCAN_TABLE_COLUMNS.contains(&column.as_str()) {
+                    ctx.warning(format!(
+                        "sort_by column '{}' is not a report table column; rows keep scan order",
+                        column
+                    ));
+                }
+            }
+            Err(err) => ctx.error(err),
+        }
+    }
+    match report.options.get("deduplicate") {
+        Some(mode) => {
+            if let Err(err) = parse_deduplicate_options(mode, report.options.get("deduplicate_key"))
+            {
+                ctx.error(err);
+            }
+        }
+        None if report.options.contains_key("deduplicate_key") => {
+            ctx.warning("deduplicate_key has no effect without option deduplicate \"findings\"");
+        }
+        None => {}
+    }
+
+    if let Some(raw) = report.options.get("archive") {
+        if !raw.contains("${")
+            && !raw.eq_ignore_ascii_case("true")
+            && !raw.eq_ignore_ascii_case("false")
+        {
+            ctx.warning(format!(
+                "option archive expects \"true\" or \"false\", found '{raw}'; the report is not archived"
+            ));
+        }
+    }
+
+    match SuppressionRule::from_options(&report.options) {
+        Ok(rules) if !rules.is_empty() && report.format != ReportFormat::Sarif => {
+            ctx.warning(format!(
+                "suppress rules only apply to sarif reports; {} reports ignore them",
+                report.format.as_str()
+            ));
+        }
+        Ok(_) => {}
+        Err(err) => ctx.error(err),
+    }
+    if report.format != ReportFormat::Sarif && report.options.contains_key("baseline_path") {
+        ctx.warning(format!(
+            "baseline_path only applies to sarif reports; {} reports ignore it",
+            report.format.as_str()
+        ));
+    }
+
+    if report.format == ReportFormat::Sarif {
+        for include in &report.includes {
+            let Some(tool) = ctx.scan_tools.get(include).cloned() else {
+                continue;
+            };
+            if !CVSS_SCAN_TOOLS.contains(&tool.as_str()) {
+                ctx.warning(format!(
+                    "'{}' is produced by tool '{}', which does not report CVSS data; SARIF results will lack security-severity",
+                    include, tool
+                ));
+            }
+        }
+    }
+}
+
+fn validate_condition(expr: &ConditionExpr, ctx: &mut ValidationContext) {
+    match expr {
+        ConditionExpr::Not(inner) => validate_condition(inner, ctx),
+        ConditionExpr::And(left, right) | ConditionExpr::Or(left, right) => {
+            let operator = if matches!(expr, ConditionExpr::And(_, _)) {
+                "&&"
+            } else {
+                "||"
+            };
+            for side in [left.as_ref(), right.as_ref()] {
+                if let ConditionExpr::Literal(value) = side {
+                    ctx.warning(format!(
+                        "'{}' has a constant '{}' operand; the condition can be simplified",
+                        operator, value
+                    ));
+                }
+                validate_condition(side, ctx);
+            }
+        }
+        ConditionExpr::LessThan(left, right)
+        | ConditionExpr::GreaterThan(left, right)
+        | ConditionExpr::LessThanOrEqual(left, right)
+        | ConditionExpr::GreaterThanOrEqual(left, right) => {
+            for operand in [left, right] {
+                if let ConditionOperand::Literal(value) = operand {
+                    if !matches!(
+                        value,
+                        LiteralValue::Integer(_)
+                            | LiteralValue::Number(_)
+                            | LiteralValue::String(_)
+                    ) {
+                        ctx.error(format!(
+                            "cannot order {} values; comparisons require numbers or strings",
+                            value.type_name()
+                        ));
+                    }
+                }
+            }
+            if let (ConditionOperand::Literal(lhs), ConditionOperand::Literal(rhs)) = (left, right)
+            {
+                if lhs.type_name() != rhs.type_name() {
+                    ctx.error(format!(
+                        "cannot compare {} with {}",
+                        lhs.type_name(),
+                        rhs.type_name()
+                    ));
+                }
+            }
+        }
+        ConditionExpr::Contains(haystack, _) => {
+            if let ConditionOperand::Literal(value) = haystack {
+                if !matches!(value, LiteralValue::String(_) | LiteralValue::Array(_)) {
+                    ctx.error(format!(
+                        "'contains' requires a string or array on the left, found {}",
+                        value.type_name()
+                    ));
+                }
+            }
+        }
+        ConditionExpr::Matches(_, pattern) => match pattern {
+            ConditionOperand::Literal(LiteralValue::String(value)) => {
+                if let Err(err) = regex::Regex::new(value) {
+                    ctx.error(format!("invalid regular expression '{}': {err}", value));
+                }
+            }
+            _ => ctx.warning(format!(
+                "'matches' pattern {} is not a string literal; it will be resolved at runtime",
+                pattern
+            )),
+        },
+        ConditionExpr::Equals(left, right) | ConditionExpr::NotEquals(left, right) => {
+            for operand in [left, right] {
+                if let ConditionOperand::Literal(value) = operand {
+                    check_cve_literals(value, ctx);
+                }
+            }
+        }
+        ConditionExpr::Defined(name) => {
+            if ctx.variables.contains(name) && !ctx.overrides.contains(name) {
+                ctx.warning(format!(
+                    "'{}' is always declared at this point; 'defined {}' is always true",
+                    name, name
+                ));
+            }
+        }
+        ConditionExpr::Literal(_) | ConditionExpr::Variable(_) => {}
+    }
+    if let ConditionExpr::Contains(_, ConditionOperand::Literal(needle)) = expr {
+        check_cve_literals(needle, ctx);
+    }
+}
+
+fn check_cve_literals(value: &LiteralValue, ctx: &mut ValidationContext) {
+    match value {
+        LiteralValue::String(text) => {
+            let prefixed = text
+                .get(..4)
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case("CVE-"));
+            if prefixed && !is_cve_id(text) {
+                ctx.warning(format!(
+                    "'{}' does not look like a CVE identifier (expected CVE-YYYY-NNNN)",
+                    text
+                ));
+            }
+        }
+        LiteralValue::Array(items) => {
+            for item in items {
+                check_cve_literals(item, ctx);
+            }
+        }
+        LiteralValue::Object(entries) => {
+            for item in entries.values() {
+                check_cve_literals(item, ctx);
+            }
+        }
+        LiteralValue::Integer(_)
+        | LiteralValue::Number(_)
+        | LiteralValue::Boolean(_)
+        | LiteralValue::Null
+        | LiteralValue::Arithmetic { .. }
+        | LiteralValue::Env(_)
+        | LiteralValue::File(_) => {}
+    }
+}
+
+// Matches `CVE-\d{4}-\d{4,}`.
+fn is_cve_id(text: &str) -> bool {
+    let mut parts = text.split('-');
+    let all_digits = |part: &str| part.chars().all(|ch| ch.is_ascii_digit());
+    matches!(
+        (parts.next(), parts.next(), parts.next(), parts.next()),
+        (Some("CVE"), Some(year), Some(sequence), None)
+            if year.len() == 4 && all_digits(year) && sequence.len() >= 4 && all_digits(sequence)
+    )
+}
+
+fn validate_parallel(block: &ParallelStep, ctx: &mut ValidationContext) {
+    if block
+        .steps
+        .iter()
+        .all(|step| matches!(step, Step::Comment(_)))
+    {
+        ctx.warning("parallel block is empty");
+    }
+    for step in &block.steps {
+        if let Step::Report(report) = step {
+            ctx.warning(format!(
+                "report '{}' runs concurrently with its siblings and cannot see their artifacts",
+                report.name
+            ));
+        }
+    }
+}
+
+fn validate_merge(merge: &MergeStep, ctx: &mut ValidationContext) {
+    if merge.output.trim().is_empty() {
+        ctx.error("merge_artifacts output name cannot be empty");
+    }
+    if merge.inputs.len() < 2 {
+        ctx.warning("merge_artifacts with a single input only copies the artifact");
+    }
+    for input in &merge.inputs {
+        if input.contains("${") {
+            check_placeholders(input, ctx);
+            continue;
+        }
+        if !ctx.artifacts.contains(input) {
+            ctx.error(format!("'{}' is not produced by an earlier step", input));
+        } else if !ctx.scan_tools.contains_key(input) {
+            ctx.error(format!("'{}' is not a scan artifact", input));
+        }
+    }
+}
+
+fn validate_transform(transform: &TransformStep, ctx: &mut ValidationContext) {
+    if transform.source.contains("${") {
+        check_placeholders(&transform.source, ctx);
+    } else if !ctx.artifacts.contains(&transform.source) {
+        ctx.error(format!(
+            "'{}' is not produced by an earlier step",
+            transform.source
+        ));
+    }
+    for operation in &transform.operations {
+        match operation {
+            TransformOp::Filter { condition, .. } => validate_condition(condition, ctx),
+            TransformOp::Set { value, .. } => check_literal_placeholders(value, ctx),
+            TransformOp::Rename { path, to } => {
+                if path.rsplit('.').next() == Some(to.as_str()) {
+                    ctx.warning(format!("'{operation}' renames a field to its own name"));
+                }
+            }
+            TransformOp::Project { .. } => {}
+        }
+    }
+}
+
+fn validate_notify(notify: &NotifyStep, ctx: &mut ValidationContext) {
+    check_placeholders(&notify.url, ctx);
+    if let Some(body) = &notify.body {
+        check_placeholders(body, ctx);
+    }
+    check_map_placeholders(&notify.headers, ctx);
+    let url = notify.url.trim();
+    if url.is_empty() {
+        ctx.error("notify url cannot be empty");
+    } else if !url.starts_with("${") {
+        if url.starts_with("http://") {
+            ctx.warning("notify url uses plain http; payloads are sent unencrypted");
+        } else if !url.starts_with("https://") {
+            ctx.error(format!(
+                "notify url must start with http:// or https:// (found '{url}')"
+            ));
+        }
+    }
+
+    const METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE"];
+    if !METHODS.contains(&notify.method.as_str()) {
+        ctx.error(format!(
+            "unsupported notify method '{}' (expected one of: {})",
+            notify.method,
+            METHODS.join(", ")
+        ));
+    } else if notify.method == "GET" && notify.body.is_some() {
+        ctx.warning("notify body is ignored by most servers for GET requests");
+    }
+}
+
+// Flags `${name}` placeholders that no preceding `let`, loop, or override
+// declares. `${secret:...}` references are resolved at runtime and skipped,
+// as are `${name:-default}` placeholders, which never fail.
+fn check_placeholders(text: &str, ctx: &mut ValidationContext) {
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start + 2..].find('}') else {
+            return;
+        };
+        let token = rest[start + 2..start + 2 + end].trim();
+        rest = &rest[start + 2 + end + 1..];
+        if token.is_empty() || token.starts_with("secret:") {
+            continue;
+        }
+        let mut pipes = token.split('|');
+        let (token, fallback) = split_placeholder(pipes.next().unwrap_or_default().trim());
+        for transform in pipes.map(str::trim) {
+            if !PLACEHOLDER_TRANSFORMS.contains(&transform) {
+                ctx.error(format!(
+                    "unknown transform '{transform}' (expected one of: {})",
+                    PLACEHOLDER_TRANSFORMS.join(", ")
+                ));
+            }
+        }
+        if matches!(fallback, Some(PlaceholderFallback::Default(_)))
+            || ctx.variables.contains(token)
+        {
+            continue;
+        }
+        let token = placeholder_root(token);
+        if ctx.variables.contains(token) {
+            continue;
+        }
+        if token.starts_with(LOOP_VAR_PREFIX) {
+            ctx.error(format!(
+                "'{token}' is only available inside a for loop body"
+            ));
+        } else if ctx.maybe_variables.contains(token) {
+            ctx.warning(format!(
+                "variable '{token}' may be undefined here; it is only declared inside a conditional branch or loop body"
+            ));
+        } else {
+            match fallback {
+                Some(PlaceholderFallback::Required(message)) if !message.is_empty() => {
+                    ctx.error(format!("undefined variable '{token}': {message}"))
+                }
+                _ => ctx.error(format!("undefined variable '{token}'")),
+            }
+        }
+    }
+}
+
+fn check_map_placeholders(values: &BTreeMap<String, String>, ctx: &mut ValidationContext) {
+    for value in values.values() {
+        check_placeholders(value, ctx);
+    }
+}
+
+fn check_literal_placeholders(value: &LiteralValue, ctx: &mut ValidationContext) {
+    match value {
+        LiteralValue::String(text) | LiteralValue::Env(text) | LiteralValue::File(text) => {
+            check_placeholders(text, ctx)
+        }
+        LiteralValue::Array(items) => {
+            for item in items {
+                check_literal_placeholders(item, ctx);
+            }
+        }
+        LiteralValue::Object(map) => {
+            for item in map.values() {
+                check_literal_placeholders(item, ctx);
+            }
+        }
+        LiteralValue::Arithmetic { left, right, .. } => {
+            for operand in [left, right] {
+                if let ConditionOperand::Literal(literal) = operand.as_ref() {
+                    check_literal_placeholders(literal, ctx);
+                }
+            }
+        }
+        LiteralValue::Integer(_)
+        | LiteralValue::Number(_)
+        | LiteralValue::Boolean(_)
+        | LiteralValue::Null => {}
+    }
+}
+
+fn validate_assert(assert_step: &AssertStep, ctx: &mut ValidationContext) {
+    validate_condition(&assert_step.condition, ctx);
+    if matches!(assert_step.condition, ConditionExpr::Literal(_)) {
+        ctx.warning("assertion condition is constant");
+    }
+    for path in assert_step.condition.variable_names() {
+        let Some((root, _)) = path.split_once('.') else {
+            continue;
+        };
+        if root == "loop" && ctx.loop_depth > 0 {
+            continue;
+        }
+        if !ctx.variables.contains(root) && !ctx.artifacts.contains(root) {
+            ctx.error(format!(
+                "assertion references unknown artifact '{}' (not produced by a preceding step)",
+                root
+            ));
+        }
+    }
 }
 
 fn validate_loop(loop_step: &LoopStep, ctx: &mut ValidationContext) {
-    if let LoopIterable::Literal(literal) = &loop_step.iterable {
-        match literal {
-            LiteralValue::Array(_) | LiteralValue::String(_) => {}
+    if let Some(guard) = &loop_step.guard {
+        validate_condition(guard, ctx);
+    }
+    if loop_step.max_iter == Some(0) {
+        ctx.error("max_iter must be greater than zero");
+    }
+    match &loop_step.iterable {
+        LoopIterable::Literal(literal) => match literal {
+            LiteralValue::Array(items) if items.len() > 1_000 => ctx.warning(format!(
+                "loop iterates over a literal array of {} items",
+                items.len()
+            )),
+            LiteralValue::Object(_) => {}
+            LiteralValue::Array(_) | LiteralValue::String(_)
+                if loop_step.value_iterator.is_none() => {}
+            other if loop_step.value_iterator.is_some() => ctx.error(format!(
+                "'for {}' requires an object to iterate over, found {}",
+                loop_step.bindings(),
+                other.type_name()
+            )),
             other => ctx.error(format!(
-                "loop iterable must be an array or string literal, found {}",
+                "loop iterable must be an array, object or string literal, found {}",
                 other.display()
             )),
+        },
+        LoopIterable::Range { start, end, .. } => {
+            if loop_step.value_iterator.is_some() {
+                ctx.error(format!(
+                    "'for {}' requires an object to iterate over, found a range",
+                    loop_step.bindings()
+                ));
+            }
+            if start > end {
+                ctx.error(format!(
+                    "range start {} is greater than end {} ({})",
+                    start, end, loop_step.iterable
+                ));
+            } else if let Some(count) = loop_step.iterable.range_len() {
+                if count > 10_000 {
+                    ctx.warning(format!(
+                        "range {} spans {count} iterations",
+                        loop_step.iterable
+                    ));
+                }
+            }
         }
+        LoopIterable::Variable(_) => {}
     }
 }
 
@@ -309,14 +1545,36 @@ struct ToolSchemaDef {
     required: &'static [&'static str],
     optional: &'static [&'static str],
     allow_additional: bool,
+    examples: &'static [ToolSchemaExampleDef],
+}
+
+struct ToolSchemaExampleDef {
+    description: &'static str,
+    params: &'static [(&'static str, &'static str)],
 }
 
 impl ToolSchemaDef {
-    fn allows(&self, key: &str) -> bool {
-        self.required
-            .iter()
-            .chain(self.optional.iter())
-            .any(|candidate| *candidate == key)
+    fn to_schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: self.name.to_string(),
+            kind: Some(self.kind.to_string()),
+            description: Some(self.description.to_string()),
+            required: self.required.iter().map(|s| (*s).to_string()).collect(),
+            optional: self.optional.iter().map(|s| (*s).to_string()).collect(),
+            allow_additional: self.allow_additional,
+            examples: self
+                .examples
+                .iter()
+                .map(|example| ToolSchemaExample {
+                    description: example.description.to_string(),
+                    params: example
+                        .params
+                        .iter()
+                        .map(|(key, value)| (key.to_string(), value.to_string()))
+                        .collect(),
+                })
+                .collect(),
+        }
     }
 }
 
@@ -326,40 +1584,199 @@ const BUILTIN_SCHEMAS: &[ToolSchemaDef] = &[
         kind: "scan",
         description: "Nmap TCP/UDP scanner",
         required: &["target"],
-        optional: &["flags"],
+        optional: &["flags", "no_dedup", "timeout"],
         allow_additional: false,
+        examples: &[
+            ToolSchemaExampleDef {
+                description: "Service and version detection on one host",
+                params: &[("target", "10.0.0.5"), ("flags", "-sV -Pn")],
+            },
+            ToolSchemaExampleDef {
+                description: "Top 100 TCP ports across a /24 with a time limit",
+                params: &[
+                    ("target", "192.168.1.0/24"),
+                    ("flags", "--top-ports 100 -T4"),
+                    ("timeout", "10m"),
+                ],
+            },
+        ],
     },
     ToolSchemaDef {
         name: "gobuster",
         kind: "scan",
         description: "Gobuster content discovery",
         required: &["target", "args"],
-        optional: &["flags", "wordlist", "mode"],
+        optional: &["flags", "wordlist", "mode", "cwd", "no_dedup", "timeout"],
         allow_additional: false,
+        examples: &[
+            ToolSchemaExampleDef {
+                description: "Directory brute force with a common wordlist",
+                params: &[
+                    ("target", "https://example.com"),
+                    ("args", "dir -w /usr/share/wordlists/dirb/common.txt"),
+                ],
+            },
+            ToolSchemaExampleDef {
+                description: "Virtual host discovery with JSON output",
+                params: &[
+                    ("target", "https://example.com"),
+                    ("args", "vhost -w subdomains.txt --append-domain"),
+                    ("flags", "--output=json"),
+                ],
+            },
+        ],
+    },
+    ToolSchemaDef {
+        name: "masscan",
+        kind: "scan",
+        description: "Masscan asynchronous port sweeper",
+        required: &["target", "rate"],
+        optional: &["ports", "flags", "no_dedup", "timeout"],
+        allow_additional: false,
+        examples: &[
+            ToolSchemaExampleDef {
+                description: "Full TCP port sweep with XML output",
+                params: &[
+                    ("target", "10.0.0.0/24"),
+                    ("rate", "1000"),
+                    ("ports", "1-65535"),
+                    ("flags", "--output-format xml"),
+                ],
+            },
+            ToolSchemaExampleDef {
+                description: "Web ports only, keeping duplicate findings",
+                params: &[
+                    ("target", "10.0.0.0/16"),
+                    ("rate", "10000"),
+                    ("flags", "-p80,443,8080 --output-format xml"),
+                    ("no_dedup", "true"),
+                ],
+            },
+        ],
+    },
+    ToolSchemaDef {
+        name: "nuclei",
+        kind: "scan",
+        description: "Nuclei template-based vulnerability scanner",
+        required: &["target", "templates"],
+        optional: &["flags", "args", "cwd", "no_dedup", "timeout"],
+        allow_additional: false,
+        examples: &[
+            ToolSchemaExampleDef {
+                description: "CVE templates against one site",
+                params: &[
+                    ("target", "https://example.com"),
+                    ("templates", "http/cves/"),
+                    ("flags", "-jsonl"),
+                ],
+            },
+            ToolSchemaExampleDef {
+                description: "Critical and high severity templates with a time limit",
+                params: &[
+                    ("target", "https://example.com"),
+                    ("templates", "http/"),
+                    ("flags", "-jsonl -severity critical,high"),
+                    ("timeout", "30m"),
+                ],
+            },
+        ],
+    },
+    ToolSchemaDef {
+        name: "ffuf",
+        kind: "scan",
+        description: "ffuf web fuzzer",
+        required: &["url", "wordlist"],
+        optional: &["method", "match_codes", "flags", "args", "cwd", "timeout"],
+        allow_additional: false,
+        examples: &[
+            ToolSchemaExampleDef {
+                description: "Directory fuzzing with a common wordlist",
+                params: &[
+                    ("url", "https://example.com/FUZZ"),
+                    ("wordlist", "/usr/share/wordlists/dirb/common.txt"),
+                ],
+            },
+            ToolSchemaExampleDef {
+                description: "POST parameter fuzzing that keeps only 200 responses",
+                params: &[
+                    ("url", "https://example.com/login?user=FUZZ"),
+                    ("wordlist", "usernames.txt"),
+                    ("method", "POST"),
+                    ("match_codes", "200"),
+                ],
+            },
+        ],
+    },
+    ToolSchemaDef {
+        name: "subfinder",
+        kind: "scan",
+        description: "Subfinder passive subdomain enumeration",
+        required: &["domain"],
+        optional: &["sources", "flags", "cwd", "timeout"],
+        allow_additional: false,
+        examples: &[
+            ToolSchemaExampleDef {
+                description: "Enumerate subdomains from every default source",
+                params: &[("domain", "example.com"), ("flags", "-silent")],
+            },
+            ToolSchemaExampleDef {
+                description: "Query selected sources with a time limit",
+                params: &[
+                    ("domain", "example.com"),
+                    ("sources", "crtsh,alienvault"),
+                    ("timeout", "5m"),
+                ],
+            },
+        ],
     },
     ToolSchemaDef {
         name: "script",
         kind: "script",
         description: "Generic script execution",
         required: &["run"],
-        optional: &["args", "cwd"],
+        optional: &["args", "cwd", "env", "json_output", "timeout"],
         allow_additional: false,
+        examples: &[
+            ToolSchemaExampleDef {
+                description: "Run a shell command",
+                params: &[("run", "curl -sI https://example.com")],
+            },
+            ToolSchemaExampleDef {
+                description: "Run a script whose JSON output becomes the artifact",
+                params: &[
+                    ("run", "./scripts/check_tls.py"),
+                    ("args", "example.com 443"),
+                    ("json_output", "true"),
+                ],
+            },
+        ],
     },
 ];
 
 const SCHEMA_VERSION: &str = "1.0.0";
 
-fn lookup_schema(tool: &str) -> Option<&'static ToolSchemaDef> {
-    BUILTIN_SCHEMAS.iter().find(|schema| schema.name == tool)
+// User-provided schemas win over builtin ones of the same name.
+fn lookup_schema(tool: &str, plugins: &[ToolSchema]) -> Option<ToolSchema> {
+    plugins
+        .iter()
+        .find(|schema| schema.name == tool)
+        .cloned()
+        .or_else(|| {
+            BUILTIN_SCHEMAS
+                .iter()
+                .find(|schema| schema.name == tool)
+                .map(ToolSchemaDef::to_schema)
+        })
 }
 
 fn validate_with_schema(
     tool: &str,
     params: &BTreeMap<String, String>,
-    schema: &ToolSchemaDef,
+    schema: &ToolSchema,
     ctx: &mut ValidationContext,
 ) {
-    check_required(params, schema.required, ctx, tool);
+    let required: Vec<&str> = schema.required.iter().map(String::as_str).collect();
+    check_required(params, &required, ctx, tool);
 
     if let Some(value) = params.get("args") {
         if tool == "gobuster" && value.trim().is_empty() {
@@ -371,6 +1788,74 @@ fn validate_with_schema(
             ctx.error("parameter 'target' cannot be empty");
         }
     }
+    if tool == "nuclei" && !nuclei_emits_json(params.get("flags").map(String::as_str)) {
+        ctx.warning(
+            "nuclei flags do not include -jsonl or -json; output will be stored raw without findings",
+        );
+    }
+    if tool == "masscan" && !masscan_emits_xml(params.get("flags").map(String::as_str)) {
+        ctx.warning(
+            "masscan flags do not include --output-format xml; output will be stored raw without findings",
+        );
+    }
+    for key in ["templates", "wordlist", "domain", "url"] {
+        if params.get(key).is_some_and(|value| value.trim().is_empty()) {
+            ctx.error(format!(
+                "parameter '{key}' cannot be empty for tool '{tool}'"
+            ));
+        }
+    }
+    if let Some(value) = params.get("rate").filter(|value| !value.contains("${")) {
+        if tool == "masscan" && !value.trim().parse::<u64>().is_ok_and(|rate| rate > 0) {
+            ctx.error(format!(
+                "parameter 'rate' must be a positive number of packets per second, found '{value}'"
+            ));
+        }
+    }
+    if let Some(value) = params.get("url").filter(|value| !value.contains("${")) {
+        let value = value.trim();
+        if tool == "ffuf" && !value.is_empty() {
+            if !value.starts_with("http://") && !value.starts_with("https://") {
+                ctx.error(format!(
+                    "parameter 'url' must start with http:// or https://, found '{value}'"
+                ));
+            }
+            let elsewhere = ["flags", "args"]
+                .iter()
+                .any(|key| params.get(*key).is_some_and(|value| value.contains("FUZZ")));
+            if !value.contains("FUZZ") && !elsewhere {
+                ctx.warning(
+                    "ffuf url does not contain the FUZZ keyword; ffuf will refuse to run without it",
+                );
+            }
+        }
+    }
+    if let Some(value) = params.get("domain").filter(|value| !value.contains("${")) {
+        if tool == "subfinder" && (value.contains("://") || value.contains('/')) {
+            ctx.error(format!(
+                "parameter 'domain' must be a bare domain such as example.com, found '{value}'"
+            ));
+        }
+    }
+    for flag in ["no_dedup", "json_output"] {
+        if let Some(value) = params.get(flag) {
+            if !value.eq_ignore_ascii_case("true") && !value.eq_ignore_ascii_case("false") {
+                ctx.error(format!(
+                    "parameter '{flag}' must be true or false, found '{}'",
+                    value
+                ));
+            }
+        }
+    }
+    if let Some(value) = params.get("timeout").filter(|value| !value.contains("${")) {
+        match parse_duration_ms(value.trim()) {
+            Ok(ms) if ms < 1000 => ctx.warning(format!(
+                "timeout '{value}' is shorter than 1s; the tool will likely be killed before it finishes"
+            )),
+            Ok(_) => {}
+            Err(err) => ctx.error(format!("invalid timeout: {err}")),
+        }
+    }
     if let Some(value) = params.get("run") {
         if tool == "script" && value.trim().is_empty() {
             ctx.error("parameter 'run' cannot be empty");
@@ -388,3 +1873,598 @@ fn validate_with_schema(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::parse_scenario_all;
+
+    fn diagnostics_for(source: &str) -> Vec<Diagnostic> {
+        validate_scenario(&parse_scenario_all(source).expect("failed to parse scenario"))
+    }
+
+    #[test]
+    fn undefined_placeholders_are_reported_in_declaration_order() {
+        let diagnostics = diagnostics_for(
+            "let url = \"https://${host}/\"\nlet host = \"example.com\"\nscript probe {\n  run \"curl ${host} ${secret:token} ${missing}\"\n}\n",
+        );
+        let errors: Vec<&str> = diagnostics
+            .iter()
+            .filter(|diag| diag.is_error())
+            .map(|diag| diag.message.as_str())
+            .collect();
+        assert_eq!(
+            errors,
+            vec!["undefined variable 'host'", "undefined variable 'missing'"]
+        );
+    }
+
+    #[test]
+    fn placeholder_defaults_and_transforms_are_checked() {
+        let diagnostics = diagnostics_for(
+            "let url = \"https://${host:-localhost|lower}:${port:?pass --var port}/${path|slug}\"\n",
+        );
+        let errors: Vec<&str> = diagnostics
+            .iter()
+            .filter(|diag| diag.is_error())
+            .map(|diag| diag.message.as_str())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "undefined variable 'port': pass --var port",
+                "unknown transform 'slug' (expected one of: upper, lower, trim, base64, urlencode)",
+                "undefined variable 'path'",
+            ]
+        );
+    }
+
+    #[test]
+    fn branch_and_loop_variables_only_warn_afterwards() {
+        let diagnostics = diagnostics_for(
+            "let mode = \"fast\"\nif mode == \"fast\" {\n  let rate = \"100\"\n  let both = \"a\"\n} else {\n  let both = \"b\"\n}\nfor item in [\"a\", \"b\"] {\n  let last = \"${item}-${loop.index}\"\n}\nlet summary = \"${rate} ${both} ${last}\"\nlet stale = \"${item}\"\n",
+        );
+        let warnings: Vec<&str> = diagnostics
+            .iter()
+            .filter(|diag| !diag.is_error())
+            .map(|diag| diag.message.as_str())
+            .collect();
+        assert_eq!(warnings.len(), 2, "{diagnostics:?}");
+        assert!(warnings[0].starts_with("variable 'rate' may be undefined"));
+        assert!(warnings[1].starts_with("variable 'last' may be undefined"));
+        let errors: Vec<&Diagnostic> = diagnostics.iter().filter(|diag| diag.is_error()).collect();
+        assert_eq!(errors.len(), 1, "{diagnostics:?}");
+        assert_eq!(errors[0].message, "undefined variable 'item'");
+    }
+
+    #[test]
+    fn defined_warns_only_for_unconditionally_declared_variables() {
+        let source = "let mode = \"fast\"\nif defined mode {\n  let rate = \"100\"\n}\nif defined rate && defined target {\n  let done = true\n}\n";
+        let warnings: Vec<String> = diagnostics_for(source)
+            .into_iter()
+            .filter(|diag| !diag.is_error())
+            .map(|diag| diag.message)
+            .collect();
+        assert_eq!(
+            warnings,
+            vec!["'mode' is always declared at this point; 'defined mode' is always true"]
+        );
+
+        let scenario = parse_scenario_all(source).expect("failed to parse scenario");
+        let overrides = HashMap::from([("target".to_string(), LiteralValue::Boolean(true))]);
+        assert_eq!(
+            validate_scenario_with_vars(&scenario, &overrides)
+                .iter()
+                .filter(|diag| !diag.is_error())
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn function_calls_are_checked_against_definitions() {
+        let diagnostics = diagnostics_for(
+            "func probe(target) {\n  scan web nmap {\n    target \"${target}\"\n  }\n  if true {\n    func nested() {\n    }\n  }\n}\ncall probe(\"10.0.0.1\")\nreport summary {\n  include findings_web\n}\ncall probe()\ncall absent(1)\nfunc a() {\n  call b()\n}\nfunc b() {\n  call a()\n}\n",
+        );
+        let errors: Vec<&str> = diagnostics
+            .iter()
+            .filter(|diag| diag.is_error())
+            .map(|diag| diag.message.as_str())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "recursive function call: a -> b -> a; functions cannot call themselves",
+                "functions must be defined at the top level",
+                "function 'probe' expects 1 argument(s), got 0",
+                "undefined function 'absent'",
+            ],
+            "{diagnostics:?}"
+        );
+    }
+
+    #[test]
+    fn transforms_require_a_source_and_keep_scan_outputs_mergeable() {
+        let diagnostics = diagnostics_for(
+            "scan web nmap {\n  target \"10.0.0.1\"\n}\ntransform triage from scan_web -> scan_triaged {\n  filter findings where severity == \"high\"\n  rename findings[*].port to port\n}\nmerge_artifacts scan_web scan_triaged -> scan_all\ntransform orphan from scan_db {\n  set findings[*].owner \"${team}\"\n}\n",
+        );
+        let messages: Vec<(&DiagnosticLevel, &str)> = diagnostics
+            .iter()
+            .map(|diag| (&diag.level, diag.message.as_str()))
+            .collect();
+        assert!(
+            messages.contains(&(
+                &DiagnosticLevel::Warning,
+                "'rename findings[*].port to port' renames a field to its own name"
+            )),
+            "{diagnostics:?}"
+        );
+        let errors: Vec<&str> = diagnostics
+            .iter()
+            .filter(|diag| diag.is_error())
+            .map(|diag| diag.message.as_str())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "'scan_db' is not produced by an earlier step",
+                "undefined variable 'team'",
+            ],
+            "{diagnostics:?}"
+        );
+    }
+
+    #[test]
+    fn checkpoints_inside_parallel_blocks_warn() {
+        let diagnostics = diagnostics_for(
+            "checkpoint start\nparallel {\n  checkpoint branch\n  sleep pause 1s\n}\ncheckpoint start\n",
+        );
+        let messages: Vec<(&DiagnosticLevel, &str)> = diagnostics
+            .iter()
+            .map(|diag| (&diag.level, diag.message.as_str()))
+            .collect();
+        assert!(messages.contains(&(
+            &DiagnosticLevel::Warning,
+            "checkpoint inside a parallel block only saves the state of its own branch"
+        )));
+        let errors: Vec<&Diagnostic> = diagnostics.iter().filter(|diag| diag.is_error()).collect();
+        assert_eq!(errors.len(), 1, "{diagnostics:?}");
+        assert!(errors[0].message.starts_with(
+            "duplicate step name 'start': checkpoint reuses the name of the checkpoint"
+        ));
+    }
+
+    #[test]
+    fn unknown_tags_warn_when_known_tags_is_declared() {
+        let source = "script probe {\n  tags [\"fast\", \"prdo\"]\n  run \"echo hi\"\n}\n";
+        assert!(diagnostics_for(source).is_empty());
+
+        let diagnostics =
+            diagnostics_for(&format!("let known_tags = [\"fast\", \"prod\"]\n{source}"));
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].level, DiagnosticLevel::Warning);
+        assert_eq!(diagnostics[0].location.as_deref(), Some("script probe"));
+        assert_eq!(
+            diagnostics[0].message,
+            "tag 'prdo' is not listed in known_tags"
+        );
+    }
+
+    #[test]
+    fn keyring_secrets_warn_about_headless_environments() {
+        let diagnostics = diagnostics_for(
+            "secret token from keyring {\n  service = \"axion-ci\"\n  account = \"deploy-bot\"\n}\n",
+        );
+        let messages: Vec<(&DiagnosticLevel, &str)> = diagnostics
+            .iter()
+            .map(|diag| (&diag.level, diag.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![(
+                &DiagnosticLevel::Warning,
+                "keyring secrets need an OS keychain, which headless CI runners usually lack; run with --keyring-mock-env and set AXION_KEYRING_AXION_CI_DEPLOY_BOT"
+            )]
+        );
+    }
+
+    #[test]
+    fn short_or_invalid_timeouts_are_reported() {
+        let diagnostics = diagnostics_for(
+            "script quick {\n  run \"true\"\n  timeout \"500ms\"\n}\nscan web nmap {\n  target \"10.0.0.1\"\n  timeout \"soon\"\n}\nscan db nmap {\n  target \"10.0.0.2\"\n  timeout \"30s\"\n}\n",
+        );
+        let levels: Vec<&DiagnosticLevel> = diagnostics.iter().map(|diag| &diag.level).collect();
+        assert_eq!(
+            levels,
+            vec![&DiagnosticLevel::Warning, &DiagnosticLevel::Error]
+        );
+        assert!(diagnostics[0].message.contains("shorter than 1s"));
+        assert!(diagnostics[1].message.starts_with("invalid timeout"));
+    }
+
+    #[test]
+    fn retry_counts_must_be_between_one_and_ten() {
+        let diagnostics = diagnostics_for(
+            "scan web nmap {\n  target \"10.0.0.1\"\n  retry 0\n}\nscript a {\n  run \"true\"\n  retry { count 11; delay \"1s\" }\n}\nscript b {\n  run \"true\"\n  retry 10\n}\n",
+        );
+        let messages: Vec<&str> = diagnostics
+            .iter()
+            .map(|diag| diag.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "retry count must be between 1 and 10, found 0",
+                "retry count must be between 1 and 10, found 11",
+            ]
+        );
+    }
+
+    #[test]
+    fn on_failure_reports_cannot_include_the_failed_artifact() {
+        let diagnostics = diagnostics_for(
+            "script export {\n  run \"./export.sh\"\n}\non_failure {\n  script dump {\n    run \"./dump.sh\"\n  }\n  report stdout {\n    include script_export\n    include script_dump\n  }\n}\nreport html {\n  include script_dump\n}\n",
+        );
+        let messages: Vec<(&DiagnosticLevel, &str)> = diagnostics
+            .iter()
+            .map(|diag| (&diag.level, diag.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                (
+                    &DiagnosticLevel::Error,
+                    "report 'stdout' includes 'script_export', which 'export' did not produce when it failed"
+                ),
+                (
+                    &DiagnosticLevel::Warning,
+                    "include 'script_dump' is only produced inside a conditional branch or loop body and may be missing at runtime"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn include_patterns_warn_instead_of_failing() {
+        let diagnostics = diagnostics_for(
+            "report stdout {\n  include findings_*\n}\nreport html {\n  include findings_[\n}\n",
+        );
+        let levels: Vec<&DiagnosticLevel> = diagnostics.iter().map(|diag| &diag.level).collect();
+        assert_eq!(levels, [&DiagnosticLevel::Warning, &DiagnosticLevel::Error]);
+        assert!(diagnostics[0]
+            .message
+            .contains("matched against artifacts at run time"));
+        assert!(diagnostics[1]
+            .message
+            .starts_with("invalid include pattern 'findings_['"));
+    }
+
+    #[test]
+    fn deduplicate_options_are_checked() {
+        let diagnostics = diagnostics_for(
+            "report stdout {\n  option deduplicate \"findings\"\n  option deduplicate_key \"asset_id,banner\"\n}\nreport html {\n  option deduplicate_key \"port\"\n}\n",
+        );
+        let messages: Vec<&str> = diagnostics
+            .iter()
+            .filter(|diag| !diag.message.contains("does not include any artifacts"))
+            .map(|diag| diag.message.as_str())
+            .collect();
+        assert_eq!(messages.len(), 2, "{messages:?}");
+        assert!(messages[0].starts_with("unknown deduplicate_key field 'banner'"));
+        assert_eq!(
+            messages[1],
+            "deduplicate_key has no effect without option deduplicate \"findings\""
+        );
+    }
+
+    #[test]
+    fn aws_secrets_note_network_use_and_permissions() {
+        let diagnostics = diagnostics_for(
+            "secret db from aws_secrets_manager {\n  secret_id = \"prod/db\"\n}\nsecret empty from aws_secrets_manager {\n  secret_id = \"\"\n}\n",
+        );
+        let messages: Vec<(&DiagnosticLevel, &str)> = diagnostics
+            .iter()
+            .map(|diag| (&diag.level, diag.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    &DiagnosticLevel::Warning,
+                    "aws_secrets_manager secrets are fetched from AWS at run time; the step fails when the API is unreachable"
+                ),
+                (
+                    &DiagnosticLevel::Info,
+                    "AWS credentials need secretsmanager:GetSecretValue on 'prod/db' (and kms:Decrypt if the secret uses a customer managed key)"
+                ),
+                (
+                    &DiagnosticLevel::Error,
+                    "aws_secrets_manager secret requires a secret_id"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_step_names_are_errors_except_across_branches() {
+        let diagnostics = diagnostics_for(
+            "let target = \"10.0.0.1\"\nif target == \"10.0.0.1\" {\n  scan web nmap {\n    target \"${target}\"\n  }\n} else {\n  scan web nmap {\n    target \"10.0.0.2\"\n  }\n}\nscript web {\n  run \"echo hi\"\n}\nscan web nmap {\n  target \"10.0.0.3\"\n}\nfor host in [\"a\", \"b\"] {\n  script probe {\n    run \"echo ${host}\"\n  }\n  script web {\n    run \"echo ${host}\"\n  }\n}\n",
+        );
+        let errors: Vec<&Diagnostic> = diagnostics.iter().filter(|diag| diag.is_error()).collect();
+        assert_eq!(errors.len(), 1, "{diagnostics:?}");
+        assert_eq!(errors[0].location.as_deref(), Some("scan web"));
+        assert!(
+            errors[0]
+                .message
+                .starts_with("duplicate step name 'web': scan reuses the name of the scan at '"),
+            "{}",
+            errors[0].message
+        );
+        assert!(diagnostics.iter().any(|diag| !diag.is_error()
+            && diag.location.as_deref() == Some("for host > script probe")
+            && diag.message.contains("runs once per iteration")));
+        assert!(diagnostics.iter().any(|diag| !diag.is_error()
+            && diag.location.as_deref() == Some("for host > script web")
+            && diag.message.starts_with(
+                "duplicate step name 'web': script reuses the name of the script at 'script web'"
+            )));
+    }
+
+    #[test]
+    fn reassigned_variables_are_not_duplicate_steps() {
+        let diagnostics = diagnostics_for(
+            "let x = 1\nlet x = 2\nlet total = \"\"\nfor i in 1..=3 {\n  let total = \"${total}${i}\"\n}\n",
+        );
+        assert!(
+            !diagnostics
+                .iter()
+                .any(|diag| diag.message.starts_with("duplicate step name")),
+            "{diagnostics:?}"
+        );
+    }
+
+    #[test]
+    fn report_includes_must_name_produced_artifacts() {
+        let diagnostics = diagnostics_for(
+            "let deep = true\nscan discovery nmap {\n  target \"10.0.0.1\"\n} -> hosts\nif deep {\n  script extra {\n    run \"echo hi\"\n  }\n}\nreport summary {\n  include hosts\n  include findings_discovery\n  include script_extra\n  include findings_typo\n}\n",
+        );
+        let messages: Vec<(bool, &str)> = diagnostics
+            .iter()
+            .filter(|diag| diag.location.as_deref() == Some("report summary"))
+            .map(|diag| (diag.is_error(), diag.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    true,
+                    "include 'findings_discovery' does not match any artifact produced by an earlier step"
+                ),
+                (
+                    false,
+                    "include 'script_extra' is only produced inside a conditional branch or loop body and may be missing at runtime"
+                ),
+                (
+                    true,
+                    "include 'findings_typo' does not match any artifact produced by an earlier step"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn large_target_ranges_produce_info_notes() {
+        let diagnostics =
+            diagnostics_for("scan sweep nmap {\n  target \"10.0.0.0/16 192.168.1.0/24\"\n}\n");
+        let infos: Vec<&Diagnostic> = diagnostics.iter().filter(|diag| diag.is_info()).collect();
+        assert_eq!(infos.len(), 1, "{diagnostics:?}");
+        assert!(!infos[0].is_error() && !infos[0].is_warning());
+        assert!(infos[0]
+            .message
+            .contains("10.0.0.0/16 covers 65536 addresses"));
+        assert_eq!(
+            serde_json::to_value(infos[0]).expect("serialize diagnostic")["level"],
+            serde_json::json!("info")
+        );
+    }
+
+    #[test]
+    fn cidr_scopes_declare_hosts_and_warn_when_huge() {
+        let diagnostics = diagnostics_for(
+            "asset_group lab {\n  scope \"192.168.1.0/24\"\n}\nasset_group dmz {\n  scope \"172.16.0.0/16\"\n}\nasset_group corp {\n  scope \"10.0.0.0/8\"\n}\nfor host in lab_hosts {\n  let current = \"${host}\"\n}\nlet everyone = \"${corp_hosts}\"\n",
+        );
+        let errors: Vec<&str> = diagnostics
+            .iter()
+            .filter(|diag| diag.is_error())
+            .map(|diag| diag.message.as_str())
+            .collect();
+        assert_eq!(errors, vec!["undefined variable 'corp_hosts'"]);
+        let warnings: Vec<&str> = diagnostics
+            .iter()
+            .filter(|diag| diag.is_warning())
+            .map(|diag| diag.message.as_str())
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "scope spans 65536 addresses; loops over dmz_hosts exceed the 10000-iteration limit unless they raise `max_iter`",
+                "scope spans 16777216 addresses (more than 1048576); corp_hosts will not be defined",
+            ]
+        );
+    }
+
+    #[test]
+    fn suppress_rules_are_parsed_and_scoped_to_sarif() {
+        let diagnostics = diagnostics_for(
+            "scan web nmap {\n  target \"10.0.0.1\"\n}\nreport gate using sarif {\n  include findings_web\n  option suppress 'asset_id matches \"dev-.*\" and rule_id == \"nmap\"'\n  option suppress_broken \"port >\"\n}\nreport notes using markdown {\n  include findings_web\n  option suppress 'severity == \"low\"'\n}\n",
+        );
+        let messages: Vec<&str> = diagnostics
+            .iter()
+            .filter(|diag| diag.is_error() || diag.message.contains("suppress"))
+            .map(|diag| diag.message.as_str())
+            .collect();
+        assert_eq!(messages.len(), 2, "{diagnostics:?}");
+        assert!(
+            messages[0].starts_with("invalid suppress_broken rule 'port >': invalid syntax"),
+            "{}",
+            messages[0]
+        );
+        assert!(messages[0].ends_with(
+            "(expected a condition on finding fields, e.g. severity == \"low\" and port > 1024)"
+        ));
+        assert_eq!(
+            messages[1],
+            "suppress rules only apply to sarif reports; markdown reports ignore them"
+        );
+    }
+
+    #[test]
+    fn schema_plugins_add_and_override_tools() {
+        let plugins = parse_schema_plugin(
+            r#"
+[[tool]]
+name = "semgrep"
+kind = "scan"
+description = "Semgrep static analysis"
+required = ["target", "config"]
+optional = ["flags"]
+
+[[tool]]
+name = "nmap"
+required = ["target"]
+optional = ["ports"]
+"#,
+        )
+        .expect("plugin parses");
+        assert_eq!(plugins.len(), 2);
+        assert!(!plugins[1].allow_additional);
+
+        let scenario = parse_scenario_all(
+            "scan code semgrep {\n  target \"src\"\n  rules \"auto\"\n}\nscan web nmap {\n  target \"10.0.0.1\"\n  ports \"80\"\n  flags \"-sV\"\n}\n",
+        )
+        .expect("failed to parse scenario");
+        let messages: Vec<String> =
+            validate_scenario_with_schemas(&scenario, &HashMap::new(), &plugins)
+                .into_iter()
+                .map(|diag| diag.message)
+                .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "missing required parameter 'config' for tool 'semgrep'",
+                "unknown parameter 'rules' for tool 'semgrep'; it will be ignored",
+                "unknown parameter 'flags' for tool 'nmap'; it will be ignored",
+            ]
+        );
+
+        let bundle = tool_schema_bundle(&plugins);
+        assert_eq!(bundle.tools[0].name, "semgrep");
+        assert_eq!(
+            bundle
+                .tools
+                .iter()
+                .filter(|tool| tool.name == "nmap")
+                .count(),
+            1
+        );
+        assert_eq!(bundle.tools.len(), builtin_tool_schemas().len() + 1);
+    }
+
+    #[test]
+    fn builtin_schema_examples_validate_cleanly() {
+        for schema in builtin_tool_schemas() {
+            assert!(schema.examples.len() >= 2, "{} needs examples", schema.name);
+            for example in &schema.examples {
+                let header = match schema.kind.as_deref() {
+                    Some("script") => "script example {".to_string(),
+                    _ => format!("scan example {} {{", schema.name),
+                };
+                let body: String = example
+                    .params
+                    .iter()
+                    .map(|(key, value)| format!("  {key} \"{value}\"\n"))
+                    .collect();
+                let diagnostics = diagnostics_for(&format!("{header}\n{body}}}\n"));
+                assert!(
+                    !diagnostics
+                        .iter()
+                        .any(|diag| diag.is_error() || diag.is_warning()),
+                    "{}: {diagnostics:?}",
+                    example.description
+                );
+            }
+        }
+
+        let bundle = serde_json::to_value(builtin_tool_schema_bundle()).expect("bundle json");
+        assert_eq!(
+            bundle["tools"][0]["examples"][0]["params"]["target"],
+            serde_json::json!("10.0.0.5")
+        );
+    }
+
+    #[test]
+    fn discovery_tool_schemas_check_their_parameters() {
+        let diagnostics = diagnostics_for(
+            "scan vulns nuclei {\n  target \"https://example.com\"\n  flags \"-jsonl\"\n}\nscan sweep masscan {\n  target \"10.0.0.0/24\"\n  rate \"fast\"\n  flags \"--output-format xml\"\n}\nscan dirs ffuf {\n  url \"example.com/admin\"\n  wordlist \"common.txt\"\n}\nscan subs subfinder {\n  domain \"https://example.com\"\n}\n",
+        );
+        let messages: Vec<&str> = diagnostics
+            .iter()
+            .map(|diag| diag.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "missing required parameter 'templates' for tool 'nuclei'",
+                "parameter 'rate' must be a positive number of packets per second, found 'fast'",
+                "parameter 'url' must start with http:// or https://, found 'example.com/admin'",
+                "ffuf url does not contain the FUZZ keyword; ffuf will refuse to run without it",
+                "parameter 'domain' must be a bare domain such as example.com, found 'https://example.com'",
+            ],
+            "{diagnostics:?}"
+        );
+    }
+
+    #[test]
+    fn tool_schemas_export_as_json_schema_defs() {
+        let document = tool_json_schema(&builtin_tool_schema_bundle());
+        assert_eq!(document["$schema"], json!(JSON_SCHEMA_DIALECT));
+        let nuclei = &document["$defs"]["nuclei"];
+        assert_eq!(nuclei["required"], json!(["target", "templates"]));
+        assert_eq!(nuclei["properties"]["timeout"], json!({ "type": "string" }));
+        assert_eq!(nuclei["additionalProperties"], json!(false));
+        assert_eq!(nuclei["examples"][0]["templates"], json!("http/cves/"));
+        assert_eq!(
+            document["definitions"]["subfinder"],
+            json!({ "$ref": "#/$defs/subfinder" })
+        );
+        assert_eq!(
+            document["$defs"].as_object().map(|defs| defs.len()),
+            Some(builtin_tool_schemas().len())
+        );
+    }
+
+    #[test]
+    fn schema_plugins_reject_empty_names_and_bad_toml() {
+        let err = parse_schema_plugin("[[tool]]\nname = \"\"\nrequired = [\"target\"]\n")
+            .expect_err("empty name rejected");
+        assert_eq!(err, "schema plugin tool #1 has an empty name");
+        let err = parse_schema_plugin("[[tool]]\nname = 3\n").expect_err("bad toml rejected");
+        assert!(err.starts_with("invalid schema plugin"), "{err}");
+    }
+
+    #[test]
+    fn overrides_count_as_declared() {
+        let scenario = parse_scenario_all("scan web nmap {\n  target \"${target}\"\n}\n")
+            .expect("failed to parse scenario");
+        assert!(validate_scenario(&scenario)
+            .iter()
+            .any(Diagnostic::is_error));
+        let overrides = HashMap::from([(
+            "target".to_string(),
+            LiteralValue::String("10.0.0.1".to_string()),
+        )]);
+        assert!(!validate_scenario_with_vars(&scenario, &overrides)
+            .iter()
+            .any(Diagnostic::is_error));
+    }
+}
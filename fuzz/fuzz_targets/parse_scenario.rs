@@ -0,0 +1,40 @@
+#![no_main]
+
+use std::collections::HashMap;
+
+use axion_core::{
+    evaluate_condition_expression, parse_literal_expression, parse_scenario_all, LiteralValue,
+};
+use libfuzzer_sys::fuzz_target;
+
+// Scenario files are read as UTF-8, so other inputs never reach the parser.
+// Every entry point must return an error for bad input instead of panicking.
+fuzz_target!(|data: &[u8]| {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    #[allow(deprecated)]
+    let first = axion_core::parse_scenario(source);
+    let all = parse_scenario_all(source);
+    assert_eq!(first.is_ok(), all.is_ok());
+    if let Err(errors) = all {
+        assert!(!errors.is_empty());
+    }
+
+    let _ = parse_literal_expression(source);
+
+    let variables = HashMap::from([
+        (
+            "name".to_string(),
+            LiteralValue::String("axion".to_string()),
+        ),
+        ("count".to_string(), LiteralValue::Integer(3)),
+        ("enabled".to_string(), LiteralValue::Boolean(true)),
+        (
+            "ports".to_string(),
+            LiteralValue::Array(vec![LiteralValue::Integer(80), LiteralValue::Integer(443)]),
+        ),
+    ]);
+    let _ = evaluate_condition_expression(source, &variables);
+});
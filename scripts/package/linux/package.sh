@@ -23,6 +23,30 @@ if [[ ! -f "$BINARY_PATH" ]]; then
 fi
 cp "$BINARY_PATH" "$STAGE_ROOT/bin/axion"
 
+# Completion scripts do not depend on the target, so a cross build generates
+# them with a host binary instead of running the target one.
+HOST="$(rustc -vV | sed -n 's/^host: //p')"
+COMPLETIONS_BINARY="$BINARY_PATH"
+if [[ "$TARGET" != "$HOST" ]]; then
+  echo "[package] building host axion-cli ($CONFIGURATION, $HOST) for completions"
+  if cargo build --"$CONFIGURATION" -p axion-cli >/dev/null; then
+    COMPLETIONS_BINARY="$REPO_ROOT/target/$CONFIGURATION/axion-cli"
+  else
+    COMPLETIONS_BINARY=""
+  fi
+fi
+
+if [[ -n "$COMPLETIONS_BINARY" ]] && "$COMPLETIONS_BINARY" completions bash >/dev/null 2>&1; then
+  echo "[package] generating shell completions"
+  mkdir -p "$STAGE_ROOT/completions"
+  "$COMPLETIONS_BINARY" completions bash >"$STAGE_ROOT/completions/axion.bash"
+  "$COMPLETIONS_BINARY" completions zsh >"$STAGE_ROOT/completions/_axion"
+  "$COMPLETIONS_BINARY" completions fish >"$STAGE_ROOT/completions/axion.fish"
+  "$COMPLETIONS_BINARY" completions powershell >"$STAGE_ROOT/completions/axion.ps1"
+else
+  echo "[package] warning: no runnable axion-cli for $HOST; skipping shell completions"
+fi
+
 for dir in examples docs tools ui/react-flow-prototype/dist; do
   if [[ -d "$REPO_ROOT/$dir" ]]; then
     mkdir -p "$(dirname "$STAGE_ROOT/$dir")"
@@ -51,6 +75,13 @@ cp "./bin/axion" "$BIN_DIR/axion"
 cp -R ./examples "$SHARE_DIR/examples"
 cp -R ./tools "$SHARE_DIR/tools"
 
+if [[ -d ./completions ]]; then
+  mkdir -p "$PREFIX/share/bash-completion/completions" "$PREFIX/share/zsh/site-functions" "$PREFIX/share/fish/vendor_completions.d"
+  cp ./completions/axion.bash "$PREFIX/share/bash-completion/completions/axion"
+  cp ./completions/_axion "$PREFIX/share/zsh/site-functions/_axion"
+  cp ./completions/axion.fish "$PREFIX/share/fish/vendor_completions.d/axion.fish"
+fi
+
 echo "[axion] Installed to $PREFIX (binary: $BIN_DIR/axion)"
 echo "[axion] Ensure $BIN_DIR is on your PATH."
 EOF
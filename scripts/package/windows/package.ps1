@@ -31,6 +31,29 @@ if (-not (Test-Path $binaryPath)) {
 }
 Copy-Item $binaryPath -Destination (Join-Path $stageRoot "bin\axion.exe")
 
+# Completion scripts do not depend on the target, so a cross build generates
+# them with a host binary instead of running the target one.
+$hostTarget = ((rustc -vV | Select-String "^host: ").Line -replace "^host: ", "").Trim()
+$completionsBinary = $binaryPath
+if ($Target -ne $hostTarget) {
+    Write-Host "[package] building host axion-cli ($Configuration, $hostTarget) for completions"
+    cargo build --$Configuration -p axion-cli | Out-Null
+    $completionsBinary = Join-Path $RepoRoot ("target\$Configuration\axion-cli.exe")
+    if ($LASTEXITCODE -ne 0 -or -not (Test-Path $completionsBinary)) {
+        $completionsBinary = $null
+    }
+}
+
+if ($completionsBinary) {
+    Write-Host "[package] generating shell completions"
+    $completionsDir = Join-Path $stageRoot "completions"
+    New-Item -ItemType Directory -Force -Path $completionsDir | Out-Null
+    & $completionsBinary completions powershell | Set-Content -Path (Join-Path $completionsDir "axion.ps1") -Encoding UTF8
+    & $completionsBinary completions bash | Set-Content -Path (Join-Path $completionsDir "axion.bash") -Encoding UTF8
+} else {
+    Write-Warning "[package] no runnable axion-cli for $hostTarget; skipping shell completions"
+}
+
 $copyDirs = @("examples", "docs", "tools", "ui\react-flow-prototype\dist")
 foreach ($dir in $copyDirs) {
     $src = Join-Path $RepoRoot $dir
@@ -60,9 +83,13 @@ New-Item -ItemType Directory -Force -Path `$BinDir | Out-Null
 Copy-Item ".\bin\axion.exe" -Destination (Join-Path `$BinDir "axion.exe") -Force
 Copy-Item ".\tools" -Destination (Join-Path `$Prefix "tools") -Recurse -Force
 Copy-Item ".\examples" -Destination (Join-Path `$Prefix "examples") -Recurse -Force
+if (Test-Path ".\completions") {
+    Copy-Item ".\completions" -Destination (Join-Path `$Prefix "completions") -Recurse -Force
+}
 
 Write-Host "[axion] Installed to `$Prefix"
 Write-Host "[axion] Add `$BinDir to PATH if not already configured."
+Write-Host "[axion] For tab completion, dot-source `$Prefix\completions\axion.ps1 from your PowerShell profile."
 "@
 Set-Content -Path (Join-Path $stageRoot "install.ps1") -Value $installScript -Encoding UTF8
 